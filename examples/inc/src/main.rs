@@ -17,6 +17,10 @@ impl Component for Model {
     type Message = Message;
     type Properties = ();
 
+    fn create(_props: ()) -> Self {
+        Self::default()
+    }
+
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         match msg {
             Message::Inc => {
@@ -23,6 +23,10 @@ impl Component for AboutDialog {
     type Message = ();
     type Properties = ();
 
+    fn create(_props: ()) -> Self {
+        Self::default()
+    }
+
     fn view(&self) -> VNode<Self> {
         gtk! {
             <Dialog::with_buttons(
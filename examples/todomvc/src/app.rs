@@ -124,6 +124,10 @@ impl Component for Model {
     type Message = Msg;
     type Properties = ();
 
+    fn create(_props: ()) -> Self {
+        Self::default()
+    }
+
     fn update(&mut self, msg: Self::Message) -> UpdateAction<Self> {
         let left = self.filter(Filter::Active).count();
         match msg {
@@ -0,0 +1,142 @@
+use proc_macro2::{Literal, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The prefix assets are registered under. Must match `vgtk::assets::PREFIX`.
+const PREFIX: &str = "/vgtk/assets";
+
+fn error(span: Span, message: &str) -> TokenStream {
+    quote_spanned! {span =>
+        compile_error! { #message }
+    }
+}
+
+/// Parse the macro's single string literal argument.
+fn parse_path(input: TokenStream) -> Result<(String, Span), TokenStream> {
+    let mut tokens = input.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => {
+            let text = literal.to_string();
+            if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+                Ok((text[1..text.len() - 1].to_string(), literal.span()))
+            } else {
+                Err(error(
+                    literal.span(),
+                    "expected a directory name as a string literal",
+                ))
+            }
+        }
+        (Some(token), _) => Err(error(
+            token.span(),
+            "expected a directory name as a string literal",
+        )),
+        (None, _) => Err(error(
+            Span::call_site(),
+            "expected a directory name as a string literal",
+        )),
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Compile every file in `dir` into a resource bundle using
+/// `glib-compile-resources`, returning the bundle and the files in it.
+fn compile(dir: &Path) -> Result<(Vec<u8>, Vec<PathBuf>), String> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)
+        .map_err(|err| format!("unable to read assets from {}: {}", dir.display(), err))?;
+    files.sort();
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gresources>\n");
+    let _ = writeln!(xml, "  <gresource prefix=\"{}\">", PREFIX);
+    for file in &files {
+        let name = file
+            .strip_prefix(dir)
+            .unwrap()
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let _ = writeln!(xml, "    <file>{}</file>", escape(&name));
+    }
+    xml.push_str("  </gresource>\n</gresources>\n");
+
+    // Work in a directory of our own, so concurrent builds can't trip over
+    // each other.
+    let work_dir = std::env::temp_dir().join(format!(
+        "vgtk-assets-{}-{:?}",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    fs::create_dir_all(&work_dir).map_err(|err| err.to_string())?;
+    let xml_path = work_dir.join("assets.gresource.xml");
+    let target = work_dir.join("assets.gresource");
+    fs::write(&xml_path, xml).map_err(|err| err.to_string())?;
+    let output = Command::new("glib-compile-resources")
+        .arg("--sourcedir")
+        .arg(dir)
+        .arg("--target")
+        .arg(&target)
+        .arg(&xml_path)
+        .output();
+    let result = match output {
+        Ok(output) if output.status.success() => fs::read(&target).map_err(|err| err.to_string()),
+        Ok(output) => Err(format!(
+            "glib-compile-resources failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(err) => Err(format!("unable to run glib-compile-resources: {}", err)),
+    };
+    let _ = fs::remove_dir_all(&work_dir);
+    result.map(|data| (data, files))
+}
+
+pub fn expand_include_assets(input: TokenStream) -> TokenStream {
+    let (path, span) = match parse_path(input) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
+    let dir = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(root) => PathBuf::from(root).join(path),
+        None => PathBuf::from(path),
+    };
+    let (data, files) = match compile(&dir) {
+        Ok(result) => result,
+        Err(message) => return error(span, &message),
+    };
+    let data = Literal::byte_string(&data);
+    // Include every asset as well, even though we don't use them directly,
+    // so the bundle gets rebuilt when one of them changes.
+    let dependencies = files.iter().map(|file| {
+        let file = file.to_string_lossy();
+        quote!(
+            const _: &[u8] = include_bytes!(#file);
+        )
+    });
+    quote!(
+        {
+            #(#dependencies)*
+            vgtk::assets::register(#data)
+        }
+    )
+}
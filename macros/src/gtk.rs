@@ -1,18 +1,111 @@
-use proc_macro2::{Group, Ident, Literal, TokenStream};
+use proc_macro2::{Ident, Literal, Span, TokenStream, TokenTree};
 use quote::{quote, quote_spanned};
+use vgtk_syntax::context::{Attribute, GtkComponent, GtkElement, GtkMatch, GtkWidget, Modifier};
+use vgtk_syntax::lexer::{to_stream, Token};
 
-use crate::context::{Attribute, GtkComponent, GtkElement, GtkWidget};
-use crate::lexer::{to_stream, Token};
+use crate::suggest;
 
 fn to_string_literal<S: ToString>(s: S) -> Literal {
     Literal::string(&s.to_string())
 }
 
+/// Test whether an attribute is a `key=value` attribute, which identifies
+/// the node among its siblings rather than setting a property.
+fn is_key(attribute: &Attribute) -> bool {
+    match attribute {
+        Attribute::Property {
+            child: false,
+            parent,
+            name,
+            ..
+        } => parent.is_empty() && name == "key",
+        _ => false,
+    }
+}
+
+/// Test whether an attribute is a `key(recycle)=value` attribute, which lets
+/// the widget be reused for a sibling with another key.
+fn is_recycled_key(attribute: &Attribute) -> bool {
+    match attribute {
+        Attribute::Property { modifiers, .. } if is_key(attribute) => {
+            modifiers.iter().any(|modifier| modifier.name == "recycle")
+        }
+        _ => false,
+    }
+}
+
+/// Test whether an attribute is a `tooltip=value` attribute, which gives a
+/// widget a custom tooltip built from a node of its own.
+fn is_tooltip(attribute: &Attribute) -> bool {
+    match attribute {
+        Attribute::Property {
+            child: false,
+            parent,
+            name,
+            ..
+        } => parent.is_empty() && name == "tooltip",
+        _ => false,
+    }
+}
+
+fn expand_tooltip(attribute: &Attribute) -> TokenStream {
+    match attribute {
+        Attribute::Property {
+            modifiers, value, ..
+        } => {
+            if let Some(modifier) = modifiers.first() {
+                let span = modifier.name.span();
+                return quote_spanned! {span =>
+                    compile_error! { "the `tooltip` attribute cannot have modifiers" }
+                };
+            }
+            let span = value[0].span();
+            let value = to_stream(value);
+            quote_spanned!(span => {
+                let tooltip: VNode<_> = (#value);
+                Some(std::boxed::Box::new(tooltip))
+            })
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn expand_key(attribute: &Attribute, widget: bool) -> TokenStream {
+    match attribute {
+        Attribute::Property {
+            modifiers, value, ..
+        } => {
+            for modifier in modifiers {
+                let span = modifier.name.span();
+                if modifier.name != "recycle" {
+                    return quote_spanned! {span =>
+                        compile_error! { "unknown key modifier; expected `recycle`" }
+                    };
+                }
+                if !modifier.value.is_empty() {
+                    return quote_spanned! {span =>
+                        compile_error! { "the `recycle` modifier doesn't take a value" }
+                    };
+                }
+                if !widget {
+                    return quote_spanned! {span =>
+                        compile_error! { "only widgets can be recycled, not components" }
+                    };
+                }
+            }
+            let value = to_stream(value);
+            quote!(Some(vgtk::vnode::key(&(#value))))
+        }
+        _ => unreachable!(),
+    }
+}
+
 fn count_attributes(attributes: &[Attribute]) -> (usize, usize, usize) {
     let mut props = 0;
     let mut child_props = 0;
     let mut handlers = 0;
-    for attribute in attributes {
+    let special = |attribute: &&Attribute| is_key(attribute) || is_tooltip(attribute);
+    for attribute in attributes.iter().filter(|attribute| !special(attribute)) {
         match attribute {
             Attribute::Property { child, .. } => {
                 if *child {
@@ -22,6 +115,7 @@ fn count_attributes(attributes: &[Attribute]) -> (usize, usize, usize) {
                 }
             }
             Attribute::Handler { .. } => handlers += 1,
+            Attribute::Spread { .. } => {}
         }
     }
     (props, child_props, handlers)
@@ -32,26 +126,62 @@ pub fn expand_gtk(gtk: &GtkElement) -> TokenStream {
         GtkElement::Widget(widget) => expand_widget(widget),
         GtkElement::Component(component) => expand_component(component),
         GtkElement::Block(_block) => panic!("blocks not allowed in this position"),
+        GtkElement::Let(_binding) => panic!("let bindings not allowed in this position"),
+        GtkElement::Match(_gtk_match) => panic!("match expressions not allowed in this position"),
     }
 }
 
 pub fn expand_component(gtk: &GtkComponent) -> TokenStream {
     let name = to_stream(&gtk.name);
+    // Like Rust's struct update syntax, a `{..value}` attribute gives the
+    // properties the other attributes don't set.
+    let mut spreads = gtk
+        .attributes
+        .iter()
+        .filter_map(|attribute| match attribute {
+            Attribute::Spread { value } => Some(value),
+            _ => None,
+        });
+    let base = match (spreads.next(), spreads.next()) {
+        (_, Some(value)) => {
+            let span = value.first().map_or_else(Span::call_site, Token::span);
+            return quote_spanned! {span =>
+                compile_error! { "a component can only have one `..` attribute" }
+            };
+        }
+        (Some(value), None) => expand_spread_value(value),
+        (None, None) => quote!(<#name as Component>::Properties::default()),
+    };
     let mut out = quote!(
+        #[allow(unused_imports)]
         use vgtk::{Component, vnode::VComponent, vnode::PropTransform};
         let mut vcomp = VComponent::new::<#name>();
-        let mut props = <#name as Component>::Properties::default();
+        let mut props: <#name as Component>::Properties = #base;
     );
     for attribute in &gtk.attributes {
         out.extend(match attribute {
+            Attribute::Spread { .. } => quote!(),
+            attribute if is_key(attribute) => {
+                let key = expand_key(attribute, false);
+                quote!(
+                    vcomp.key = #key;
+                )
+            }
             Attribute::Property {
                 child,
                 parent,
                 name,
+                modifiers,
                 value,
             } => {
+                if let Some(modifier) = modifiers.first() {
+                    let span = modifier.name.span();
+                    return quote_spanned! {span =>
+                        compile_error! { "component properties cannot have modifiers" }
+                    };
+                }
                 if *child {
-                    let prop = expand_property(None, *child, parent, name, value);
+                    let prop = expand_property(None, *child, parent, name, &[], value);
                     quote!(
                         vcomp.child_props.push(#prop);
                     )
@@ -70,6 +200,7 @@ pub fn expand_component(gtk: &GtkComponent) -> TokenStream {
             }
             Attribute::Handler {
                 name,
+                modifiers,
                 async_keyword,
                 args,
                 body,
@@ -79,6 +210,12 @@ pub fn expand_component(gtk: &GtkComponent) -> TokenStream {
                         compile_error! { "component callbacks cannot be async" }
                     };
                 }
+                if let Some(modifier) = modifiers.first() {
+                    let span = modifier.name.span();
+                    return quote_spanned! {span =>
+                        compile_error! { "component callbacks cannot have modifiers" }
+                    };
+                }
                 let name = Ident::new(&format!("on_{}", name.to_string()), name.span());
                 let args = to_stream(args);
                 let body = to_stream(body);
@@ -95,26 +232,36 @@ pub fn expand_component(gtk: &GtkComponent) -> TokenStream {
     })
 }
 
-fn is_block(gtk: &GtkElement) -> Option<&Group> {
-    match gtk {
-        GtkElement::Block(block) => Some(block),
-        _ => None,
-    }
-}
-
 pub fn expand_widget(gtk: &GtkWidget) -> TokenStream {
     let name = to_stream(&gtk.name);
+    // The key's value is usually a variable of the view's, which a local
+    // `key` mustn't shadow.
+    let key = Ident::new("key", Span::mixed_site());
+    let tooltip = Ident::new("tooltip", Span::mixed_site());
+    let recycle = gtk.attributes.iter().any(is_recycled_key);
     let (prop_count, child_prop_count, handler_count) = count_attributes(&gtk.attributes);
     let mut out = quote!(
+        #[allow(unused_imports)]
         use vgtk::vnode::{VNode, VHandler, VProperty, VObject, VComponent};
+        #[allow(unused_imports)]
         use vgtk::scope::Scope;
+        #[allow(unused_imports)]
         use vgtk::lib::glib::StaticType;
+        #[allow(unused_imports)]
         use std::vec::Vec;
         let object_type = #name::static_type();
+        #[allow(unused_mut)]
         let mut properties = Vec::with_capacity(#prop_count);
+        #[allow(unused_mut)]
         let mut child_props = Vec::with_capacity(#child_prop_count);
+        #[allow(unused_mut)]
         let mut handlers = Vec::with_capacity(#handler_count);
-        let mut children = Vec::new();
+        #[allow(unused_mut)]
+        let mut construct_props = Vec::new();
+        #[allow(unused_mut)]
+        let mut #key = None;
+        #[allow(unused_mut)]
+        let mut #tooltip = None;
     );
     if !gtk.constructor.is_empty() {
         let cons = to_stream(&gtk.constructor);
@@ -130,13 +277,45 @@ pub fn expand_widget(gtk: &GtkWidget) -> TokenStream {
     }
     for attribute in &gtk.attributes {
         out.extend(match attribute {
+            attribute if is_key(attribute) => {
+                let value = expand_key(attribute, true);
+                quote!(
+                    #key = #value;
+                )
+            }
+            attribute if is_tooltip(attribute) => {
+                let value = expand_tooltip(attribute);
+                quote!(
+                    #tooltip = #value;
+                )
+            }
+            Attribute::Property {
+                child,
+                parent,
+                name,
+                modifiers,
+                value,
+            } if is_construct(modifiers) => {
+                let prop = expand_construct_property(
+                    *child || !parent.is_empty(),
+                    !gtk.constructor.is_empty(),
+                    &name,
+                    &modifiers,
+                    &value,
+                );
+                quote!(
+                    construct_props.push(#prop);
+                )
+            }
             Attribute::Property {
                 child,
                 parent,
                 name,
+                modifiers,
                 value,
             } => {
-                let prop = expand_property(Some(&gtk.name), *child, &parent, &name, &value);
+                let prop =
+                    expand_property(Some(&gtk.name), *child, &parent, &name, &modifiers, &value);
                 if *child {
                     quote!(
                         child_props.push(#prop);
@@ -149,24 +328,62 @@ pub fn expand_widget(gtk: &GtkWidget) -> TokenStream {
             }
             Attribute::Handler {
                 name,
+                modifiers,
                 async_keyword,
                 args,
                 body,
-            } => expand_handler(&gtk.name, &name, async_keyword.as_ref(), &args, &body),
+            } => expand_handler(
+                &gtk.name,
+                &name,
+                &modifiers,
+                async_keyword.as_ref(),
+                &args,
+                &body,
+            ),
+            Attribute::Spread { value } => {
+                let span = value[0].span();
+                let spread = expand_spread_value(value);
+                let spread = quote_spanned!(span => vgtk::properties::spread(&#spread));
+                quote!(
+                    properties.extend(#spread);
+                )
+            }
         });
     }
-    for child in &gtk.children {
-        if let Some(block) = is_block(child) {
-            out.extend(quote!(
-                children.extend(#block);
-            ));
-        } else {
-            let child = expand_gtk(child);
-            out.extend(quote!(
-                children.push(#child);
-            ));
+    // The children are collected in their own scope, so that `let` bindings
+    // only apply to the siblings which follow them, and under a hygienic name,
+    // so that those bindings can't shadow it.
+    let children = Ident::new("children", Span::mixed_site());
+    let mut children_out = quote!(
+        #[allow(unused_mut)]
+        let mut #children = Vec::new();
+    );
+    for (index, child) in gtk.children.iter().enumerate() {
+        match child {
+            GtkElement::Block(block) => children_out.extend(quote!(
+                #children.extend(#block);
+            )),
+            GtkElement::Let(binding) => children_out.extend(to_stream(binding)),
+            GtkElement::Match(gtk_match) => {
+                let gtk_match = expand_match(index, gtk_match);
+                children_out.extend(quote!(
+                    #children.extend(#gtk_match);
+                ))
+            }
+            child => {
+                let child = expand_gtk(child);
+                children_out.extend(quote!(
+                    #children.push(#child);
+                ));
+            }
         }
     }
+    out.extend(quote!(
+        let children = {
+            #children_out
+            #children
+        };
+    ));
     quote!({
         #out
         VNode::Object(VObject {
@@ -174,19 +391,153 @@ pub fn expand_widget(gtk: &GtkWidget) -> TokenStream {
             constructor,
             properties,
             child_props,
+            construct_props,
             handlers,
             children,
+            key: #key,
+            recycle: #recycle,
+            tooltip: #tooltip,
         })
     })
 }
 
+/// Expand a `match` expression at a child position. Each arm's nodes are
+/// keyed by where the match is among its siblings, and which arm they came
+/// from, so that switching arms replaces the old arm's widgets and
+/// subcomponents rather than patching them to look like the new arm's.
+fn expand_match(site: usize, gtk_match: &GtkMatch) -> TokenStream {
+    let scrutinee = to_stream(&gtk_match.scrutinee);
+    let arms = gtk_match.arms.iter().enumerate().map(|(arm, match_arm)| {
+        let pattern = to_stream(&match_arm.pattern);
+        let body = match &match_arm.body {
+            GtkElement::Block(block) => quote!(#block),
+            body => {
+                let body = expand_gtk(body);
+                quote!(std::iter::once(#body))
+            }
+        };
+        quote!(
+            #pattern => vgtk::vnode::match_arm(#site, #arm, #body),
+        )
+    });
+    quote!(
+        match #scrutinee {
+            #(#arms)*
+        }
+    )
+}
+
+/// Expand the value of a `{..value}` attribute.
+fn expand_spread_value(value: &[Token]) -> TokenStream {
+    let span = value[0].span();
+    let value = to_stream(value);
+    quote_spanned!(span => (#value))
+}
+
+fn is_construct(modifiers: &[Modifier]) -> bool {
+    modifiers
+        .iter()
+        .any(|modifier| modifier.name == "construct")
+}
+
+/// Expand a `name(construct)=value` attribute, for a construct-only property
+/// which is given to the object when it's created.
+pub fn expand_construct_property(
+    qualified: bool,
+    has_constructor: bool,
+    name: &Ident,
+    modifiers: &[Modifier],
+    value: &[Token],
+) -> TokenStream {
+    for modifier in modifiers {
+        let span = modifier.name.span();
+        if modifier.name != "construct" {
+            return quote_spanned! {span =>
+                compile_error! { "construct properties can't have other modifiers" }
+            };
+        }
+        if !modifier.value.is_empty() {
+            return quote_spanned! {span =>
+                compile_error! { "the `construct` modifier doesn't take a value" }
+            };
+        }
+    }
+    if qualified {
+        let span = name.span();
+        return quote_spanned! {span =>
+            compile_error! { "construct properties can't be child properties or qualified with a type" }
+        };
+    }
+    if has_constructor {
+        let span = name.span();
+        return quote_spanned! {span =>
+            compile_error! { "construct properties can't be used along with a constructor" }
+        };
+    }
+    let prop_name = name.to_string();
+    let prop_name = prop_name.trim_start_matches("r#").replace('_', "-");
+    let value_span = value[0].span();
+    let value = to_stream(value);
+    quote_spanned!(value_span =>
+        vgtk::vnode::VConstructProperty {
+            name: #prop_name,
+            value: vgtk::lib::glib::ToValue::to_value(&(#value)),
+        }
+    )
+}
+
 pub fn expand_property(
     object_type: Option<&[Token]>,
     child_prop: bool,
     parent: &[Token],
     name: &Ident,
+    modifiers: &[Modifier],
     value: &[Token],
 ) -> TokenStream {
+    let mut animate = None;
+    let mut optional = false;
+    for modifier in modifiers {
+        match modifier.name.to_string().as_str() {
+            "animate" if child_prop => {
+                let span = modifier.name.span();
+                return quote_spanned! {span =>
+                    compile_error! { "child properties cannot be animated" }
+                };
+            }
+            "animate" if modifier.value.is_empty() => {
+                let span = modifier.name.span();
+                return quote_spanned! {span =>
+                    compile_error! { "the `animate` modifier needs a duration, like `animate=200ms`" }
+                };
+            }
+            "animate" => animate = Some(expand_duration(&modifier.value)),
+            "optional" if child_prop => {
+                let span = modifier.name.span();
+                return quote_spanned! {span =>
+                    compile_error! { "child properties cannot be optional" }
+                };
+            }
+            "optional" if !modifier.value.is_empty() => {
+                let span = modifier.name.span();
+                return quote_spanned! {span =>
+                    compile_error! { "the `optional` modifier doesn't take a value" }
+                };
+            }
+            "optional" => optional = true,
+            _ => {
+                let span = modifier.name.span();
+                return quote_spanned! {span =>
+                    compile_error! { "unknown property modifier; expected `animate`, `construct` or `optional`" }
+                };
+            }
+        }
+    }
+    if optional && animate.is_some() {
+        let span = name.span();
+        return quote_spanned! {span =>
+            compile_error! { "optional properties cannot be animated" }
+        };
+    }
     let child_prefix = if child_prop { "child_" } else { "" };
     let mut parent_type: Vec<Token> = parent.to_vec();
     while let Some(Token::Punct2(_, _, _, _)) = parent_type.last() {
@@ -203,7 +554,13 @@ pub fn expand_property(
     );
     let value_span = value[0].span();
     let value = to_stream(value);
-    let value = quote_spanned!(value_span => (#value).into_property_value());
+    let into_property_value = Ident::new("into_property_value", value_span);
+    // The comparison and coercion methods are where a value of the wrong type
+    // gets caught, so give them the value's span to put the error there.
+    let compare = Ident::new("compare", value_span);
+    let update = Ident::new("update", value_span);
+    let coerce = Ident::new("coerce", value_span);
+    let hint = suggest::property(name, child_prop);
     let prop_name = to_string_literal(name);
     let setter_prelude = if let Some(object_type) = object_type {
         let object_type = to_stream(object_type);
@@ -214,17 +571,51 @@ pub fn expand_property(
     } else {
         quote!()
     };
-    let setter_body = if !child_prop {
+    let setter_body = if let Some(duration) = animate {
+        let (get, set) = if parent_type.is_empty() {
+            (quote!(object.#getter()), quote!(object.#setter(value)))
+        } else {
+            (
+                quote!(#parent_type::#getter(object)),
+                quote!(#parent_type::#setter(object, value)),
+            )
+        };
+        // Apply the initial value directly, and animate any later changes.
+        quote!(
+            if force {
+                let value = value.#coerce();
+                #set;
+                true
+            } else if !value.#compare(#get) {
+                vgtk::properties::animate(object, #prop_name, #duration, #get, value.#coerce(), |object, value| {
+                    #set;
+                });
+                true
+            } else {
+                false
+            }
+        )
+    } else if !child_prop {
         if parent_type.is_empty() {
             quote!(
-                if force || !value.compare(object.#getter()) {
-                    object.#setter(value.coerce());
+                if force || !value.#compare(object.#getter()) {
+                    if force || !value.#update(object.#getter()) {
+                        object.#setter(value.#coerce());
+                    }
+                    true
+                } else {
+                    false
                 }
             )
         } else {
             quote!(
-                if force || !value.compare(#parent_type::#getter(object)) {
-                    #parent_type::#setter(object, value.coerce());
+                if force || !value.#compare(#parent_type::#getter(object)) {
+                    if force || !value.#update(#parent_type::#getter(object)) {
+                        #parent_type::#setter(object, value.#coerce());
+                    }
+                    true
+                } else {
+                    false
                 }
             )
         }
@@ -232,22 +623,60 @@ pub fn expand_property(
         quote!(
             let parent: &#parent_type = parent.expect("child attribute without a reachable parent").downcast_ref()
                   .unwrap_or_else(|| panic!("downcast to {:?} failed on parent in property setter", #parent_type::static_type()));
-            if force || !value.compare(parent.#getter(object)) {
-                parent.#setter(object, value.coerce());
+            if force || !value.#compare(parent.#getter(object)) {
+                parent.#setter(object, value.#coerce());
+                true
+            } else {
+                false
             }
         )
     };
+    // An optional property's value is an `Option`, which sets the property
+    // when it's `Some`, and puts it back to its default when it's `None`.
+    let (convert, setter_body) = if optional {
+        let gobject_name = name.to_string();
+        let gobject_name = gobject_name.trim_start_matches("r#").replace('_', "-");
+        (
+            quote!(
+                let value = value.map(|value| value.#into_property_value());
+            ),
+            quote!(
+                match &value {
+                    Some(value) => { #setter_body }
+                    None => vgtk::properties::reset(object.upcast_ref(), #gobject_name, force),
+                }
+            ),
+        )
+    } else {
+        (
+            quote!(
+                let value = value.#into_property_value();
+            ),
+            setter_body,
+        )
+    };
     quote!(
         {
+            #[allow(unused_imports)]
             use vgtk::lib::gtk::{Container, Widget};
+            #[allow(unused_imports)]
             use vgtk::lib::glib::{StaticType, object::Cast};
+            #[allow(unused_imports)]
             use vgtk::properties::{
                 IntoPropertyValue, PropertyValue, PropertyValueCoerce, PropertyValueCompare,
             };
-            let value = #value;
+            #hint
+            let value = (#value);
+            let fingerprint = {
+                #[allow(unused_imports)]
+                use vgtk::properties::{Fingerprint, NoFingerprint};
+                (&&vgtk::properties::Fingerprinted(&value)).fingerprint()
+            };
+            #convert
             VProperty {
                 name: #prop_name,
-                set: std::boxed::Box::new(move |object: &vgtk::lib::glib::Object, parent: Option<&vgtk::lib::glib::Object>, force: bool| {
+                fingerprint,
+                set: std::boxed::Box::new(move |object: &vgtk::lib::glib::Object, #[allow(unused_variables)] parent: Option<&vgtk::lib::glib::Object>, force: bool| {
                     #setter_prelude
                     #setter_body
                 }),
@@ -256,48 +685,174 @@ pub fn expand_property(
     )
 }
 
+/// Expand a duration given as a number of milliseconds or a literal with an
+/// `ms` or `s` suffix, or else as an arbitrary `Duration` expression.
+fn expand_duration(tokens: &[Token]) -> TokenStream {
+    if let [Token::Literal(literal)] = tokens {
+        let text = literal.to_string();
+        let split = text
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or_else(|| text.len());
+        let (number, suffix) = text.split_at(split);
+        let factor = match suffix {
+            "" | "ms" => Some(1.0),
+            "s" => Some(1000.0),
+            _ => None,
+        };
+        return match (number.replace('_', "").parse::<f64>(), factor) {
+            (Ok(number), Some(factor)) => {
+                let millis = Literal::u64_unsuffixed((number * factor).round() as u64);
+                quote!(std::time::Duration::from_millis(#millis))
+            }
+            _ => {
+                let span = literal.span();
+                quote_spanned! {span =>
+                    compile_error! { "expected a duration like `200ms` or `1s`" }
+                }
+            }
+        };
+    }
+    let duration = to_stream(tokens);
+    quote!({
+        let duration: std::time::Duration = #duration;
+        duration
+    })
+}
+
+/// Signals whose handlers take patterns as their arguments, which don't have
+/// to match every event. These are the decoded event signals described in
+/// `vgtk::event`.
+const PATTERN_SIGNALS: &[&str] = &["key_press", "key_release", "button_press", "button_release"];
+
+/// Expand a handler whose arguments are patterns into a closure which runs
+/// `block` when they match, and lets the event carry on when they don't.
+fn expand_patterns(args: &[Token], block: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = to_stream(args).into_iter().collect();
+    let mut patterns = vec![TokenStream::new()];
+    // Leave out the closure's bars.
+    for token in &tokens[1..tokens.len() - 1] {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => patterns.push(TokenStream::new()),
+            token => patterns.last_mut().unwrap().extend(Some(token.clone())),
+        }
+    }
+    patterns.retain(|pattern| !pattern.is_empty());
+    let names: Vec<Ident> = (0..patterns.len())
+        .map(|index| Ident::new(&format!("arg{}", index), Span::mixed_site()))
+        .collect();
+    // The events are matched by value, so that their fields bind by value
+    // too. The widget stays a reference.
+    let values = names.iter().enumerate().map(|(index, name)| match index {
+        0 => quote!(#name),
+        _ => quote!(*#name),
+    });
+    quote!(move |#(#names),*| {
+        #[allow(unreachable_patterns)]
+        match (#(#values,)*) {
+            (#(#patterns,)*) => #block,
+            _ => vgtk::event::unhandled(),
+        }
+    })
+}
+
 pub fn expand_handler(
     object_type: &[Token],
     name: &Ident,
+    modifiers: &[Modifier],
     async_keyword: Option<&Token>,
     args: &[Token],
     body: &[Token],
 ) -> TokenStream {
+    let mut limit = None;
+    for modifier in modifiers {
+        let kind = modifier.name.to_string();
+        let span = modifier.name.span();
+        match kind.as_str() {
+            _ if limit.is_some() => {
+                return quote_spanned! {span =>
+                    compile_error! { "a handler can only have one of `throttle` and `debounce`" }
+                };
+            }
+            "throttle" | "debounce" if modifier.value.is_empty() => {
+                let message = format!(
+                    "the `{}` modifier needs a duration, like `{}=100ms`",
+                    kind, kind
+                );
+                return quote_spanned! {span => compile_error! { #message } };
+            }
+            "throttle" | "debounce" => {
+                let kind = Ident::new(&kind, span);
+                let duration = expand_duration(&modifier.value);
+                limit = Some(quote!(vgtk::vnode::RateLimit::#kind(&scope, #duration)));
+            }
+            _ => {
+                return quote_spanned! {span =>
+                    compile_error! { "unknown handler modifier; expected `throttle` or `debounce`" }
+                };
+            }
+        }
+    }
     let object_type = to_stream(object_type);
     let args_s = to_stream(args);
     let body_s = to_stream(body);
-    let connect = Ident::new(&format!("connect_{}", name.to_string()), name.span());
+    // `notify_<property>` handles changes to a property, which gtk-rs spells
+    // `connect_property_<property>_notify`.
+    let name_s = name.to_string();
+    let connect = match name_s.strip_prefix("notify_") {
+        Some(property) => format!("connect_property_{}_notify", property),
+        None => format!("connect_{}", name_s),
+    };
+    let connect = Ident::new(&connect, name.span());
     let signal_name = to_string_literal(name);
     let location = args.first().expect("signal handler is empty!").span();
     let signal_id = to_string_literal(format!("{:?}", location));
+    let body_span = body.first().map_or_else(Span::call_site, Token::span);
+    // The rate limiter is invisible to the handler's body.
+    let limiter = Ident::new("limit", Span::mixed_site());
+    let (dispatch, limit_arg, clone_limit) = match limit {
+        Some(_) => (
+            Ident::new("dispatch_limited", body_span),
+            quote!(&#limiter),
+            quote!(let #limiter = #limiter.clone();),
+        ),
+        None => (Ident::new("dispatch", body_span), quote!(&scope), quote!()),
+    };
+    let limit =
+        limit.map(|limit| quote!(let #limiter = #limit.attach(object.upcast_ref(), #signal_id);));
+    let hint = suggest::signal(name);
     let inner_block = if async_keyword.is_some() {
         quote!({
             let scope = scope.clone();
+            #clone_limit
             vgtk::lib::glib::MainContext::ref_thread_default().spawn_local(
                 async move {
-                    let (msg, ret) = async move { #body_s }.await;
-                    scope.send_message(msg);
-                    ret
+                    vgtk::vnode::#dispatch(#limit_arg, async move { #body_s }.await)
                 }
             )
         })
     } else {
         quote!({
-            let (msg, ret) = { #body_s };
-            scope.send_message(msg);
-            ret
+            vgtk::vnode::#dispatch(#limit_arg, { #body_s })
         })
     };
+    let closure = if PATTERN_SIGNALS.contains(&name_s.as_str()) {
+        expand_patterns(args, inner_block)
+    } else {
+        quote!(move #args_s #inner_block)
+    };
     quote!(
+        #hint
         handlers.push(VHandler {
             name: #signal_name,
-            id: #signal_id,
+            id: std::borrow::Cow::Borrowed(#signal_id),
             set: std::boxed::Box::new(move |object: &vgtk::lib::glib::Object, scope: &Scope<_>| {
+                #[allow(unused_imports)]
                 use vgtk::lib::glib::object::Cast;
                 let object: &#object_type = object.downcast_ref()
                       .unwrap_or_else(|| panic!("downcast to {:?} failed in signal setter", #object_type::static_type()));
                 let scope: Scope<_> = scope.clone();
-                object.#connect(move #args_s #inner_block)
+                #limit
+                object.#connect(#closure)
             })
         });
     )
@@ -5,22 +5,23 @@
 extern crate proc_macro;
 
 use proc_macro_hack::proc_macro_hack;
+use vgtk_syntax::lexer::Tokens;
 
-mod context;
+mod assets;
 mod error;
 mod gtk;
-mod lexer;
-mod parser;
+mod suggest;
 
 #[proc_macro_hack]
 pub fn gtk(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // let orig_stream = input.clone();
     // let input: proc_macro2::TokenStream = input.into();
     // panic!("{:?}", input);
-    let stream: lexer::Tokens = input.into();
+    let stream: proc_macro2::TokenStream = input.into();
+    let stream: Tokens = stream.into();
     // panic!("{:?}", stream);
 
-    let result = parser::grammar::GtkElementParser::new().parse(stream.lexer());
+    let result = vgtk_syntax::parse_tokens(&stream);
     match result {
         Err(err) => error::parse_error(&stream, &err),
         Ok(element) => gtk::expand_gtk(&element),
@@ -38,3 +39,8 @@ pub fn gtk(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // ))
     // .expect("unable to write to macroexpand.log");
 }
+
+#[proc_macro_hack]
+pub fn include_assets(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    assets::expand_include_assets(input.into()).into()
+}
@@ -0,0 +1,93 @@
+//! "Did you mean" hints for misspelt property and signal names.
+//!
+//! The macro can't see the types it's expanding into, so it checks attribute
+//! names against the property setters and signal connectors found in gtk-rs
+//! and vgtk's own extension traits, which are listed one per line in
+//! `properties.txt` and `signals.txt`. A name that isn't in the list but is
+//! close to one that is gets a warning on the attribute pointing at the
+//! likely candidate. It's only a warning, because an app can legitimately add
+//! its own properties and signals through extension traits, and if the name
+//! really is wrong the compiler will report an error on the same token
+//! anyway.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote_spanned;
+
+const PROPERTIES: &str = include_str!("properties.txt");
+const SIGNALS: &str = include_str!("signals.txt");
+
+/// Warn about a property name which looks like a misspelling of a known one.
+/// Child properties are looked up with their `child_` prefix.
+pub fn property(name: &Ident, child_prop: bool) -> TokenStream {
+    let prefix = if child_prop { "child_" } else { "" };
+    let name_s = name.to_string();
+    let name_s = name_s.trim_start_matches("r#");
+    match suggest(PROPERTIES, &format!("{}{}", prefix, name_s)) {
+        Some(known) => hint(
+            "unknown_property",
+            &format!("did you mean `{}`?", known.trim_start_matches(prefix)),
+            name.span(),
+        ),
+        None => TokenStream::new(),
+    }
+}
+
+/// Warn about a signal name which looks like a misspelling of a known one.
+pub fn signal(name: &Ident) -> TokenStream {
+    match suggest(SIGNALS, &name.to_string()) {
+        Some(known) => hint(
+            "unknown_signal",
+            &format!("did you mean `on {}`?", known),
+            name.span(),
+        ),
+        None => TokenStream::new(),
+    }
+}
+
+/// Find the closest known name, unless `name` is known itself or nothing is
+/// close enough to be a plausible typo.
+fn suggest<'a>(known: &'a str, name: &str) -> Option<&'a str> {
+    if known.lines().any(|line| line == name) {
+        return None;
+    }
+    let limit = std::cmp::max(1, name.len() / 4);
+    known
+        .lines()
+        .map(|line| (distance(line, name), line))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, line)| line)
+}
+
+/// Levenshtein distance between two names.
+fn distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut row: Vec<usize> = (0..=right.len()).collect();
+    for (i, l) in left.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, r) in right.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if l == *r {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[right.len()]
+}
+
+/// Produce a warning located at `span`. Stable Rust has no way for a proc
+/// macro to emit a warning directly, so this uses a deprecated constant,
+/// whose name and note make up the message.
+fn hint(name: &str, note: &str, span: Span) -> TokenStream {
+    let name = Ident::new(name, span);
+    quote_spanned!(span => {
+        #[deprecated(note = #note)]
+        #[allow(non_upper_case_globals)]
+        const #name: () = ();
+        #name
+    };)
+}
@@ -2,6 +2,7 @@ use std::fmt::{Debug, Error, Formatter};
 
 use proc_macro2::{Group, Ident};
 
+use crate::error::RsxParseError;
 use crate::lexer::{Token, Tokens};
 
 #[derive(Debug, Clone)]
@@ -23,6 +24,60 @@ pub enum GtkElement {
     Widget(GtkWidget),
     Component(GtkComponent),
     Block(Group),
+    Let(Tokens),
+    Match(GtkMatch),
+}
+
+/// A `match` expression at a child position, whose arms are elements.
+#[derive(Debug, Clone)]
+pub struct GtkMatch {
+    pub scrutinee: Tokens,
+    pub arms: Vec<MatchArm>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Tokens,
+    pub body: GtkElement,
+}
+
+/// A `key=value` modifier on an attribute, like `opacity(animate=200ms)=...`,
+/// or a bare `key`, like `type(construct)=...`, in which case `value` is
+/// empty.
+#[derive(Debug, Clone)]
+pub struct Modifier {
+    pub name: Ident,
+    pub value: Tokens,
+}
+
+/// Parse the contents of an attribute's modifier list.
+pub fn parse_modifiers(token: Token) -> Result<Vec<Modifier>, RsxParseError> {
+    let tokens: Tokens = match token {
+        Token::Group(_, ref group) => group.stream().into(),
+        _ => unreachable!(),
+    };
+    let mut modifiers = Vec::new();
+    for item in tokens.split(|token| matches!(token, Token::Punct1(',', _))) {
+        match item {
+            [] => {}
+            [Token::Ident(name), Token::Punct1('=', _), value @ ..] if !value.is_empty() => {
+                modifiers.push(Modifier {
+                    name: name.clone(),
+                    value: value.iter().cloned().collect(),
+                })
+            }
+            [Token::Ident(name)] => modifiers.push(Modifier {
+                name: name.clone(),
+                value: Tokens::default(),
+            }),
+            [token, ..] => {
+                return Err(RsxParseError::InvalidModifier {
+                    token: token.clone(),
+                })
+            }
+        }
+    }
+    Ok(modifiers)
 }
 
 #[derive(Clone)]
@@ -31,14 +86,19 @@ pub enum Attribute {
         child: bool,
         parent: Tokens,
         name: Ident,
+        modifiers: Vec<Modifier>,
         value: Tokens,
     },
     Handler {
         name: Ident,
+        modifiers: Vec<Modifier>,
         async_keyword: Option<Token>,
         args: Tokens,
         body: Tokens,
     },
+    /// A `{..value}` attribute, which spreads a set of properties over
+    /// the element.
+    Spread { value: Tokens },
 }
 
 fn stringify_attr_value(token: &Token) -> String {
@@ -58,6 +118,7 @@ impl Debug for Attribute {
                 parent,
                 name,
                 value,
+                ..
             } => {
                 let attrs: Vec<String> = value.iter().map(stringify_attr_value).collect();
                 let mut name = name.to_string();
@@ -73,6 +134,7 @@ impl Debug for Attribute {
                 async_keyword,
                 args,
                 body,
+                ..
             } => {
                 let args: Vec<String> = args.iter().map(stringify_attr_value).collect();
                 let attrs: Vec<String> = body.iter().map(stringify_attr_value).collect();
@@ -90,6 +152,10 @@ impl Debug for Attribute {
                     attrs.join(", ")
                 )
             }
+            Attribute::Spread { value } => {
+                let attrs: Vec<String> = value.iter().map(|token| token.to_string()).collect();
+                write!(f, "( ..{} )", attrs.join(" "))
+            }
         }
     }
 }
@@ -102,6 +168,7 @@ impl PartialEq<(&str, &str)> for Attribute {
                 parent,
                 name,
                 value,
+                ..
             } => {
                 let mut name = name.to_string();
                 if !parent.is_empty() {
@@ -114,6 +181,7 @@ impl PartialEq<(&str, &str)> for Attribute {
             Attribute::Handler { name, .. } => {
                 format!("on {}", name.to_string()) == other.0 // FIXME: only compares handler name
             }
+            Attribute::Spread { .. } => false,
         }
     }
 }
@@ -0,0 +1,166 @@
+use std::fmt::{Display, Formatter};
+
+use lalrpop_util::ParseError::*;
+use proc_macro2::{LexError, Span};
+
+use crate::lexer::{to_stream, Token, Tokens};
+
+/// The parser's own error type, as produced by the generated grammar.
+pub type ParseError = lalrpop_util::ParseError<usize, Token, RsxParseError>;
+
+/// Errors found by the grammar's actions, rather than by the parser itself.
+#[derive(Debug)]
+pub enum RsxParseError {
+    TagMismatch {
+        open: Tokens,
+        close: Tokens,
+    },
+    UnexpectedConstructor {
+        name: Tokens,
+        args: Token,
+    },
+    InvalidModifier {
+        token: Token,
+    },
+    InMatchArms {
+        arms: Tokens,
+        error: Box<ParseError>,
+    },
+    EmptySpread {
+        token: Token,
+    },
+}
+
+/// A syntax error in a `gtk!` block.
+///
+/// `span` is where the error was found, or `None` if it's at the end of the
+/// input. When parsing source text with [`parse()`][crate::parse], spans
+/// carry line and column numbers, which its `Display` output starts with.
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl Error {
+    fn new(span: Option<Span>, message: impl Into<String>) -> Self {
+        Error {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => {
+                let start = span.start();
+                write!(f, "{}:{}: {}", start.line, start.column + 1, self.message)
+            }
+            None => self.message.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<LexError> for Error {
+    fn from(error: LexError) -> Self {
+        Error::new(Some(error.span()), error.to_string())
+    }
+}
+
+fn pprint_token(token: &str) -> &str {
+    match token {
+        "BraceGroupToken" => "code block",
+        "ParenGroupToken" => "parenthesised block",
+        "BracketGroupToken" => "array",
+        "LiteralToken" => "literal",
+        "IdentToken" => "identifier",
+        a => a,
+    }
+}
+
+fn pprint_tokens(tokens: &[String]) -> String {
+    let tokens: Vec<&str> = tokens.iter().map(|s| pprint_token(&s)).collect();
+    if tokens.len() > 1 {
+        let start = tokens[..tokens.len() - 1].join(", ");
+        let end = &tokens[tokens.len() - 1];
+        format!("{} or {}", start, end)
+    } else {
+        tokens[0].to_string()
+    }
+}
+
+/// Describe a parse error as a list of messages, the most relevant first.
+pub fn describe(input: &[Token], error: &ParseError) -> Vec<Error> {
+    match error {
+        InvalidToken { location } => {
+            vec![Error::new(Some(input[*location].span()), "invalid token")]
+        }
+        UnrecognizedEOF { expected, .. } => vec![Error::new(
+            None,
+            format!(
+                "unexpected end of gtk! macro; missing {}",
+                pprint_tokens(&expected)
+            ),
+        )],
+        UnrecognizedToken {
+            token: (_, token, _),
+            expected,
+        } => vec![Error::new(
+            Some(token.span()),
+            format!("expected {}", pprint_tokens(&expected)),
+        )],
+        ExtraToken {
+            token: (_, token, _),
+        } => vec![Error::new(Some(token.span()), "superfluous token")],
+        User {
+            error: RsxParseError::TagMismatch { open, close },
+        } => vec![
+            Error::new(
+                Some(close[0].span()),
+                format!(
+                    "expected closing tag `</{}>`, found `</{}>`",
+                    to_stream(open),
+                    to_stream(close)
+                ),
+            ),
+            Error::new(Some(open[0].span()), "unclosed tag"),
+        ],
+        User {
+            error: RsxParseError::UnexpectedConstructor { name, args },
+        } => vec![Error::new(
+            Some(args.span()),
+            format!(
+                "type is not a function - did you mean to call `{}::new()`?",
+                name
+            ),
+        )],
+        User {
+            error: RsxParseError::InvalidModifier { token },
+        } => vec![Error::new(
+            Some(token.span()),
+            "expected an attribute modifier of the form `name=value` or `name`",
+        )],
+        User {
+            error: RsxParseError::EmptySpread { token },
+        } => vec![Error::new(
+            Some(token.span()),
+            "expected a value to spread after `..`",
+        )],
+        User {
+            error: RsxParseError::InMatchArms { arms, error },
+        } => match (&**error, arms.last()) {
+            (UnrecognizedEOF { expected, .. }, Some(last)) => vec![Error::new(
+                Some(last.span()),
+                format!(
+                    "unexpected end of match arms; missing {}",
+                    pprint_tokens(&expected)
+                ),
+            )],
+            (error, _) => describe(arms, error),
+        },
+    }
+}
@@ -0,0 +1,411 @@
+//! The canonical formatting of `gtk!` blocks.
+//!
+//! Elements go one to a line, with their children indented by four spaces
+//! and a closing tag lined up with the opening one. An opening tag which
+//! would go past 100 columns gets its attributes one to a line instead.
+//! Rust code in attribute values, code blocks and `let` bindings is spaced
+//! roughly the way rustfmt would, but is otherwise left as it was written,
+//! except for `gtk!` blocks nested inside it, which are formatted too.
+
+use proc_macro2::Delimiter;
+
+use crate::context::{Attribute, GtkElement, GtkMatch, GtkWidget, Modifier};
+use crate::lexer::{Token, Tokens};
+
+const INDENT: &str = "    ";
+const WIDTH: usize = 100;
+
+/// Format a syntax tree in the canonical style, without a trailing newline.
+pub fn format(ast: &GtkElement) -> String {
+    let mut out = String::new();
+    element(&mut out, ast, 0);
+    out
+}
+
+fn indent(out: &mut String, level: usize) {
+    for _ in 0..level {
+        out.push_str(INDENT);
+    }
+}
+
+fn element(out: &mut String, element: &GtkElement, level: usize) {
+    match element {
+        GtkElement::Widget(widget) => self::widget(out, widget, level),
+        GtkElement::Component(component) => {
+            let name = format!("<@{}", code(&component.name, level));
+            tag(out, &name, &component.attributes, " />", level);
+        }
+        GtkElement::Block(group) => {
+            let body: Tokens = group.stream().into();
+            out.push_str(&braces(&body, level));
+        }
+        GtkElement::Let(tokens) => out.push_str(&code(tokens, level)),
+        GtkElement::Match(GtkMatch { scrutinee, arms }) => {
+            out.push_str(&format!("match {} {{", code(scrutinee, level)));
+            for arm in arms {
+                out.push('\n');
+                indent(out, level + 1);
+                out.push_str(&format!("{} => ", code(&arm.pattern, level + 1)));
+                self::element(out, &arm.body, level + 1);
+                out.push(',');
+            }
+            out.push('\n');
+            indent(out, level);
+            out.push('}');
+        }
+    }
+}
+
+fn widget(out: &mut String, widget: &GtkWidget, level: usize) {
+    let name = format!(
+        "<{}{}",
+        code(&widget.name, level),
+        code(&widget.constructor, level)
+    );
+    if widget.children.is_empty() {
+        tag(out, &name, &widget.attributes, " />", level);
+        return;
+    }
+    tag(out, &name, &widget.attributes, ">", level);
+    for child in &widget.children {
+        out.push('\n');
+        indent(out, level + 1);
+        element(out, child, level + 1);
+    }
+    out.push('\n');
+    indent(out, level);
+    out.push_str(&format!("</{}>", code(&widget.name, level)));
+}
+
+/// Write an opening tag, on one line if it fits, or else with an attribute
+/// on each line.
+fn tag(out: &mut String, name: &str, attributes: &[Attribute], close: &str, level: usize) {
+    let attributes: Vec<String> = attributes
+        .iter()
+        .map(|attribute| self::attribute(attribute, level + 1))
+        .collect();
+    let line = std::iter::once(name.to_string())
+        .chain(attributes.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let column = out.len() - out.rfind('\n').map_or(0, |newline| newline + 1);
+    let fits = column + line.len() + close.len() <= WIDTH;
+    if attributes.is_empty() || (fits && !line.contains('\n')) {
+        out.push_str(&line);
+        out.push_str(close);
+        return;
+    }
+    out.push_str(name);
+    for attribute in attributes {
+        out.push('\n');
+        indent(out, level + 1);
+        out.push_str(&attribute);
+    }
+    out.push('\n');
+    indent(out, level);
+    out.push_str(close.trim_start());
+}
+
+fn attribute(attribute: &Attribute, level: usize) -> String {
+    match attribute {
+        Attribute::Property {
+            child,
+            parent,
+            name,
+            modifiers,
+            value,
+        } => {
+            let qualifier = if !child && !parent.is_empty() {
+                "@"
+            } else {
+                ""
+            };
+            format!(
+                "{}{}{}{}={}",
+                qualifier,
+                code(parent, level),
+                name,
+                self::modifiers(modifiers, level),
+                code(value, level)
+            )
+        }
+        Attribute::Handler {
+            name,
+            modifiers,
+            async_keyword,
+            args,
+            body,
+        } => format!(
+            "on {}{}={}{} {}",
+            name,
+            self::modifiers(modifiers, level),
+            if async_keyword.is_some() {
+                "async "
+            } else {
+                ""
+            },
+            closure_args(args, level),
+            code(body, level)
+        ),
+        Attribute::Spread { value } => format!("{{..{}}}", code(value, level)),
+    }
+}
+
+fn modifiers(modifiers: &[Modifier], level: usize) -> String {
+    if modifiers.is_empty() {
+        return String::new();
+    }
+    let modifiers: Vec<String> = modifiers
+        .iter()
+        .map(|modifier| {
+            if modifier.value.is_empty() {
+                modifier.name.to_string()
+            } else {
+                format!("{}={}", modifier.name, code(&modifier.value, level))
+            }
+        })
+        .collect();
+    format!("({})", modifiers.join(", "))
+}
+
+/// Format a closure's argument list, which comes with its bars.
+fn closure_args(args: &[Token], level: usize) -> String {
+    match args {
+        [_, inner @ .., _] => format!("|{}|", code(inner, level)),
+        _ => code(args, level),
+    }
+}
+
+fn is_punct(token: &Token, chars: &str) -> bool {
+    match token {
+        Token::Punct1(c, _) => chars.len() == 1 && chars.starts_with(*c),
+        Token::Punct2(a, b, _, _) => {
+            chars.len() == 2 && chars.starts_with(*a) && chars.ends_with(*b)
+        }
+        Token::Punct3(a, b, c, _, _, _) => {
+            let text: String = [*a, *b, *c].iter().collect();
+            text == chars
+        }
+        _ => false,
+    }
+}
+
+fn is_keyword(token: &Token) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "else", "for", "if", "in", "let", "loop", "match",
+        "move", "mut", "ref", "return", "while",
+    ];
+    match token {
+        Token::Ident(ident) => KEYWORDS.contains(&ident.to_string().as_str()),
+        Token::Keyword(..) => true,
+        _ => false,
+    }
+}
+
+/// Whether a token can end an operand, so that a `&`, `*`, `-` or `|` after
+/// it is a binary operator rather than a prefix.
+fn ends_operand(token: &Token) -> bool {
+    match token {
+        Token::Ident(_) | Token::Literal(_) | Token::Group(..) => !is_keyword(token),
+        Token::Punct1(c, _) => *c == '?',
+        _ => false,
+    }
+}
+
+/// Whether a `<` after `prev` opens a list of type arguments.
+fn opens_generics(prev: Option<&Token>) -> bool {
+    match prev {
+        Some(token @ Token::Punct2(..)) => is_punct(token, "::"),
+        Some(Token::Ident(ident)) => ident.to_string().starts_with(char::is_uppercase),
+        _ => false,
+    }
+}
+
+/// Format a run of Rust tokens, spacing them out the way rustfmt would in
+/// the common cases. `level` is the indentation level the code starts at,
+/// which nested `gtk!` blocks and multi-line code blocks are indented from.
+fn code(tokens: &[Token], level: usize) -> String {
+    statements(tokens, level).0
+}
+
+/// Format the contents of braces, with the braces, on one line if they fit
+/// on one, or else indented on the lines between them, with each statement,
+/// match arm or field on a line of its own. `level` is the indentation level
+/// of the line the braces open on.
+fn braces(tokens: &[Token], level: usize) -> String {
+    let (inner, breaks) = statements(tokens, level + 1);
+    if inner.is_empty() {
+        return "{}".to_string();
+    }
+    if !inner.contains('\n') {
+        return format!("{{ {} }}", inner);
+    }
+    let mut out = String::from("{\n");
+    indent(&mut out, level + 1);
+    let mut start = 0;
+    for end in breaks {
+        out.push_str(inner[start..end].trim_start());
+        out.push('\n');
+        indent(&mut out, level + 1);
+        start = end;
+    }
+    out.push_str(inner[start..].trim_start());
+    out.push('\n');
+    indent(&mut out, level);
+    out.push('}');
+    out
+}
+
+/// Format a run of Rust tokens like [`code()`][code], also giving the places
+/// in the output after each `,` or `;` which separates one statement, match
+/// arm or field from the next, where the code could go on to a new line.
+fn statements(tokens: &[Token], level: usize) -> (String, Vec<usize>) {
+    let mut out = String::new();
+    let mut breaks = Vec::new();
+    // How many type argument lists we're inside.
+    let mut generics = 0usize;
+    // Whether we're inside a closure's argument list.
+    let mut closure_args = false;
+    // Whether the next token starts an operand, which makes `&`, `*`, `-`,
+    // `!` and `|` prefixes rather than binary operators.
+    let mut operand = true;
+    // Whether the previous token was a prefix, which sticks to what follows.
+    let mut after_prefix = false;
+    let mut prev: Option<&Token> = None;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        let mut text = match token {
+            Token::Group(delimiter, group) => {
+                let inner: Tokens = group.stream().into();
+                match delimiter {
+                    Delimiter::Brace => braces(&inner, level),
+                    _ => self::group(*delimiter, &code(&inner, level)),
+                }
+            }
+            token => token.to_string(),
+        };
+        if let Some(block) = nested_gtk(&tokens[index..], level) {
+            text = block;
+            index += 2;
+        }
+        let prefix = operand && ["&", "*", "-", "!"].iter().any(|p| is_punct(token, p));
+        let opens_closure = operand && !closure_args && is_punct(token, "|");
+        let closes_closure = closure_args && is_punct(token, "|");
+        let opens_generics = is_punct(token, "<") && opens_generics(prev);
+        if let Some(prev) = prev {
+            let tight_before = [",", ";", ".", "?", ":", "::", "..", "..="]
+                .iter()
+                .any(|p| is_punct(token, p))
+                || matches!(
+                    token,
+                    Token::Group(Delimiter::Parenthesis, _) | Token::Group(Delimiter::Bracket, _)
+                ) && (ends_operand(prev) || is_punct(prev, "!"))
+                || is_punct(token, "!") && matches!(prev, Token::Ident(_))
+                || opens_generics
+                || generics > 0 && (is_punct(token, ">") || is_punct(token, ">>"))
+                || closes_closure;
+            let tight_after = [".", "::", "'", "..", "..="]
+                .iter()
+                .any(|p| is_punct(prev, p))
+                || generics > 0 && is_punct(prev, "<")
+                || after_prefix;
+            if !tight_before && !tight_after {
+                out.push(' ');
+            }
+        }
+        out.push_str(&text);
+        let separator = is_punct(token, ",") || is_punct(token, ";");
+        if separator && generics == 0 && !closure_args && index + 1 < tokens.len() {
+            breaks.push(out.len());
+        }
+        if opens_generics {
+            generics += 1;
+        } else if is_punct(token, ">") {
+            generics = generics.saturating_sub(1);
+        } else if is_punct(token, ">>") {
+            generics = generics.saturating_sub(2);
+        }
+        if opens_closure || closes_closure {
+            closure_args = opens_closure;
+        }
+        after_prefix = prefix || opens_closure;
+        operand = prefix
+            || opens_closure
+            || closes_closure
+            || operand && is_punct(token, "||")
+            || !ends_operand(token);
+        prev = Some(token);
+        index += 1;
+    }
+    (out, breaks)
+}
+
+/// Format a `gtk!` block at the start of `tokens`, if there is one and it
+/// parses.
+fn nested_gtk(tokens: &[Token], level: usize) -> Option<String> {
+    match tokens {
+        [Token::Ident(ident), bang, Token::Group(Delimiter::Brace, group), ..]
+            if ident == "gtk" && is_punct(bang, "!") =>
+        {
+            let ast = crate::parse_tokens(&group.stream().into()).ok()?;
+            let mut out = String::from("gtk! {\n");
+            indent(&mut out, level + 1);
+            element(&mut out, &ast, level + 1);
+            out.push('\n');
+            indent(&mut out, level);
+            out.push('}');
+            Some(out)
+        }
+        _ => None,
+    }
+}
+
+fn group(delimiter: Delimiter, inner: &str) -> String {
+    match delimiter {
+        Delimiter::Parenthesis => format!("({})", inner),
+        Delimiter::Bracket => format!("[{}]", inner),
+        Delimiter::Brace => format!("{{ {} }}", inner),
+        Delimiter::None => inner.to_string(),
+    }
+}
+
+/// Whether some source code has a comment in it, outside of string and
+/// character literals.
+pub(crate) fn has_comment(source: &str) -> bool {
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if matches!(chars.peek(), Some('/') | Some('*')) => return true,
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            // A character literal, as opposed to a lifetime.
+            '\'' => {
+                let mut lookahead = chars.clone();
+                match (lookahead.next(), lookahead.next()) {
+                    (Some('\\'), _) => {
+                        chars.next();
+                        chars.next();
+                        chars.find(|&c| c == '\'');
+                    }
+                    (Some(_), Some('\'')) => {
+                        chars.next();
+                        chars.next();
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
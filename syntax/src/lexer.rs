@@ -11,6 +11,8 @@ pub type Spanned<Tok, Loc, Error> = Result<(Loc, Tok, Loc), Error>;
 #[derive(Debug, Clone)]
 pub enum Keyword {
     Async,
+    Let,
+    Match,
     On,
 }
 
@@ -18,6 +20,8 @@ fn keywordise(token: Token) -> Token {
     match token {
         Token::Ident(ident) => match ident.to_string().as_str() {
             "async" => Token::Keyword(Keyword::Async, ident),
+            "let" => Token::Keyword(Keyword::Let, ident),
+            "match" => Token::Keyword(Keyword::Match, ident),
             "on" => Token::Keyword(Keyword::On, ident),
             _ => Token::Ident(ident),
         },
@@ -25,6 +29,46 @@ fn keywordise(token: Token) -> Token {
     }
 }
 
+// The operators made of more than one punctuation character which the
+// grammar, or Rust, has a use for. Other punctuation is left as single
+// characters, even when it's written without spaces in between, like the
+// `=` and `|` in `on clicked=|_| ...`.
+const PUNCT2: &[&str] = &[
+    "==", "!=", "<=", ">=", "<<", ">>", "&&", "||", "->", "=>", "::", "</", "/>", "<@", "..", "+=",
+    "-=", "*=", "/=", "|=", "&=", "^=", "%=",
+];
+const PUNCT3: &[&str] = &["..=", "...", "<<=", ">>="];
+
+fn joins(spacing: Spacing, chars: &[char], operators: &[&str]) -> bool {
+    let operator: String = chars.iter().collect();
+    spacing == Spacing::Joint && operators.contains(&operator.as_str())
+}
+
+// A code block which starts with `..`, like `{..props}`, is an attribute
+// spread rather than a code block.
+fn is_spread(group: &Group) -> bool {
+    if group.delimiter() != Delimiter::Brace {
+        return false;
+    }
+    let mut tokens = group.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second)), third) => {
+            // Not `..=` or `...`, though.
+            let range = match third {
+                Some(TokenTree::Punct(third)) => {
+                    second.spacing() == Spacing::Joint && "=.".contains(third.as_char())
+                }
+                _ => false,
+            };
+            first.as_char() == '.'
+                && first.spacing() == Spacing::Joint
+                && second.as_char() == '.'
+                && !range
+        }
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Token {
     Ident(Ident),
@@ -34,6 +78,7 @@ pub enum Token {
     Punct3(char, char, char, Punct, Punct, Punct),
     Group(Delimiter, Group),
     Keyword(Keyword, Ident),
+    Spread(Group),
 }
 
 impl Token {
@@ -47,6 +92,7 @@ impl Token {
             Token::Punct3(_, _, _, punct, _, _) => punct.span(),
             Token::Group(_, group) => group.span(),
             Token::Keyword(_, ident) => ident.span(),
+            Token::Spread(group) => group.span(),
         }
     }
 
@@ -75,6 +121,7 @@ impl Display for Token {
             }
             Token::Group(_, group) => group.fmt(f),
             Token::Keyword(_, ident) => ident.fmt(f),
+            Token::Spread(group) => group.fmt(f),
         }
     }
 }
@@ -329,7 +376,8 @@ impl From<TokenStream> for Tokens {
                     if let Some(prev) = vec.pop() {
                         let next = match prev {
                             Token::Punct1(prev_chr, prev_punct) => {
-                                if prev_punct.spacing() == Spacing::Joint {
+                                if joins(prev_punct.spacing(), &[prev_chr, punct.as_char()], PUNCT2)
+                                {
                                     Token::Punct2(prev_chr, punct.as_char(), prev_punct, punct)
                                 } else {
                                     vec.push(Token::Punct1(prev_chr, prev_punct));
@@ -337,7 +385,11 @@ impl From<TokenStream> for Tokens {
                                 }
                             }
                             Token::Punct2(prev_chr_1, prev_chr_2, prev_p1, prev_p2) => {
-                                if prev_p2.spacing() == Spacing::Joint {
+                                if joins(
+                                    prev_p2.spacing(),
+                                    &[prev_chr_1, prev_chr_2, punct.as_char()],
+                                    PUNCT3,
+                                ) {
                                     Token::Punct3(
                                         prev_chr_1,
                                         prev_chr_2,
@@ -363,6 +415,7 @@ impl From<TokenStream> for Tokens {
                         vec.push(punct.into());
                     }
                 }
+                TokenTree::Group(group) if is_spread(&group) => vec.push(Token::Spread(group)),
                 TokenTree::Group(group) => vec.push(group.into()),
             }
         }
@@ -370,19 +423,6 @@ impl From<TokenStream> for Tokens {
     }
 }
 
-impl From<proc_macro::TokenStream> for Tokens {
-    fn from(stream: proc_macro::TokenStream) -> Self {
-        let stream: TokenStream = stream.into();
-        stream.into()
-    }
-}
-
-impl From<Tokens> for proc_macro::TokenStream {
-    fn from(tokens: Tokens) -> Self {
-        tokens.to_stream().into()
-    }
-}
-
 pub fn to_stream<'a, I: IntoIterator<Item = &'a Token>>(tokens: I) -> TokenStream {
     let mut stream = TokenStream::new();
     for token in tokens {
@@ -401,6 +441,7 @@ pub fn to_stream<'a, I: IntoIterator<Item = &'a Token>>(tokens: I) -> TokenStrea
             ]),
             Token::Group(_, group) => stream.extend(vec![TokenTree::Group(group.clone())]),
             Token::Keyword(_, ident) => stream.extend(vec![TokenTree::Ident(ident.clone())]),
+            Token::Spread(group) => stream.extend(vec![TokenTree::Group(group.clone())]),
         }
     }
     stream
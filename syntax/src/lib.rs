@@ -0,0 +1,73 @@
+//! The syntax of vgtk's `gtk!` macro, as a library.
+//!
+//! This is the parser the macro itself uses, for tools which need to read
+//! `gtk!` blocks: formatters, linters, editor plugins and the like. Parse the
+//! contents of a block, meaning what goes between the macro's braces, with
+//! [`parse()`][parse], and print it back out in the canonical style with
+//! [`format()`][format], or do both at once with
+//! [`format_str()`][format_str].
+//!
+//! ```rust
+//! let source = r#"<Box orientation=Orientation::Vertical><Label label="Hello" /></Box>"#;
+//! assert_eq!(
+//!     vgtk_syntax::format_str(source).unwrap(),
+//!     "<Box orientation=Orientation::Vertical>\n    <Label label=\"Hello\" />\n</Box>"
+//! );
+//! ```
+//!
+//! The syntax tree is made of the same tokens the macro sees, so Rust
+//! expressions in attribute values and code blocks come out as token lists
+//! rather than as parsed expressions. Parsing source text gives every token
+//! a span with its line and column.
+
+#![deny(rust_2018_idioms, unsafe_code)]
+
+pub mod context;
+pub mod error;
+mod format;
+pub mod lexer;
+mod parser;
+
+use proc_macro2::TokenStream;
+
+pub use crate::context::{
+    Attribute, GtkComponent, GtkElement, GtkMatch, GtkWidget, MatchArm, Modifier,
+};
+pub use crate::error::Error;
+use crate::error::ParseError;
+pub use crate::format::format;
+use crate::lexer::Tokens;
+
+/// The syntax tree of a `gtk!` block, which is the single element at its
+/// root.
+pub type Ast = GtkElement;
+
+/// Parse the contents of a `gtk!` block.
+pub fn parse(source: &str) -> Result<Ast, Error> {
+    let stream: TokenStream = source.parse()?;
+    let tokens: Tokens = stream.into();
+    parse_tokens(&tokens).map_err(|error| error::describe(&tokens, &error).remove(0))
+}
+
+/// Parse the contents of a `gtk!` block which have already been lexed.
+///
+/// This is what the macro uses, as it needs the parser's own error to report
+/// everything wrong with the block, rather than just the first problem.
+pub fn parse_tokens(tokens: &Tokens) -> Result<Ast, ParseError> {
+    parser::grammar::GtkElementParser::new().parse(tokens.lexer())
+}
+
+/// Parse the contents of a `gtk!` block and format them in the canonical
+/// style.
+///
+/// Comments aren't part of the syntax tree, so rather than lose them, this
+/// refuses to format a block which has any.
+pub fn format_str(source: &str) -> Result<String, Error> {
+    if format::has_comment(source) {
+        return Err(Error {
+            message: "can't format a gtk! block containing comments".to_string(),
+            span: None,
+        });
+    }
+    parse(source).map(|ast| format(&ast))
+}
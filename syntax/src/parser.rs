@@ -1,5 +1,5 @@
 // We REALLY don't want to lint the generated parser code.
-#![allow(clippy::all)]
+#![allow(clippy::all, rust_2018_idioms)]
 
 use lalrpop_util::lalrpop_mod;
 
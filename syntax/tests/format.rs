@@ -0,0 +1,117 @@
+use vgtk_syntax::format_str;
+
+/// Format some source, and check that formatting the result again leaves it
+/// as it is.
+fn format(source: &str) -> String {
+    let formatted = format_str(source).unwrap();
+    assert_eq!(format_str(&formatted).unwrap(), formatted);
+    formatted
+}
+
+#[test]
+fn elements_go_one_to_a_line() {
+    assert_eq!(
+        format("<Box spacing=10><Label label=\"Hello\"/><Button label=\"Go\" on clicked=|_| Message::Go/></Box>"),
+        "<Box spacing=10>
+    <Label label=\"Hello\" />
+    <Button label=\"Go\" on clicked=|_| Message::Go />
+</Box>"
+    );
+}
+
+#[test]
+fn long_tags_get_an_attribute_to_a_line() {
+    assert_eq!(
+        format("<Entry placeholder_text=\"Type something long in here\" text=self.text.clone() hexpand=true on changed=|entry| Message::Changed(entry.get_text().to_string()) />"),
+        "<Entry
+    placeholder_text=\"Type something long in here\"
+    text=self.text.clone()
+    hexpand=true
+    on changed=|entry| Message::Changed(entry.get_text().to_string())
+/>"
+    );
+}
+
+#[test]
+fn short_code_blocks_stay_on_one_line() {
+    assert_eq!(
+        format("<Box>{self.items.iter().map(render)}</Box>"),
+        "<Box>
+    { self.items.iter().map(render) }
+</Box>"
+    );
+}
+
+#[test]
+fn nested_blocks_are_indented_inside_the_code_around_them() {
+    assert_eq!(
+        format("<Box>{ self.items.iter().map(|item| gtk!{ <Label label=item /> }) }</Box>"),
+        "<Box>
+    {
+        self.items.iter().map(|item| gtk! {
+            <Label label=item />
+        })
+    }
+</Box>"
+    );
+}
+
+#[test]
+fn nested_blocks_in_match_arms_get_a_line_each() {
+    assert_eq!(
+        format("<Box>{ match p { A => gtk!{ <Label /> }, B => gtk!{ <Button /> } } }</Box>"),
+        "<Box>
+    {
+        match p {
+            A => gtk! {
+                <Label />
+            },
+            B => gtk! {
+                <Button />
+            }
+        }
+    }
+</Box>"
+    );
+}
+
+#[test]
+fn nested_blocks_in_if_and_else_are_indented_inside_them() {
+    assert_eq!(
+        format("<Box>{ if x { gtk!{ <Label /> } } else { gtk!{ <Button /> } } }</Box>"),
+        "<Box>
+    {
+        if x {
+            gtk! {
+                <Label />
+            }
+        } else {
+            gtk! {
+                <Button />
+            }
+        }
+    }
+</Box>"
+    );
+}
+
+#[test]
+fn statements_get_a_line_each_but_type_and_closure_arguments_dont() {
+    assert_eq!(
+        format("<Box>{ let v: HashMap<K, V> = m; let f = |a, b| a + b; gtk!{ <Label label=v /> } }</Box>"),
+        "<Box>
+    {
+        let v: HashMap<K, V> = m;
+        let f = |a, b| a + b;
+        gtk! {
+            <Label label=v />
+        }
+    }
+</Box>"
+    );
+}
+
+#[test]
+fn source_with_comments_is_left_alone() {
+    assert!(format_str("<Box>{ x /* why */ }</Box>").is_err());
+}
@@ -0,0 +1,56 @@
+//! Build and patch benchmarks for the virtual DOM.
+//!
+//! Run them all with `cargo bench`, or just the ones whose names contain
+//! some text with eg. `cargo bench -- reorder`. Set `VGTK_BENCH_SECONDS`
+//! to change how long each one runs for.
+//!
+//! Set `VGTK_BENCH_SAVE` to a file name to save the results there, and
+//! `VGTK_BENCH_BASELINE` to the name of a file saved earlier to compare the
+//! results with it, failing if any scenario has got slower by more than
+//! `VGTK_BENCH_TOLERANCE` percent, 10 by default.
+
+use std::time::Duration;
+
+use vgtk::bench;
+
+fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+}
+
+fn main() {
+    let seconds = env("VGTK_BENCH_SECONDS").unwrap_or(1.0);
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    let mut measurements = Vec::new();
+    for scenario in bench::standard() {
+        let name = scenario.name();
+        if filters.is_empty() || filters.iter().any(|filter| name.contains(filter)) {
+            let measurement = bench::run(scenario, Duration::from_secs_f64(seconds));
+            println!("{}", measurement);
+            measurements.push(measurement);
+        }
+    }
+
+    if let Some(path) = env::<String>("VGTK_BENCH_SAVE") {
+        bench::save(&path, &measurements).expect("unable to save the results");
+        println!("Saved the results to {}", path);
+    }
+    if let Some(path) = env::<String>("VGTK_BENCH_BASELINE") {
+        let baseline = bench::load(&path).expect("unable to load the baseline");
+        let tolerance = env("VGTK_BENCH_TOLERANCE").unwrap_or(10.0) / 100.0;
+        let regressions = bench::compare(&baseline, &measurements, tolerance);
+        if regressions.is_empty() {
+            println!("No regressions from {}", path);
+        } else {
+            println!("Regressions from {}:", path);
+            for regression in &regressions {
+                println!("{}", regression);
+            }
+            std::process::exit(1);
+        }
+    }
+}
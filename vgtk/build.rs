@@ -0,0 +1,11 @@
+fn main() {
+    // Like gtk-rs, set `gdk_backend="x11"` and the like for the backends GDK
+    // was built with, which decides whether `gtk::Socket` and `gtk::Plug`
+    // exist, and with them `vgtk::isolate`.
+    println!("cargo:rustc-check-cfg=cfg(gdk_backend, values(any()))");
+    if let Ok(targets) = pkg_config::get_variable("gtk+-3.0", "targets") {
+        for target in targets.split_whitespace() {
+            println!("cargo:rustc-cfg=gdk_backend=\"{}\"", target);
+        }
+    }
+}
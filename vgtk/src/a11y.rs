@@ -0,0 +1,74 @@
+//! Screen reader announcements.
+//!
+//! Changes which happen away from the keyboard focus, like a search finishing
+//! or a download failing, go unnoticed by screen reader users unless they're
+//! announced. Use [`announce()`][announce] to announce a message right away, or
+//! give a widget an `announce` attribute to have its value announced every time
+//! it changes, much like an ARIA live region:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::ext::WidgetExtHelpers;
+//! # use vgtk::lib::gtk::{Label, LabelExt};
+//! # fn view(status: &str) -> VNode<()> {
+//! gtk! {
+//!     <Label label=status announce=status />
+//! }
+//! # }
+//! ```
+//!
+//! Announcements are made using ATK's `notification` signal where available,
+//! which requires ATK 2.46, and its `announcement` signal otherwise.
+//!
+//! [announce]: fn.announce.html
+
+use glib::{IsA, ObjectExt};
+use gtk::{Widget, WidgetExt};
+
+use crate::component::current_window;
+
+/// How urgently an announcement should be made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Politeness {
+    /// Wait until the screen reader has finished what it's saying.
+    Polite,
+    /// Interrupt whatever the screen reader is saying.
+    Assertive,
+}
+
+impl Politeness {
+    // The values of `AtkLive`.
+    fn to_atk(self) -> i32 {
+        match self {
+            Politeness::Polite => 1,
+            Politeness::Assertive => 2,
+        }
+    }
+}
+
+/// Announce a message to screen reader users, on behalf of the current
+/// window.
+///
+/// Returns `false` if there's no current window, or if the accessibility
+/// toolkit doesn't support announcements.
+pub fn announce(message: &str, politeness: Politeness) -> bool {
+    match current_window() {
+        Some(window) => announce_from(&window, message, politeness),
+        None => false,
+    }
+}
+
+/// Announce a message to screen reader users, on behalf of a widget.
+///
+/// Returns `false` if the accessibility toolkit doesn't support
+/// announcements.
+pub fn announce_from<W: IsA<Widget>>(widget: &W, message: &str, politeness: Politeness) -> bool {
+    let accessible = match widget.as_ref().get_accessible() {
+        Some(accessible) => accessible,
+        None => return false,
+    };
+    accessible
+        .emit("notification", &[&message, &politeness.to_atk()])
+        .or_else(|_| accessible.emit("announcement", &[&message]))
+        .is_ok()
+}
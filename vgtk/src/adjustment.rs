@@ -0,0 +1,128 @@
+// A `gtk::Adjustment` which `gtk!` compares by its parameters; see
+// `types::Adjustment`.
+
+use glib::subclass;
+use glib::subclass::prelude::*;
+use glib::translate::*;
+use glib::{glib_object_impl, glib_object_subclass, glib_wrapper, Cast, IsA, StaticType};
+use gtk::AdjustmentExt;
+
+glib_wrapper! {
+    /// A [`gtk::Adjustment`][Adjustment] for use as a property value, which is
+    /// compared by its parameters rather than by identity.
+    ///
+    /// A new `gtk::Adjustment` in every view would replace the widget's
+    /// adjustment every time it renders, and with it whatever the user was in
+    /// the middle of doing with it, like dragging a slider. Use this in its
+    /// place, and the widget keeps its adjustment, which is just reconfigured
+    /// whenever the parameters in the view change:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{gtk, VNode};
+    /// # use vgtk::lib::gtk::*;
+    /// use vgtk::types::Adjustment;
+    /// # fn view(volume: f64) -> VNode<()> { gtk! {
+    /// <Scale adjustment=Adjustment::new(volume, 0.0, 100.0, 1.0, 10.0, 0.0) hexpand=true />
+    /// # }}
+    /// ```
+    ///
+    /// The value is a parameter like any other, so if the user can change it,
+    /// keep track of it with a `value_changed` handler, or the next render will
+    /// put it back.
+    ///
+    /// [Adjustment]: ../../gtk/struct.Adjustment.html
+    pub struct Adjustment(
+        Object<subclass::simple::InstanceStruct<imp::Adjustment>,
+        subclass::simple::ClassStruct<imp::Adjustment>,
+        AdjustmentClass>)
+        @extends gtk::Adjustment;
+
+    match fn {
+        get_type => || imp::Adjustment::get_type().to_glib(),
+    }
+}
+
+impl Adjustment {
+    /// Describe an adjustment, with the same parameters as
+    /// [`gtk::Adjustment::new()`][new].
+    ///
+    /// [new]: ../../gtk/struct.Adjustment.html#method.new
+    pub fn new(
+        value: f64,
+        lower: f64,
+        upper: f64,
+        step_increment: f64,
+        page_increment: f64,
+        page_size: f64,
+    ) -> Self {
+        let adjustment: Self = glib::Object::new(Self::static_type(), &[])
+            .expect("unable to create Adjustment")
+            .downcast()
+            .unwrap();
+        adjustment.configure(
+            value,
+            lower,
+            upper,
+            step_increment,
+            page_increment,
+            page_size,
+        );
+        adjustment
+    }
+
+    /// Test whether an existing adjustment has the same parameters as this
+    /// one.
+    pub fn matches<A: IsA<gtk::Adjustment>>(&self, adjustment: &A) -> bool {
+        let adjustment = adjustment.as_ref();
+        adjustment.get_value() == self.get_value()
+            && adjustment.get_lower() == self.get_lower()
+            && adjustment.get_upper() == self.get_upper()
+            && adjustment.get_step_increment() == self.get_step_increment()
+            && adjustment.get_page_increment() == self.get_page_increment()
+            && adjustment.get_page_size() == self.get_page_size()
+    }
+
+    /// Give an existing adjustment the same parameters as this one.
+    pub fn apply_to<A: IsA<gtk::Adjustment>>(&self, adjustment: &A) {
+        adjustment.as_ref().configure(
+            self.get_value(),
+            self.get_lower(),
+            self.get_upper(),
+            self.get_step_increment(),
+            self.get_page_increment(),
+            self.get_page_size(),
+        );
+    }
+}
+
+mod imp {
+    use super::*;
+
+    pub struct Adjustment;
+
+    impl ObjectSubclass for Adjustment {
+        const NAME: &'static str = "VgtkAdjustment";
+        type ParentType = gtk::Adjustment;
+        type Instance = subclass::simple::InstanceStruct<Self>;
+        type Class = subclass::simple::ClassStruct<Self>;
+
+        glib_object_subclass!();
+
+        fn new() -> Self {
+            Adjustment
+        }
+    }
+
+    impl ObjectImpl for Adjustment {
+        glib_object_impl!();
+    }
+
+    // GTK has no subclassing support for adjustments, as they have no virtual
+    // methods worth overriding, so provide the bare minimum ourselves.
+    #[allow(unsafe_code)]
+    unsafe impl IsSubclassable<Adjustment> for gtk::AdjustmentClass {
+        fn override_vfuncs(&mut self) {
+            <glib::ObjectClass as IsSubclassable<Adjustment>>::override_vfuncs(self);
+        }
+    }
+}
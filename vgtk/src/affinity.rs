@@ -0,0 +1,99 @@
+//! Checks against using GTK objects from the wrong thread.
+//!
+//! GTK isn't thread safe: its objects must only ever be used from the thread
+//! running the main loop. Breaking this rule tends to cause crashes which are
+//! very hard to trace back to their cause, so in debug builds `vgtk` remembers
+//! which thread it was started on and which component built each object, and
+//! its own functions panic with a message saying so if they're called from
+//! another thread. You can add the same check to your own code using
+//! [`assert_owner()`][assert_owner].
+//!
+//! To do work on another thread, send a message to the component's
+//! [`Scope`][Scope] when it's done instead of touching its widgets.
+//!
+//! In release builds, none of this is checked.
+//!
+//! [assert_owner]: fn.assert_owner.html
+//! [Scope]: ../struct.Scope.html
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use glib::{IsA, Object, ObjectExt};
+
+const COMPONENT_KEY: &str = "vgtk-component";
+
+static CLAIMED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static IS_OWNER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Mark the current thread as the one `vgtk` runs on.
+pub(crate) fn claim() {
+    if cfg!(debug_assertions) {
+        IS_OWNER.with(|owner| owner.set(true));
+        CLAIMED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Record which component built an object, for error messages.
+pub(crate) fn tag(object: &Object, component: &'static str) {
+    if cfg!(debug_assertions) {
+        #[allow(unsafe_code)]
+        unsafe {
+            object.set_data(COMPONENT_KEY, component);
+        }
+    }
+}
+
+fn is_wrong_thread() -> bool {
+    cfg!(debug_assertions) && CLAIMED.load(Ordering::SeqCst) && !IS_OWNER.with(|owner| owner.get())
+}
+
+fn thread_name() -> String {
+    let thread = std::thread::current();
+    match thread.name() {
+        Some(name) => format!("`{}`", name),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
+/// Panic if called from a thread other than the one `vgtk` is running on.
+///
+/// `api` should name the function being called, for the panic message.
+pub fn assert_main_thread(api: &str) {
+    if is_wrong_thread() {
+        panic!(
+            "vgtk: `{}` was called from thread {}, but it must only be called from \
+             the thread running vgtk's main loop. To get results from another thread, \
+             send a message to your component's Scope.",
+            api,
+            thread_name()
+        );
+    }
+}
+
+/// Panic if `object` is being used from a thread other than the one `vgtk` is
+/// running on.
+///
+/// `api` should name the function being called, for the panic message, which
+/// will also say which component built the object, if `vgtk` knows.
+pub fn assert_owner<O: IsA<Object>>(object: &O, api: &str) {
+    if is_wrong_thread() {
+        let object = object.as_ref();
+        #[allow(unsafe_code)]
+        let component = unsafe { object.get_data::<&'static str>(COMPONENT_KEY) }
+            .map(|component| format!(" belonging to component `{}`", component))
+            .unwrap_or_default();
+        panic!(
+            "vgtk: `{}` was called on a {}{} from thread {}, but GTK objects must only \
+             be used from the thread running vgtk's main loop. To get results from \
+             another thread, send a message to your component's Scope.",
+            api,
+            object.get_type(),
+            component,
+            thread_name()
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Images and other assets embedded in your application.
+//!
+//! The [`include_assets!`][include_assets] macro compiles every file in a
+//! directory into a [`gio::Resource`][Resource] at build time, embeds it in
+//! your binary, and registers it when it's run. Relative to your crate's
+//! `Cargo.toml`:
+//!
+//! ```rust,no_run
+//! # fn main() {
+//! vgtk::include_assets!("assets");
+//! # }
+//! ```
+//!
+//! Compiling assets requires the `glib-compile-resources` tool, which comes
+//! with GLib's development files. Changing an asset rebuilds the bundle, but
+//! adding a new file to the directory won't be noticed until something else
+//! causes your crate to be rebuilt.
+//!
+//! Once registered, you can refer to an image by its path inside the
+//! directory using the `resource` attribute on [`Image`][Image], along with
+//! `size` to give the size to draw it at in logical pixels:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::ext::ImageExtHelpers;
+//! # use vgtk::lib::gtk::Image;
+//! # fn view() -> VNode<()> {
+//! gtk! {
+//!     <Image resource="icons/logo.svg" size=48 />
+//! }
+//! # }
+//! ```
+//!
+//! Images are loaded at the right resolution for the widget's scale factor,
+//! and reloaded if it changes, so vector images stay sharp on HiDPI screens.
+//! For bitmaps, you can provide HiDPI variants alongside the original with
+//! the scale in their names: `icons/logo@2x.png` will be used instead of
+//! `icons/logo.png` at a scale factor of 2 or more.
+//!
+//! Loaded images are kept around, so an asset used in many places is only
+//! decoded once for each size it's drawn at, until
+//! [`vgtk::memory`][memory] trims them.
+//!
+//! [include_assets]: ../macro.include_assets.html
+//! [Resource]: ../../gio/struct.Resource.html
+//! [Image]: ../../gtk/struct.Image.html
+//! [memory]: ../memory/index.html
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use gdk_pixbuf::Pixbuf;
+use gio::{Resource, ResourceLookupFlags};
+use glib::Bytes;
+
+/// The resource path prefix assets are registered under.
+pub const PREFIX: &str = "/vgtk/assets";
+
+thread_local! {
+    // The images which have been loaded, by resource path and size in
+    // pixels.
+    static PIXBUFS: RefCell<HashMap<(String, Option<i32>), Pixbuf>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Register a compiled resource bundle.
+///
+/// You'll usually want the [`include_assets!`][include_assets] macro, which
+/// calls this for you.
+///
+/// [include_assets]: ../macro.include_assets.html
+pub fn register(data: &'static [u8]) {
+    let resource =
+        Resource::from_data(&Bytes::from_static(data)).expect("invalid asset resource bundle");
+    gio::resources_register(&resource);
+}
+
+/// Get the full resource path of an asset.
+///
+/// Paths starting with a `/` are taken to be full resource paths already.
+pub fn path(name: &str) -> String {
+    if name.starts_with('/') {
+        name.to_string()
+    } else {
+        format!("{}/{}", PREFIX, name)
+    }
+}
+
+/// Test whether an asset exists.
+pub fn exists(name: &str) -> bool {
+    gio::resources_get_info(&path(name), ResourceLookupFlags::NONE).is_ok()
+}
+
+/// Find the name of the variant of an asset for a scale factor, falling back
+/// to the asset itself.
+fn variant(name: &str, scale: i32) -> String {
+    for factor in (2..=scale).rev() {
+        let (stem, ext) = match name.rfind('.') {
+            Some(dot) if !name[dot..].contains('/') => name.split_at(dot),
+            _ => (name, ""),
+        };
+        let variant = format!("{}@{}x{}", stem, factor, ext);
+        if exists(&variant) {
+            return variant;
+        }
+    }
+    name.to_string()
+}
+
+/// Load an image asset.
+///
+/// The image is loaded at its natural size, or scaled to fit inside a square
+/// of `size` pixels if given, keeping its aspect ratio. `scale` is the scale
+/// factor it's meant for: `size` is multiplied by it, and the asset's HiDPI
+/// variant is used if there is one.
+///
+/// Images are cached, so loading the same asset at the same size again
+/// returns the same `Pixbuf`, which shouldn't be modified.
+pub fn load_pixbuf(name: &str, size: Option<i32>, scale: i32) -> Result<Pixbuf, glib::Error> {
+    let scale = scale.max(1);
+    let key = (path(&variant(name, scale)), size.map(|size| size * scale));
+    if let Some(pixbuf) = PIXBUFS.with(|pixbufs| pixbufs.borrow().get(&key).cloned()) {
+        return Ok(pixbuf);
+    }
+    let pixbuf = match key {
+        (ref path, Some(pixels)) => Pixbuf::from_resource_at_scale(path, pixels, pixels, true),
+        (ref path, None) => Pixbuf::from_resource(path),
+    }?;
+    PIXBUFS.with(|pixbufs| pixbufs.borrow_mut().insert(key, pixbuf.clone()));
+    Ok(pixbuf)
+}
+
+/// Forget every image loaded so far, returning how many there were.
+pub(crate) fn trim_pixbufs() -> usize {
+    PIXBUFS.with(|pixbufs| pixbufs.borrow_mut().drain().count())
+}
@@ -0,0 +1,529 @@
+//! Measuring how fast `vgtk` builds and patches widget trees.
+//!
+//! This builds synthetic widget trees of various shapes, without showing
+//! them, and times how many times a second it can build each one from
+//! scratch, and how many times a second it can patch it to match a new
+//! view. Use it to see what a change to the patcher does to performance, or
+//! to get a feel for how big a view you can afford to render.
+//!
+//! The crate's own benchmarks run the [`standard()`][standard] scenarios
+//! with `cargo bench`, and you can run any of them yourself:
+//!
+//! ```rust,no_run
+//! # use std::time::Duration;
+//! use vgtk::bench::{self, Scenario};
+//!
+//! println!("{}", bench::run(Scenario::Wide(1000), Duration::from_secs(2)));
+//! ```
+//!
+//! To catch a change which makes things slower, [`save()`][save] a set of
+//! measurements as a baseline, and [`compare()`][compare] later ones with
+//! it. The crate's benchmarks do this when given a baseline file, which
+//! makes them easy to run before and after a change, or in CI:
+//!
+//! ```text
+//! VGTK_BENCH_SAVE=baseline.txt cargo bench
+//! # ...make some changes...
+//! VGTK_BENCH_BASELINE=baseline.txt cargo bench
+//! ```
+//!
+//! Timings vary from one run to the next, so a scenario only counts as
+//! having regressed when it's slower than its baseline by more than a
+//! tolerance, 10% by default.
+//!
+//! GTK still needs a display to initialise, even though nothing is shown, so
+//! on a machine without one, run it under `xvfb-run` or with
+//! `GDK_BACKEND=broadway`. Like the rest of GTK, it has to run on the main
+//! thread.
+//!
+//! [standard]: fn.standard.html
+//! [save]: fn.save.html
+//! [compare]: fn.compare.html
+
+use std::cell::Cell;
+use std::fmt::{Display, Error, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use futures::channel::mpsc::unbounded;
+use glib::MainContext;
+use gtk::prelude::*;
+use gtk::{Box as GtkBox, CheckButton, Label, Orientation};
+
+use crate::component::{ComponentMessage, ComponentTask};
+use crate::scope::Scope;
+use crate::vdom::State;
+use crate::{Component, UpdateAction, VNode};
+
+/// The shape of a synthetic widget tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scenario {
+    /// A box with this many labels in it, patched with a view which hasn't
+    /// changed, to measure the cost of patching itself.
+    Wide(usize),
+    /// Boxes nested this deep, with a label at the bottom which changes with
+    /// every patch.
+    Deep(usize),
+    /// A list of this many rows of alternating types, which moves along by
+    /// one row with every patch, like a list which has been sorted
+    /// differently.
+    Reorder(usize),
+    /// This many labels, with every property changing with every patch.
+    PropChurn(usize),
+    /// A box with this many subcomponents in it, whose properties change
+    /// with every patch.
+    Components(usize),
+    /// A running component which is sent this many messages at a time,
+    /// which it handles in one batch before rendering once. Each patch is
+    /// one round of messages, and each build mounts the component.
+    Messages(usize),
+}
+
+impl Scenario {
+    /// A short name for the scenario, like `wide-1000`.
+    pub fn name(&self) -> String {
+        match self {
+            Scenario::Wide(size) => format!("wide-{}", size),
+            Scenario::Deep(size) => format!("deep-{}", size),
+            Scenario::Reorder(size) => format!("reorder-{}", size),
+            Scenario::PropChurn(size) => format!("prop-churn-{}", size),
+            Scenario::Components(size) => format!("components-{}", size),
+            Scenario::Messages(size) => format!("messages-{}", size),
+        }
+    }
+
+    /// Find the scenario with a name given by [`name()`][name].
+    ///
+    /// [name]: #method.name
+    pub fn from_name(name: &str) -> Option<Self> {
+        let split = name.rfind('-')?;
+        let size = name[split + 1..].parse().ok()?;
+        match &name[..split] {
+            "wide" => Some(Scenario::Wide(size)),
+            "deep" => Some(Scenario::Deep(size)),
+            "reorder" => Some(Scenario::Reorder(size)),
+            "prop-churn" => Some(Scenario::PropChurn(size)),
+            "components" => Some(Scenario::Components(size)),
+            "messages" => Some(Scenario::Messages(size)),
+            _ => None,
+        }
+    }
+
+    /// The number of widgets in the tree.
+    pub fn widgets(&self) -> usize {
+        // Every scenario has one widget for each unit of size, plus one,
+        // except for the lone label receiving messages.
+        match self {
+            Scenario::Wide(size)
+            | Scenario::Deep(size)
+            | Scenario::Reorder(size)
+            | Scenario::PropChurn(size)
+            | Scenario::Components(size) => size + 1,
+            Scenario::Messages(_) => 1,
+        }
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario::Wide(0)
+    }
+}
+
+/// The scenarios the crate's own benchmarks run, in a couple of sizes each.
+pub fn standard() -> Vec<Scenario> {
+    vec![
+        Scenario::Wide(100),
+        Scenario::Wide(1000),
+        Scenario::Deep(10),
+        Scenario::Deep(100),
+        Scenario::Reorder(100),
+        Scenario::Reorder(1000),
+        Scenario::PropChurn(100),
+        Scenario::PropChurn(1000),
+        Scenario::Components(10),
+        Scenario::Components(100),
+        Scenario::Messages(1),
+        Scenario::Messages(100),
+    ]
+}
+
+/// The results of running a scenario.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    /// The scenario which was run.
+    pub scenario: Scenario,
+    /// How many times a second the tree was built from scratch.
+    pub builds_per_sec: f64,
+    /// How many times a second the tree was patched to match a new view.
+    pub patches_per_sec: f64,
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "{:<20} {:>6} widgets {:>12.1} builds/s {:>12.1} patches/s",
+            self.scenario.name(),
+            self.scenario.widgets(),
+            self.builds_per_sec,
+            self.patches_per_sec
+        )
+    }
+}
+
+/// A component which renders a scenario's tree. It's never run; we just
+/// need something to build its views for.
+#[derive(Default)]
+struct Tree {
+    scenario: Scenario,
+    generation: usize,
+}
+
+impl Tree {
+    fn deep(&self, depth: usize) -> VNode<Self> {
+        if depth == 0 {
+            gtk! { <Label label=self.generation.to_string() /> }
+        } else {
+            gtk! {
+                <GtkBox>
+                    { self.deep(depth - 1) }
+                </GtkBox>
+            }
+        }
+    }
+
+    fn row(&self, index: usize, size: usize) -> VNode<Self> {
+        let position = (index + self.generation) % size;
+        let label = format!("Row {}", position);
+        if position.is_multiple_of(2) {
+            gtk! { <Label label=label /> }
+        } else {
+            gtk! { <CheckButton label=label /> }
+        }
+    }
+}
+
+impl Component for Tree {
+    type Message = ();
+    type Properties = ();
+
+    fn create(_props: ()) -> Self {
+        Self::default()
+    }
+
+    fn view(&self) -> VNode<Self> {
+        match self.scenario {
+            Scenario::Wide(size) => gtk! {
+                <GtkBox orientation=Orientation::Vertical>
+                    { (0..size).map(|index| gtk! { <Label label=format!("Row {}", index) /> }) }
+                </GtkBox>
+            },
+            Scenario::Deep(size) => self.deep(size),
+            Scenario::Reorder(size) => gtk! {
+                <GtkBox orientation=Orientation::Vertical>
+                    { (0..size).map(|index| self.row(index, size)) }
+                </GtkBox>
+            },
+            Scenario::Components(size) => gtk! {
+                <GtkBox orientation=Orientation::Vertical>
+                    {
+                        (0..size).map(|index| gtk! {
+                            <@Row label=format!("Row {} of {}", index, self.generation) />
+                        })
+                    }
+                </GtkBox>
+            },
+            // This one's view belongs to `Counter`.
+            Scenario::Messages(_) => gtk! { <GtkBox/> },
+            Scenario::PropChurn(size) => {
+                let odd = self.generation % 2 == 1;
+                gtk! {
+                    <GtkBox orientation=Orientation::Vertical>
+                        {
+                            (0..size).map(move |index| gtk! {
+                                <Label label=format!("Row {} of {}", index, self.generation)
+                                       tooltip_text=format!("Generation {}", self.generation)
+                                       selectable=odd sensitive=!odd
+                                       xalign={ if odd { 0.0 } else { 1.0 } } />
+                            })
+                        }
+                    </GtkBox>
+                }
+            }
+        }
+    }
+}
+
+/// A subcomponent for the `Components` scenario.
+#[derive(Clone, Debug, Default)]
+struct Row {
+    label: String,
+}
+
+impl Component for Row {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! { <Label label=self.label.clone() /> }
+    }
+}
+
+thread_local! {
+    // How many messages `Counter`s have handled.
+    static HANDLED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A component for the `Messages` scenario, which renders how many messages
+/// it's handled.
+#[derive(Default)]
+struct Counter {
+    count: usize,
+}
+
+impl Component for Counter {
+    type Message = ();
+    type Properties = ();
+
+    fn create(_props: ()) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, _msg: ()) -> UpdateAction<Self> {
+        self.count += 1;
+        HANDLED.with(|handled| handled.set(handled.get() + 1));
+        UpdateAction::Render
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! { <Label label=self.count.to_string() /> }
+    }
+}
+
+/// Run the main loop until there's nothing left for it to do, so component
+/// tasks get to handle what they've been sent.
+fn settle() {
+    let context = MainContext::default();
+    while context.pending() {
+        context.iteration(false);
+    }
+}
+
+/// Mount a `Counter` in a task of its own, returning its scope and a way to
+/// unmount it.
+fn mount_counter() -> (Scope<Counter>, impl FnOnce()) {
+    let (channel, task) = ComponentTask::<Counter, ()>::new((), None, None);
+    let scope = task.scope();
+    MainContext::ref_thread_default().spawn_local(task);
+    channel.unbounded_send(ComponentMessage::Mounted).unwrap();
+    settle();
+    let unmount = move || {
+        channel.unbounded_send(ComponentMessage::Unmounted).unwrap();
+        settle();
+    };
+    (scope, unmount)
+}
+
+/// Run the `Messages` scenario.
+fn run_messages(size: usize, duration: Duration) -> Measurement {
+    let builds_per_sec = per_second(duration, || mount_counter().1());
+
+    let (scope, unmount) = mount_counter();
+    let context = MainContext::default();
+    let patches_per_sec = per_second(duration, || {
+        let target = HANDLED.with(Cell::get) + size;
+        scope
+            .send_batched(std::iter::repeat_n((), size))
+            .expect("the component stopped running");
+        while HANDLED.with(Cell::get) < target {
+            context.iteration(true);
+        }
+        settle();
+    });
+    unmount();
+
+    Measurement {
+        scenario: Scenario::Messages(size),
+        builds_per_sec,
+        patches_per_sec,
+    }
+}
+
+/// Run `f` over and over for about `duration`, returning how many times a
+/// second it ran.
+fn per_second(duration: Duration, mut f: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    let mut count = 0usize;
+    while count == 0 || start.elapsed() < duration {
+        f();
+        count += 1;
+    }
+    count as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Run a scenario, spending about `duration` building the tree and the same
+/// again patching it.
+///
+/// This initialises GTK if nothing else has yet.
+pub fn run(scenario: Scenario, duration: Duration) -> Measurement {
+    if !gtk::is_initialized() {
+        gtk::init().expect("GTK failed to initialise");
+    }
+    if let Scenario::Messages(size) = scenario {
+        return run_messages(size, duration);
+    }
+    let scope: Scope<Tree> = Scope::new("vgtk::bench", unbounded().0);
+    let mut tree = Tree {
+        scenario,
+        generation: 0,
+    };
+
+    let builds_per_sec = per_second(duration, || {
+        let view = tree.view();
+        State::build(&view, None, &scope).unmount();
+        // Let subcomponents see they've been unmounted.
+        settle();
+    });
+
+    let mut state = State::build(&tree.view(), None, &scope);
+    let mut generation = 0;
+    let patches_per_sec = per_second(duration, || {
+        generation += 1;
+        // Build the view as part of each patch, as a component would.
+        tree.generation = generation;
+        let view = tree.view();
+        if let Err(error) = state.patch(&view, None, &scope) {
+            panic!("{}: failed to patch: {}", scenario.name(), error);
+        }
+        // Let subcomponents handle their new properties.
+        settle();
+    });
+    state.unmount();
+    settle();
+
+    Measurement {
+        scenario,
+        builds_per_sec,
+        patches_per_sec,
+    }
+}
+
+/// Save measurements to a file, one scenario to a line, to
+/// [`compare()`][compare] later measurements with.
+///
+/// [compare]: fn.compare.html
+pub fn save<P: AsRef<Path>>(path: P, measurements: &[Measurement]) -> io::Result<()> {
+    let lines: Vec<String> = measurements
+        .iter()
+        .map(|measurement| {
+            format!(
+                "{} {} {}\n",
+                measurement.scenario.name(),
+                measurement.builds_per_sec,
+                measurement.patches_per_sec
+            )
+        })
+        .collect();
+    fs::write(path, lines.concat())
+}
+
+/// Load measurements saved with [`save()`][save], skipping any lines which
+/// don't make sense.
+///
+/// [save]: fn.save.html
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<Measurement>> {
+    let data = fs::read_to_string(path)?;
+    Ok(data
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(Measurement {
+                scenario: Scenario::from_name(fields.next()?)?,
+                builds_per_sec: fields.next()?.parse().ok()?,
+                patches_per_sec: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect())
+}
+
+/// A scenario which has got slower than its baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    /// The scenario which got slower.
+    pub scenario: Scenario,
+    /// What got slower, `"builds"` or `"patches"`.
+    pub phase: &'static str,
+    /// How many times a second it ran in the baseline.
+    pub baseline: f64,
+    /// How many times a second it runs now.
+    pub current: f64,
+}
+
+impl Regression {
+    /// How much slower it is than the baseline, as a fraction of it.
+    pub fn slowdown(&self) -> f64 {
+        1.0 - self.current / self.baseline
+    }
+}
+
+impl Display for Regression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "{:<20} {} fell {:.1}% from {:.1}/s to {:.1}/s",
+            self.scenario.name(),
+            self.phase,
+            self.slowdown() * 100.0,
+            self.baseline,
+            self.current
+        )
+    }
+}
+
+/// Find the scenarios which have got slower than their baseline by more
+/// than `tolerance`, a fraction like `0.1` for 10%.
+///
+/// Scenarios which aren't in the baseline are skipped.
+pub fn compare(
+    baseline: &[Measurement],
+    current: &[Measurement],
+    tolerance: f64,
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for measurement in current {
+        let old = match baseline
+            .iter()
+            .find(|old| old.scenario == measurement.scenario)
+        {
+            Some(old) => old,
+            None => continue,
+        };
+        let phases = [
+            ("builds", old.builds_per_sec, measurement.builds_per_sec),
+            ("patches", old.patches_per_sec, measurement.patches_per_sec),
+        ];
+        for &(phase, baseline, current) in &phases {
+            if current < baseline * (1.0 - tolerance) {
+                regressions.push(Regression {
+                    scenario: measurement.scenario,
+                    phase,
+                    baseline,
+                    current,
+                });
+            }
+        }
+    }
+    regressions
+}
@@ -35,11 +35,13 @@ use std::rc::Rc;
 /// # impl Component for MyComponent {
 /// #     type Message = ();
 /// #     type Properties = Self;
+/// #     fn create(props: Self) -> Self { props }
 /// #     fn view(&self) -> VNode<Self> { todo!() }
 /// # }
 /// # #[derive(Clone, Debug)] enum ParentMessage { StringReceived(String) }
 /// # #[derive(Default)] struct Parent;
 /// # impl Component for Parent { type Message = ParentMessage; type Properties = ();
+/// # fn create(_props: ()) -> Self { Self::default() }
 /// # fn view(&self) -> VNode<Self> { gtk! {
 /// <@MyComponent on_message=|string| ParentMessage::StringReceived(string) />
 /// # }}}
@@ -83,7 +85,6 @@ impl<A> Clone for Callback<A> {
 impl<A> PartialEq for Callback<A> {
     fn eq(&self, other: &Self) -> bool {
         if let (Some(left), Some(right)) = (&self.0, &other.0) {
-            #[allow(clippy::vtable_address_comparisons)] // It's OK, it just needs to be a best guess.
             Rc::ptr_eq(left, right)
         } else {
             false
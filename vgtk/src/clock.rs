@@ -0,0 +1,253 @@
+//! Timers which tests can run on a virtual clock.
+//!
+//! vgtk's own timers, like the ones behind the `throttle` and `debounce`
+//! handler modifiers, [`SignalGovernor`][SignalGovernor] policies and toast
+//! timeouts, run on GLib's main loop as usual, except while a
+//! [`test::Executor`][Executor] is running, when they run on its virtual
+//! clock instead, which only moves when the test says so. Use
+//! [`sleep()`][sleep] instead of `glib::timeout_future()` in a component's
+//! own futures, like the ones it passes to
+//! [`UpdateAction::defer()`][defer], to get the same.
+//!
+//! [SignalGovernor]: ../governor/struct.SignalGovernor.html
+//! [Executor]: ../test/struct.Executor.html
+//! [sleep]: fn.sleep.html
+//! [defer]: ../enum.UpdateAction.html#method.defer
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Error, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use glib::{Continue, SourceId};
+
+type Callback = Box<dyn FnMut() -> Continue>;
+
+/// The virtual clock a `test::Executor` runs timers on.
+#[derive(Default)]
+pub(crate) struct Virtual {
+    now: Duration,
+    next_id: u64,
+    // Each timer's due time, interval and callback, by ID, which also keeps
+    // timers due at the same time in the order they were added.
+    timers: BTreeMap<u64, (Duration, Duration, Callback)>,
+    // The timer whose callback is running, unless it's been cancelled.
+    firing: Option<u64>,
+}
+
+thread_local! {
+    static VIRTUAL: RefCell<Option<Virtual>> = const { RefCell::new(None) };
+}
+
+/// Switch this thread's timers to a virtual clock, returning `false` if
+/// they're already using one.
+pub(crate) fn start_virtual() -> bool {
+    VIRTUAL.with(|clock| {
+        let mut clock = clock.borrow_mut();
+        if clock.is_some() {
+            return false;
+        }
+        *clock = Some(Virtual::default());
+        true
+    })
+}
+
+/// Switch this thread's timers back to GLib's, throwing away any virtual
+/// timers which haven't fired.
+pub(crate) fn stop_virtual() {
+    let clock = VIRTUAL.with(|clock| clock.borrow_mut().take());
+    // Drop the timers outside the borrow, as they may own things which
+    // cancel timers when they're dropped.
+    drop(clock);
+}
+
+/// How much virtual time has passed, if the virtual clock is running.
+pub(crate) fn virtual_now() -> Option<Duration> {
+    VIRTUAL.with(|clock| clock.borrow().as_ref().map(|clock| clock.now))
+}
+
+/// How many virtual timers are waiting to fire.
+pub(crate) fn virtual_pending() -> usize {
+    VIRTUAL.with(|clock| {
+        clock
+            .borrow()
+            .as_ref()
+            .map_or(0, |clock| clock.timers.len())
+    })
+}
+
+/// Fire the earliest virtual timer due no later than `until`, moving the
+/// virtual clock forward to when it was due, and returning `false` if there
+/// isn't one.
+pub(crate) fn fire_next(until: Duration) -> bool {
+    let next = VIRTUAL.with(|clock| {
+        let mut clock = clock.borrow_mut();
+        let clock = clock.as_mut()?;
+        let (id, due) = clock
+            .timers
+            .iter()
+            .map(|(id, (due, _, _))| (*id, *due))
+            .filter(|(_, due)| *due <= until)
+            .min_by_key(|(_, due)| *due)?;
+        let (_, interval, callback) = clock.timers.remove(&id)?;
+        clock.now = due;
+        clock.firing = Some(id);
+        Some((id, interval, callback))
+    });
+    let (id, interval, mut callback) = match next {
+        Some(next) => next,
+        None => return false,
+    };
+    // The callback may add or cancel timers, including itself, so it can't
+    // run while the clock is borrowed.
+    let again = callback().0;
+    VIRTUAL.with(|clock| {
+        if let Some(clock) = clock.borrow_mut().as_mut() {
+            if again && clock.firing == Some(id) {
+                let due = clock.now + interval;
+                clock.timers.insert(id, (due, interval, callback));
+            }
+            clock.firing = None;
+        }
+    });
+    true
+}
+
+/// Move the virtual clock forward to `time`, without firing anything.
+pub(crate) fn set_virtual_now(time: Duration) {
+    VIRTUAL.with(|clock| {
+        if let Some(clock) = clock.borrow_mut().as_mut() {
+            clock.now = clock.now.max(time);
+        }
+    })
+}
+
+/// A timer started with [`timeout_add()`][timeout_add].
+///
+/// [timeout_add]: fn.timeout_add.html
+#[derive(Debug)]
+pub(crate) enum Timer {
+    Glib(SourceId),
+    Virtual(u64),
+}
+
+impl Timer {
+    /// Stop the timer before it next fires.
+    pub(crate) fn cancel(self) {
+        match self {
+            Timer::Glib(source) => glib::source_remove(source),
+            Timer::Virtual(id) => {
+                let timer = VIRTUAL.with(|clock| {
+                    let mut clock = clock.borrow_mut();
+                    let clock = clock.as_mut()?;
+                    if clock.firing == Some(id) {
+                        clock.firing = None;
+                    }
+                    clock.timers.remove(&id)
+                });
+                drop(timer);
+            }
+        }
+    }
+}
+
+/// Call `callback` every `interval` until it returns `Continue(false)`, like
+/// `glib::timeout_add_local()`, on the virtual clock if it's running.
+pub(crate) fn timeout_add<F>(interval: Duration, callback: F) -> Timer
+where
+    F: FnMut() -> Continue + 'static,
+{
+    let callback = VIRTUAL.with(|clock| match clock.borrow_mut().as_mut() {
+        Some(clock) => {
+            let id = clock.next_id;
+            clock.next_id += 1;
+            let due = clock.now + interval;
+            clock.timers.insert(id, (due, interval, Box::new(callback)));
+            Err(id)
+        }
+        None => Ok(callback),
+    });
+    match callback {
+        Ok(callback) => Timer::Glib(glib::timeout_add_local(
+            interval.as_millis() as u32,
+            callback,
+        )),
+        Err(id) => Timer::Virtual(id),
+    }
+}
+
+/// Wait for `duration`, on the virtual clock if it's running.
+pub fn sleep(duration: Duration) -> Sleep {
+    if virtual_now().is_none() {
+        let future = glib::timeout_future(duration.as_millis() as u32);
+        return Sleep(Inner::Glib(future));
+    }
+    let state: Rc<Fired> = Default::default();
+    let fired = state.clone();
+    let timer = timeout_add(duration, move || {
+        fired.0.set(true);
+        if let Some(waker) = fired.1.take() {
+            waker.wake();
+        }
+        Continue(false)
+    });
+    Sleep(Inner::Virtual(Some(timer), state))
+}
+
+/// Whether a virtual `Sleep` has fired, and what to wake when it does.
+type Fired = (Cell<bool>, Cell<Option<Waker>>);
+
+/// A future returned by [`sleep()`][sleep], which finishes once its time is
+/// up.
+///
+/// [sleep]: fn.sleep.html
+pub struct Sleep(Inner);
+
+enum Inner {
+    Glib(Pin<Box<dyn Future<Output = ()> + Send>>),
+    Virtual(Option<Timer>, Rc<Fired>),
+}
+
+impl Debug for Sleep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str(match self.0 {
+            Inner::Glib(_) => "Sleep",
+            Inner::Virtual(..) => "Sleep(virtual)",
+        })
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<()> {
+        match &mut self.0 {
+            Inner::Glib(future) => future.as_mut().poll(ctx),
+            Inner::Virtual(timer, state) => {
+                if state.0.get() {
+                    timer.take();
+                    Poll::Ready(())
+                } else {
+                    state.1.set(Some(ctx.waker().clone()));
+                    Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Inner::Virtual(timer, state) = &mut self.0 {
+            if let Some(timer) = timer.take() {
+                if !state.0.get() {
+                    timer.cancel();
+                }
+            }
+        }
+    }
+}
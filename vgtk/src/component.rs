@@ -1,24 +1,45 @@
 use futures::{
-    channel::mpsc::{unbounded, UnboundedSender},
+    channel::{
+        mpsc::{unbounded, UnboundedSender},
+        oneshot,
+    },
     future::FutureExt,
     stream::{select, Stream},
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
     StreamExt,
 };
-use glib::{Cast, MainContext, Object, ObjectExt, WeakRef};
-use gtk::{Application, GtkApplicationExt, Widget, WidgetExt, Window};
+use glib::{Cast, MainContext, Object, ObjectExt, SignalHandlerId, WeakRef};
+use gtk::{
+    Application, Container, ContainerExt, GtkApplicationExt, Spinner, SpinnerExt, Widget,
+    WidgetExt, Window,
+};
 
-use std::any::TypeId;
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Error, Formatter};
 use std::future::Future;
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
 use std::pin::Pin;
+use std::rc::Rc;
 use std::sync::RwLock;
 
 use colored::Colorize;
-use log::{debug, trace};
 
-use crate::scope::{AnyScope, Scope};
-use crate::vdom::State;
+use crate::config::{ComponentConfig, Priority};
+use crate::debug;
+use crate::error;
+use crate::links::Link;
+use crate::middleware;
+use crate::query::Exposures;
+use crate::scope::{AnyScope, ParentScope, Scope};
+use crate::session;
+use crate::shutdown;
+use crate::splash::Splash;
+use crate::strict;
+use crate::theme::{self, Theme};
+use crate::vdom::{self, diff, PatchError, State};
 use crate::vnode::VNode;
 
 /// An action resulting from a [`Component::update()`](trait.Component.html#method.update).
@@ -55,6 +76,7 @@ pub enum UpdateAction<C: Component> {
     /// # struct Foo;
     /// # impl Component for Foo {
     /// #     type Message = Message; type Properties = ();
+    /// #     fn create(_props: ()) -> Self { Self::default() }
     /// #     fn view(&self) -> VNode<Self> { gtk!{ <Box/> } }
     /// fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
     ///     match message {
@@ -71,6 +93,50 @@ pub enum UpdateAction<C: Component> {
     /// [defer]: #method.defer
     /// [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
     Defer(Pin<Box<dyn Future<Output = C::Message> + 'static>>),
+    /// Perform several actions in response to a single message.
+    ///
+    /// The actions are performed in order, and the component will re-render
+    /// once if any of them asks for a render. You should use
+    /// [`UpdateAction::batch()`][batch] to construct this.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # #[derive(Clone, Debug)]
+    /// enum Message {
+    ///     StartJobs,
+    ///     JobDone,
+    /// }
+    ///
+    /// # use vgtk::{gtk, Component, VNode, UpdateAction};
+    /// # use vgtk::lib::gtk::Box;
+    /// # #[derive(Default)]
+    /// # struct Foo { running: usize }
+    /// # impl Component for Foo {
+    /// #     type Message = Message; type Properties = ();
+    /// #     fn create(_props: ()) -> Self { Self::default() }
+    /// #     fn view(&self) -> VNode<Self> { gtk!{ <Box/> } }
+    /// fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+    ///     match message {
+    ///         Message::StartJobs => {
+    ///             self.running = 2;
+    ///             UpdateAction::batch(vec![
+    ///                 UpdateAction::Render,
+    ///                 UpdateAction::defer(async { Message::JobDone }),
+    ///                 UpdateAction::defer(async { Message::JobDone }),
+    ///             ])
+    ///         }
+    ///         Message::JobDone => {
+    ///             self.running -= 1;
+    ///             UpdateAction::Render
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [batch]: #method.batch
+    Batch(Vec<UpdateAction<C>>),
 }
 
 impl<C: Component> UpdateAction<C> {
@@ -80,6 +146,11 @@ impl<C: Component> UpdateAction<C> {
     pub fn defer(job: impl Future<Output = C::Message> + 'static) -> Self {
         UpdateAction::Defer(job.boxed_local())
     }
+
+    /// Construct an action which performs each of the given actions in order.
+    pub fn batch(actions: impl IntoIterator<Item = UpdateAction<C>>) -> Self {
+        UpdateAction::Batch(actions.into_iter().collect())
+    }
 }
 
 impl<C, F> From<F> for UpdateAction<C>
@@ -92,29 +163,111 @@ where
     }
 }
 
+/// What to do when the application is about to quit, returned from
+/// [`Component::before_quit()`][before_quit].
+///
+/// [before_quit]: trait.Component.html#method.before_quit
+pub enum ShutdownAction {
+    /// Go ahead and quit.
+    Continue,
+    /// Don't quit after all.
+    Veto,
+    /// Decide later, and quit if the [`Future`][Future] resolves to `true`.
+    ///
+    /// This is how to ask the user what to do about their unsaved changes
+    /// without holding up the main loop. You should use
+    /// [`ShutdownAction::defer()`][defer] to construct this.
+    ///
+    /// [defer]: #method.defer
+    /// [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
+    Defer(Pin<Box<dyn Future<Output = bool> + 'static>>),
+}
+
+impl ShutdownAction {
+    /// Construct a deferred decision given a [`Future`][Future].
+    ///
+    /// [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
+    pub fn defer(decision: impl Future<Output = bool> + 'static) -> Self {
+        ShutdownAction::Defer(decision.boxed_local())
+    }
+}
+
+/// A keyed subcomponent which has moved from one component's view to
+/// another's, passed to [`Component::moved()`][moved].
+///
+/// [moved]: trait.Component.html#method.moved
+#[derive(Clone, Debug)]
+pub struct Moved {
+    /// The type name of the subcomponent.
+    pub component: &'static str,
+    key: u64,
+    /// The window it was in before, if it was in one.
+    pub from: Option<Window>,
+    /// The window it's in now, if it's in one.
+    pub to: Option<Window>,
+}
+
+impl Moved {
+    pub(crate) fn new(
+        component: &'static str,
+        key: u64,
+        from: Option<Window>,
+        to: Option<Window>,
+    ) -> Self {
+        Moved {
+            component,
+            key,
+            from,
+            to,
+        }
+    }
+
+    /// Test whether the subcomponent has the `key` attribute `key`.
+    pub fn has_key<K: Hash + ?Sized>(&self, key: &K) -> bool {
+        self.key == crate::vnode::key(key)
+    }
+}
+
 /// This is the trait your UI components should implement.
 ///
-/// You must always provide `Message` and `Properties` types, and the `view()` method.
+/// You must always provide `Message` and `Properties` types, and the `create()` and
+/// `view()` methods.
 /// `Properties` only makes sense when used as a subcomponent, and should be set to the
 /// unit type `()` for your top level component.
 ///
 /// A default implementation for `update` is provided which does nothing and always
 /// returns `UpdateAction::None`. You will probably want to reimplement this.
 ///
-/// You don't have to implement `create` and `change` for a top level component, but
-/// you'll have to implement them for a subcomponent. The default implementation for
-/// `create` just constructs the default value for your component, ignoring its
-/// properties entirely (which is what you want for a top level component) and the
-/// default `change` will panic to remind you that you need to implement it.
+/// `create` is how a subcomponent is constructed from its properties. A top level
+/// component is constructed with [`Default::default()`][default] by
+/// [`vgtk::run()`][run], so its `create` can usually just return `Self::default()`,
+/// or given its initial state with [`vgtk::run_with_state()`][run_with_state], so
+/// it doesn't need `Default` if it's launched that way, and its `create` is never
+/// called.
+///
+/// You don't have to implement `change` for a top level component, but you'll have
+/// to implement it for a subcomponent. Its default implementation will panic to
+/// remind you that you need to implement it.
+///
+/// [default]: https://doc.rust-lang.org/std/default/trait.Default.html
+/// [run]: fn.run.html
+/// [run_with_state]: fn.run_with_state.html
 ///
 /// A sensible pattern for a subcomponent without local state is to make
 /// `Self::Properties = Self`. `create` can then just return its input argument,
 /// and `change` could be as simple as `*self = props; UpdateAction::Render`, though
 /// you might want to compare the input with the current state if possible and return
 /// `UpdateAction::Render` only when they're different.
-pub trait Component: Default + Unpin {
+pub trait Component: Sized + Unpin {
     /// The type of messages you can send to the `Component::update()` function.
-    type Message: Clone + Send + Debug + Unpin;
+    ///
+    /// Messages don't have to be `Clone` or `Send`, so they can carry GTK
+    /// objects or large buffers. If they are `Send`, the component's
+    /// [`Scope`][Scope] is too, and you can send it messages from other
+    /// threads; if they aren't, it can only be used on the main thread.
+    ///
+    /// [Scope]: struct.Scope.html
+    type Message: Debug + Unpin;
 
     /// A struct type which holds the properties for your `Component`.
     ///
@@ -148,16 +301,109 @@ pub trait Component: Default + Unpin {
         UpdateAction::None
     }
 
+    /// Process several messages at once.
+    ///
+    /// Messages which arrive during the same main loop iteration, such as
+    /// those sent with [`Scope::send_batched()`][send_batched], are delivered
+    /// together through this method, in the order they were sent, before
+    /// the component gets a chance to render.
+    ///
+    /// The default implementation calls `Component::update()` with each of
+    /// them in turn. If your component receives messages at a high rate, like
+    /// readings from a sensor, you can reimplement it to handle a batch more
+    /// efficiently than one message at a time, eg. by only keeping the latest
+    /// reading.
+    ///
+    /// [send_batched]: struct.Scope.html#method.send_batched
+    fn update_batch(&mut self, messages: Vec<Self::Message>) -> UpdateAction<Self> {
+        let actions: Vec<_> = messages
+            .into_iter()
+            .map(|message| self.update(message))
+            .collect();
+        UpdateAction::batch(actions)
+    }
+
+    /// Decide whether a message makes the one queued right before it
+    /// redundant, in which case the earlier one is dropped.
+    ///
+    /// This is called for each message arriving right behind another one
+    /// in the same batch, before either is passed to
+    /// [`update_batch()`][update_batch]. It's a way to collapse a flood of
+    /// messages from a busy signal, like a slider being dragged, into the
+    /// latest one, eg. by comparing `previous == next`, or comparing their
+    /// variants with [`std::mem::discriminant()`][discriminant].
+    ///
+    /// The default implementation returns `false`, so every message is
+    /// delivered. To slow down the messages a signal handler sends, rather
+    /// than collapsing them after the fact, see the `throttle` and
+    /// `debounce` modifiers in the [`gtk!`][gtk!] macro.
+    ///
+    /// [update_batch]: #method.update_batch
+    /// [discriminant]: https://doc.rust-lang.org/std/mem/fn.discriminant.html
+    /// [gtk!]: macro.gtk.html
+    fn coalesce(&self, _previous: &Self::Message, _next: &Self::Message) -> bool {
+        false
+    }
+
+    /// Decide how urgently a message should be handled.
+    ///
+    /// Messages which arrive together are sorted by priority before they're
+    /// passed to [`update_batch()`][update_batch], and a render asked for
+    /// by a batch of only [`Priority::Low`][Low] messages is put off until
+    /// the main loop is idle. Use this to keep the user's input responsive
+    /// while the component is busy with a flood of background messages.
+    ///
+    /// The default implementation returns `Priority::Normal` for every
+    /// message.
+    ///
+    /// [update_batch]: #method.update_batch
+    /// [Low]: config/enum.Priority.html#variant.Low
+    fn priority(&self, _msg: &Self::Message) -> Priority {
+        Priority::Normal
+    }
+
+    /// The runtime settings for this type of component, like how many
+    /// messages it lets wait for it before it starts dropping them.
+    ///
+    /// This is called for each instance when it's created. The default
+    /// implementation returns `ComponentConfig::default()`, which lets any
+    /// number of messages wait, and puts off renders while the component is
+    /// hidden. See the [`config`][config] module for
+    /// details.
+    ///
+    /// [config]: config/index.html
+    fn config() -> ComponentConfig {
+        ComponentConfig::default()
+    }
+
     /// Construct a new `Component` given a `Component::Properties` object.
     ///
-    /// The default implementation ignores the `Properties` argument and constructs
-    /// the component using [`Default::default()`][default]. This is what you want
-    /// for a top level component, and almost certainly not what you want for a
-    /// subcomponent.
+    /// This is how subcomponents are constructed, and the properties are the
+    /// ones constructed from the attributes in their parent's view. Top level
+    /// components aren't constructed with this, unless they're launched as a
+    /// dialog with [`vgtk::run_dialog_props()`][run_dialog_props].
     ///
-    /// [default]: https://doc.rust-lang.org/std/default/trait.Default.html
-    fn create(_props: Self::Properties) -> Self {
-        Default::default()
+    /// [run_dialog_props]: fn.run_dialog_props.html
+    fn create(props: Self::Properties) -> Self;
+
+    /// Do whatever the `Component` needs to do once it's been created, like
+    /// starting to load its initial data.
+    ///
+    /// This is called once, right after [`create()`][create], and before
+    /// the first call to [`view()`][view]. Return `UpdateAction::Defer` to
+    /// run an async action, like fetching data or reading a configuration
+    /// file, whose result is delivered to [`update()`][update] like any
+    /// other message, rather than spawning one yourself from
+    /// [`mounted()`][mounted].
+    ///
+    /// The default implementation returns `UpdateAction::None`.
+    ///
+    /// [create]: #tymethod.create
+    /// [view]: #tymethod.view
+    /// [update]: #method.update
+    /// [mounted]: #method.mounted
+    fn init(&mut self) -> UpdateAction<Self> {
+        UpdateAction::None
     }
 
     /// Update a `Component`'s properties.
@@ -182,6 +428,227 @@ pub trait Component: Default + Unpin {
     /// need to be aware of when this happens.
     fn unmounted(&mut self) {}
 
+    /// This method is called on the top level component when the application
+    /// is asked to quit, to decide whether it should.
+    ///
+    /// That's when [`vgtk::quit()`][quit] is called, or when the user closes
+    /// the application's last window. Return
+    /// [`ShutdownAction::Veto`][Veto] to keep running, or
+    /// [`ShutdownAction::defer()`][defer] to decide later, like after asking
+    /// the user whether to save their work first:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{ext::*, gtk, Component, ShutdownAction, VNode};
+    /// # use vgtk::lib::gtk::{ButtonsType, DialogExt, DialogFlags, GtkWindowExt, MessageDialog,
+    /// #     MessageType, ResponseType, WidgetExt};
+    /// # use vgtk::lib::gio::ApplicationFlags;
+    /// # use vgtk::lib::gtk::Application;
+    /// # #[derive(Default)] struct Editor { unsaved: bool }
+    /// # impl Component for Editor {
+    /// #     type Message = (); type Properties = ();
+    /// #     fn create(_props: ()) -> Self { Self::default() }
+    /// #     fn view(&self) -> VNode<Self> {
+    /// #         gtk! { <Application::new_unwrap(None, ApplicationFlags::empty()) /> }
+    /// #     }
+    /// fn before_quit(&mut self) -> ShutdownAction {
+    ///     if !self.unsaved {
+    ///         return ShutdownAction::Continue;
+    ///     }
+    ///     let dialog = MessageDialog::new(
+    ///         vgtk::current_window().as_ref(),
+    ///         DialogFlags::MODAL,
+    ///         MessageType::Question,
+    ///         ButtonsType::YesNo,
+    ///         "Quit without saving?",
+    ///     );
+    ///     dialog.show();
+    ///     ShutdownAction::defer(async move {
+    ///         let response = vgtk::on_signal!(dialog, connect_response).await;
+    ///         dialog.close();
+    ///         response == Ok(ResponseType::Yes)
+    ///     })
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Calling [`Application::quit()`][Application::quit] yourself quits
+    /// without asking. This method is never called on a subcomponent. The
+    /// default implementation always returns `ShutdownAction::Continue`.
+    ///
+    /// [quit]: ../fn.quit.html
+    /// [Veto]: enum.ShutdownAction.html#variant.Veto
+    /// [defer]: enum.ShutdownAction.html#method.defer
+    /// [Application::quit]: ../gio/trait.ApplicationExt.html#tymethod.quit
+    fn before_quit(&mut self) -> ShutdownAction {
+        ShutdownAction::Continue
+    }
+
+    /// Turn a link the application was asked to open into a message for the
+    /// top level component, or `None` if it isn't one it understands.
+    ///
+    /// The application has to have the `HANDLES_OPEN` flag for this to be
+    /// called. See [`vgtk::links`][links]. This method is never called on a
+    /// subcomponent. The default implementation returns `None`.
+    ///
+    /// [links]: ../links/index.html
+    fn open_link(_link: &Link) -> Option<Self::Message> {
+        None
+    }
+
+    /// Turn the command line arguments the application was launched with
+    /// into a message for the top level component, when it's run with
+    /// [`vgtk::run_single_instance()`][run_single_instance].
+    ///
+    /// This is called when the application starts, and again each time it's
+    /// launched while it's already running, with that launch's arguments,
+    /// including the program name. This method is never called on a
+    /// subcomponent. The default implementation returns `None`.
+    ///
+    /// [run_single_instance]: ../fn.run_single_instance.html
+    fn activated(_args: &[String]) -> Option<Self::Message> {
+        None
+    }
+
+    /// Choose a splash window to show while the top level component starts
+    /// up, or `None` to show its windows straight away.
+    ///
+    /// The component's windows are held back until it calls
+    /// [`vgtk::splash::done()`][done]. See [`vgtk::splash`][splash]. This
+    /// method is never called on a subcomponent. The default implementation
+    /// returns `None`.
+    ///
+    /// [done]: ../splash/fn.done.html
+    /// [splash]: ../splash/index.html
+    fn splash() -> Option<Splash> {
+        None
+    }
+
+    /// This method is called when part of the `Component`'s widget tree
+    /// couldn't be patched to match its view, like when a property setter
+    /// rejects its value, or a container won't take a new child.
+    ///
+    /// The rest of the widget tree has still been patched, and the widgets
+    /// which failed are left as they were. The error has already been
+    /// logged, so the default implementation does nothing, but a component
+    /// can report it somewhere more visible, or remember it to render
+    /// something else next time. See [`PatchError`][PatchError].
+    ///
+    /// [PatchError]: debug/struct.PatchError.html
+    fn on_patch_error(&mut self, _error: &PatchError) {}
+
+    /// This method is called once, as the `Component` is torn down for good,
+    /// after its widget tree has been removed and all of its subcomponents
+    /// have been torn down, right before it's dropped.
+    ///
+    /// Because subcomponents always go before the components containing
+    /// them, this is the place to release anything your subcomponents might
+    /// still be using, like files, sockets or subscriptions. See also
+    /// [`Scope::on_drop()`][on_drop].
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [on_drop]: ../scope/struct.Scope.html#method.on_drop
+    fn destroyed(&mut self) {}
+
+    /// This method is called after the `Component`'s widget tree has been
+    /// built or updated to reflect its current view.
+    ///
+    /// `first_render` is `true` the first time this is called, right after the
+    /// widgets have been constructed, and `false` after every subsequent
+    /// re-render. [`current_object()`][current_object] will return the
+    /// component's top level object while this runs, so this is the place for
+    /// work which needs realised widgets, like measuring, scrolling or moving
+    /// keyboard focus. It isn't called after a render whose widgets couldn't
+    /// be patched, which goes to
+    /// [`on_patch_error()`][on_patch_error] instead.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [current_object]: fn.current_object.html
+    /// [on_patch_error]: #method.on_patch_error
+    fn rendered(&mut self, _first_render: bool) {}
+
+    /// Save the `Component`'s state, to restore with
+    /// [`restore_state()`][restore_state] the next time the app starts.
+    ///
+    /// This is only called if you've enabled sessions with
+    /// [`vgtk::session::enable()`][enable], every time the component has been
+    /// updated. The default implementation returns `None`, which means
+    /// there's nothing worth saving.
+    ///
+    /// [restore_state]: #method.restore_state
+    /// [enable]: session/fn.enable.html
+    fn save_state(&self) -> Option<String> {
+        None
+    }
+
+    /// Restore the state saved by [`save_state()`][save_state] the last time
+    /// the app ran.
+    ///
+    /// This is called right after [`create()`][create], before the component
+    /// renders its first view. The default implementation does nothing.
+    ///
+    /// [save_state]: #method.save_state
+    /// [create]: #tymethod.create
+    fn restore_state(&mut self, _state: &str) {}
+
+    /// This method is called when the desktop theme changes, like when the
+    /// user switches to a dark theme.
+    ///
+    /// The default implementation does nothing. If your view depends on the
+    /// theme, for instance to pick images which stand out against the
+    /// background, store what you need from `theme` and return
+    /// `UpdateAction::Render`.
+    fn theme_changed(&mut self, _theme: &Theme) -> UpdateAction<Self> {
+        UpdateAction::None
+    }
+
+    /// This method is called when a subcomponent with a `key` attribute has
+    /// moved between this component's view and another's, which may be in
+    /// another window, like when the user drags a card from one window to
+    /// another.
+    ///
+    /// If a subcomponent leaves one view and one of the same type with the
+    /// same key turns up in another before the next frame is drawn, the
+    /// running subcomponent moves there along with its widgets, rather than
+    /// being torn down and built again, and this is called on both
+    /// components, or once if it moved within the same view.
+    ///
+    /// The default implementation does nothing.
+    fn moved(&mut self, _moved: &Moved) -> UpdateAction<Self> {
+        UpdateAction::None
+    }
+
+    /// Take a snapshot of the `Component` to pass to
+    /// [`should_render()`][should_render] the next time it wants to re-render.
+    ///
+    /// The default implementation returns `None`, which means
+    /// [`should_render()`][should_render] will never be called. If your
+    /// component is cheap to clone, you can just return `Some(self.clone())`.
+    ///
+    /// [should_render]: #method.should_render
+    fn render_snapshot(&self) -> Option<Self> {
+        None
+    }
+
+    /// Decide whether the `Component` really needs to re-render.
+    ///
+    /// This is called when [`update()`][update] or [`change()`][change] has
+    /// asked for a render, before calling [`view()`][view], with `old` being
+    /// the snapshot taken by [`render_snapshot()`][render_snapshot] after the
+    /// last render. Return `false` to skip the render, if nothing has changed
+    /// which would alter the output of an expensive view function.
+    ///
+    /// The default implementation always returns `true`.
+    ///
+    /// [update]: #method.update
+    /// [change]: #method.change
+    /// [view]: #tymethod.view
+    /// [render_snapshot]: #method.render_snapshot
+    fn should_render(&self, _old: &Self) -> bool {
+        true
+    }
+
     /// Build a `VNode` tree to represent your UI.
     ///
     /// This is called whenever the `Component` needs to re-render, and its UI
@@ -197,6 +664,7 @@ pub trait Component: Default + Unpin {
 impl Component for () {
     type Message = ();
     type Properties = ();
+    fn create(_props: ()) -> Self {}
     fn view(&self) -> VNode<Self> {
         unimplemented!("tried to render a null component")
     }
@@ -204,9 +672,15 @@ impl Component for () {
 
 pub(crate) enum ComponentMessage<C: Component> {
     Update(C::Message),
+    // A message the component sent itself while it was busy.
+    Reentrant(C::Message),
     Props(C::Properties),
     Mounted,
     Unmounted,
+    // The subcomponent has moved, and its parent is now another component.
+    Reparent(ParentScope),
+    // The application is about to quit.
+    Quit,
 }
 
 impl<C: Component> Debug for ComponentMessage<C> {
@@ -221,20 +695,22 @@ impl<C: Component> Debug for ComponentMessage<C> {
                 )
                 .green()
             ),
+            ComponentMessage::Reentrant(msg) => write!(
+                f,
+                "{}",
+                format!(
+                    "ComponentMessage::Reentrant({})",
+                    format!("{:?}", msg).bright_white().bold()
+                )
+                .green()
+            ),
             ComponentMessage::Props(_) => write!(f, "{}", "ComponentMessage::Props(...)".green()),
             ComponentMessage::Mounted => write!(f, "{}", "ComponentMessage::Mounted".green()),
             ComponentMessage::Unmounted => write!(f, "{}", "ComponentMessage::Unmounted".green()),
-        }
-    }
-}
-
-impl<C: Component> Clone for ComponentMessage<C> {
-    fn clone(&self) -> Self {
-        match self {
-            ComponentMessage::Update(msg) => ComponentMessage::Update(msg.clone()),
-            ComponentMessage::Props(props) => ComponentMessage::Props(props.clone()),
-            ComponentMessage::Mounted => ComponentMessage::Mounted,
-            ComponentMessage::Unmounted => ComponentMessage::Unmounted,
+            ComponentMessage::Reparent(_) => {
+                write!(f, "{}", "ComponentMessage::Reparent(...)".green())
+            }
+            ComponentMessage::Quit => write!(f, "{}", "ComponentMessage::Quit".green()),
         }
     }
 }
@@ -264,13 +740,26 @@ where
         props: C::Properties,
         parent: Option<&Object>,
         parent_scope: Option<&Scope<P>>,
+    ) -> Self {
+        Self::with_state(C::create(props), parent, parent_scope)
+    }
+
+    /// Start building a `ComponentTask` for a component whose state has
+    /// already been constructed.
+    pub(crate) fn with_state(
+        mut state: C,
+        parent: Option<&Object>,
+        parent_scope: Option<&Scope<P>>,
     ) -> Self {
         let (sys_send, sys_recv) = unbounded();
         let (user_send, user_recv) = unbounded();
 
-        // As `C::Message` must be `Send` but `C::Properties` can't be,
-        // we keep two senders but merge them into a single receiver at
-        // the task end.
+        // The `Scope` only carries `C::Message`s, so that it can be sent to
+        // other threads when they're `Send`, while the system sender carries
+        // `C::Properties`, which never are, so we keep two senders but merge
+        // them into a single receiver at the task end. The user channel is
+        // unbounded, but the `Scope` applies the limits from
+        // `Component::config()` to it as messages are sent.
         let channel = Pin::new(Box::new(select(
             user_recv.map(ComponentMessage::Update),
             sys_recv,
@@ -278,20 +767,63 @@ where
 
         let type_name = std::any::type_name::<C>();
         let scope = match parent_scope {
-            Some(ref p) => p.inherit(type_name, user_send),
+            Some(p) => p.inherit(type_name, user_send),
             None => Scope::new(type_name, user_send),
         };
-        let state = C::create(props);
-        let initial_view = state.view();
+        let session_key = session::component_key(type_name);
+        if let Some(saved) = session_key.as_deref().and_then(session::component_state) {
+            state.restore_state(&saved);
+        }
+        let rerender = Rerender::register();
+        // We're most likely being built while our parent patches its widget
+        // tree, so make it look like our own task is running, the way it
+        // will be for every later call to `view()`. Anything `init()`
+        // changed is in the initial view, so there's no need to render again
+        // if it asks to.
+        let reentrant = Default::default();
+        let exposures = Default::default();
+        let context = LocalContext::new(
+            &scope,
+            parent_scope.map(|scope| scope.clone().into()),
+            None,
+            rerender.0,
+            &reentrant,
+            &exposures,
+        );
+        let (init, initial_view) = context.enter(|| {
+            let init = state.init();
+            (init, state.view())
+        });
         let ui_state = State::build_root(&initial_view, parent, &scope);
+        let task = ComponentTask {
+            scope,
+            parent_scope: parent_scope.cloned(),
+            reparented: None,
+            state,
+            ui_state: Some(ui_state),
+            channel,
+            rendered: false,
+            last_render: None,
+            parent: parent.cloned(),
+            shared_root: None,
+            rerender,
+            theme: theme::generation(),
+            session_key,
+            updated: false,
+            idle_render: false,
+            idle: Default::default(),
+            defer_hidden: C::config().defer_hidden,
+            hidden: None,
+            revealed: Default::default(),
+            mount: None,
+            reentrant,
+            exposures,
+            teardown: None,
+            done: None,
+        };
+        task.perform(init);
         PartialComponentTask {
-            task: ComponentTask {
-                scope,
-                parent_scope: parent_scope.cloned(),
-                state,
-                ui_state: Some(ui_state),
-                channel,
-            },
+            task,
             view: initial_view,
             sender: sys_send,
         }
@@ -299,17 +831,44 @@ where
 
     /// Finalise the partially constructed `ComponentTask` by constructing its
     /// children.
+    ///
+    /// If the component asks for an incremental mount, this only starts
+    /// building them, and the task carries on as it runs.
     pub(crate) fn finalise(
         mut self,
     ) -> (UnboundedSender<ComponentMessage<C>>, ComponentTask<C, P>) {
         if let Some(ref mut ui_state) = self.task.ui_state {
-            ui_state.build_children(&self.view, &self.task.scope);
+            // The spinner needs an empty container to go in, which rules
+            // out the likes of `Dialog`, whose content area is already in it,
+            // and one of our own, rather than a subcomponent's.
+            let container = match ui_state {
+                State::Gtk(_) => ui_state.object().downcast::<Container>().ok(),
+                State::Component(_) => None,
+            }
+            .filter(|container| container.get_children().is_empty());
+            match (C::config().mount_chunk, container) {
+                (Some(chunk), Some(container)) => {
+                    let placeholder = Spinner::new();
+                    container.add(&placeholder);
+                    placeholder.start();
+                    placeholder.show();
+                    container.show();
+                    self.task.mount = Some(Mount {
+                        view: self.view,
+                        chunk,
+                        placeholder,
+                        ready: Rc::new(Cell::new(true)),
+                        render: false,
+                    });
+                }
+                _ => ui_state.build_children(&self.view, &self.task.scope),
+            }
         }
         (self.sender, self.task)
     }
 
     pub(crate) fn object(&self) -> Object {
-        self.task.ui_state.as_ref().unwrap().object().clone()
+        self.task.ui_state.as_ref().unwrap().object()
     }
 
     pub(crate) fn scope(&self) -> Scope<C> {
@@ -324,9 +883,56 @@ where
 {
     scope: Scope<C>,
     parent_scope: Option<Scope<P>>,
+    // The parent we've moved to, if we're a subcomponent which has.
+    reparented: Option<ParentScope>,
     state: C,
     ui_state: Option<State<C>>,
     channel: Pin<Box<dyn Stream<Item = ComponentMessage<C>>>>,
+    rendered: bool,
+    last_render: Option<C>,
+    parent: Option<Object>,
+    shared_root: Option<Rc<RefCell<Object>>>,
+    rerender: (usize, usize),
+    theme: usize,
+    // Where to save the component's state, if sessions are enabled.
+    session_key: Option<String>,
+    // Whether the state may have changed since it was last saved.
+    updated: bool,
+    // Whether a render asked for by low priority messages is waiting for
+    // the main loop to go idle, and whether it has.
+    idle_render: bool,
+    idle: Rc<Cell<bool>>,
+    // Whether to put off renders while the widget is hidden, and the widget
+    // we're waiting to see mapped, if we are.
+    defer_hidden: bool,
+    hidden: Option<(Widget, SignalHandlerId)>,
+    revealed: Rc<Cell<bool>>,
+    // The incremental mount in progress, if there is one.
+    mount: Option<Mount<C>>,
+    // Messages the component has sent itself while it was being polled,
+    // waiting to be delivered ahead of the channel.
+    reentrant: Rc<RefCell<VecDeque<C::Message>>>,
+    // The snapshots the component has exposed with `Queryable::expose()`,
+    // which follow its state until it's destroyed.
+    exposures: Rc<RefCell<Exposures<C>>>,
+    // Once we've been unmounted, the subcomponents we're waiting on to be
+    // torn down before we can be.
+    teardown: Option<Vec<oneshot::Receiver<()>>>,
+    // Dropped along with the task, to tell whoever's waiting that we're
+    // gone.
+    done: Option<oneshot::Sender<()>>,
+}
+
+/// An incremental mount in progress, building the rest of the initial view
+/// whenever the main loop is idle.
+struct Mount<C: Component> {
+    view: VNode<C>,
+    chunk: usize,
+    placeholder: Spinner,
+    // Whether the main loop has been idle since the last chunk was built.
+    ready: Rc<Cell<bool>>,
+    // Whether a render was asked for in the meantime.
+    render: bool,
 }
 
 impl<C, P> ComponentTask<C, P>
@@ -342,78 +948,377 @@ where
         PartialComponentTask::new(props, parent, parent_scope).finalise()
     }
 
+    pub(crate) fn with_state(
+        state: C,
+        parent: Option<&Object>,
+        parent_scope: Option<&Scope<P>>,
+    ) -> (UnboundedSender<ComponentMessage<C>>, Self) {
+        PartialComponentTask::with_state(state, parent, parent_scope).finalise()
+    }
+
     fn run_job(&self, job: impl Future<Output = C::Message> + 'static) {
-        let scope = self.scope.clone();
+        // A job can outlive the component, which shouldn't be kept around
+        // just to have its result thrown away.
+        let scope = self.scope.downgrade();
         MainContext::ref_thread_default().spawn_local(async move {
-            scope.send_message(job.await);
+            if let Err(message) = scope.send_message(job.await) {
+                component_log!(
+                    Debug,
+                    scope,
+                    "{} {}: {}",
+                    scope.name().magenta().bold(),
+                    "job finished after unmounting".bright_red(),
+                    format!("{:?}", message).bright_white().bold()
+                );
+            }
         })
     }
 
+    /// Perform an `UpdateAction`, returning `true` if it asks for a render.
+    fn perform(&self, action: UpdateAction<C>) -> bool {
+        match action {
+            UpdateAction::Defer(job) => {
+                self.run_job(job);
+                false
+            }
+            UpdateAction::Render => true,
+            UpdateAction::None => false,
+            UpdateAction::Batch(actions) => {
+                // Every action has to be performed, not just the ones up to
+                // the first which renders.
+                let mut render = false;
+                for action in actions {
+                    render |= self.perform(action);
+                }
+                render
+            }
+        }
+    }
+
+    /// Get a channel which closes once this task has been torn down and
+    /// dropped.
+    pub(crate) fn finished(&mut self) -> oneshot::Receiver<()> {
+        let (done, finished) = oneshot::channel();
+        self.done = Some(done);
+        finished
+    }
+
+    /// Finish tearing the component down once its subcomponents are gone.
+    fn tear_down(&mut self, ctx: &mut Context<'_>) -> Poll<()> {
+        let children = self.teardown.get_or_insert_with(Vec::new);
+        let mut waiting = Vec::new();
+        for mut child in children.drain(..) {
+            if child.poll_unpin(ctx).is_pending() {
+                waiting.push(child);
+            }
+        }
+        if !waiting.is_empty() {
+            *children = waiting;
+            return Poll::Pending;
+        }
+        self.state.destroyed();
+        self.exposures.borrow_mut().withdraw_all();
+        self.scope.dropped();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("destroyed");
+        #[cfg(not(feature = "tracing"))]
+        component_log!(
+            Debug,
+            self.scope,
+            "{} {}",
+            "Component destroyed:".bright_red(),
+            self.scope.name().magenta().bold()
+        );
+        Poll::Ready(())
+    }
+
     pub(crate) fn process(&mut self, ctx: &mut Context<'_>) -> Poll<()> {
-        let mut render = false;
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("component", component = self.scope.name());
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        if self.teardown.is_some() {
+            return self.tear_down(ctx);
+        }
+        let mounted = self.continue_mount(ctx);
+        if !self.rendered && self.ui_state.is_some() && self.mount.is_none() {
+            // The task is first polled once its widget tree has been built.
+            self.rendered = true;
+            self.last_render = self.state.render_snapshot();
+            self.state.rendered(true);
+        }
+        // A render put off while we were hidden can't be turned down by
+        // `should_render()`, because the last one didn't happen.
+        let force =
+            self.poll_rerender(ctx) || (self.hidden.is_some() && self.revealed.replace(false));
+        let mut render = force || mounted || (self.idle_render && self.idle.replace(false));
+        // Whether anything but low priority messages asked for a render.
+        let mut urgent = render;
+        if let Some(theme) = theme::changed_since(&mut self.theme) {
+            let action = self.state.theme_changed(&theme);
+            render |= self.perform(action);
+            urgent = render;
+        }
+        for moved in self.take_moved() {
+            let action = self.state.moved(&moved);
+            let rendering = self.perform(action);
+            render |= rendering;
+            urgent |= rendering;
+        }
+        let mut batch = Vec::new();
         loop {
-            let next = Stream::poll_next(self.channel.as_mut(), ctx);
-            trace!(
+            let reentrant = self.reentrant.borrow_mut().pop_front();
+            let next = match reentrant {
+                Some(msg) => Poll::Ready(Some(ComponentMessage::Reentrant(msg))),
+                None => Stream::poll_next(self.channel.as_mut(), ctx),
+            };
+            #[cfg(feature = "tracing")]
+            tracing::trace!(next = ?next, "polled");
+            #[cfg(not(feature = "tracing"))]
+            component_log!(
+                Trace,
+                self.scope,
                 "{} {}",
                 self.scope.name().bright_black(),
                 format!("{:?}", next).bright_black().bold()
             );
+            // Gather up consecutive messages, and deliver them as a batch
+            // before anything else happens.
+            if !batch.is_empty()
+                && !matches!(
+                    next,
+                    Poll::Ready(Some(ComponentMessage::Update(_)))
+                        | Poll::Ready(Some(ComponentMessage::Reentrant(_)))
+                )
+            {
+                let mut messages = std::mem::take(&mut batch);
+                let state = &self.state;
+                messages.sort_by_key(|message| state.priority(message));
+                let low = messages
+                    .iter()
+                    .all(|message| state.priority(message) == Priority::Low);
+                #[cfg(feature = "tracing")]
+                let span =
+                    tracing::debug_span!("update", count = messages.len(), messages = ?messages);
+                #[cfg(feature = "tracing")]
+                let _entered = span.enter();
+                let object = self.object();
+                let state = &mut self.state;
+                let action = catch_panic(&self.scope, object, || state.update_batch(messages));
+                self.updated = true;
+                self.exposures.borrow().refresh(&self.state);
+                if let Some(action) = action {
+                    let rendering = self.perform(action);
+                    render |= rendering;
+                    urgent |= rendering && !low;
+                }
+            }
+            // Anything the batch sent back to us comes before rendering.
+            if matches!(next, Poll::Pending) && !self.reentrant.borrow().is_empty() {
+                continue;
+            }
             match next {
                 Poll::Ready(Some(msg)) => match msg {
-                    ComponentMessage::Update(msg) => match self.state.update(msg) {
-                        UpdateAction::Defer(job) => {
-                            self.run_job(job);
-                        }
-                        UpdateAction::Render => {
-                            render = true;
-                        }
-                        UpdateAction::None => {}
-                    },
-                    ComponentMessage::Props(props) => match self.state.change(props) {
-                        UpdateAction::Defer(job) => {
-                            self.run_job(job);
-                        }
-                        UpdateAction::Render => {
-                            render = true;
+                    ComponentMessage::Update(msg) if !self.scope.delivered() => component_log!(
+                        Debug,
+                        self.scope,
+                        "{} {}: {}",
+                        self.scope.name().magenta().bold(),
+                        "message dropped by a full queue".bright_red(),
+                        format!("{:?}", msg).bright_white().bold()
+                    ),
+                    ComponentMessage::Update(msg) | ComponentMessage::Reentrant(msg) => {
+                        match middleware::dispatch(self.scope.name(), msg) {
+                            Some(msg) => {
+                                let state = &self.state;
+                                if batch.last().is_some_and(|last| state.coalesce(last, &msg)) {
+                                    batch.pop();
+                                }
+                                batch.push(msg)
+                            }
+                            None => component_log!(
+                                Debug,
+                                self.scope,
+                                "{} {}",
+                                self.scope.name().magenta().bold(),
+                                "message dropped by middleware".bright_red()
+                            ),
                         }
-                        UpdateAction::None => {}
-                    },
+                    }
+                    ComponentMessage::Props(props) => {
+                        let action = self.state.change(props);
+                        self.updated = true;
+                        self.exposures.borrow().refresh(&self.state);
+                        let rendering = self.perform(action);
+                        render |= rendering;
+                        urgent |= rendering;
+                    }
                     ComponentMessage::Mounted => {
-                        debug!(
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("mounted");
+                        #[cfg(not(feature = "tracing"))]
+                        component_log!(
+                            Debug,
+                            self.scope,
                             "{} {}",
                             "Component mounted:".bright_blue(),
                             self.scope.name().magenta().bold()
                         );
                         self.state.mounted();
                     }
+                    ComponentMessage::Reparent(parent) => {
+                        // Callbacks from now on go to the new parent.
+                        LOCAL_CONTEXT
+                            .with(|key| key.write().unwrap().parent_scope = Some(parent()));
+                        self.reparented = Some(parent);
+                    }
                     ComponentMessage::Unmounted => {
-                        if let Some(state) = self.ui_state.take() {
-                            state.unmount();
-                        }
+                        let ui_state = self.ui_state.take();
+                        let children = vdom::teardown(|| {
+                            if let Some(state) = ui_state {
+                                state.unmount();
+                            }
+                        });
                         self.state.unmounted();
-                        debug!(
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("unmounted");
+                        #[cfg(not(feature = "tracing"))]
+                        component_log!(
+                            Debug,
+                            self.scope,
                             "{} {}",
                             "Component unmounted:".bright_red(),
                             self.scope.name().magenta().bold()
                         );
-                        return Poll::Ready(());
+                        self.teardown = Some(children);
+                        return self.tear_down(ctx);
                     }
+                    ComponentMessage::Quit => match self.state.before_quit() {
+                        ShutdownAction::Continue => shutdown::quit_now(),
+                        ShutdownAction::Veto => component_log!(
+                            Debug,
+                            self.scope,
+                            "{} {}",
+                            self.scope.name().magenta().bold(),
+                            "vetoed quitting".bright_red()
+                        ),
+                        ShutdownAction::Defer(decision) => MainContext::ref_thread_default()
+                            .spawn_local(async move {
+                                if decision.await {
+                                    shutdown::quit_now();
+                                }
+                            }),
+                    },
                 },
-                Poll::Pending if render => {
+                Poll::Pending if render && !urgent => {
+                    if !std::mem::replace(&mut self.idle_render, true) {
+                        let idle = self.idle.clone();
+                        let waker = ctx.waker().clone();
+                        glib::idle_add_local(move || {
+                            idle.set(true);
+                            waker.wake_by_ref();
+                            glib::Continue(false)
+                        });
+                    }
+                    return Poll::Pending;
+                }
+                Poll::Pending if render && self.mount.is_some() => {
+                    if let Some(ref mut mount) = self.mount {
+                        mount.render = true;
+                    }
+                    return Poll::Pending;
+                }
+                Poll::Pending if render && (force || self.should_render()) => {
+                    self.idle_render = false;
+                    if self.wait_until_shown(ctx) {
+                        return Poll::Pending;
+                    }
+                    #[cfg(feature = "tracing")]
+                    let span = tracing::debug_span!("render", outcome = tracing::field::Empty);
+                    #[cfg(feature = "tracing")]
+                    let _entered = span.enter();
                     if let Some(ref mut ui_state) = self.ui_state {
                         // we patch
-                        let new_view = self.state.view();
-                        self.scope.mute();
-                        if !ui_state.patch(&new_view, None, &self.scope) {
-                            unimplemented!(
-                                "{}: don't know how to propagate failed patch",
-                                self.scope.name()
-                            );
+                        let object = Some(ui_state.object());
+                        let state = &self.state;
+                        let name = self.scope.name();
+                        let strict = strict::Check::before_view(state);
+                        let new_view = match catch_panic(&self.scope, object, || {
+                            timed(Phase::View, name, || state.view())
+                        }) {
+                            Some(view) => view,
+                            // Keep showing the last view that worked.
+                            None => {
+                                #[cfg(feature = "tracing")]
+                                span.record("outcome", "panicked");
+                                return Poll::Pending;
+                            }
+                        };
+                        if let Some(ref strict) = strict {
+                            strict.check_view(&self.scope, &self.state);
+                        }
+                        let scope = &self.scope;
+                        diff::begin(strict.is_some());
+                        let patched = vdom::muted(|| {
+                            timed(Phase::Patch, name, || {
+                                ui_state.patch(&new_view, None, scope)
+                            })
+                        });
+                        if let Some((diff, log)) = diff::end(name) {
+                            if log {
+                                component_log!(
+                                    Debug,
+                                    self.scope,
+                                    "{} {}\n{}",
+                                    "Patched".bright_blue(),
+                                    name.magenta().bold(),
+                                    diff
+                                );
+                            }
+                            if let Some(ref strict) = strict {
+                                strict.check_patch(&self.scope, &diff, force);
+                            }
+                        }
+                        // Whether the widgets now match the view.
+                        let (outcome, succeeded) = match patched {
+                            Ok(outcome) if outcome.is_unchanged() => ("unchanged", true),
+                            Ok(_) => ("patched", true),
+                            Err(error) if error.needs_rebuild() => {
+                                let rebuilt = vdom::muted(|| {
+                                    timed(Phase::Rebuild, name, || self.rebuild(&new_view, &error))
+                                });
+                                if rebuilt {
+                                    ("rebuilt", true)
+                                } else {
+                                    ("failed", false)
+                                }
+                            }
+                            Err(error) => {
+                                self.patch_failed(&error);
+                                ("failed", false)
+                            }
+                        };
+                        #[cfg(feature = "tracing")]
+                        span.record("outcome", outcome);
+                        #[cfg(not(feature = "tracing"))]
+                        let _ = outcome;
+                        // A failed patch leaves the widgets short of what the
+                        // view wanted, which isn't a render to react to, or
+                        // one for `should_render()` to compare against.
+                        if succeeded {
+                            self.last_render = self.state.render_snapshot();
+                            self.state.rendered(false);
                         }
-                        self.scope.unmute();
                         return Poll::Pending;
                     } else {
-                        debug!(
+                        #[cfg(feature = "tracing")]
+                        span.record("outcome", "exited");
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("rendering in the absence of a UI state; exiting");
+                        #[cfg(not(feature = "tracing"))]
+                        component_log!(
+                            Debug,
+                            self.scope,
                             "{} {}",
                             self.scope.name().magenta().bold(),
                             "rendering in the absence of a UI state; exiting".bright_red()
@@ -422,20 +1327,204 @@ where
                     }
                 }
                 Poll::Ready(None) => {
-                    debug!(
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("terminating because all channel handles dropped");
+                    #[cfg(not(feature = "tracing"))]
+                    component_log!(
+                        Debug,
+                        self.scope,
                         "{} {}",
                         self.scope.name().magenta().bold(),
                         "terminating because all channel handles dropped".bright_red()
                     );
                     return Poll::Ready(());
                 }
-                Poll::Pending => return Poll::Pending,
+                Poll::Pending => {
+                    #[cfg(feature = "tracing")]
+                    {
+                        if render {
+                            tracing::debug!(outcome = "skipped", "should_render() declined");
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    /// Save the component's state to the session, if it might have changed.
+    fn save_state(&mut self) {
+        if let Some(ref key) = self.session_key {
+            if std::mem::take(&mut self.updated) {
+                session::store_component_state(key, self.state.save_state());
             }
         }
     }
 
+    /// Make sure `rerender_all()` can wake us up, and find out whether it's
+    /// been called since we last rendered.
+    fn poll_rerender(&mut self, ctx: &Context<'_>) -> bool {
+        let (task, generation) = self.rerender;
+        RERENDER.with(|rerender| {
+            let mut rerender = rerender.borrow_mut();
+            rerender.waiting.insert(task, ctx.waker().clone());
+            self.rerender.1 = rerender.generation;
+            rerender.requested.remove(&task) || rerender.generation != generation
+        })
+    }
+
+    /// Take the subcomponents which have moved into or out of our view since
+    /// we last looked.
+    fn take_moved(&self) -> Vec<Moved> {
+        let (task, _) = self.rerender;
+        RERENDER
+            .with(|rerender| rerender.borrow_mut().moved.remove(&task))
+            .unwrap_or_default()
+    }
+
+    /// Build the next chunk of an incremental mount, if one's in progress
+    /// and the main loop has been idle since the last one, returning `true`
+    /// if this finished it and a render was asked for in the meantime.
+    fn continue_mount(&mut self, ctx: &Context<'_>) -> bool {
+        let (mount, ui_state) = match (&mut self.mount, &mut self.ui_state) {
+            (Some(mount), Some(ui_state)) if mount.ready.replace(false) => (mount, ui_state),
+            _ => return false,
+        };
+        let mut budget = mount.chunk;
+        if !ui_state.build_some_children(&mount.view, &self.scope, &mut budget) {
+            let ready = mount.ready.clone();
+            let waker = ctx.waker().clone();
+            glib::idle_add_local(move || {
+                ready.set(true);
+                waker.wake_by_ref();
+                glib::Continue(false)
+            });
+            return false;
+        }
+        if let Some(container) = ui_state.object().downcast_ref::<Container>() {
+            container.remove(&mount.placeholder);
+        }
+        ui_state.attach_children(&mount.view);
+        component_log!(
+            Debug,
+            self.scope,
+            "{} {}",
+            self.scope.name().magenta().bold(),
+            "finished mounting incrementally".bright_black()
+        );
+        self.mount.take().is_some_and(|mount| mount.render)
+    }
+
+    /// Check whether the component's widget is hidden inside a window which
+    /// isn't, like on a `Stack` page which isn't showing, and if so, arrange
+    /// to render once it's shown, returning `true`.
+    fn wait_until_shown(&mut self, ctx: &Context<'_>) -> bool {
+        let widget = match self
+            .object()
+            .and_then(|object| object.downcast::<Widget>().ok())
+        {
+            Some(widget) if self.defer_hidden => widget,
+            _ => return false,
+        };
+        let hidden = !widget.get_mapped()
+            && widget
+                .get_toplevel()
+                .is_some_and(|toplevel| toplevel != widget && toplevel.get_mapped());
+        if !hidden {
+            if let Some((widget, handler)) = self.hidden.take() {
+                widget.disconnect(handler);
+            }
+            return false;
+        }
+        if !matches!(self.hidden, Some((ref waiting, _)) if *waiting == widget) {
+            if let Some((widget, handler)) = self.hidden.take() {
+                widget.disconnect(handler);
+            }
+            let revealed = self.revealed.clone();
+            let waker = ctx.waker().clone();
+            let handler = widget.connect_map(move |_| {
+                revealed.set(true);
+                waker.wake_by_ref();
+            });
+            self.hidden = Some((widget, handler));
+        }
+        component_log!(
+            Debug,
+            self.scope,
+            "{} {}",
+            self.scope.name().magenta().bold(),
+            "is hidden; rendering once it's shown".bright_black()
+        );
+        true
+    }
+
+    fn should_render(&self) -> bool {
+        match self.last_render {
+            Some(ref old) => self.state.should_render(old),
+            None => true,
+        }
+    }
+
+    /// Throw away the widget tree and build it again from scratch, because
+    /// it couldn't be patched to match the new view, returning whether it
+    /// could be.
+    fn rebuild(&mut self, view: &VNode<C>, error: &PatchError) -> bool {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("patch failed; rebuilding from scratch: {}", error);
+        #[cfg(not(feature = "tracing"))]
+        component_log!(
+            Debug,
+            self.scope,
+            "{} {} {}",
+            self.scope.name().magenta().bold(),
+            "patch failed; rebuilding from scratch:".bright_red(),
+            error
+        );
+        let old_state = self.ui_state.take().expect("rebuild without a UI state");
+        let slot = match old_state.slot(view) {
+            Ok(slot) => slot,
+            Err(error) => {
+                self.ui_state = Some(old_state);
+                self.patch_failed(&error);
+                return false;
+            }
+        };
+        let new_state = old_state.replace(slot, view, self.parent.as_ref(), &self.scope);
+        if let Some(ref shared_root) = self.shared_root {
+            // Let the parent component know where our top level object went.
+            *shared_root.borrow_mut() = new_state.object();
+        }
+        self.ui_state = Some(new_state);
+        true
+    }
+
+    /// Report part of a patch which failed, leaving the widgets it couldn't
+    /// patch as they were.
+    fn patch_failed(&mut self, error: &PatchError) {
+        component_log!(
+            Error,
+            self.scope,
+            "{} {}: {}",
+            "Patch failed:".bright_red(),
+            self.scope.name().magenta().bold(),
+            error.to_string().bright_white().bold()
+        );
+        self.state.on_patch_error(error);
+    }
+
+    /// Get a handle to this component's top level object which will be kept
+    /// up to date if the object is replaced.
+    pub(crate) fn share_root(&mut self) -> Rc<RefCell<Object>> {
+        let object = self
+            .object()
+            .expect("can't share a root without a UI state");
+        self.shared_root
+            .get_or_insert_with(|| Rc::new(RefCell::new(object)))
+            .clone()
+    }
+
     pub(crate) fn object(&self) -> Option<Object> {
-        self.ui_state.as_ref().map(|state| state.object().clone())
+        self.ui_state.as_ref().map(State::object)
     }
 
     pub(crate) fn scope(&self) -> Scope<C> {
@@ -459,6 +1548,153 @@ where
     }
 }
 
+impl<C, P> Drop for ComponentTask<C, P>
+where
+    C: Component,
+    P: Component,
+{
+    fn drop(&mut self) {
+        let (task, _) = self.rerender;
+        // This may run during thread teardown, after `RERENDER` is gone.
+        let _ = RERENDER.try_with(|rerender| {
+            let mut rerender = rerender.borrow_mut();
+            rerender.waiting.remove(&task);
+            rerender.requested.remove(&task);
+            rerender.moved.remove(&task);
+        });
+    }
+}
+
+/// In debug builds, run `f`, catching any panic and reporting it in the
+/// component's window instead of letting it take down the main loop.
+fn catch_panic<C: 'static + Component, R>(
+    scope: &Scope<C>,
+    object: Option<Object>,
+    f: impl FnOnce() -> R,
+) -> Option<R> {
+    if !cfg!(debug_assertions) {
+        return Some(f());
+    }
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            let message = error::panic_message(payload);
+            component_log!(
+                Error,
+                scope,
+                "{} {}: {}",
+                "Component panicked:".bright_red(),
+                scope.name().magenta().bold(),
+                message.bright_white().bold()
+            );
+            debug::show_panic(object.as_ref(), scope.name(), &message);
+            None
+        }
+    }
+}
+
+/// The parts of rendering the profiler times.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Phase {
+    View,
+    Patch,
+    Rebuild,
+}
+
+/// Run `f`, recording how long it took if the `profiling` feature is
+/// enabled.
+#[inline(always)]
+fn timed<R>(phase: Phase, component: &'static str, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "profiling")]
+    return crate::profile::time(phase, component, f);
+    #[cfg(not(feature = "profiling"))]
+    {
+        let _ = (phase, component);
+        f()
+    }
+}
+
+/// Component tasks waiting to be woken up by `rerender_all()`.
+#[derive(Default)]
+struct Rerender {
+    generation: usize,
+    next_task: usize,
+    waiting: HashMap<usize, Waker>,
+    // Tasks which have asked to render again through a `RenderHandle`.
+    requested: HashSet<usize>,
+    // Subcomponents which have moved into or out of tasks' views.
+    moved: HashMap<usize, Vec<Moved>>,
+}
+
+impl Rerender {
+    /// Allocate an ID for a new task, paired with the current generation.
+    fn register() -> (usize, usize) {
+        RERENDER.with(|rerender| {
+            let mut rerender = rerender.borrow_mut();
+            rerender.next_task += 1;
+            (rerender.next_task, rerender.generation)
+        })
+    }
+}
+
+thread_local! {
+    static RERENDER: RefCell<Rerender> = RefCell::new(Default::default());
+}
+
+/// Make every component on this thread render its view again, regardless of
+/// whether its state has changed, for when the view depends on something
+/// outside of it, like the current locale.
+pub(crate) fn rerender_all() {
+    RERENDER.with(|rerender| rerender.borrow_mut().generation += 1);
+    wake_all();
+}
+
+/// Wake up every component on this thread, so it can check for changes in
+/// global state like the theme.
+pub(crate) fn wake_all() {
+    let waiting = RERENDER.with(|rerender| std::mem::take(&mut rerender.borrow_mut().waiting));
+    for (_, waker) in waiting {
+        waker.wake();
+    }
+}
+
+/// A handle for making one component render its view again, regardless of
+/// whether its state has changed, for components whose view depends on
+/// something outside of it, like their allocated size.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RenderHandle(usize);
+
+impl RenderHandle {
+    /// Get a handle for the current component, if there is one.
+    pub(crate) fn current() -> Option<Self> {
+        LOCAL_CONTEXT.with(|key| key.read().unwrap().current_task.map(RenderHandle))
+    }
+
+    /// Make the component render again the next time it's polled.
+    pub(crate) fn request(&self) {
+        let waker = RERENDER.with(|rerender| {
+            let mut rerender = rerender.borrow_mut();
+            rerender.requested.insert(self.0);
+            rerender.waiting.remove(&self.0)
+        });
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Tell the component a subcomponent has moved into or out of its view.
+    pub(crate) fn report(&self, moved: Moved) {
+        let waker = RERENDER.with(|rerender| {
+            let mut rerender = rerender.borrow_mut();
+            rerender.moved.entry(self.0).or_default().push(moved);
+            rerender.waiting.remove(&self.0)
+        });
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
 /// Get the current [`Object`][Object].
 ///
 /// When called from inside a [`Component`][Component], it will return the top level [`Object`][Object]
@@ -513,10 +1749,119 @@ pub fn current_window() -> Option<Window> {
     })
 }
 
+/// Get a function which sends messages of type `M` to the current
+/// component, if there is one and its messages are of that type.
+pub(crate) fn current_sender<M: 'static>() -> Option<impl Fn(M)> {
+    let sender = LOCAL_CONTEXT.with(|key| key.read().unwrap().current_sender.clone())?;
+    if !sender.is::<Box<dyn Fn(M)>>() {
+        return None;
+    }
+    Some(move |message| (sender.downcast_ref::<Box<dyn Fn(M)>>().unwrap())(message))
+}
+
+/// Get the [`Scope`][Scope] of the current component, if there is one and
+/// it's a `C`.
+///
+/// [Scope]: ../struct.Scope.html
+pub(crate) fn current_scope<C: 'static + Component>() -> Option<Scope<C>> {
+    let scope = LOCAL_CONTEXT.with(|key| key.read().unwrap().current_scope.clone())?;
+    scope.downcast_ref::<Scope<C>>().cloned()
+}
+
+/// Hold on to a message a component is sending itself while its task is
+/// running, like from inside `update()`, returning it if it's meant for
+/// any other component, or sent from any other time or thread.
+///
+/// The task delivers these right after it's done with the message it's
+/// handling, ahead of any others waiting in its channel, and before it
+/// renders, so a component sending itself messages sees them handled in a
+/// predictable order.
+pub(crate) fn hold_reentrant<C: 'static + Component>(
+    scope: &Scope<C>,
+    message: C::Message,
+) -> Option<C::Message> {
+    let queue = LOCAL_CONTEXT.with(|key| {
+        let lock = key.read().unwrap();
+        let current = lock.current_scope.as_ref()?.downcast_ref::<Scope<C>>()?;
+        if current == scope {
+            lock.current_queue.clone()
+        } else {
+            None
+        }
+    });
+    match queue.and_then(|queue| queue.downcast::<RefCell<VecDeque<C::Message>>>().ok()) {
+        Some(queue) => {
+            queue.borrow_mut().push_back(message);
+            None
+        }
+        None => Some(message),
+    }
+}
+
+/// Keep track of the snapshots the current component exposes, if it's a
+/// `C`.
+pub(crate) fn with_exposures<C: 'static + Component>(f: impl FnOnce(&mut Exposures<C>)) {
+    let exposures = LOCAL_CONTEXT.with(|key| key.read().unwrap().current_exposures.clone());
+    let exposures = exposures.and_then(|any| any.downcast::<RefCell<Exposures<C>>>().ok());
+    if let Some(exposures) = exposures {
+        f(&mut exposures.borrow_mut());
+    }
+}
+
 #[derive(Default)]
 struct LocalContext {
     parent_scope: Option<AnyScope>,
     current_object: Option<WeakRef<Object>>,
+    // A `Box<dyn Fn(C::Message)>` sending to the current component.
+    current_sender: Option<Rc<dyn Any>>,
+    // The current component's `Scope<C>`.
+    current_scope: Option<Rc<dyn Any>>,
+    // The current component's rerender task ID.
+    current_task: Option<usize>,
+    // Where the current component's messages to itself go, a
+    // `RefCell<VecDeque<C::Message>>`.
+    current_queue: Option<Rc<dyn Any>>,
+    // The current component's `RefCell<Exposures<C>>`.
+    current_exposures: Option<Rc<dyn Any>>,
+}
+
+impl LocalContext {
+    fn new<C>(
+        scope: &Scope<C>,
+        parent_scope: Option<AnyScope>,
+        object: Option<&Object>,
+        task: usize,
+        queue: &Rc<RefCell<VecDeque<C::Message>>>,
+        exposures: &Rc<RefCell<Exposures<C>>>,
+    ) -> Self
+    where
+        C: 'static + Component,
+    {
+        LocalContext {
+            parent_scope,
+            current_object: object.map(|object| object.downgrade()),
+            current_sender: {
+                let scope = scope.clone();
+                let sender: Box<dyn Fn(C::Message)> =
+                    Box::new(move |message| scope.send_message(message));
+                Some(Rc::new(sender))
+            },
+            current_scope: Some(Rc::new(scope.clone())),
+            current_task: Some(task),
+            current_queue: Some(queue.clone()),
+            current_exposures: Some(exposures.clone()),
+        }
+    }
+
+    /// Run `f` in this context, then put back whatever context was there
+    /// before.
+    fn enter<R>(self, f: impl FnOnce() -> R) -> R {
+        let previous =
+            LOCAL_CONTEXT.with(|key| std::mem::replace(&mut *key.write().unwrap(), self));
+        let result = f();
+        LOCAL_CONTEXT.with(|key| *key.write().unwrap() = previous);
+        result
+    }
 }
 
 thread_local! {
@@ -531,19 +1876,35 @@ where
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
-        LOCAL_CONTEXT.with(|key| {
-            *key.write().unwrap() = LocalContext {
-                parent_scope: self.parent_scope.as_ref().map(|scope| scope.clone().into()),
-                current_object: self
-                    .ui_state
-                    .as_ref()
-                    .map(|state| state.object().downgrade()),
+        let object = self.ui_state.as_ref().map(|state| state.object());
+        let parent = match &self.reparented {
+            Some(parent) => Some(parent()),
+            None => self.parent_scope.clone().map(Into::into),
+        };
+        let context = LocalContext::new(
+            &self.scope,
+            parent,
+            object.as_ref(),
+            self.rerender.0,
+            &self.reentrant,
+            &self.exposures,
+        );
+        let task = self.get_mut();
+        let root = task.parent_scope.is_none();
+        context.enter(|| {
+            let polled = if root {
+                // A top level component which panics is done for.
+                error::catch_root(|| task.process(ctx)).unwrap_or(Poll::Ready(()))
+            } else {
+                task.process(ctx)
             };
-        });
-        let polled = self.get_mut().process(ctx);
-        LOCAL_CONTEXT.with(|key| {
-            *key.write().unwrap() = Default::default();
-        });
-        polled
+            task.save_state();
+            // Messages sent to ourselves while rendering are handled next
+            // time round.
+            if polled.is_pending() && !task.reentrant.borrow().is_empty() {
+                ctx.waker().wake_by_ref();
+            }
+            polled
+        })
     }
 }
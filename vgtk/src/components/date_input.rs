@@ -0,0 +1,123 @@
+use std::fmt::{Display, Error, Formatter};
+
+use gtk::{prelude::*, Calendar, MenuButton, Popover};
+
+use crate::ext::CalendarExtHelpers;
+use crate::{Callback, Component, UpdateAction, VNode};
+
+/// A calendar date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// The year.
+    pub year: u32,
+    /// The month, from 1 to 12.
+    pub month: u32,
+    /// The day of the month, from 1 to 31.
+    pub day: u32,
+}
+
+impl Date {
+    /// Construct a date.
+    pub fn new(year: u32, month: u32, day: u32) -> Self {
+        Date { year, month, day }
+    }
+
+    /// Get today's date, in the local time zone.
+    pub fn today() -> Self {
+        let now = glib::DateTime::new_now_local();
+        Date {
+            year: now.get_year() as u32,
+            month: now.get_month() as u32,
+            day: now.get_day_of_month() as u32,
+        }
+    }
+
+    fn from_calendar((year, month, day): (u32, u32, u32)) -> Self {
+        Date {
+            year,
+            month: month + 1,
+            day,
+        }
+    }
+
+    fn to_calendar(self) -> (u32, u32, u32) {
+        (self.year, self.month.saturating_sub(1), self.day)
+    }
+}
+
+impl Default for Date {
+    fn default() -> Self {
+        Date::today()
+    }
+}
+
+/// Formats the date as `YYYY-MM-DD`.
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A button showing a date, which pops up a calendar to pick a new one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DateInput {
+    /// The current date.
+    pub value: Date,
+    /// Called with the new date when the user picks one.
+    pub on_change: Callback<Date>,
+}
+
+/// Messages for [`DateInput`][DateInput].
+///
+/// [DateInput]: struct.DateInput.html
+#[derive(Clone, Debug)]
+pub enum DateInputMessage {
+    /// A day was selected in the calendar.
+    Selected(Date),
+}
+
+impl Component for DateInput {
+    type Message = DateInputMessage;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+        match message {
+            DateInputMessage::Selected(date) if date != self.value => {
+                self.value = date;
+                self.on_change.send(date);
+                UpdateAction::Render
+            }
+            DateInputMessage::Selected(_) => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <MenuButton label=self.value.to_string()>
+                <Popover>
+                    <Calendar date=self.value.to_calendar()
+                              on day_selected=|calendar| {
+                                  (DateInputMessage::Selected(Date::from_calendar(calendar.get_date())), ())
+                              }
+                              on day_selected_double_click=|calendar| {
+                                  if let Some(popover) = calendar.get_ancestor(Popover::static_type()) {
+                                      if let Ok(popover) = popover.downcast::<Popover>() {
+                                          popover.popdown();
+                                      }
+                                  }
+                                  (DateInputMessage::Selected(Date::from_calendar(calendar.get_date())), ())
+                              } />
+                </Popover>
+            </MenuButton>
+        }
+    }
+}
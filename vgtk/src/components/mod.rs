@@ -0,0 +1,38 @@
+//! Ready made subcomponents for common kinds of input, and for layouts
+//! which adapt to the space they're given.
+//!
+//! Each of these takes its current `value` as a property and reports changes
+//! through an `on change` callback, so the parent component stays in charge
+//! of the value:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::components::NumberInput;
+//! # #[derive(Clone, Debug)] enum Message { Quantity(f64) }
+//! # #[derive(Default)] struct Order { quantity: f64 }
+//! # impl Component for Order {
+//! #     type Message = Message; type Properties = ();
+//! #     fn create(_props: ()) -> Self { Self::default() }
+//! fn view(&self) -> VNode<Self> {
+//!     gtk! {
+//!         <@NumberInput value=self.quantity min=1.0 max=99.0
+//!                       on change=|quantity| Message::Quantity(quantity) />
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! The callbacks are only called when the user changes the value, not when a
+//! new value arrives from the parent.
+
+mod date_input;
+mod number_input;
+mod responsive;
+mod slider_button;
+mod time_input;
+
+pub use date_input::{Date, DateInput, DateInputMessage};
+pub use number_input::{NumberInput, NumberInputMessage};
+pub use responsive::{Breakpoint, Responsive};
+pub use slider_button::{SliderButton, SliderButtonMessage};
+pub use time_input::{Time, TimeInput, TimeInputMessage};
@@ -0,0 +1,78 @@
+use gtk::{prelude::*, SpinButton};
+
+use crate::ext::SpinButtonExtHelpers;
+use crate::{Callback, Component, UpdateAction, VNode};
+
+/// A spin button for entering a number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberInput {
+    /// The current value.
+    pub value: f64,
+    /// The smallest value allowed.
+    pub min: f64,
+    /// The largest value allowed.
+    pub max: f64,
+    /// How much the value changes with each click of the buttons.
+    pub step: f64,
+    /// The number of decimal places to show.
+    pub digits: u32,
+    /// Called with the new value when the user changes it.
+    pub on_change: Callback<f64>,
+}
+
+impl Default for NumberInput {
+    fn default() -> Self {
+        NumberInput {
+            value: 0.0,
+            min: f64::from(i32::MIN),
+            max: f64::from(i32::MAX),
+            step: 1.0,
+            digits: 0,
+            on_change: Callback::default(),
+        }
+    }
+}
+
+/// Messages for [`NumberInput`][NumberInput].
+///
+/// [NumberInput]: struct.NumberInput.html
+#[derive(Clone, Debug)]
+pub enum NumberInputMessage {
+    /// The spin button's value changed.
+    Changed(f64),
+}
+
+impl Component for NumberInput {
+    type Message = NumberInputMessage;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+        match message {
+            // Setting the value from the parent also emits `value-changed`,
+            // which we shouldn't echo back to it.
+            NumberInputMessage::Changed(value) if value != self.value => {
+                self.value = value;
+                self.on_change.send(value);
+                UpdateAction::None
+            }
+            NumberInputMessage::Changed(_) => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <SpinButton min=self.min max=self.max step=self.step digits=self.digits
+                        numeric=true value=self.value
+                        on value_changed=|spin| (NumberInputMessage::Changed(spin.get_value()), ()) />
+        }
+    }
+}
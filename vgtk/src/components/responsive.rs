@@ -0,0 +1,217 @@
+use std::cell::Cell;
+use std::fmt::{Debug, Error, Formatter};
+use std::rc::Rc;
+
+use gtk::{prelude::*, Box as GtkBox, Stack};
+
+use crate::component::{current_object, RenderHandle};
+use crate::widgets::Responsive as ResponsiveWidget;
+use crate::{Component, Scope, UpdateAction, VNode};
+
+/// One of the layouts a [`Responsive`][Responsive] can show, and the width
+/// it needs.
+///
+/// [Responsive]: struct.Responsive.html
+pub struct Breakpoint<Model: 'static + Component> {
+    min_width: i32,
+    view: Rc<dyn Fn() -> VNode<Responsive<Model>>>,
+}
+
+impl<Model: 'static + Component> Breakpoint<Model> {
+    /// A layout to show when there are at least `min_width` pixels to show
+    /// it in.
+    pub fn new<F>(min_width: i32, view: F) -> Self
+    where
+        F: Fn() -> VNode<Responsive<Model>> + 'static,
+    {
+        Breakpoint {
+            min_width,
+            view: Rc::new(view),
+        }
+    }
+}
+
+impl<Model: 'static + Component> Clone for Breakpoint<Model> {
+    fn clone(&self) -> Self {
+        Breakpoint {
+            min_width: self.min_width,
+            view: self.view.clone(),
+        }
+    }
+}
+
+impl<Model: 'static + Component> Debug for Breakpoint<Model> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_struct("Breakpoint")
+            .field("min_width", &self.min_width)
+            .finish()
+    }
+}
+
+/// A subcomponent which shows one of several layouts, depending on how wide
+/// it's been allocated.
+///
+/// Each [`Breakpoint`][Breakpoint] gives the width its layout needs, and a
+/// closure which builds it. The widest layout which fits is shown, or the
+/// narrowest if none of them do. When the width crosses a breakpoint, the
+/// `Responsive` renders the new layout by itself, without its parent having
+/// to keep track of its size.
+///
+/// The layouts are views of the `Responsive`, whose messages are
+/// `Model`'s messages, so their signal handlers produce messages for the
+/// parent component `Model` just like the rest of its view:
+///
+/// ```rust,no_run
+/// # #![recursion_limit="1024"]
+/// # use vgtk::{gtk, Component, VNode};
+/// # use vgtk::components::{Breakpoint, Responsive};
+/// # use vgtk::lib::gtk::*;
+/// # #[derive(Clone, Debug)] enum Message { Save }
+/// # #[derive(Default)] struct App;
+/// # impl Component for App {
+/// #     type Message = Message; type Properties = ();
+/// #     fn create(_props: ()) -> Self { Self::default() }
+/// fn view(&self) -> VNode<Self> {
+///     gtk! {
+///         <@Responsive<App> breakpoints=vec![
+///             Breakpoint::new(0, || gtk! {
+///                 <Box orientation=Orientation::Vertical>
+///                     <Button label="Save" on clicked=|_| Message::Save />
+///                 </Box>
+///             }),
+///             Breakpoint::new(800, || gtk! {
+///                 <Box orientation=Orientation::Horizontal>
+///                     <Button label="Save" on clicked=|_| Message::Save />
+///                 </Box>
+///             }),
+///         ] />
+///     }
+/// }
+/// # }
+/// ```
+///
+/// It's a [`widgets::Responsive`][widgets::Responsive] underneath, which
+/// does the choosing, with a breakpoint's width for each layout's
+/// `Responsive::min_width`. But where that keeps every layout in the widget
+/// tree and only shows one of them, this only builds the layout it's
+/// showing, and throws it away when it switches to another, so state held
+/// in the others doesn't survive switching. Each layout should be able to
+/// shrink to its breakpoint's width, or the `Responsive` can't get narrow
+/// enough to switch to a narrower one.
+///
+/// Until it's been allocated a size, it shows the narrowest layout.
+///
+/// [Breakpoint]: struct.Breakpoint.html
+/// [widgets::Responsive]: ../widgets/struct.Responsive.html
+pub struct Responsive<Model: 'static + Component> {
+    /// The layouts to choose from, in any order.
+    pub breakpoints: Vec<Breakpoint<Model>>,
+    // Which of the breakpoints, narrowest first, the widget is showing,
+    // which its notify handler shares with us.
+    showing: Rc<Cell<usize>>,
+}
+
+impl<Model: 'static + Component> Default for Responsive<Model> {
+    fn default() -> Self {
+        Responsive {
+            breakpoints: Vec::new(),
+            showing: Default::default(),
+        }
+    }
+}
+
+impl<Model: 'static + Component> Clone for Responsive<Model> {
+    fn clone(&self) -> Self {
+        Responsive {
+            breakpoints: self.breakpoints.clone(),
+            showing: self.showing.clone(),
+        }
+    }
+}
+
+impl<Model: 'static + Component> Debug for Responsive<Model> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_struct("Responsive")
+            .field("breakpoints", &self.breakpoints)
+            .field("showing", &self.showing.get())
+            .finish()
+    }
+}
+
+impl<Model: 'static + Component> Responsive<Model> {
+    fn set_breakpoints(&mut self, mut breakpoints: Vec<Breakpoint<Model>>) {
+        // The widget falls back to its first child when none of them fit.
+        breakpoints.sort_by_key(|breakpoint| breakpoint.min_width);
+        self.breakpoints = breakpoints;
+    }
+}
+
+impl<Model: 'static + Component> Component for Responsive<Model> {
+    type Message = Model::Message;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        let mut responsive = Responsive::default();
+        responsive.set_breakpoints(props.breakpoints);
+        responsive
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        self.set_breakpoints(props.breakpoints);
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+        // Our layouts are the parent's view, so their messages are its too.
+        Scope::<Model>::current_parent().send_message(message);
+        UpdateAction::None
+    }
+
+    fn rendered(&mut self, first_render: bool) {
+        if !first_render {
+            return;
+        }
+        let (object, handle) = match (current_object(), RenderHandle::current()) {
+            (Some(object), Some(handle)) => (object, handle),
+            _ => return,
+        };
+        let stack = match object.downcast::<Stack>() {
+            Ok(stack) => stack,
+            Err(_) => return,
+        };
+        let showing = self.showing.clone();
+        stack.connect_property_visible_child_notify(move |stack| {
+            let visible = stack.get_visible_child();
+            let index = stack
+                .get_children()
+                .iter()
+                .position(|child| Some(child) == visible.as_ref());
+            if let Some(index) = index.filter(|index| *index != showing.get()) {
+                showing.set(index);
+                handle.request();
+            }
+        });
+    }
+
+    fn view(&self) -> VNode<Self> {
+        let showing = self.showing.get();
+        let layouts = self
+            .breakpoints
+            .iter()
+            .enumerate()
+            .map(|(index, breakpoint)| {
+                let layout = (index == showing).then(|| (breakpoint.view)());
+                gtk! {
+                    <GtkBox hexpand=true homogeneous=true
+                            ResponsiveWidget::min_width=breakpoint.min_width>
+                        { layout }
+                    </GtkBox>
+                }
+            });
+        gtk! {
+            <ResponsiveWidget hexpand=true>
+                { layouts }
+            </ResponsiveWidget>
+        }
+    }
+}
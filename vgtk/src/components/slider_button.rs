@@ -0,0 +1,96 @@
+use gtk::{prelude::*, ScaleButton};
+
+use crate::ext::{ScaleButtonExtHelpers, WidgetExtHelpers};
+use crate::{Callback, Component, UpdateAction, VNode};
+
+/// An icon button which pops up a slider, for settings like brightness or
+/// zoom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SliderButton {
+    /// The name of the icon to show on the button.
+    pub icon: String,
+    /// What the slider controls, shown in the button's tooltip along with
+    /// the current value.
+    pub label: String,
+    /// The current value.
+    pub value: f64,
+    /// The smallest value allowed.
+    pub min: f64,
+    /// The largest value allowed.
+    pub max: f64,
+    /// How much the value changes with each step of the slider.
+    pub step: f64,
+    /// The number of decimal places to show in the tooltip.
+    pub digits: u32,
+    /// Called with the new value when the user changes it.
+    pub on_change: Callback<f64>,
+}
+
+impl Default for SliderButton {
+    fn default() -> Self {
+        SliderButton {
+            icon: String::new(),
+            label: String::new(),
+            value: 0.0,
+            min: 0.0,
+            max: 100.0,
+            step: 1.0,
+            digits: 0,
+            on_change: Callback::default(),
+        }
+    }
+}
+
+/// Messages for [`SliderButton`][SliderButton].
+///
+/// [SliderButton]: struct.SliderButton.html
+#[derive(Clone, Debug)]
+pub enum SliderButtonMessage {
+    /// The slider's value changed.
+    Changed(f64),
+}
+
+impl SliderButton {
+    fn tooltip(&self) -> String {
+        let value = format!("{:.*}", self.digits as usize, self.value);
+        if self.label.is_empty() {
+            value
+        } else {
+            format!("{}: {}", self.label, value)
+        }
+    }
+}
+
+impl Component for SliderButton {
+    type Message = SliderButtonMessage;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+        match message {
+            SliderButtonMessage::Changed(value) if value != self.value => {
+                self.value = value;
+                self.on_change.send(value);
+                UpdateAction::Render
+            }
+            SliderButtonMessage::Changed(_) => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <ScaleButton icon=self.icon.clone() min=self.min max=self.max step=self.step
+                         value=self.value tooltip_text=self.tooltip()
+                         accessible_name=self.label.clone()
+                         on value_changed=|_, value| (SliderButtonMessage::Changed(value), ()) />
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::{Display, Error, Formatter};
+
+use gtk::{prelude::*, Box, Inhibit, Label, Orientation, SpinButton};
+
+use crate::ext::SpinButtonExtHelpers;
+use crate::{Callback, Component, UpdateAction, VNode};
+
+/// A time of day.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    /// The hour, from 0 to 23.
+    pub hour: u32,
+    /// The minute, from 0 to 59.
+    pub minute: u32,
+}
+
+impl Time {
+    /// Construct a time.
+    pub fn new(hour: u32, minute: u32) -> Self {
+        Time { hour, minute }
+    }
+}
+
+/// Formats the time as `HH:MM`.
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// A pair of spin buttons for entering a time of day.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimeInput {
+    /// The current time.
+    pub value: Time,
+    /// Called with the new time when the user changes it.
+    pub on_change: Callback<Time>,
+}
+
+/// Messages for [`TimeInput`][TimeInput].
+///
+/// [TimeInput]: struct.TimeInput.html
+#[derive(Clone, Debug)]
+pub enum TimeInputMessage {
+    /// The hour changed.
+    Hour(u32),
+    /// The minute changed.
+    Minute(u32),
+    /// A spin button was redrawn.
+    Formatted,
+}
+
+/// Show a spin button's value with two digits.
+fn format(spin: &SpinButton) -> Inhibit {
+    spin.set_text(&format!("{:02}", spin.get_value_as_int()));
+    Inhibit(true)
+}
+
+impl Component for TimeInput {
+    type Message = TimeInputMessage;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+        let time = match message {
+            TimeInputMessage::Hour(hour) => Time { hour, ..self.value },
+            TimeInputMessage::Minute(minute) => Time {
+                minute,
+                ..self.value
+            },
+            TimeInputMessage::Formatted => return UpdateAction::None,
+        };
+        if time != self.value {
+            self.value = time;
+            self.on_change.send(time);
+        }
+        UpdateAction::None
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! {
+            <Box orientation=Orientation::Horizontal spacing=4>
+                <SpinButton min=0.0 max=23.0 step=1.0 numeric=true wrap=true
+                            value=f64::from(self.value.hour)
+                            on output=|spin| (TimeInputMessage::Formatted, format(spin))
+                            on value_changed=|spin| {
+                                (TimeInputMessage::Hour(spin.get_value_as_int() as u32), ())
+                            } />
+                <Label label=":" />
+                <SpinButton min=0.0 max=59.0 step=1.0 numeric=true wrap=true
+                            value=f64::from(self.value.minute)
+                            on output=|spin| (TimeInputMessage::Formatted, format(spin))
+                            on value_changed=|spin| {
+                                (TimeInputMessage::Minute(spin.get_value_as_int() as u32), ())
+                            } />
+            </Box>
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Per component runtime settings.
+//!
+//! A component's messages wait in a queue until its task gets around to
+//! them, and by default that queue can grow as long as it needs to. If a
+//! component can fall behind, like one receiving readings from a busy
+//! background thread, you can give it a capacity and a policy for what
+//! happens when it's full, by implementing
+//! [`Component::config()`][config]:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, ComponentConfig, VNode};
+//! # use vgtk::config::Overflow;
+//! # use vgtk::lib::gtk::Box;
+//! # #[derive(Clone, Debug)] enum Message { Reading(f64) }
+//! # #[derive(Default)] struct Sensor;
+//! impl Component for Sensor {
+//! #   type Message = Message; type Properties = ();
+//! #   fn create(_props: ()) -> Self { Self::default() }
+//!     // ...
+//!     fn config() -> ComponentConfig {
+//!         ComponentConfig::default()
+//!             .with_capacity(100)
+//!             .with_overflow(Overflow::DropOldest)
+//!     }
+//! #   fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! }
+//! ```
+//!
+//! The capacity only applies to messages sent through the component's
+//! [`Scope`][Scope], which includes those returned from signal handlers and
+//! [`UpdateAction::Defer`][Defer] jobs. Property changes from the
+//! component's parent are always delivered.
+//!
+//! Messages can also be given a [`Priority`][Priority], by implementing
+//! [`Component::priority()`][priority]. Messages waiting together are
+//! delivered in order of priority, so that, say, the user's input doesn't
+//! wait behind a pile of progress updates, and when only low priority
+//! messages ask for a render, it waits until the main loop is idle.
+//!
+//! A component which is out of sight, like one on a hidden `Stack` page or
+//! inside a collapsed `Expander`, puts off its renders until it's shown
+//! again, at which point it renders its latest state. Turn this off with
+//! [`ComponentConfig::with_defer_hidden()`][defer_hidden] for a component
+//! which has to keep its widgets up to date while they're hidden.
+//!
+//! A component with a very large view can also build its widgets a few at a
+//! time, whenever the main loop is idle, showing a spinner until they're all
+//! done, rather than holding up everything else while it builds them all at
+//! once. Turn this on with
+//! [`ComponentConfig::with_incremental_mount()`][incremental_mount].
+//!
+//! [config]: ../trait.Component.html#method.config
+//! [incremental_mount]: struct.ComponentConfig.html#method.with_incremental_mount
+//! [defer_hidden]: struct.ComponentConfig.html#method.with_defer_hidden
+//! [Scope]: ../scope/struct.Scope.html
+//! [Defer]: ../enum.UpdateAction.html#variant.Defer
+//! [Priority]: enum.Priority.html
+//! [priority]: ../trait.Component.html#method.priority
+
+use std::sync::{Condvar, Mutex};
+
+use colored::Colorize;
+use glib::MainContext;
+use log::warn;
+
+/// What to do with a message sent to a component whose queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Make room for the new message by dropping the oldest one waiting.
+    DropOldest,
+    /// Drop the new message.
+    DropNewest,
+    /// Make the sending thread wait until there's room for the message,
+    /// logging a warning when it has to.
+    ///
+    /// The main thread can't wait, because it's the one which would have to
+    /// make the room, so a message sent from the main thread is queued
+    /// regardless, with a warning.
+    Block,
+}
+
+/// How urgently a message should be handled.
+///
+/// Messages which arrive together are delivered to
+/// [`Component::update_batch()`][update_batch] highest priority first, and
+/// otherwise in the order they were sent.
+///
+/// [update_batch]: ../trait.Component.html#method.update_batch
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Messages which should be handled before any others, like those
+    /// coming from the user's input.
+    High,
+    /// The priority of every message unless the component says otherwise.
+    #[default]
+    Normal,
+    /// Messages which can wait, like progress updates from a background
+    /// job. If a batch of only low priority messages asks for a render, the
+    /// render waits until the main loop has nothing more urgent to do.
+    Low,
+}
+
+/// Runtime settings for a component.
+///
+/// The default is an unlimited message queue, and putting off renders while
+/// the component is hidden.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentConfig {
+    /// The most messages to keep waiting, or `None` for no limit.
+    pub capacity: Option<usize>,
+    /// What to do with a message sent when there are too many waiting.
+    pub overflow: Overflow,
+    /// Whether to put off rendering while the component's widget isn't
+    /// mapped, but its window is.
+    pub defer_hidden: bool,
+    /// How many widgets to build at a time when the component is first
+    /// mounted, or `None` to build them all at once.
+    pub mount_chunk: Option<usize>,
+}
+
+impl Default for ComponentConfig {
+    fn default() -> Self {
+        ComponentConfig {
+            capacity: None,
+            overflow: Overflow::DropOldest,
+            defer_hidden: true,
+            mount_chunk: None,
+        }
+    }
+}
+
+impl ComponentConfig {
+    /// Keep up to `capacity` messages waiting.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Choose what to do with a message sent when there are too many
+    /// waiting.
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Choose whether to put off rendering while the component's widget is
+    /// hidden inside a window which isn't.
+    pub fn with_defer_hidden(mut self, defer_hidden: bool) -> Self {
+        self.defer_hidden = defer_hidden;
+        self
+    }
+
+    /// Build the component's widgets `chunk` at a time when it's first
+    /// mounted, letting the main loop run in between, and show a spinner
+    /// until they're all built.
+    ///
+    /// This only applies to a component whose top level widget is a
+    /// container, which the spinner is added to. Any renders the component
+    /// asks for in the meantime are put off until it's done.
+    pub fn with_incremental_mount(mut self, chunk: usize) -> Self {
+        self.mount_chunk = Some(chunk.max(1));
+        self
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    // Messages in the channel.
+    queued: usize,
+    // How many of those to throw away as they arrive, because they were
+    // dropped to make room for newer ones.
+    dropped: usize,
+}
+
+impl Counts {
+    fn waiting(&self) -> usize {
+        self.queued - self.dropped
+    }
+}
+
+/// Keeps track of the messages waiting in a component's channel, and applies
+/// its overflow policy.
+///
+/// The channel itself is unbounded, so that a message can never be refused
+/// by it, and dropping the oldest message is done by skipping it when it
+/// arrives.
+pub(crate) struct Backlog {
+    config: ComponentConfig,
+    counts: Mutex<Counts>,
+    room: Condvar,
+}
+
+impl Backlog {
+    pub(crate) fn new(config: ComponentConfig) -> Self {
+        Backlog {
+            config,
+            counts: Default::default(),
+            room: Condvar::new(),
+        }
+    }
+
+    /// Make room for a message about to be sent to the component `name`,
+    /// returning `false` if it should be dropped instead.
+    pub(crate) fn admit(&self, name: &str) -> bool {
+        let mut counts = self.counts.lock().unwrap();
+        let capacity = match self.config.capacity {
+            Some(capacity) if counts.waiting() >= capacity => capacity,
+            _ => {
+                counts.queued += 1;
+                return true;
+            }
+        };
+        match self.config.overflow {
+            Overflow::DropOldest => {
+                warn!(
+                    "{} {}",
+                    "Message queue is full, dropping the oldest message for".bright_red(),
+                    name.magenta().bold()
+                );
+                counts.dropped += 1;
+            }
+            Overflow::DropNewest => {
+                warn!(
+                    "{} {}",
+                    "Message queue is full, dropping a message for".bright_red(),
+                    name.magenta().bold()
+                );
+                return false;
+            }
+            Overflow::Block if MainContext::default().is_owner() => warn!(
+                "{} {}",
+                "Message queue is full, but can't block the main thread sending to".bright_red(),
+                name.magenta().bold()
+            ),
+            Overflow::Block => {
+                warn!(
+                    "{} {}",
+                    "Message queue is full, blocking until there's room for".bright_red(),
+                    name.magenta().bold()
+                );
+                while counts.waiting() >= capacity {
+                    counts = self.room.wait(counts).unwrap();
+                }
+            }
+        }
+        counts.queued += 1;
+        true
+    }
+
+    /// Forget a message which was admitted but couldn't be sent.
+    pub(crate) fn cancel(&self) {
+        self.counts.lock().unwrap().queued -= 1;
+        self.room.notify_one();
+    }
+
+    /// Account for a message arriving at the component, returning `false`
+    /// if it was dropped to make room for a newer one.
+    pub(crate) fn received(&self) -> bool {
+        let mut counts = self.counts.lock().unwrap();
+        counts.queued -= 1;
+        let keep = if counts.dropped > 0 {
+            counts.dropped -= 1;
+            false
+        } else {
+            true
+        };
+        self.room.notify_one();
+        keep
+    }
+}
@@ -0,0 +1,537 @@
+//! Tools for inspecting a running application.
+//!
+//! In debug builds, a component which panics in `update()` or `view()`
+//! doesn't take the whole application down with it. Instead, the panic is
+//! logged, and shown on top of the component's window in a red overlay,
+//! along with the component's name, while the component carries on showing
+//! the last view which worked. Release builds let the panic unwind as usual.
+//!
+//! With the `tracing` feature enabled, a component's task reports what it's
+//! doing through [`tracing`][tracing] instead of the `log` crate. Each time
+//! it wakes up, it enters a `component` span with the component's name in
+//! its `component` field, and inside that an `update` span for each batch of
+//! messages, with the messages in its `messages` field, and a `render` span
+//! for each render, whose `outcome` field says whether the widget tree was
+//! `patched`, `rebuilt`, or left alone because the view `panicked`. A
+//! subscriber can filter and format these however it likes.
+//!
+//! [tracing]: https://docs.rs/tracing/
+
+use std::any::type_name;
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::os::raw::c_ulong;
+use std::sync::RwLock;
+
+use futures::channel::mpsc::UnboundedReceiver;
+use glib::translate::ToGlib;
+use glib::{Cast, IsA, Object, ObjectExt, ParamFlags, SignalHandlerId, Type};
+use gtk::prelude::WidgetExtManual;
+use gtk::{
+    Align, BinExt, Box as GtkBox, BoxExt, Button, ButtonExt, Container, ContainerExt, CssProvider,
+    CssProviderExt, Grid, GtkWindowExt, Label, LabelExt, Orientation, PackType, Popover,
+    PopoverExt, PositionType, StyleContextExt, Widget, WidgetExt, Window,
+};
+use log::{Level, LevelFilter};
+
+use crate::affinity;
+use crate::component::Component;
+use crate::ext::GridExtHelpers;
+use crate::vdom::diff;
+
+pub use crate::vdom::diff::{Change, NodeDiff, PatchDiff, Reason};
+pub use crate::vdom::error::{PatchError, PatchErrorKind};
+
+// Log level overrides set with `set_level()`, by target.
+static LEVELS: RwLock<Vec<(String, LevelFilter)>> = RwLock::new(Vec::new());
+
+/// Test whether `target` is `prefix` or one of its descendants, where a `*`
+/// in `prefix` matches any run of characters.
+fn is_under(target: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || matches(target, prefix)
+        || target
+            .match_indices("::")
+            .any(|(index, _)| matches(&target[..index], prefix))
+}
+
+/// Test whether `text` matches `pattern`, where a `*` matches any run of
+/// characters.
+fn matches(text: &str, pattern: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    if !text.starts_with(first) {
+        return false;
+    }
+    let mut rest = &text[first.len()..];
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // No wildcards.
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// How specific an override is, which is how many characters it matches
+/// exactly.
+fn specificity(prefix: &str) -> usize {
+    prefix.len() - prefix.matches('*').count()
+}
+
+/// Override how much is logged about a component, or about every component
+/// in a module.
+///
+/// Each component logs what happens to it, like the messages it receives and
+/// when it mounts and unmounts, using the full path of its type as the log
+/// target, eg. `my_app::sidebar::Sidebar`. This sets the most verbose level
+/// to log for `target` and every target under it, so `"my_app::sidebar"`
+/// covers every component in that module, and `""` covers every component.
+/// A `*` in `target` matches any run of characters, so `"*Dialog"` covers
+/// every component whose name ends in `Dialog`, wherever it is. When several
+/// overrides apply, the most specific one, the one with the most characters
+/// which aren't `*`, wins.
+///
+/// You can call this at any time, so you can turn up the logging for one
+/// noisy component while you're debugging it:
+///
+/// ```rust,no_run
+/// use log::LevelFilter;
+///
+/// vgtk::debug::set_level("", LevelFilter::Warn);
+/// vgtk::debug::set_level("my_app::sidebar::Sidebar", LevelFilter::Trace);
+/// vgtk::debug::set_level("my_app::*Dialog", LevelFilter::Debug);
+/// ```
+///
+/// To name a single component by its type, use
+/// [`set_level_for()`][set_level_for].
+///
+/// Your logger still gets the final say over what's logged, so it needs to
+/// let the more verbose messages through, eg. by running with
+/// `RUST_LOG=trace` if you're using `env_logger`.
+///
+/// [set_level_for]: fn.set_level_for.html
+pub fn set_level(target: &str, level: LevelFilter) {
+    let mut levels = LEVELS.write().unwrap();
+    match levels.iter_mut().find(|(prefix, _)| prefix == target) {
+        Some(entry) => entry.1 = level,
+        None => levels.push((target.to_string(), level)),
+    }
+    if level > log::max_level() {
+        log::set_max_level(level);
+    }
+}
+
+/// Remove an override set with [`set_level()`][set_level].
+///
+/// [set_level]: fn.set_level.html
+pub fn reset_level(target: &str) {
+    LEVELS
+        .write()
+        .unwrap()
+        .retain(|(prefix, _)| prefix != target);
+}
+
+/// Override how much is logged about component `C`.
+///
+/// This is [`set_level()`][set_level] with the component's target filled
+/// in, so it keeps working when the component moves to another module:
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, Component, VNode};
+/// # use vgtk::lib::gtk::*;
+/// use log::LevelFilter;
+///
+/// # #[derive(Default)] struct Sidebar;
+/// # impl Component for Sidebar {
+/// #     type Message = (); type Properties = ();
+/// #     fn create(_props: ()) -> Self { Self::default() }
+/// #     fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+/// # }
+/// vgtk::debug::set_level_for::<Sidebar>(LevelFilter::Trace);
+/// ```
+///
+/// [set_level]: fn.set_level.html
+pub fn set_level_for<C: Component>(level: LevelFilter) {
+    set_level(type_name::<C>(), level)
+}
+
+/// Remove an override set with [`set_level_for()`][set_level_for].
+///
+/// [set_level_for]: fn.set_level_for.html
+pub fn reset_level_for<C: Component>() {
+    reset_level(type_name::<C>())
+}
+
+/// Get the level override which applies to a target, if any.
+pub fn level(target: &str) -> Option<LevelFilter> {
+    LEVELS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(prefix, _)| is_under(target, prefix))
+        .max_by_key(|(prefix, _)| specificity(prefix))
+        .map(|(_, level)| *level)
+}
+
+/// Get the level override which applies to component `C`, if any.
+pub fn level_for<C: Component>() -> Option<LevelFilter> {
+    level(type_name::<C>())
+}
+
+/// Get every override set with [`set_level()`][set_level], in the order
+/// they were first set.
+///
+/// [set_level]: fn.set_level.html
+pub fn levels() -> Vec<(String, LevelFilter)> {
+    LEVELS.read().unwrap().clone()
+}
+
+/// Test whether a component should log at `level`, according to the
+/// overrides set with `set_level()`.
+pub(crate) fn enabled(target: &str, level: Level) -> bool {
+    self::level(target).is_none_or(|filter| level <= filter)
+}
+
+/// Log what every patch does to the widget tree.
+///
+/// When a component renders, its widget tree is patched to match the new
+/// view, which means changing properties on the widgets it can keep, adding
+/// and removing widgets, and throwing away and building again the widgets
+/// which can't be patched, like when a widget's type has changed. That last
+/// one loses any state the widget had, like its scroll position or its
+/// focus, so when that happens unexpectedly, turn this on to find out why.
+///
+/// With logging on, each patch is logged at the `Debug` level as a tree of
+/// the widgets it changed, which properties it changed on them, and why any
+/// of them had to be rebuilt:
+///
+/// ```text
+/// GtkBox
+///   GtkLabel: changed label
+///   GtkSpinner: rebuilt because type changed from GtkButton to GtkSpinner
+///   GtkButton: rebuilt because an earlier sibling was rebuilt
+///   ... 12 unchanged
+/// ```
+///
+/// Recording patches slows rendering down a little, so it's off by default.
+pub fn set_log_patches(enabled: bool) {
+    diff::set_log(enabled);
+}
+
+/// Get a [`Stream`][Stream] of every patch from now on, as a structured
+/// [`PatchDiff`][PatchDiff], for tools to inspect.
+///
+/// See [`set_log_patches()`][set_log_patches] for what's in it. Patches are
+/// only recorded while something's listening, so drop the stream when
+/// you're done with it.
+///
+/// [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+/// [PatchDiff]: struct.PatchDiff.html
+/// [set_log_patches]: fn.set_log_patches.html
+pub fn patches() -> UnboundedReceiver<PatchDiff> {
+    diff::subscribe()
+}
+
+/// Serialise a live widget tree into [`GtkBuilder`][Builder] XML.
+///
+/// Every widget is written out with its type and the properties which have
+/// been changed from their defaults, along with its packing if its parent is
+/// a [`Box`][Box] or a [`Grid`][Grid]. Properties which hold objects or other
+/// complex values are left out, as are signal handlers.
+///
+/// This is handy for reducing a bug report to a test case GTK developers can
+/// load into their own tools, or for moving a layout out of your code and
+/// into a UI file.
+///
+/// [Builder]: ../../gtk/struct.Builder.html
+/// [Box]: ../../gtk/struct.Box.html
+/// [Grid]: ../../gtk/struct.Grid.html
+pub fn export_ui_xml<W: IsA<Widget>>(widget: &W) -> String {
+    affinity::assert_owner(widget.as_ref(), "vgtk::debug::export_ui_xml");
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<interface>\n");
+    out.push_str("  <requires lib=\"gtk+\" version=\"3.24\"/>\n");
+    write_object(&mut out, widget.as_ref(), 1);
+    out.push_str("</interface>\n");
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_property(out: &mut String, depth: usize, name: &str, value: &str) {
+    indent(out, depth);
+    let _ = writeln!(
+        out,
+        "<property name=\"{}\">{}</property>",
+        name,
+        escape(value)
+    );
+}
+
+/// Render a property value as a string GtkBuilder can parse, if it's of a
+/// simple enough type.
+fn property_string(value: &glib::Value) -> Option<String> {
+    let value_type = value.type_();
+    let simple = match value_type {
+        Type::Bool
+        | Type::I8
+        | Type::U8
+        | Type::I32
+        | Type::U32
+        | Type::ILong
+        | Type::ULong
+        | Type::I64
+        | Type::U64
+        | Type::F32
+        | Type::F64
+        | Type::String => true,
+        _ => value_type.is_a(&Type::BaseEnum) || value_type.is_a(&Type::BaseFlags),
+    };
+    if simple {
+        value.transform::<String>()?.get::<String>().ok()?
+    } else {
+        None
+    }
+}
+
+fn write_object(out: &mut String, widget: &Widget, depth: usize) {
+    indent(out, depth);
+    let _ = writeln!(out, "<object class=\"{}\">", widget.get_type());
+
+    let object: &Object = widget.upcast_ref();
+    for pspec in object.list_properties() {
+        let flags = pspec.get_flags();
+        if !flags.contains(ParamFlags::READWRITE) || flags.contains(ParamFlags::DEPRECATED) {
+            continue;
+        }
+        let name = pspec.get_name();
+        let value = match object.get_property(name.as_str()) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        let default = pspec
+            .get_default_value()
+            .and_then(|value| property_string(&value));
+        if let Some(value) = property_string(&value) {
+            if Some(&value) != default.as_ref() {
+                write_property(out, depth + 1, &name, &value);
+            }
+        }
+    }
+
+    if let Some(window) = widget.downcast_ref::<Window>() {
+        if let Some(titlebar) = window.get_titlebar() {
+            indent(out, depth + 1);
+            out.push_str("<child type=\"titlebar\">\n");
+            write_object(out, &titlebar, depth + 2);
+            indent(out, depth + 1);
+            out.push_str("</child>\n");
+        }
+    }
+    if let Some(container) = widget.downcast_ref::<Container>() {
+        for child in container.get_children() {
+            indent(out, depth + 1);
+            out.push_str("<child>\n");
+            write_object(out, &child, depth + 2);
+            write_packing(out, container, &child, depth + 2);
+            indent(out, depth + 1);
+            out.push_str("</child>\n");
+        }
+    }
+
+    indent(out, depth);
+    out.push_str("</object>\n");
+}
+
+fn write_packing(out: &mut String, container: &Container, child: &Widget, depth: usize) {
+    let mut packing = Vec::new();
+    if let Some(box_) = container.downcast_ref::<GtkBox>() {
+        let (expand, fill, padding, pack_type) = box_.query_child_packing(child);
+        packing.push(("expand", expand.to_string()));
+        packing.push(("fill", fill.to_string()));
+        packing.push(("padding", padding.to_string()));
+        if pack_type == PackType::End {
+            packing.push(("pack_type", "end".to_string()));
+        }
+    } else if let Some(grid) = container.downcast_ref::<Grid>() {
+        let position = grid.get_child_position(child);
+        packing.push(("left_attach", position.left.to_string()));
+        packing.push(("top_attach", position.top.to_string()));
+        packing.push(("width", position.width.to_string()));
+        packing.push(("height", position.height.to_string()));
+    }
+    if packing.is_empty() {
+        return;
+    }
+    indent(out, depth);
+    out.push_str("<packing>\n");
+    for (name, value) in packing {
+        write_property(out, depth + 1, name, &value);
+    }
+    indent(out, depth);
+    out.push_str("</packing>\n");
+}
+
+const HANDLERS_KEY: &str = "vgtk-handlers";
+
+/// A signal handler the `gtk!` macro connected to an object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Connection {
+    /// The signal's name, as it's spelled in the `on` attribute, like
+    /// `clicked` or `notify_text`.
+    pub signal: &'static str,
+    /// The handler's ID, which says where in the source it was declared.
+    pub id: String,
+    /// The ID GLib gave the connection.
+    pub handler: c_ulong,
+}
+
+/// List the signal handlers connected to an object for its current view,
+/// in the order they were declared.
+///
+/// These are kept up to date with every render, so a handler whose `on`
+/// attribute is gone from the view is gone from the list too. Connections
+/// are only recorded in debug builds, so in a release build this is always
+/// empty.
+pub fn handlers<O: IsA<Object>>(object: &O) -> Vec<Connection> {
+    affinity::assert_owner(object.as_ref(), "vgtk::debug::handlers");
+    #[allow(unsafe_code)]
+    unsafe { object.as_ref().get_data::<Vec<Connection>>(HANDLERS_KEY) }
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub(crate) fn record_handlers(
+    object: &Object,
+    handlers: &[(&'static str, Cow<'static, str>, SignalHandlerId)],
+) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let connections: Vec<Connection> = handlers
+        .iter()
+        .map(|&(signal, ref id, ref handle)| Connection {
+            signal,
+            id: id.to_string(),
+            handler: handle.to_glib(),
+        })
+        .collect();
+    #[allow(unsafe_code)]
+    unsafe {
+        object.set_data(HANDLERS_KEY, connections);
+    }
+}
+
+const PANIC_OVERLAY_KEY: &str = "vgtk-panic-overlay";
+
+const PANIC_OVERLAY_CSS: &str = "
+popover.vgtk-panic {
+    background-color: #a51d2d;
+    color: #ffffff;
+}
+";
+
+/// Show a component's panic on top of its window.
+///
+/// This replaces any panic shown in the window before.
+pub(crate) fn show_panic(object: Option<&Object>, component: &str, message: &str) {
+    let window = object
+        .and_then(|object| object.downcast_ref::<Widget>())
+        .and_then(|widget| widget.get_toplevel())
+        .and_then(|toplevel| toplevel.downcast::<Window>().ok())
+        .or_else(crate::current_window);
+    let anchor = match window.as_ref().and_then(BinExt::get_child) {
+        Some(anchor) => anchor,
+        None => return,
+    };
+    let window = window.unwrap();
+
+    #[allow(unsafe_code)]
+    unsafe {
+        if let Some(old) = window.get_data::<Popover>(PANIC_OVERLAY_KEY) {
+            old.destroy();
+        }
+    }
+
+    let popover = Popover::new(Some(&anchor));
+    popover.set_modal(false);
+    popover.set_position(PositionType::Bottom);
+    popover.set_pointing_to(&gdk::Rectangle {
+        x: anchor.get_allocated_width() / 2,
+        y: 0,
+        width: 1,
+        height: 1,
+    });
+    let style = popover.get_style_context();
+    style.add_class("vgtk-panic");
+    let provider = CssProvider::new();
+    if provider
+        .load_from_data(PANIC_OVERLAY_CSS.as_bytes())
+        .is_ok()
+    {
+        style.add_provider(&provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    }
+
+    let content = GtkBox::new(Orientation::Vertical, 6);
+    content.set_border_width(12);
+    let heading = Label::new(None);
+    heading.set_markup(&format!(
+        "<b>{} panicked</b>",
+        glib::markup_escape_text(component)
+    ));
+    heading.set_halign(Align::Start);
+    content.add(&heading);
+    let text = Label::new(Some(message));
+    text.set_halign(Align::Start);
+    text.set_xalign(0.0);
+    text.set_line_wrap(true);
+    text.set_max_width_chars(80);
+    text.set_selectable(true);
+    content.add(&text);
+    let hint = Label::new(Some(
+        "The component is showing the last view that worked. \
+         See the terminal for more about the panic.",
+    ));
+    hint.set_halign(Align::Start);
+    hint.set_line_wrap(true);
+    hint.set_max_width_chars(80);
+    content.add(&hint);
+    let dismiss = Button::with_label("Dismiss");
+    dismiss.set_halign(Align::End);
+    let weak = popover.downgrade();
+    dismiss.connect_clicked(move |_| {
+        if let Some(popover) = weak.upgrade() {
+            #[allow(unsafe_code)]
+            unsafe {
+                popover.destroy();
+            }
+        }
+    });
+    content.add(&dismiss);
+    popover.add(&content);
+    content.show_all();
+    popover.popup();
+
+    #[allow(unsafe_code)]
+    unsafe {
+        window.set_data(PANIC_OVERLAY_KEY, popover);
+    }
+}
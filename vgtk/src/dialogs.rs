@@ -0,0 +1,234 @@
+//! Common dialogs as one-liners.
+//!
+//! [`alert()`][alert], [`confirm()`][confirm] and [`prompt()`][prompt] open
+//! a modal message dialog on top of the current window, and return a
+//! [`Future`][Future] which resolves to the user's answer, so they fit
+//! right into [`UpdateAction::defer()`][defer]:
+//!
+//! ```rust,no_run
+//! # use vgtk::{Component, UpdateAction, VNode, dialogs};
+//! # #[derive(Clone, Debug)] enum Message { Delete, Confirmed(bool) }
+//! # #[derive(Default)] struct Files;
+//! # impl Component for Files {
+//! #     type Message = Message; type Properties = ();
+//! #     fn create(_props: ()) -> Self { Self::default() }
+//! fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!     match message {
+//!         Message::Delete => {
+//!             let confirmed = dialogs::confirm(
+//!                 dialogs::Message::new("Delete this file?")
+//!                     .secondary("You can't undo this.")
+//!                     .accept_label("_Delete")
+//!                     .destructive(),
+//!             );
+//!             UpdateAction::defer(async { Message::Confirmed(confirmed.await) })
+//!         }
+//!         Message::Confirmed(true) => {
+//!             // ...
+//! #           UpdateAction::None
+//!         }
+//!         Message::Confirmed(false) => UpdateAction::None,
+//!     }
+//! }
+//! # fn view(&self) -> VNode<Self> { unimplemented!() }
+//! # }
+//! ```
+//!
+//! Plain strings work too, eg. `dialogs::alert("Saved.")`.
+//!
+//! The dialog is set up when you call the function, so call it from
+//! `update()` rather than inside an `async` block, where there's no current
+//! window to open it on top of. It's shown once the future is first polled.
+//!
+//! [alert]: fn.alert.html
+//! [confirm]: fn.confirm.html
+//! [prompt]: fn.prompt.html
+//! [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
+//! [defer]: ../enum.UpdateAction.html#method.defer
+
+use std::cell::Cell;
+use std::future::Future;
+
+use futures::channel::oneshot;
+use glib::{Cast, IsA};
+use gtk::prelude::*;
+use gtk::{
+    Box as GtkBox, ButtonsType, DialogFlags, Entry, MessageDialog, MessageType, ResponseType,
+    Window,
+};
+
+use crate::i18n;
+
+/// The contents of a message dialog.
+///
+/// The text is shown in bold, with any secondary text below it.
+#[derive(Clone, Debug, Default)]
+pub struct Message {
+    text: String,
+    secondary: Option<String>,
+    markup: bool,
+    message_type: Option<MessageType>,
+    accept_label: Option<String>,
+    destructive: bool,
+    parent: Option<Window>,
+}
+
+impl Message {
+    /// Construct a message with plain text.
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Message {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Construct a message with Pango markup, which applies to its secondary
+    /// text as well.
+    pub fn markup<S: Into<String>>(markup: S) -> Self {
+        Message {
+            text: markup.into(),
+            markup: true,
+            ..Default::default()
+        }
+    }
+
+    /// Add secondary text, explaining the message in more detail.
+    pub fn secondary<S: Into<String>>(mut self, text: S) -> Self {
+        self.secondary = Some(text.into());
+        self
+    }
+
+    /// Set the kind of message, which determines the dialog's icon.
+    ///
+    /// [`alert()`][alert] defaults to `MessageType::Info`, and the other
+    /// dialogs to `MessageType::Question`.
+    ///
+    /// [alert]: fn.alert.html
+    pub fn message_type(mut self, message_type: MessageType) -> Self {
+        self.message_type = Some(message_type);
+        self
+    }
+
+    /// Set the label of the button that accepts the dialog, instead of "OK".
+    ///
+    /// Prefix a letter with `_` to make it the button's mnemonic.
+    pub fn accept_label<S: Into<String>>(mut self, label: S) -> Self {
+        self.accept_label = Some(label.into());
+        self
+    }
+
+    /// Mark accepting the dialog as destructive, like deleting something.
+    ///
+    /// The accept button is styled as a warning, and stops being the
+    /// default, so pressing Enter cancels instead.
+    pub fn destructive(mut self) -> Self {
+        self.destructive = true;
+        self
+    }
+
+    /// Set the window to open the dialog on top of, instead of the current
+    /// window.
+    pub fn parent<W: IsA<Window>>(mut self, parent: &W) -> Self {
+        self.parent = Some(parent.clone().upcast());
+        self
+    }
+
+    fn build(self, message_type: MessageType, cancel: bool) -> MessageDialog {
+        let parent = self.parent.or_else(crate::current_window);
+        let dialog = MessageDialog::new(
+            parent.as_ref(),
+            DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+            self.message_type.unwrap_or(message_type),
+            ButtonsType::None,
+            "",
+        );
+        if self.markup {
+            dialog.set_markup(&self.text);
+        } else {
+            dialog.set_property_text(Some(&self.text));
+        }
+        if let Some(secondary) = &self.secondary {
+            dialog.set_property_secondary_text(Some(secondary));
+            dialog.set_property_secondary_use_markup(self.markup);
+        }
+        if cancel {
+            dialog.add_button(&i18n::dgettext("gtk30", "_Cancel"), ResponseType::Cancel);
+        }
+        let accept_label = self
+            .accept_label
+            .unwrap_or_else(|| i18n::dgettext("gtk30", "_OK"));
+        let accept = dialog.add_button(&accept_label, ResponseType::Ok);
+        if self.destructive && cancel {
+            accept
+                .get_style_context()
+                .add_class(&gtk::STYLE_CLASS_DESTRUCTIVE_ACTION);
+            dialog.set_default_response(ResponseType::Cancel);
+        } else {
+            dialog.set_default_response(ResponseType::Ok);
+        }
+        dialog
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Message::new(text)
+    }
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Message::new(text)
+    }
+}
+
+async fn run(dialog: MessageDialog) -> ResponseType {
+    let (notify, response) = oneshot::channel();
+    let notify = Cell::new(Some(notify));
+    dialog.connect_response(move |_, response| {
+        if let Some(notify) = notify.take() {
+            let _ = notify.send(response);
+        }
+    });
+    dialog.present();
+    let response = response.await.unwrap_or(ResponseType::DeleteEvent);
+    dialog.close();
+    response
+}
+
+/// Show a message, with an "OK" button to dismiss it.
+pub fn alert<M: Into<Message>>(message: M) -> impl Future<Output = ()> {
+    let dialog = message.into().build(MessageType::Info, false);
+    async move {
+        run(dialog).await;
+    }
+}
+
+/// Ask the user to confirm something, resolving to `true` if they do, or
+/// `false` if they cancel or close the dialog.
+pub fn confirm<M: Into<Message>>(message: M) -> impl Future<Output = bool> {
+    let dialog = message.into().build(MessageType::Question, true);
+    async move { run(dialog).await == ResponseType::Ok }
+}
+
+/// Ask the user to enter some text, starting out as `initial`, resolving to
+/// `None` if they cancel or close the dialog.
+pub fn prompt<M: Into<Message>>(message: M, initial: &str) -> impl Future<Output = Option<String>> {
+    let dialog = message.into().build(MessageType::Question, true);
+    let entry = Entry::new();
+    entry.set_text(initial);
+    entry.set_activates_default(true);
+    if let Some(area) = dialog
+        .get_message_area()
+        .and_then(|area| area.downcast::<GtkBox>().ok())
+    {
+        area.pack_start(&entry, false, false, 0);
+        entry.show();
+    }
+    async move {
+        match run(dialog).await {
+            ResponseType::Ok => Some(entry.get_text().to_string()),
+            _ => None,
+        }
+    }
+}
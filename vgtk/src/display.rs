@@ -0,0 +1,295 @@
+//! The monitors the app is shown on, and the windows it shows there.
+//!
+//! Layouts which change with the space they have, like switching between a
+//! narrow and a wide view, or showing a sidebar only on a big enough screen,
+//! need to know about the monitors and windows they're on, and need to know
+//! when that changes.
+//!
+//! [`monitors()`][monitors] describes the monitors the display has. When one
+//! is plugged in or unplugged, or one's geometry or scale factor changes,
+//! every component renders again, so views can just ask for it:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Box, Orientation};
+//! # fn view() -> VNode<()> {
+//! let wide = vgtk::display::primary_monitor()
+//!     .map(|monitor| monitor.workarea.width >= 1200)
+//!     .unwrap_or(true);
+//! gtk! {
+//!     <Box orientation={ if wide { Orientation::Horizontal } else { Orientation::Vertical } } />
+//! }
+//! # }
+//! ```
+//!
+//! Windows change size far more often than monitors do, so rather than
+//! render everything every time, [`window_changes()`][window_changes] gives
+//! you a [`Stream`][Stream] of a window's [`WindowGeometry`][WindowGeometry]
+//! to turn into messages for the component it belongs to, eg. with a
+//! [`SignalGovernor`][SignalGovernor] so you don't get one for every pixel
+//! the user drags the window's edge:
+//!
+//! ```rust,no_run
+//! # use std::time::Duration;
+//! # use vgtk::display::WindowGeometry;
+//! # use vgtk::governor::{Policy, SignalGovernor};
+//! # use vgtk::lib::gtk::Window;
+//! # use vgtk::Scope;
+//! # #[derive(Default)] struct App;
+//! # #[derive(Clone, Debug)] enum Message { Resized(WindowGeometry) }
+//! # impl vgtk::Component for App { type Message = Message; type Properties = ();
+//! # fn create(_props: ()) -> Self { Self::default() }
+//! # fn view(&self) -> vgtk::VNode<Self> { unimplemented!() } }
+//! # fn example(scope: Scope<App>, window: &Window) {
+//! SignalGovernor::new(scope).subscribe(
+//!     vgtk::display::window_changes(window),
+//!     Policy::Debounce(Duration::from_millis(100)),
+//!     |mut geometries| Message::Resized(geometries.remove(0)),
+//! );
+//! # }
+//! ```
+//!
+//! For use outside of components, [`changes()`][changes] gives you a
+//! [`Stream`][Stream] of the monitors every time they change.
+//!
+//! [monitors]: fn.monitors.html
+//! [window_changes]: fn.window_changes.html
+//! [changes]: fn.changes.html
+//! [WindowGeometry]: struct.WindowGeometry.html
+//! [SignalGovernor]: ../governor/struct.SignalGovernor.html
+//! [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use gdk::{Display, Rectangle, WindowExt as GdkWindowExt};
+use glib::{IsA, ObjectExt};
+use gtk::{GtkWindowExt, WidgetExt, Window};
+
+use colored::Colorize;
+use log::debug;
+
+use crate::component::rerender_all;
+
+/// One of the display's monitors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    /// The monitor's model name, if it has one.
+    pub model: Option<String>,
+    /// The name of the monitor's manufacturer, if it has one.
+    pub manufacturer: Option<String>,
+    /// Where the monitor is, and how big it is, in application pixels.
+    pub geometry: Rectangle,
+    /// The part of the monitor windows can go in, without the panels and
+    /// docks the desktop keeps there.
+    pub workarea: Rectangle,
+    /// The number of device pixels to each application pixel, which is 2 on
+    /// a HiDPI monitor.
+    pub scale_factor: i32,
+    /// The refresh rate in millihertz, or 0 if it's unknown.
+    pub refresh_rate: i32,
+    /// Whether this is the primary monitor.
+    pub primary: bool,
+}
+
+impl Monitor {
+    fn read(monitor: &gdk::Monitor) -> Self {
+        Monitor {
+            model: monitor.get_model().map(|model| model.to_string()),
+            manufacturer: monitor.get_manufacturer().map(|name| name.to_string()),
+            geometry: monitor.get_geometry(),
+            workarea: monitor.get_workarea(),
+            scale_factor: monitor.get_scale_factor(),
+            refresh_rate: monitor.get_refresh_rate(),
+            primary: monitor.is_primary(),
+        }
+    }
+}
+
+/// Where a window is, how big it is, and which monitor it's on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowGeometry {
+    /// The window's position, in root window coordinates. Some windowing
+    /// systems, like Wayland, don't tell applications where their windows
+    /// are, in which case this is always `(0, 0)`.
+    pub position: (i32, i32),
+    /// The window's width, in application pixels.
+    pub width: i32,
+    /// The window's height, in application pixels.
+    pub height: i32,
+    /// The scale factor of the monitor the window is on.
+    pub scale_factor: i32,
+    /// The index into [`monitors()`][monitors] of the monitor the window is
+    /// on, if it's been shown.
+    ///
+    /// [monitors]: fn.monitors.html
+    pub monitor: Option<usize>,
+}
+
+#[derive(Default)]
+struct Watch {
+    started: bool,
+    current: Vec<Monitor>,
+    subscribers: Vec<UnboundedSender<Vec<Monitor>>>,
+}
+
+thread_local! {
+    static WATCH: RefCell<Watch> = RefCell::new(Default::default());
+}
+
+fn gdk_monitors(display: &Display) -> Vec<gdk::Monitor> {
+    (0..display.get_n_monitors())
+        .filter_map(|index| display.get_monitor(index))
+        .collect()
+}
+
+fn read_monitors() -> Vec<Monitor> {
+    Display::get_default()
+        .map(|display| gdk_monitors(&display).iter().map(Monitor::read).collect())
+        .unwrap_or_default()
+}
+
+/// Check whether the monitors have changed, and notify everyone if they have.
+fn refresh() {
+    let monitors = read_monitors();
+    let changed = WATCH.with(|watch| {
+        let mut watch = watch.borrow_mut();
+        if watch.current == monitors {
+            return false;
+        }
+        debug!(
+            "{} {} monitors",
+            "Display changed:".bright_blue(),
+            monitors.len()
+        );
+        watch.current = monitors.clone();
+        watch
+            .subscribers
+            .retain(|subscriber| subscriber.unbounded_send(monitors.clone()).is_ok());
+        true
+    });
+    if changed {
+        rerender_all();
+    }
+}
+
+fn watch_monitor(monitor: &gdk::Monitor) {
+    monitor.connect_property_geometry_notify(|_| refresh());
+    monitor.connect_property_workarea_notify(|_| refresh());
+    monitor.connect_property_scale_factor_notify(|_| refresh());
+    monitor.connect_property_refresh_rate_notify(|_| refresh());
+}
+
+/// Start listening for changes to the monitors, if we aren't already.
+fn watch() {
+    let started = WATCH.with(|watch| {
+        let mut watch = watch.borrow_mut();
+        let started = watch.started;
+        if !started {
+            watch.started = true;
+            watch.current = read_monitors();
+        }
+        started
+    });
+    if started {
+        return;
+    }
+    if let Some(display) = Display::get_default() {
+        for monitor in gdk_monitors(&display) {
+            watch_monitor(&monitor);
+        }
+        display.connect_monitor_added(|_, monitor| {
+            watch_monitor(monitor);
+            refresh();
+        });
+        display.connect_monitor_removed(|_, _| refresh());
+    }
+}
+
+/// Get the display's monitors.
+pub fn monitors() -> Vec<Monitor> {
+    watch();
+    WATCH.with(|watch| watch.borrow().current.clone())
+}
+
+/// Get the primary monitor, or the first one if none of them is primary.
+pub fn primary_monitor() -> Option<Monitor> {
+    let monitors = monitors();
+    monitors
+        .iter()
+        .find(|monitor| monitor.primary)
+        .or_else(|| monitors.first())
+        .cloned()
+}
+
+/// Get a [`Stream`][Stream] which produces the monitors every time they
+/// change.
+///
+/// [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+pub fn changes() -> UnboundedReceiver<Vec<Monitor>> {
+    watch();
+    let (sender, receiver) = unbounded();
+    WATCH.with(|watch| watch.borrow_mut().subscribers.push(sender));
+    receiver
+}
+
+/// Get a window's current geometry.
+pub fn window_geometry<W: IsA<Window>>(window: &W) -> WindowGeometry {
+    let window = window.as_ref();
+    let (width, height) = window.get_size();
+    let monitor = window.get_window().and_then(|gdk_window| {
+        let display = gdk_window.get_display();
+        let current = display.get_monitor_at_window(&gdk_window)?;
+        gdk_monitors(&display)
+            .iter()
+            .position(|monitor| *monitor == current)
+    });
+    WindowGeometry {
+        position: window.get_position(),
+        width,
+        height,
+        scale_factor: window.get_scale_factor(),
+        monitor,
+    }
+}
+
+/// Get a [`Stream`][Stream] which produces a window's geometry every time it
+/// moves, changes size, or moves to a monitor with a different scale factor.
+///
+/// The stream ends when the window is destroyed, and the window stops
+/// reporting its changes once you drop the stream.
+///
+/// [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+pub fn window_changes<W: IsA<Window>>(window: &W) -> UnboundedReceiver<WindowGeometry> {
+    let (sender, receiver) = unbounded();
+    let last = Rc::new(RefCell::new(window_geometry(window)));
+    let handlers = Rc::new(RefCell::new(Vec::new()));
+    let update = {
+        let handlers = handlers.clone();
+        move |window: &Window| {
+            let geometry = window_geometry(window);
+            if *last.borrow() == geometry {
+                return;
+            }
+            *last.borrow_mut() = geometry;
+            if sender.unbounded_send(geometry).is_err() {
+                for handler in handlers.borrow_mut().drain(..) {
+                    window.disconnect(handler);
+                }
+            }
+        }
+    };
+    let update = Rc::new(update);
+    let window = window.as_ref();
+    let on_configure = update.clone();
+    let on_scale = update.clone();
+    handlers.borrow_mut().extend(vec![
+        window.connect_configure_event(move |window, _| {
+            on_configure(window);
+            false
+        }),
+        window.connect_property_scale_factor_notify(move |window| on_scale(window)),
+    ]);
+    receiver
+}
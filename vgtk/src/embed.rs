@@ -0,0 +1,139 @@
+//! Mounting components inside an application which isn't built with vgtk.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::channel::mpsc::UnboundedSender;
+use glib::{Cast, IsA, MainContext, Object, ObjectExt};
+use gtk::{Container, ContainerExt, Widget};
+
+use crate::affinity;
+use crate::component::{Component, ComponentMessage, ComponentTask};
+use crate::scope::Scope;
+
+/// Mount a component inside a [`Container`][Container] which vgtk doesn't
+/// own, creating it with the given properties.
+///
+/// This is how to start using vgtk in an existing GTK application one part
+/// at a time. The component's top level widget is added to `container`, and
+/// it runs the same as it would anywhere else, on the application's main
+/// loop, until the [`MountHandle`][MountHandle] is dropped, which tears it
+/// down and removes its widget again.
+///
+/// The component's top level object must be a widget, or this function will
+/// panic. It must be called on the thread GTK runs on, after GTK has been
+/// initialised.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, Component, VNode};
+/// # use vgtk::lib::gtk::*;
+/// # #[derive(Clone, Debug)] enum Message { Refresh }
+/// # #[derive(Clone, Default)] struct Sidebar;
+/// # impl Component for Sidebar {
+/// #     type Message = Message; type Properties = Self;
+/// #     fn create(props: Self) -> Self { props }
+/// #     fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+/// # }
+/// # fn legacy(pane: &Paned) {
+/// // In the existing application:
+/// let handle = vgtk::mount::<Sidebar>(pane, Sidebar::default());
+/// handle.send(Message::Refresh);
+/// // Later, when the pane goes away:
+/// drop(handle);
+/// # }
+/// ```
+///
+/// [Container]: ../gtk/struct.Container.html
+/// [MountHandle]: struct.MountHandle.html
+pub fn mount<C: 'static + Component>(
+    container: &impl IsA<Container>,
+    props: C::Properties,
+) -> MountHandle<C> {
+    mount_with_state(container, C::create(props))
+}
+
+/// Mount a component inside a [`Container`][Container] which vgtk doesn't
+/// own, starting from the given state.
+///
+/// See [`vgtk::mount()`][mount].
+///
+/// [Container]: ../gtk/struct.Container.html
+/// [mount]: fn.mount.html
+pub fn mount_with_state<C: 'static + Component>(
+    container: &impl IsA<Container>,
+    initial: C,
+) -> MountHandle<C> {
+    affinity::claim();
+    let container: &Container = container.as_ref();
+    let (channel, mut task) =
+        ComponentTask::<C, ()>::with_state(initial, Some(container.upcast_ref()), None);
+    let root = task.share_root();
+    let widget: Widget = root.borrow().clone().downcast().unwrap_or_else(|object| {
+        panic!(
+            "The top level object of a mounted component must be a Widget, but {} was found.",
+            object.get_type()
+        )
+    });
+    container.add(&widget);
+    let scope = task.scope();
+    MainContext::ref_thread_default().spawn_local(task);
+    channel.unbounded_send(ComponentMessage::Mounted).unwrap();
+    MountHandle {
+        channel,
+        scope,
+        root,
+    }
+}
+
+/// A component mounted with [`vgtk::mount()`][mount].
+///
+/// Dropping the handle unmounts the component, and removes its widget from
+/// the container it was mounted in.
+///
+/// [mount]: fn.mount.html
+pub struct MountHandle<C: Component> {
+    channel: UnboundedSender<ComponentMessage<C>>,
+    scope: Scope<C>,
+    root: Rc<RefCell<Object>>,
+}
+
+impl<C: 'static + Component> MountHandle<C> {
+    /// Send the component a message.
+    pub fn send(&self, message: C::Message) {
+        // The task may have stopped if it panicked, which has been reported.
+        let _ = self.scope.try_send(message);
+    }
+
+    /// Get the component's [`Scope`][Scope], to send it messages from
+    /// elsewhere, like another thread.
+    ///
+    /// [Scope]: struct.Scope.html
+    pub fn scope(&self) -> Scope<C> {
+        self.scope.clone()
+    }
+
+    /// Give the component new properties, the way a parent component would
+    /// when it renders.
+    pub fn set_props(&self, props: C::Properties) {
+        let _ = self.channel.unbounded_send(ComponentMessage::Props(props));
+    }
+
+    /// Get the component's top level widget.
+    ///
+    /// This can change over the component's lifetime, if it has to rebuild
+    /// its widget tree from scratch.
+    pub fn widget(&self) -> Widget {
+        self.root.borrow().clone().downcast().unwrap()
+    }
+
+    /// Unmount the component, the same as dropping the handle.
+    pub fn unmount(self) {}
+}
+
+impl<C: Component> Drop for MountHandle<C> {
+    fn drop(&mut self) {
+        let _ = self.channel.unbounded_send(ComponentMessage::Unmounted);
+    }
+}
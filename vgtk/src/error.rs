@@ -0,0 +1,105 @@
+//! Errors from starting and running an application.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
+
+use gio::prelude::*;
+
+/// Why an application couldn't be started, or stopped running, as returned
+/// from [`vgtk::try_run()`][try_run] and friends.
+///
+/// [try_run]: fn.try_run.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// GTK couldn't be initialised, usually because there's no display to
+    /// connect to.
+    Init(String),
+    /// The top level component's view doesn't have an
+    /// [`Application`][Application] at the top, but an object of the named
+    /// type.
+    ///
+    /// [Application]: ../gtk/struct.Application.html
+    NotAnApplication(String),
+    /// The [`Application`][Application] couldn't be registered, like when
+    /// its ID isn't valid or the session bus can't be reached.
+    ///
+    /// [Application]: ../gtk/struct.Application.html
+    Register(String),
+    /// The top level component panicked, with the panic's message.
+    Panic(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Init(reason) => write!(f, "GTK failed to initialise: {}", reason),
+            Error::NotAnApplication(found) => write!(
+                f,
+                "The top level object must be an Application, but {} was found.",
+                found
+            ),
+            Error::Register(reason) => write!(f, "unable to register Application: {}", reason),
+            Error::Panic(message) => write!(f, "the top level component panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+thread_local! {
+    // Whether to catch panics in top level components, and the first one
+    // caught.
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    static PANICKED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Get the message a panic was raised with.
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "Box<dyn Any>".to_string(),
+        },
+    }
+}
+
+/// Run `f`, turning a panic into an error.
+pub(crate) fn catch<R>(f: impl FnOnce() -> R) -> Result<R, Error> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| Error::Panic(panic_message(payload)))
+}
+
+/// Run `f`, catching any panic in a top level component along the way,
+/// which ends the application, and return the first one's message.
+pub(crate) fn catching<R>(f: impl FnOnce() -> R) -> (R, Option<String>) {
+    let outer = CATCHING.with(|catching| catching.replace(true));
+    let result = f();
+    CATCHING.with(|catching| catching.set(outer));
+    (
+        result,
+        PANICKED.with(|panicked| panicked.borrow_mut().take()),
+    )
+}
+
+/// Run `f` for a top level component, and if it panics while we're
+/// catching panics, remember it and quit the application, returning `None`.
+pub(crate) fn catch_root<R>(f: impl FnOnce() -> R) -> Option<R> {
+    if !CATCHING.with(Cell::get) {
+        return Some(f());
+    }
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            let message = panic_message(payload);
+            PANICKED.with(|panicked| {
+                panicked.borrow_mut().get_or_insert(message);
+            });
+            if let Some(app) = gio::Application::get_default() {
+                app.quit();
+            }
+            None
+        }
+    }
+}
@@ -1,83 +1,256 @@
-use glib::prelude::*;
-use glib::{Object, SignalHandlerId, Value};
-use std::cell::Cell;
-use std::cmp::Ordering;
+//! Keyboard and pointer events, decoded.
+//!
+//! GTK's `key-press-event` and `button-press-event` signals give their
+//! handlers raw [`gdk::EventKey`][EventKey]s and
+//! [`gdk::EventButton`][EventButton]s, which leave decoding key values, modifier
+//! masks and button numbers to the handler. The `key_press`, `key_release`,
+//! `button_press` and `button_release` signals in `ext::WidgetExtHelpers` do
+//! the decoding first, handing over a [`KeyEvent`][KeyEvent] or a
+//! [`ButtonEvent`][ButtonEvent] instead.
+//!
+//! In the [`gtk!`][gtk!] macro, the arguments of handlers for these signals
+//! are patterns, which don't have to match every event. A handler only
+//! handles the events its patterns match, and any other events carry on to
+//! the widget's other handlers, and its parent, as if it wasn't there:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::event::{ButtonEvent, Key, KeyEvent, MouseButton};
+//! # use vgtk::ext::*;
+//! # use vgtk::lib::gtk::*;
+//! # #[derive(Default)] struct Editor;
+//! # #[derive(Clone, Debug)] enum Message { Close, Save, Menu(f64, f64) }
+//! # impl Component for Editor { type Message = Message; type Properties = ();
+//! # fn create(_props: ()) -> Self { Self::default() }
+//! fn view(&self) -> VNode<Editor> {
+//!     gtk! {
+//!         <Window
+//!             on key_press=|_, KeyEvent { key: Key::Escape, .. }| (Message::Close, Inhibit(true))
+//!             on key_press=|_, KeyEvent { key: Key::Char('s'), ctrl: true, .. }| (Message::Save, Inhibit(true))
+//!         >
+//!             <EventBox
+//!                 on button_press=|_, ButtonEvent { button: MouseButton::Secondary, x, y, .. }| {
+//!                     (Message::Menu(x, y), Inhibit(true))
+//!                 }
+//!             >
+//!                 <TextView />
+//!             </EventBox>
+//!         </Window>
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! Because they're patterns, these handlers can't give their arguments
+//! types, like `|window: &Window, event|`, as other handlers can.
+//!
+//! [EventKey]: ../../gdk/struct.EventKey.html
+//! [EventButton]: ../../gdk/struct.EventButton.html
+//! [KeyEvent]: struct.KeyEvent.html
+//! [ButtonEvent]: struct.ButtonEvent.html
+//! [gtk!]: ../macro.gtk.html
 
-use std::sync::Arc;
+use gdk::keys::constants as keys;
+use gdk::{EventButton, EventKey, EventType, ModifierType};
+use gtk::Inhibit;
 
-use crate::component::Component;
-use crate::scope::Scope;
+/// A key, as pressed on the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// The Escape key.
+    Escape,
+    /// Either of the Return and keypad Enter keys.
+    Enter,
+    /// Tab, including Shift+Tab, which some keyboard layouts send as a key
+    /// of its own.
+    Tab,
+    /// The Backspace key.
+    Backspace,
+    /// The Delete key.
+    Delete,
+    /// The Insert key.
+    Insert,
+    /// The space bar.
+    Space,
+    /// The up arrow key.
+    Up,
+    /// The down arrow key.
+    Down,
+    /// The left arrow key.
+    Left,
+    /// The right arrow key.
+    Right,
+    /// The Home key.
+    Home,
+    /// The End key.
+    End,
+    /// The Page Up key.
+    PageUp,
+    /// The Page Down key.
+    PageDown,
+    /// A function key, from `F(1)` upwards.
+    F(u8),
+    /// A key which types a character. Letters are always lower case, so
+    /// that Shift, which is reported separately, and Caps Lock don't change
+    /// which key it is.
+    Char(char),
+    /// Any other key, by its GDK key value.
+    Other(u32),
+}
 
-pub struct Event {
-    pub source: Object,
-    pub args: Vec<Value>,
+impl From<&gdk::keys::Key> for Key {
+    fn from(keyval: &gdk::keys::Key) -> Self {
+        match *keyval {
+            keys::Escape => Key::Escape,
+            keys::Return | keys::KP_Enter | keys::ISO_Enter => Key::Enter,
+            keys::Tab | keys::KP_Tab | keys::ISO_Left_Tab => Key::Tab,
+            keys::BackSpace => Key::Backspace,
+            keys::Delete | keys::KP_Delete => Key::Delete,
+            keys::Insert | keys::KP_Insert => Key::Insert,
+            keys::space | keys::KP_Space => Key::Space,
+            keys::Up | keys::KP_Up => Key::Up,
+            keys::Down | keys::KP_Down => Key::Down,
+            keys::Left | keys::KP_Left => Key::Left,
+            keys::Right | keys::KP_Right => Key::Right,
+            keys::Home | keys::KP_Home => Key::Home,
+            keys::End | keys::KP_End => Key::End,
+            keys::Page_Up | keys::KP_Page_Up => Key::PageUp,
+            keys::Page_Down | keys::KP_Page_Down => Key::PageDown,
+            _ if (*keys::F1..=*keys::F35).contains(&**keyval) => {
+                Key::F((**keyval - *keys::F1 + 1) as u8)
+            }
+            _ => {
+                let lower = gdk::keyval_to_lower(**keyval);
+                match gdk::keyval_to_unicode(lower) {
+                    Some(c) if !c.is_control() => Key::Char(c),
+                    _ => Key::Other(**keyval),
+                }
+            }
+        }
+    }
 }
 
-pub struct SignalHandler<C: Component> {
-    id: String,
-    handler_id: Cell<Option<SignalHandlerId>>,
-    handler: Arc<dyn Fn(Event) -> C::Message>,
+/// A key press or release.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    /// The key.
+    pub key: Key,
+    /// Whether Ctrl was held down.
+    pub ctrl: bool,
+    /// Whether Shift was held down.
+    pub shift: bool,
+    /// Whether Alt was held down.
+    pub alt: bool,
+    /// Whether the Super key, also known as the Windows or Command key, was
+    /// held down.
+    pub logo: bool,
 }
 
-impl<C: Component> PartialEq for SignalHandler<C> {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+impl From<&EventKey> for KeyEvent {
+    fn from(event: &EventKey) -> Self {
+        let state = event.get_state();
+        KeyEvent {
+            key: Key::from(&event.get_keyval()),
+            ctrl: state.contains(ModifierType::CONTROL_MASK),
+            shift: state.contains(ModifierType::SHIFT_MASK),
+            alt: state.contains(ModifierType::MOD1_MASK),
+            logo: state.intersects(ModifierType::SUPER_MASK | ModifierType::MOD4_MASK),
+        }
     }
 }
 
-impl<C: Component> Eq for SignalHandler<C> {}
+/// A mouse button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    /// Usually the left button.
+    Primary,
+    /// The middle button, which is often the scroll wheel.
+    Middle,
+    /// Usually the right button.
+    Secondary,
+    /// The back button, on mice which have one.
+    Back,
+    /// The forward button, on mice which have one.
+    Forward,
+    /// Any other button, by its number.
+    Other(u32),
+}
 
-impl<C: Component> PartialOrd for SignalHandler<C> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl From<u32> for MouseButton {
+    fn from(button: u32) -> Self {
+        match button {
+            gdk::BUTTON_PRIMARY => MouseButton::Primary,
+            gdk::BUTTON_MIDDLE => MouseButton::Middle,
+            gdk::BUTTON_SECONDARY => MouseButton::Secondary,
+            8 => MouseButton::Back,
+            9 => MouseButton::Forward,
+            button => MouseButton::Other(button),
+        }
     }
 }
 
-impl<C: Component> Ord for SignalHandler<C> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.id.cmp(&other.id)
-    }
+/// A mouse button press or release.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ButtonEvent {
+    /// The button.
+    pub button: MouseButton,
+    /// Where the pointer was, relative to the widget's window.
+    pub x: f64,
+    /// Where the pointer was, relative to the widget's window.
+    pub y: f64,
+    /// How many clicks in quick succession this press makes, up to 3, so
+    /// that a double click is a press with `clicks: 2`. This is always 1 for
+    /// releases.
+    pub clicks: u32,
+    /// Whether Ctrl was held down.
+    pub ctrl: bool,
+    /// Whether Shift was held down.
+    pub shift: bool,
+    /// Whether Alt was held down.
+    pub alt: bool,
+    /// Whether the Super key was held down.
+    pub logo: bool,
 }
 
-impl<C: Component + 'static> SignalHandler<C> {
-    pub fn new<F, S>(id: S, f: F) -> Self
-    where
-        F: Fn(Event) -> C::Message + 'static,
-        S: Into<String>,
-    {
-        let id = id.into();
-        SignalHandler {
-            id,
-            handler_id: Cell::new(None),
-            handler: Arc::new(f),
+impl From<&EventButton> for ButtonEvent {
+    fn from(event: &EventButton) -> Self {
+        let state = event.get_state();
+        let (x, y) = event.get_position();
+        ButtonEvent {
+            button: MouseButton::from(event.get_button()),
+            x,
+            y,
+            clicks: match event.get_event_type() {
+                EventType::DoubleButtonPress => 2,
+                EventType::TripleButtonPress => 3,
+                _ => 1,
+            },
+            ctrl: state.contains(ModifierType::CONTROL_MASK),
+            shift: state.contains(ModifierType::SHIFT_MASK),
+            alt: state.contains(ModifierType::MOD1_MASK),
+            logo: state.intersects(ModifierType::SUPER_MASK | ModifierType::MOD4_MASK),
         }
     }
+}
 
-    pub fn connect<'a, S: Into<&'a str>>(&self, signal: S, obj: &Object, scope: Scope<C>) {
-        let signal = signal.into();
-        let handler = self.handler.clone();
-
-        let f: Arc<dyn Fn(Event) -> C::Message + Send + Sync + 'static> =
-            unsafe { Arc::from_raw(Arc::into_raw(handler) as *mut _) };
+/// What a handler whose patterns didn't match an event returns to the
+/// signal, which lets the event carry on.
+#[doc(hidden)]
+pub trait Unhandled {
+    fn unhandled() -> Self;
+}
 
-        self.handler_id.set(Some(
-            obj.connect(signal, false, move |args: &[Value]| {
-                let source: Object = args[0].get().expect("event args[0] was not an Object");
-                let event = Event {
-                    source,
-                    args: args[1..].to_owned(),
-                };
-                let msg = f(event);
-                scope.send_message(msg);
-                None
-            })
-            .unwrap_or_else(|_e| panic!("invalid signal {:?} on {}", signal, obj.get_type())),
-        ));
+impl Unhandled for Inhibit {
+    fn unhandled() -> Self {
+        Inhibit(false)
     }
+}
 
-    pub fn disconnect(&self, obj: &Object) {
-        if let Some(id) = self.handler_id.replace(None) {
-            obj.disconnect(id);
-        }
-    }
+impl Unhandled for () {
+    fn unhandled() -> Self {}
+}
+
+#[doc(hidden)]
+pub fn unhandled<R: Unhandled>() -> R {
+    R::unhandled()
 }
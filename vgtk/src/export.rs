@@ -0,0 +1,399 @@
+//! Publishing components as GTK widgets for code which doesn't use vgtk.
+//!
+//! This is the inverse of [`vgtk::mount()`][mount]: instead of putting a
+//! component inside someone else's container, it registers a new
+//! [`Bin`][Bin] subclass with the GObject type system, whose instances each
+//! run a copy of the component inside themselves. Once registered, the
+//! widget can be created from a [`Builder`][Builder] file by its type name,
+//! or from any language with GObject bindings, like any other widget.
+//!
+//! The component chooses which of its [`Properties`][Properties] fields show
+//! up as GObject properties, and which of its [`Callback`][Callback]s show up
+//! as signals, by implementing [`Export`][Export]. Setting a property gives
+//! the component new properties, exactly as a parent component would when it
+//! renders, and sending to a callback emits the signal.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Callback, Component, VNode};
+//! # use vgtk::export::{self, Export, Property, Signal};
+//! # use vgtk::lib::gtk::*;
+//! #[derive(Clone, Default)]
+//! struct Rating {
+//!     stars: u32,
+//!     on_rated: Callback<u32>,
+//! }
+//!
+//! # #[derive(Clone, Debug)] enum Message {}
+//! impl Component for Rating {
+//!     // ...
+//! #   type Message = Message; type Properties = Self;
+//! #   fn create(props: Self) -> Self { props }
+//! #   fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! }
+//!
+//! impl Export for Rating {
+//!     const TYPE_NAME: &'static str = "MyAppRating";
+//!
+//!     fn properties() -> Vec<Property<Self>> {
+//!         vec![Property::new(
+//!             "stars",
+//!             |props: &Rating| props.stars,
+//!             |props: &mut Rating, stars| props.stars = stars,
+//!         )]
+//!     }
+//!
+//!     fn signals() -> Vec<Signal<Self>> {
+//!         vec![Signal::new("rated", |props: &mut Rating, callback| {
+//!             props.on_rated = callback
+//!         })]
+//!     }
+//! }
+//!
+//! # fn main() {
+//! // Before loading any UI files which use `MyAppRating`:
+//! export::register::<Rating>();
+//! let builder = Builder::from_string(r#"
+//!     <interface>
+//!       <object class="MyAppRating" id="rating">
+//!         <property name="stars">3</property>
+//!       </object>
+//!     </interface>
+//! "#);
+//! # }
+//! ```
+//!
+//! [mount]: ../fn.mount.html
+//! [Bin]: ../../gtk/struct.Bin.html
+//! [Builder]: ../../gtk/struct.Builder.html
+//! [Properties]: ../trait.Component.html#associatedtype.Properties
+//! [Callback]: ../struct.Callback.html
+//! [Export]: trait.Export.html
+
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr::{self, NonNull};
+use std::rc::Rc;
+
+use glib::subclass::{self, simple, TypeData};
+use glib::{
+    Cast, Object, ObjectExt, ParamFlags, ParamSpec, SignalFlags, StaticType, ToValue, Type, Value,
+    WeakRef,
+};
+use gtk::subclass::prelude::*;
+use gtk::{Bin, Widget};
+
+use crate::callback::Callback;
+use crate::component::Component;
+use crate::embed::{mount, MountHandle};
+
+/// A [`Component`][Component] which can be published as a GTK widget.
+///
+/// See the [module documentation](index.html).
+///
+/// [Component]: ../trait.Component.html
+pub trait Export: 'static + Component {
+    /// The name to register the widget's type under, which is what a
+    /// [`Builder`][Builder] file refers to it by. By convention, it starts
+    /// with a prefix naming the application or library, like `GtkButton`.
+    ///
+    /// [Builder]: ../../gtk/struct.Builder.html
+    const TYPE_NAME: &'static str;
+
+    /// The component's properties which should be GObject properties.
+    fn properties() -> Vec<Property<Self>> {
+        Vec::new()
+    }
+
+    /// The component's callbacks which should be GObject signals.
+    fn signals() -> Vec<Signal<Self>> {
+        Vec::new()
+    }
+}
+
+/// A value which can be a GObject property or a signal argument.
+pub trait Exportable: 'static + ToValue + StaticType {
+    /// Describe a property of this type with the given name.
+    fn param_spec(name: &str) -> ParamSpec;
+
+    /// Convert a value of this type back from a [`Value`][Value].
+    ///
+    /// [Value]: ../../glib/value/struct.Value.html
+    fn from_value(value: &Value) -> Self;
+}
+
+impl Exportable for bool {
+    fn param_spec(name: &str) -> ParamSpec {
+        ParamSpec::boolean(name, name, name, false, ParamFlags::READWRITE)
+    }
+
+    fn from_value(value: &Value) -> Self {
+        value.get().ok().flatten().unwrap_or_default()
+    }
+}
+
+impl Exportable for String {
+    fn param_spec(name: &str) -> ParamSpec {
+        ParamSpec::string(name, name, name, None, ParamFlags::READWRITE)
+    }
+
+    fn from_value(value: &Value) -> Self {
+        value.get().ok().flatten().unwrap_or_default()
+    }
+}
+
+macro_rules! exportable_numbers {
+    ($($type:ty => $spec:ident, $zero:expr;)*) => {$(
+        impl Exportable for $type {
+            fn param_spec(name: &str) -> ParamSpec {
+                ParamSpec::$spec(
+                    name,
+                    name,
+                    name,
+                    <$type>::MIN,
+                    <$type>::MAX,
+                    $zero,
+                    ParamFlags::READWRITE,
+                )
+            }
+
+            fn from_value(value: &Value) -> Self {
+                value.get().ok().flatten().unwrap_or_default()
+            }
+        }
+    )*};
+}
+
+exportable_numbers! {
+    i32 => int, 0;
+    u32 => uint, 0;
+    i64 => int64, 0;
+    u64 => uint64, 0;
+    f32 => float, 0.0;
+    f64 => double, 0.0;
+}
+
+/// What a signal hands its handlers: either nothing, for a `Callback<()>`,
+/// or a single [`Exportable`][Exportable] value.
+///
+/// [Exportable]: trait.Exportable.html
+pub trait SignalArgs: 'static {
+    /// The types of the signal's arguments.
+    fn types() -> Vec<Type>;
+
+    /// Emit the named signal on `object` with these arguments.
+    fn emit(self, object: &Object, signal: &str);
+}
+
+impl SignalArgs for () {
+    fn types() -> Vec<Type> {
+        Vec::new()
+    }
+
+    fn emit(self, object: &Object, signal: &str) {
+        let _ = object.emit(signal, &[]);
+    }
+}
+
+impl<A: Exportable> SignalArgs for A {
+    fn types() -> Vec<Type> {
+        vec![A::static_type()]
+    }
+
+    fn emit(self, object: &Object, signal: &str) {
+        let _ = object.emit(signal, &[&self]);
+    }
+}
+
+/// A field of a component's properties published as a GObject property.
+pub struct Property<C: Export> {
+    name: &'static str,
+    spec: fn(&str) -> ParamSpec,
+    get: Box<dyn Fn(&C::Properties) -> Value>,
+    set: Box<dyn Fn(&mut C::Properties, &Value)>,
+}
+
+impl<C: Export> Property<C> {
+    /// Publish a property called `name`, which reads its value from the
+    /// component's properties with `get`, and writes it back with `set`.
+    pub fn new<A: Exportable>(
+        name: &'static str,
+        get: fn(&C::Properties) -> A,
+        set: fn(&mut C::Properties, A),
+    ) -> Self {
+        Property {
+            name,
+            spec: A::param_spec,
+            get: Box::new(move |props| get(props).to_value()),
+            set: Box::new(move |props, value| set(props, A::from_value(value))),
+        }
+    }
+}
+
+/// A [`Callback`][Callback] in a component's properties published as a
+/// GObject signal.
+///
+/// [Callback]: ../struct.Callback.html
+pub struct Signal<C: Export> {
+    name: &'static str,
+    types: Vec<Type>,
+    connect: Box<dyn Fn(&mut C::Properties, WeakRef<Object>)>,
+}
+
+impl<C: Export> Signal<C> {
+    /// Publish a signal called `name`, which is emitted when the callback
+    /// which `set` stores in the component's properties is called.
+    pub fn new<A: SignalArgs>(
+        name: &'static str,
+        set: fn(&mut C::Properties, Callback<A>),
+    ) -> Self {
+        Signal {
+            name,
+            types: A::types(),
+            connect: Box::new(move |props, object| {
+                let callback = move |args: A| {
+                    if let Some(object) = object.upgrade() {
+                        args.emit(&object, name);
+                    }
+                };
+                set(props, Callback(Some(Rc::new(callback))))
+            }),
+        }
+    }
+}
+
+thread_local! {
+    // The GObject type data for each registered component, by the
+    // component's type.
+    static TYPES: RefCell<HashMap<TypeId, NonNull<TypeData>>> = RefCell::new(HashMap::new());
+}
+
+/// Register the widget type for a component, if it hasn't been already,
+/// and return it.
+///
+/// This has to happen before a [`Builder`][Builder] can create the widget
+/// from a UI file, or before any code outside Rust can look the type up by
+/// its name.
+///
+/// # Panics
+///
+/// Panics if another type has already been registered under the
+/// component's [`TYPE_NAME`][TYPE_NAME].
+///
+/// [Builder]: ../../gtk/struct.Builder.html
+/// [TYPE_NAME]: trait.Export.html#associatedconstant.TYPE_NAME
+pub fn register<C: Export>() -> Type {
+    Exported::<C>::get_type()
+}
+
+/// Create a new instance of a component's widget, registering its type
+/// first if needed.
+pub fn new<C: Export>() -> Widget {
+    Object::new(register::<C>(), &[])
+        .expect("unable to create an exported component")
+        .downcast()
+        .unwrap()
+}
+
+/// The instance data of a component's widget.
+struct Exported<C: Export> {
+    properties: Vec<Property<C>>,
+    signals: Vec<Signal<C>>,
+    props: RefCell<C::Properties>,
+    handle: RefCell<Option<MountHandle<C>>>,
+}
+
+impl<C: Export> ObjectSubclass for Exported<C> {
+    const NAME: &'static str = C::TYPE_NAME;
+    type ParentType = Bin;
+    type Instance = simple::InstanceStruct<Self>;
+    type Class = simple::ClassStruct<Self>;
+
+    fn type_data() -> NonNull<TypeData> {
+        TYPES.with(|types| {
+            *types
+                .borrow_mut()
+                .entry(TypeId::of::<C>())
+                .or_insert_with(|| {
+                    NonNull::from(Box::leak(Box::new(TypeData {
+                        type_: Type::Invalid,
+                        parent_class: ptr::null_mut(),
+                        interface_data: ptr::null_mut(),
+                        private_offset: 0,
+                    })))
+                })
+        })
+    }
+
+    fn get_type() -> Type {
+        #[allow(unsafe_code)]
+        let type_ = unsafe { Self::type_data().as_ref().get_type() };
+        if type_ == Type::Invalid {
+            subclass::register_type::<Self>()
+        } else {
+            type_
+        }
+    }
+
+    fn class_init(class: &mut Self::Class) {
+        let properties: Vec<_> = C::properties()
+            .iter()
+            .map(|property| subclass::Property(property.name, property.spec))
+            .collect();
+        class.install_properties(&properties);
+        for signal in C::signals() {
+            class.add_signal(
+                signal.name,
+                SignalFlags::RUN_LAST,
+                &signal.types,
+                Type::Unit,
+            );
+        }
+    }
+
+    fn new() -> Self {
+        Exported {
+            properties: C::properties(),
+            signals: C::signals(),
+            props: RefCell::new(Default::default()),
+            handle: RefCell::new(None),
+        }
+    }
+}
+
+impl<C: Export> ObjectImpl for Exported<C> {
+    glib::glib_object_impl!();
+
+    fn set_property(&self, _object: &Object, id: usize, value: &Value) {
+        let mut props = self.props.borrow_mut();
+        (self.properties[id].set)(&mut props, value);
+        if let Some(handle) = &*self.handle.borrow() {
+            handle.set_props(props.clone());
+        }
+    }
+
+    fn get_property(&self, _object: &Object, id: usize) -> Result<Value, ()> {
+        Ok((self.properties[id].get)(&self.props.borrow()))
+    }
+
+    fn constructed(&self, object: &Object) {
+        self.parent_constructed(object);
+        let mut props = self.props.borrow_mut();
+        for signal in &self.signals {
+            (signal.connect)(&mut props, object.downgrade());
+        }
+        let bin: &Bin = object.downcast_ref().unwrap();
+        *self.handle.borrow_mut() = Some(mount::<C>(bin, props.clone()));
+    }
+}
+
+impl<C: Export> WidgetImpl for Exported<C> {
+    fn destroy(&self, widget: &Widget) {
+        self.handle.borrow_mut().take();
+        self.parent_destroy(widget);
+    }
+}
+
+impl<C: Export> ContainerImpl for Exported<C> {}
+
+impl<C: Export> BinImpl for Exported<C> {}
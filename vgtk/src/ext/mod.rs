@@ -7,18 +7,26 @@
 
 #![allow(missing_docs)]
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use atk::{AtkObjectExt, Role};
+use cairo::Region;
 use gdk_pixbuf::Pixbuf;
 use gio::{Action, ActionExt, ApplicationFlags};
-use glib::{GString, IsA, Object, ObjectExt};
+use glib::{Cast, GString, IsA, Object, ObjectExt, SignalHandlerId};
 use gtk::{
-    Application, ApplicationWindowExt, BoxExt, GridExt, GtkApplicationExt, GtkWindowExt,
-    HeaderBarExt, ImageExt, InfoBar, InfoBarExt, LabelExt, NotebookExt, ResponseType,
-    Widget, Window, WindowPosition, WindowType
+    ActionBarExt, AdjustmentExt, Application, ContainerExt, ApplicationWindowExt, BoxExt, CalendarExt, Entry, EntryExt, EntryIconPosition,
+    GridExt, GtkApplicationExt, GtkWindowExt, HeaderBarExt, Image, ImageExt, InfoBar, Inhibit, InfoBarExt, LabelExt, NotebookExt, ResponseType,
+    PackType, ScaleButtonExt, SizeGroup, SizeGroupExt, SizeGroupMode, SpinButtonExt, Stack, StackExt, StackTransitionType, StyleContextExt, TextDirection, Widget, WidgetExt, Window, WindowPosition, WindowType
 };
 
 use colored::Colorize;
-use log::trace;
+use log::{trace, warn};
 
+use crate::event::{ButtonEvent, KeyEvent};
+use crate::gesture;
+use crate::image::{self, Source};
 use crate::types::GridPosition;
 
 /// Helper trait for [`Application`][Application].
@@ -122,10 +130,43 @@ pub trait ApplicationWindowHelpers: ApplicationWindowExt + GtkWindowExt + IsA<Wi
 
 impl<A> ApplicationWindowHelpers for A where A: ApplicationWindowExt + GtkWindowExt + IsA<Window> {}
 
+const PLACEHOLDER_TITLEBAR_KEY: &str = "vgtk-placeholder-titlebar";
+const SHAPE_KEY: &str = "vgtk-shape";
+const CLICK_THROUGH_KEY: &str = "vgtk-click-through";
+
+/// Test whether a window's titlebar is the empty one added by the
+/// `client_decorated` attribute, which makes way for a real one.
+pub(crate) fn is_placeholder_titlebar(titlebar: &Widget) -> bool {
+    #[allow(unsafe_code)]
+    unsafe {
+        titlebar.get_data::<bool>(PLACEHOLDER_TITLEBAR_KEY).is_some()
+    }
+}
+
 /// Helper trait for [`Window`][Window].
 ///
+/// Besides exposing some properties GTK doesn't have accessors for, this
+/// provides attributes for overlays, on-screen displays and other windows
+/// which don't look like ordinary windows:
+///
+/// * `transparent=true` gives the window an alpha channel, so anything you
+///   don't draw on is see-through. This only works when a compositor is
+///   running, which you can check using `can_be_transparent()`; otherwise,
+///   the window stays opaque.
+/// * `shape` clips the window to a [`Region`][Region], and
+///   `click_through=true` lets pointer events pass through the window to
+///   whatever's under it. Whether these work depends on the windowing system,
+///   which you can check using `can_be_shaped()`.
+/// * `client_decorated=true` has GTK draw the window's decorations itself,
+///   even if it has no [`HeaderBar`][HeaderBar], which combined with
+///   `decorated=false` gives you a window with no frame but with GTK's
+///   rounded corners and shadow. It can't be changed once the window has
+///   been shown.
+///
 /// [Window]: ../../gtk/struct.Window.html
-pub trait WindowExtHelpers: GtkWindowExt {
+/// [Region]: ../../cairo/struct.Region.html
+/// [HeaderBar]: ../../gtk/struct.HeaderBar.html
+pub trait WindowExtHelpers: GtkWindowExt + WidgetExt + IsA<Window> {
     fn get_default_height(&self) -> i32 {
         self.get_property_default_height()
     }
@@ -165,9 +206,125 @@ pub trait WindowExtHelpers: GtkWindowExt {
     fn set_window_position(&self, window_position: WindowPosition) {
         self.set_property_window_position(window_position)
     }
+
+    fn can_be_transparent(&self) -> bool {
+        self.get_screen()
+            .map(|screen| screen.is_composited() && screen.get_rgba_visual().is_some())
+            .unwrap_or(false)
+    }
+
+    fn get_transparent(&self) -> bool {
+        let rgba = self.get_screen().and_then(|screen| screen.get_rgba_visual());
+        rgba.is_some() && self.get_visual() == rgba && self.get_app_paintable()
+    }
+
+    fn set_transparent(&self, transparent: bool) {
+        let screen = match self.get_screen() {
+            Some(screen) => screen,
+            None => return,
+        };
+        if transparent {
+            match screen.get_rgba_visual() {
+                Some(visual) if screen.is_composited() => {
+                    self.set_visual(Some(&visual));
+                    self.set_app_paintable(true);
+                }
+                _ => warn!(
+                    "{} no compositor is running, so the window will be opaque.",
+                    "Transparency unavailable:".bright_red()
+                ),
+            }
+        } else {
+            self.set_visual(screen.get_system_visual().as_ref());
+            self.set_app_paintable(false);
+        }
+    }
+
+    fn can_be_shaped(&self) -> bool {
+        self.get_display().supports_shapes()
+    }
+
+    fn get_shape(&self) -> Option<Region> {
+        #[allow(unsafe_code)]
+        unsafe { self.as_ref().get_data::<Region>(SHAPE_KEY) }.cloned()
+    }
+
+    fn set_shape(&self, shape: Option<&Region>) {
+        if shape.is_some() && !self.can_be_shaped() {
+            warn!(
+                "{} the windowing system doesn't support window shapes.",
+                "Window shape unavailable:".bright_red()
+            );
+        }
+        self.shape_combine_region(shape);
+        #[allow(unsafe_code)]
+        unsafe {
+            match shape {
+                Some(shape) => self.as_ref().set_data(SHAPE_KEY, shape.clone()),
+                None => drop(self.as_ref().steal_data::<Region>(SHAPE_KEY)),
+            }
+        }
+    }
+
+    fn get_click_through(&self) -> bool {
+        #[allow(unsafe_code)]
+        unsafe { self.as_ref().get_data::<bool>(CLICK_THROUGH_KEY) }.is_some()
+    }
+
+    fn set_click_through(&self, click_through: bool) {
+        if click_through {
+            if !self.get_display().supports_input_shapes() {
+                warn!(
+                    "{} the windowing system doesn't support input shapes.",
+                    "Click through unavailable:".bright_red()
+                );
+            }
+            self.input_shape_combine_region(Some(&Region::create()));
+        } else {
+            self.input_shape_combine_region(None);
+        }
+        #[allow(unsafe_code)]
+        unsafe {
+            if click_through {
+                self.as_ref().set_data(CLICK_THROUGH_KEY, true);
+            } else {
+                self.as_ref().steal_data::<bool>(CLICK_THROUGH_KEY);
+            }
+        }
+    }
+
+    fn get_client_decorated(&self) -> bool {
+        self.get_titlebar().is_some()
+    }
+
+    fn set_client_decorated(&self, client_decorated: bool) {
+        if self.get_realized() {
+            warn!(
+                "{} it can't be changed once the window has been shown.",
+                "Ignoring client_decorated:".bright_red()
+            );
+            return;
+        }
+        let titlebar = self.get_titlebar();
+        if client_decorated && titlebar.is_none() {
+            let placeholder = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            placeholder.set_no_show_all(true);
+            #[allow(unsafe_code)]
+            unsafe {
+                placeholder.set_data(PLACEHOLDER_TITLEBAR_KEY, true);
+            }
+            self.set_titlebar(Some(&placeholder));
+        } else if !client_decorated {
+            if let Some(titlebar) = titlebar {
+                if is_placeholder_titlebar(&titlebar) {
+                    self.set_titlebar(None::<&Widget>);
+                }
+            }
+        }
+    }
 }
 
-impl<A> WindowExtHelpers for A where A: GtkWindowExt {}
+impl<A> WindowExtHelpers for A where A: GtkWindowExt + WidgetExt + IsA<Window> {}
 
 /// Helper trait for [`Box`][Box].
 ///
@@ -181,6 +338,22 @@ pub trait BoxExtHelpers: BoxExt {
     fn set_child_center_widget(&self, _child: &Object, _center: bool) {
         // This is handled by add_child() rules. The setter is a no-op.
     }
+
+    fn get_child_start<P: IsA<Widget>>(&self, child: &P) -> bool {
+        self.get_child_pack_type(child) == PackType::Start
+    }
+
+    fn set_child_start<P: IsA<Widget>>(&self, child: &P, start: bool) {
+        self.set_child_pack_type(child, if start { PackType::Start } else { PackType::End });
+    }
+
+    fn get_child_end<P: IsA<Widget>>(&self, child: &P) -> bool {
+        self.get_child_pack_type(child) == PackType::End
+    }
+
+    fn set_child_end<P: IsA<Widget>>(&self, child: &P, end: bool) {
+        self.set_child_pack_type(child, if end { PackType::End } else { PackType::Start });
+    }
 }
 
 impl<A> BoxExtHelpers for A where A: BoxExt {}
@@ -197,20 +370,140 @@ pub trait HeaderBarExtHelpers: HeaderBarExt {
     fn set_child_custom_title<P: IsA<Widget>>(&self, _child: &P, _center: bool) {
         // This is handled by add_child() rules. The setter is a no-op.
     }
+
+    fn get_child_start<P: IsA<Widget>>(&self, child: &P) -> bool {
+        self.get_child_pack_type(child) == PackType::Start
+    }
+
+    fn set_child_start<P: IsA<Widget>>(&self, child: &P, start: bool) {
+        self.set_child_pack_type(child, if start { PackType::Start } else { PackType::End });
+    }
+
+    fn get_child_end<P: IsA<Widget>>(&self, child: &P) -> bool {
+        self.get_child_pack_type(child) == PackType::End
+    }
+
+    fn set_child_end<P: IsA<Widget>>(&self, child: &P, end: bool) {
+        self.set_child_pack_type(child, if end { PackType::End } else { PackType::Start });
+    }
 }
 
 impl<A> HeaderBarExtHelpers for A where A: HeaderBarExt {}
 
+/// Helper trait for [`ActionBar`][ActionBar].
+///
+/// [ActionBar]: ../../gtk/struct.ActionBar.html
+pub trait ActionBarExtHelpers: ActionBarExt {
+    fn get_child_start<P: IsA<Widget>>(&self, child: &P) -> bool {
+        self.get_child_pack_type(child) == PackType::Start
+    }
+
+    fn set_child_start<P: IsA<Widget>>(&self, child: &P, start: bool) {
+        self.set_child_pack_type(child, if start { PackType::Start } else { PackType::End });
+    }
+
+    fn get_child_end<P: IsA<Widget>>(&self, child: &P) -> bool {
+        self.get_child_pack_type(child) == PackType::End
+    }
+
+    fn set_child_end<P: IsA<Widget>>(&self, child: &P, end: bool) {
+        self.set_child_pack_type(child, if end { PackType::End } else { PackType::Start });
+    }
+}
+
+impl<A> ActionBarExtHelpers for A where A: ActionBarExt {}
+
 /// Helper trait for [`Image`][Image].
 ///
+/// This provides the `resource` attribute for showing images from the
+/// [`assets`][assets] bundle, and the `file` and `from_url` attributes, which
+/// load an image in the background, showing the icon named by the
+/// `placeholder` attribute until it's ready. (GTK's own `from_file` attribute
+/// loads the file straight away, blocking until it's done.) Use `size` to scale any of these
+/// to fit a square of that many logical pixels, and `on load_error` to find
+/// out if loading fails.
+///
+/// URLs are loaded using GIO, so loading anything but `file://` URLs requires
+/// GVfs to be installed.
+///
 /// [Image]: ../../gtk/struct.Image.html
-pub trait ImageExtHelpers: ImageExt {
+/// [assets]: ../assets/index.html
+pub trait ImageExtHelpers: ImageExt + IsA<Image> {
     fn set_pixbuf(&self, pixbuf: Option<Pixbuf>) {
         self.set_from_pixbuf(pixbuf.as_ref());
     }
+
+    fn get_resource(&self) -> Option<GString> {
+        match image::source(self.as_ref()) {
+            Some(Source::Resource(name)) => Some(name.into()),
+            _ => None,
+        }
+    }
+
+    fn set_resource(&self, name: &str) {
+        image::set_source(self.as_ref(), Source::Resource(name.to_string()));
+    }
+
+    fn get_file(&self) -> Option<GString> {
+        match image::source(self.as_ref()) {
+            Some(Source::File(path)) => Some(path.into()),
+            _ => None,
+        }
+    }
+
+    fn set_file(&self, path: &str) {
+        image::set_source(self.as_ref(), Source::File(path.to_string()));
+    }
+
+    fn get_from_url(&self) -> Option<GString> {
+        match image::source(self.as_ref()) {
+            Some(Source::Url(url)) => Some(url.into()),
+            _ => None,
+        }
+    }
+
+    fn set_from_url(&self, url: &str) {
+        image::set_source(self.as_ref(), Source::Url(url.to_string()));
+    }
+
+    fn get_placeholder(&self) -> Option<GString> {
+        image::placeholder(self.as_ref()).map(GString::from)
+    }
+
+    fn set_placeholder(&self, icon_name: &str) {
+        image::set_placeholder(self.as_ref(), Some(icon_name));
+    }
+
+    fn get_size(&self) -> i32 {
+        image::size(self.as_ref())
+    }
+
+    fn set_size(&self, size: i32) {
+        image::set_size(self.as_ref(), size);
+    }
+
+    fn connect_load_error<F: Fn(&Image, &glib::Error) + 'static>(&self, f: F) -> SignalHandlerId {
+        image::connect_load_error(self.as_ref(), f)
+    }
+}
+
+impl<A> ImageExtHelpers for A where A: ImageExt + IsA<Image> {}
+
+/// Helper trait for [`Calendar`][Calendar].
+///
+/// This provides the `date` attribute, which selects a date given as a
+/// `(year, month, day)` tuple, with months counted from zero like
+/// `Calendar::get_date()` does.
+///
+/// [Calendar]: ../../gtk/struct.Calendar.html
+pub trait CalendarExtHelpers: CalendarExt {
+    fn set_date(&self, (year, month, day): (u32, u32, u32)) {
+        self.select_month(month, year);
+        self.select_day(day);
+    }
 }
 
-impl<A> ImageExtHelpers for A where A: ImageExt {}
+impl<A> CalendarExtHelpers for A where A: CalendarExt {}
 
 /// Helper trait for [`Label`][Label].
 ///
@@ -223,6 +516,422 @@ pub trait LabelExtHelpers: LabelExt {
 
 impl<A> LabelExtHelpers for A where A: LabelExt {}
 
+/// Helper trait for [`SpinButton`][SpinButton].
+///
+/// This provides the `min`, `max` and `step` attributes, which set the bounds
+/// and step increment of the spin button's adjustment.
+///
+/// [SpinButton]: ../../gtk/struct.SpinButton.html
+pub trait SpinButtonExtHelpers: SpinButtonExt {
+    fn get_min(&self) -> f64 {
+        self.get_adjustment().get_lower()
+    }
+
+    fn set_min(&self, min: f64) {
+        self.set_range(min, self.get_max().max(min));
+    }
+
+    fn get_max(&self) -> f64 {
+        self.get_adjustment().get_upper()
+    }
+
+    fn set_max(&self, max: f64) {
+        self.set_range(self.get_min().min(max), max);
+    }
+
+    fn get_step(&self) -> f64 {
+        self.get_adjustment().get_step_increment()
+    }
+
+    fn set_step(&self, step: f64) {
+        self.set_increments(step, step * 10.0);
+    }
+}
+
+impl<A> SpinButtonExtHelpers for A where A: SpinButtonExt {}
+
+/// Helper trait for [`ScaleButton`][ScaleButton] and
+/// [`VolumeButton`][VolumeButton].
+///
+/// This provides a getter for the `icons` attribute, the `icon` attribute for
+/// using the same icon for every value, and the `min`, `max` and `step`
+/// attributes, which set the bounds and step increment of the button's
+/// adjustment.
+///
+/// [ScaleButton]: ../../gtk/struct.ScaleButton.html
+/// [VolumeButton]: ../../gtk/struct.VolumeButton.html
+pub trait ScaleButtonExtHelpers: ScaleButtonExt {
+    fn get_icons(&self) -> Vec<GString> {
+        self.get_property_icons()
+    }
+
+    fn get_icon(&self) -> Option<GString> {
+        self.get_property_icons().into_iter().next()
+    }
+
+    fn set_icon(&self, icon: &str) {
+        self.set_icons(&[icon]);
+    }
+
+    fn get_min(&self) -> f64 {
+        self.get_adjustment().get_lower()
+    }
+
+    fn set_min(&self, min: f64) {
+        let adjustment = self.get_adjustment();
+        adjustment.set_lower(min);
+        if adjustment.get_upper() < min {
+            adjustment.set_upper(min);
+        }
+    }
+
+    fn get_max(&self) -> f64 {
+        self.get_adjustment().get_upper()
+    }
+
+    fn set_max(&self, max: f64) {
+        let adjustment = self.get_adjustment();
+        adjustment.set_upper(max);
+        if adjustment.get_lower() > max {
+            adjustment.set_lower(max);
+        }
+    }
+
+    fn get_step(&self) -> f64 {
+        self.get_adjustment().get_step_increment()
+    }
+
+    fn set_step(&self, step: f64) {
+        let adjustment = self.get_adjustment();
+        adjustment.set_step_increment(step);
+        adjustment.set_page_increment(step * 10.0);
+    }
+}
+
+impl<A> ScaleButtonExtHelpers for A where A: ScaleButtonExt {}
+
+const ANNOUNCE_KEY: &str = "vgtk-announce";
+const VALIDATION_ERROR_KEY: &str = "vgtk-validation-error";
+const SIZE_GROUP_KEY: &str = "vgtk-size-group";
+const SIZE_GROUP_MODE_KEY: &str = "vgtk-size-group-mode";
+
+thread_local! {
+    static SIZE_GROUPS: RefCell<HashMap<String, SizeGroup>> = RefCell::new(HashMap::new());
+}
+
+// Take a widget out of the named size group, forgetting the group once it's
+// empty.
+fn leave_size_group(widget: &Widget, name: &str) {
+    SIZE_GROUPS.with(|groups| {
+        let mut groups = groups.borrow_mut();
+        if let Some(group) = groups.get(name) {
+            group.remove_widget(widget);
+            if group.get_widgets().is_empty() {
+                groups.remove(name);
+            }
+        }
+    });
+}
+
+// Forget the size groups whose widgets have all been destroyed, returning how
+// many there were.
+pub(crate) fn trim_size_groups() -> usize {
+    SIZE_GROUPS.with(|groups| {
+        let mut groups = groups.borrow_mut();
+        let before = groups.len();
+        groups.retain(|_, group| !group.get_widgets().is_empty());
+        before - groups.len()
+    })
+}
+
+// Put a widget in the named size group, creating it if need be, and give the
+// group the widget's mode.
+fn join_size_group(widget: &Widget, name: &str, mode: SizeGroupMode) {
+    SIZE_GROUPS.with(|groups| {
+        let mut groups = groups.borrow_mut();
+        let group = groups
+            .entry(name.to_string())
+            .or_insert_with(|| SizeGroup::new(mode));
+        group.set_mode(mode);
+        group.add_widget(widget);
+    });
+}
+
+/// Helper trait for [`Widget`][Widget].
+///
+/// This exposes the name, description and role of a widget's accessible object,
+/// which are what screen readers announce, so they can be set using attributes
+/// like `accessible_name="Save"`, and provides the `announce` attribute, which
+/// announces its value to screen reader users whenever it changes, and the
+/// `validation_error` attribute for marking invalid form fields.
+///
+/// The `size_group` attribute puts widgets in a [`SizeGroup`][SizeGroup] by
+/// name, so that every widget with the same `size_group` gets the same size,
+/// which is how you line up the fields of a form whose labels are in
+/// different boxes. Groups are shared by name across the whole app, so give
+/// each form's groups their own names. Widgets leave their group when the
+/// attribute changes to another name, or to an empty string, and when
+/// they're destroyed. Groups match widths unless a member sets
+/// `size_group_mode` to say otherwise:
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, VNode};
+/// # use vgtk::ext::*;
+/// # use vgtk::lib::gtk::*;
+/// # fn view() -> VNode<()> { gtk! {
+/// <Box orientation=Orientation::Vertical>
+///     <Box>
+///         <Label label="Name" size_group="form-labels" xalign=0.0 />
+///         <Entry hexpand=true />
+///     </Box>
+///     <Box>
+///         <Label label="Email address" size_group="form-labels" xalign=0.0 />
+///         <Entry hexpand=true />
+///     </Box>
+/// </Box>
+/// # }}
+/// ```
+///
+/// The `gesture_swipe`, `long_press` and `pinch` signals handle touch
+/// gestures, and their mouse equivalents where there are any, using GTK's
+/// gesture controllers, which are added to the widget when a handler is first
+/// connected and stay with it across patches until it's destroyed. Gestures
+/// need events, so use them on widgets with a window of their own, or wrap
+/// others in an [`EventBox`][EventBox]:
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, Component, VNode};
+/// # use vgtk::ext::*;
+/// # use vgtk::lib::gtk::*;
+/// # #[derive(Default)] struct Photo;
+/// # #[derive(Clone, Debug)] enum Message { Swipe(f64), Menu, Zoom(f64) }
+/// # impl Component for Photo { type Message = Message; type Properties = ();
+/// # fn create(_props: ()) -> Self { Self::default() }
+/// fn view(&self) -> VNode<Photo> {
+///     gtk! {
+///         <EventBox
+///             on gesture_swipe=|_, x, _| (Message::Swipe(x), ())
+///             on long_press=|_, _, _| (Message::Menu, ())
+///             on pinch=|_, scale| (Message::Zoom(scale), ())
+///         >
+///             <Image resource="photo.jpg" />
+///         </EventBox>
+///     }
+/// }
+/// # }
+/// ```
+///
+/// [Widget]: ../../gtk/struct.Widget.html
+/// [SizeGroup]: ../../gtk/struct.SizeGroup.html
+/// [EventBox]: ../../gtk/struct.EventBox.html
+pub trait WidgetExtHelpers: WidgetExt + IsA<Widget> {
+    fn get_accessible_name(&self) -> Option<GString> {
+        self.get_accessible().and_then(|accessible| accessible.get_name())
+    }
+
+    fn set_accessible_name(&self, name: &str) {
+        if let Some(accessible) = self.get_accessible() {
+            accessible.set_name(name);
+        }
+    }
+
+    fn get_accessible_description(&self) -> Option<GString> {
+        self.get_accessible()
+            .and_then(|accessible| accessible.get_description())
+    }
+
+    fn set_accessible_description(&self, description: &str) {
+        if let Some(accessible) = self.get_accessible() {
+            accessible.set_description(description);
+        }
+    }
+
+    fn get_accessible_role(&self) -> Role {
+        self.get_accessible()
+            .map(|accessible| accessible.get_role())
+            .unwrap_or(Role::Unknown)
+    }
+
+    fn set_accessible_role(&self, role: Role) {
+        if let Some(accessible) = self.get_accessible() {
+            accessible.set_role(role);
+        }
+    }
+
+    fn get_announce(&self) -> String {
+        #[allow(unsafe_code)]
+        unsafe { self.get_data::<String>(ANNOUNCE_KEY) }
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Announce `message` to screen reader users when it changes, but not
+    /// when it's first set. See [`vgtk::a11y`][a11y].
+    ///
+    /// [a11y]: ../a11y/index.html
+    fn set_announce(&self, message: &str) {
+        #[allow(unsafe_code)]
+        let previous = unsafe { self.steal_data::<String>(ANNOUNCE_KEY) };
+        // Properties can be set again with the same value on every patch,
+        // which mustn't repeat the announcement.
+        if previous.is_some() && previous.as_deref() != Some(message) && !message.is_empty() {
+            crate::a11y::announce_from(self, message, crate::a11y::Politeness::Polite);
+        }
+        #[allow(unsafe_code)]
+        unsafe {
+            self.set_data(ANNOUNCE_KEY, message.to_string());
+        }
+    }
+
+    fn get_validation_error(&self) -> Option<String> {
+        #[allow(unsafe_code)]
+        unsafe { self.get_data::<String>(VALIDATION_ERROR_KEY) }.cloned()
+    }
+
+    /// Mark the widget as invalid while `error` is set. See
+    /// [`vgtk::form`][form].
+    ///
+    /// [form]: ../form/index.html
+    fn set_validation_error(&self, error: Option<String>) {
+        let style = self.get_style_context();
+        let entry = self.dynamic_cast_ref::<Entry>();
+        #[allow(unsafe_code)]
+        let previous = unsafe { self.steal_data::<String>(VALIDATION_ERROR_KEY) };
+        match error {
+            Some(error) => {
+                style.add_class("error");
+                if let Some(entry) = entry {
+                    entry.set_icon_from_icon_name(
+                        EntryIconPosition::Secondary,
+                        Some("dialog-error-symbolic"),
+                    );
+                    entry.set_icon_tooltip_text(EntryIconPosition::Secondary, Some(&error));
+                }
+                #[allow(unsafe_code)]
+                unsafe {
+                    self.set_data(VALIDATION_ERROR_KEY, error);
+                }
+            }
+            None if previous.is_some() => {
+                style.remove_class("error");
+                if let Some(entry) = entry {
+                    entry.set_icon_from_icon_name(EntryIconPosition::Secondary, None);
+                    entry.set_icon_tooltip_text(EntryIconPosition::Secondary, None);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn get_size_group(&self) -> String {
+        #[allow(unsafe_code)]
+        unsafe { self.get_data::<String>(SIZE_GROUP_KEY) }
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Put the widget in the size group called `name`, taking it out of the
+    /// one it was in before, if any. An empty name leaves it in no group.
+    fn set_size_group(&self, name: &str) {
+        let widget = self.upcast_ref::<Widget>();
+        #[allow(unsafe_code)]
+        let previous = unsafe { self.steal_data::<String>(SIZE_GROUP_KEY) };
+        if let Some(previous) = previous {
+            leave_size_group(widget, &previous);
+        }
+        if !name.is_empty() {
+            join_size_group(widget, name, self.get_size_group_mode());
+            #[allow(unsafe_code)]
+            unsafe {
+                self.set_data(SIZE_GROUP_KEY, name.to_string());
+            }
+        }
+    }
+
+    fn get_size_group_mode(&self) -> SizeGroupMode {
+        #[allow(unsafe_code)]
+        unsafe { self.get_data::<SizeGroupMode>(SIZE_GROUP_MODE_KEY) }
+            .cloned()
+            .unwrap_or(SizeGroupMode::Horizontal)
+    }
+
+    /// Set the mode of the widget's size group, or of the group it joins
+    /// next.
+    fn set_size_group_mode(&self, mode: SizeGroupMode) {
+        #[allow(unsafe_code)]
+        unsafe {
+            self.set_data(SIZE_GROUP_MODE_KEY, mode);
+        }
+        let name = self.get_size_group();
+        if !name.is_empty() {
+            join_size_group(self.upcast_ref(), &name, mode);
+        }
+    }
+
+    /// Test whether the widget is currently laid out right to left.
+    ///
+    /// To have a component react when this changes, because the locale or the
+    /// widget's own direction changed, handle the `direction_changed` signal:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{gtk, Component, VNode};
+    /// # use vgtk::lib::gtk::{Box, TextDirection, WidgetExt};
+    /// # #[derive(Default)] struct App;
+    /// # #[derive(Clone, Debug)] enum Message { Direction(TextDirection) }
+    /// # impl Component for App { type Message = Message; type Properties = ();
+    /// # fn create(_props: ()) -> Self { Self::default() }
+    /// fn view(&self) -> VNode<App> {
+    ///     gtk! {
+    ///         <Box on direction_changed=|widget, _| (Message::Direction(widget.get_direction()), ()) />
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    fn is_rtl(&self) -> bool {
+        self.get_direction() == TextDirection::Rtl
+    }
+
+    /// Handle a swipe, with its horizontal and vertical velocity in pixels
+    /// per second.
+    fn connect_gesture_swipe<F: Fn(&Self, f64, f64) + 'static>(&self, f: F) -> SignalHandlerId {
+        gesture::connect_swipe(self, f)
+    }
+
+    /// Handle a long press, with where it was relative to the widget.
+    fn connect_long_press<F: Fn(&Self, f64, f64) + 'static>(&self, f: F) -> SignalHandlerId {
+        gesture::connect_long_press(self, f)
+    }
+
+    /// Handle a pinch, with its scale relative to when it started.
+    fn connect_pinch<F: Fn(&Self, f64) + 'static>(&self, f: F) -> SignalHandlerId {
+        gesture::connect_pinch(self, f)
+    }
+
+    /// Handle a key press, decoded. See [`vgtk::event`][event].
+    ///
+    /// [event]: ../event/index.html
+    fn connect_key_press<F: Fn(&Self, &KeyEvent) -> Inhibit + 'static>(&self, f: F) -> SignalHandlerId {
+        self.connect_key_press_event(move |widget, event| f(widget, &KeyEvent::from(event)))
+    }
+
+    /// Handle a key release, decoded.
+    fn connect_key_release<F: Fn(&Self, &KeyEvent) -> Inhibit + 'static>(&self, f: F) -> SignalHandlerId {
+        self.connect_key_release_event(move |widget, event| f(widget, &KeyEvent::from(event)))
+    }
+
+    /// Handle a mouse button press, decoded.
+    fn connect_button_press<F: Fn(&Self, &ButtonEvent) -> Inhibit + 'static>(&self, f: F) -> SignalHandlerId {
+        self.connect_button_press_event(move |widget, event| f(widget, &ButtonEvent::from(event)))
+    }
+
+    /// Handle a mouse button release, decoded.
+    fn connect_button_release<F: Fn(&Self, &ButtonEvent) -> Inhibit + 'static>(&self, f: F) -> SignalHandlerId {
+        self.connect_button_release_event(move |widget, event| f(widget, &ButtonEvent::from(event)))
+    }
+}
+
+impl<A> WidgetExtHelpers for A where A: WidgetExt + IsA<Widget> {}
+
 /// Helper trait for [`Notebook`][Notebook].
 ///
 /// [Notebook]: ../../gtk/struct.Notebook.html
@@ -245,6 +954,62 @@ pub trait NotebookExtHelpers: NotebookExt {
 
 impl<A> NotebookExtHelpers for A where A: NotebookExt {}
 
+const VISIBLE_PAGE_KEY: &str = "vgtk-visible-page";
+
+/// Helper trait for [`Stack`][Stack].
+///
+/// This provides the `visible_page` attribute, which works like
+/// `visible_child_name`, except it can name a child which hasn't been added
+/// yet, as is the case when a new stack's attributes are applied. The stack
+/// then switches to that child, without a transition, once it's been built.
+///
+/// [Stack]: ../../gtk/struct.Stack.html
+pub trait StackExtHelpers: StackExt + IsA<Stack> {
+    fn get_visible_page(&self) -> Option<GString> {
+        #[allow(unsafe_code)]
+        let pending = unsafe { self.get_data::<String>(VISIBLE_PAGE_KEY) }.cloned();
+        pending
+            .map(GString::from)
+            .or_else(|| self.get_visible_child_name())
+    }
+
+    fn set_visible_page(&self, name: &str) {
+        #[allow(unsafe_code)]
+        unsafe {
+            self.steal_data::<String>(VISIBLE_PAGE_KEY);
+        }
+        if self.get_child_by_name(name).is_some() {
+            self.set_visible_child_name(name);
+            return;
+        }
+        #[allow(unsafe_code)]
+        unsafe {
+            self.set_data(VISIBLE_PAGE_KEY, name.to_string());
+        }
+        let stack = self.upcast_ref::<Stack>().downgrade();
+        glib::idle_add_local(move || {
+            if let Some(stack) = stack.upgrade() {
+                #[allow(unsafe_code)]
+                let pending = unsafe { stack.steal_data::<String>(VISIBLE_PAGE_KEY) };
+                if let Some(name) = pending {
+                    if stack.get_child_by_name(&name).is_some() {
+                        stack.set_visible_child_full(&name, StackTransitionType::None);
+                    } else {
+                        warn!(
+                            "{} {}",
+                            "Stack has no page named".bright_red(),
+                            name.bright_white().bold()
+                        );
+                    }
+                }
+            }
+            glib::Continue(false)
+        });
+    }
+}
+
+impl<A> StackExtHelpers for A where A: StackExt + IsA<Stack> {}
+
 /// Helper trait for [`Grid`][Grid] layout.
 ///
 /// This helper enables using the GTK Grid for laying out widgets in a window.
@@ -394,8 +1159,66 @@ where
     }
 }
 
+const RESPONSE_KEY: &str = "vgtk-info-bar-response";
+
+/// Helper trait for [`InfoBar`][InfoBar].
+///
+/// Children of an [`InfoBar`][InfoBar] go in its content area, unless they
+/// have the `InfoBar::response` child attribute, which puts them in its
+/// action area instead, emitting the `response` signal with the given
+/// response when they're activated:
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, VNode};
+/// # use vgtk::ext::*;
+/// # use vgtk::lib::gtk::*;
+/// # #[derive(Clone, Debug)] enum Message { Retry, Dismiss }
+/// # #[derive(Default)] struct Model { offline: bool }
+/// # impl vgtk::Component for Model {
+/// #     type Message = Message; type Properties = ();
+/// #     fn create(_props: ()) -> Self { Self::default() }
+/// fn view(&self) -> VNode<Self> {
+///     gtk! {
+///         <InfoBar message_type=MessageType::Warning revealed=self.offline
+///                  show_close_button=true
+///                  on response=|_, response| (match response {
+///                      ResponseType::Accept => Message::Retry,
+///                      _ => Message::Dismiss,
+///                  }, ())>
+///             <Label label="You're offline." />
+///             <Button label="Retry" InfoBar::response=ResponseType::Accept />
+///         </InfoBar>
+///     }
+/// }
+/// # }
+/// ```
+///
+/// [InfoBar]: ../../gtk/struct.InfoBar.html
 pub trait InforBarExtHelpers: InfoBarExt {
     fn new_with_buttons(btns: &[InfoBarButton]) -> Self;
+
+    fn get_child_response<P: IsA<Widget>>(&self, child: &P) -> ResponseType {
+        #[allow(unsafe_code)]
+        unsafe { child.as_ref().get_data::<ResponseType>(RESPONSE_KEY) }
+            .copied()
+            .unwrap_or(ResponseType::None)
+    }
+
+    fn set_child_response<P: IsA<Widget>>(&self, child: &P, response: ResponseType) {
+        let child = child.as_ref();
+        // Move the child into the action area, or re-add it there with its
+        // new response.
+        if let Some(parent) = child.get_parent() {
+            if let Ok(parent) = parent.downcast::<gtk::Container>() {
+                parent.remove(child);
+            }
+        }
+        self.add_action_widget(child, response);
+        #[allow(unsafe_code)]
+        unsafe {
+            child.set_data(RESPONSE_KEY, response);
+        }
+    }
 }
 
 impl InforBarExtHelpers for InfoBar {
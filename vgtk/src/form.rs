@@ -0,0 +1,428 @@
+//! Helpers for building forms.
+//!
+//! A [`Form`][Form] keeps track of the values of a set of fields, checks them
+//! against their [`Validator`][Validator]s as they change, and tells you which
+//! fields are invalid and why. Keep one in your component's state, update it
+//! from your input handlers, and use it in your `view()`:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::ext::*;
+//! # use vgtk::form::{Form, Validator};
+//! # use vgtk::lib::gtk::*;
+//! #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+//! enum Field {
+//!     Name,
+//!     Email,
+//! }
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Input(Field, String),
+//! }
+//!
+//! struct SignUp {
+//!     form: Form<Field>,
+//! }
+//!
+//! impl Default for SignUp {
+//!     fn default() -> Self {
+//!         SignUp {
+//!             form: Form::new()
+//!                 .field(Field::Name, vec![Validator::required("Please enter your name")])
+//!                 .field(Field::Email, vec![
+//!                     Validator::required("Please enter your email address"),
+//!                     Validator::regex(r"^[^@]+@[^@]+$", "That doesn't look like an email address"),
+//!                 ]),
+//!         }
+//!     }
+//! }
+//!
+//! impl Component for SignUp {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Input(field, value) => self.form.set(&field, value),
+//!         }
+//!         UpdateAction::Render
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         gtk! {
+//!             <Box orientation=Orientation::Vertical>
+//!                 <Entry validation_error=self.form.error(&Field::Name)
+//!                        on changed=|entry| (Message::Input(Field::Name, entry.get_text().to_string()), ()) />
+//!                 <Entry validation_error=self.form.error(&Field::Email)
+//!                        on changed=|entry| (Message::Input(Field::Email, entry.get_text().to_string()), ()) />
+//!                 <Button label="Sign up" sensitive=self.form.is_valid() />
+//!             </Box>
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Errors are only reported for fields which have been edited, or for every
+//! field once you've called [`Form::validate()`][validate], so the user isn't
+//! greeted by a wall of complaints about a form they haven't filled in yet.
+//! The `validation_error` attribute, which works on any widget, adds the
+//! `error` style class to the widget while it has an error, and on an
+//! [`Entry`][Entry] it also shows an error icon with the message as its
+//! tooltip.
+//!
+//! When a form is submitted and some of its fields turn out to be invalid, the
+//! user needs to be shown where the problem is, even if the offending widget is
+//! buried inside nested containers or scrolled out of view. [`reveal_errors()`][reveal_errors]
+//! takes care of this, as configured by an [`ErrorReveal`][ErrorReveal].
+//!
+//! [Form]: struct.Form.html
+//! [Validator]: struct.Validator.html
+//! [validate]: struct.Form.html#method.validate
+//! [Entry]: ../../gtk/struct.Entry.html
+//! [reveal_errors]: fn.reveal_errors.html
+//! [ErrorReveal]: struct.ErrorReveal.html
+
+use std::fmt::{Debug, Error, Formatter};
+use std::rc::Rc;
+
+use glib::{Cast, IsA};
+use regex::Regex;
+use gtk::{
+    AdjustmentExt, BinExt, ScrolledWindow, ScrolledWindowExt, StyleContextExt, Viewport, Widget,
+    WidgetExt,
+};
+
+use crate::affinity;
+use crate::widgets::Section;
+
+/// A check a form field's value must pass to be valid.
+#[derive(Clone)]
+pub struct Validator {
+    check: Rc<dyn Fn(&str) -> Result<(), String>>,
+}
+
+impl Debug for Validator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "Validator")
+    }
+}
+
+impl Validator {
+    /// Construct a validator from a function, which should return the error
+    /// message to show if the value isn't valid.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + 'static,
+    {
+        Validator {
+            check: Rc::new(check),
+        }
+    }
+
+    /// A validator which requires the value to be non-empty, not counting
+    /// whitespace.
+    pub fn required(message: &str) -> Self {
+        let message = message.to_string();
+        Self::new(move |value| {
+            if value.trim().is_empty() {
+                Err(message.clone())
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    /// A validator which requires the value to match a regular expression.
+    ///
+    /// Empty values are allowed, so that the field can be optional; combine
+    /// this with [`required()`][required] if it isn't.
+    ///
+    /// Panics if the regular expression is invalid.
+    ///
+    /// [required]: #method.required
+    pub fn regex(pattern: &str, message: &str) -> Self {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|err| panic!("invalid regex in form validator: {}", err));
+        let message = message.to_string();
+        Self::new(move |value| {
+            if value.is_empty() || regex.is_match(value) {
+                Ok(())
+            } else {
+                Err(message.clone())
+            }
+        })
+    }
+
+    /// Check a value against this validator.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        (self.check)(value)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Field<K> {
+    key: K,
+    value: String,
+    validators: Vec<Validator>,
+    error: Option<String>,
+    touched: bool,
+}
+
+impl<K> Field<K> {
+    fn check(&mut self) {
+        self.error = self
+            .validators
+            .iter()
+            .find_map(|validator| validator.check(&self.value).err());
+    }
+}
+
+/// The state of a form's fields and their validation.
+///
+/// Fields are identified by keys of type `K`, which would usually be a
+/// simple enum.
+#[derive(Clone, Debug)]
+pub struct Form<K> {
+    fields: Vec<Field<K>>,
+}
+
+impl<K> Default for Form<K> {
+    fn default() -> Self {
+        Form { fields: Vec::new() }
+    }
+}
+
+impl<K: PartialEq> Form<K> {
+    /// Construct a form with no fields.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a field, with an empty value.
+    pub fn field(mut self, key: K, validators: Vec<Validator>) -> Self {
+        let mut field = Field {
+            key,
+            value: String::new(),
+            validators,
+            error: None,
+            touched: false,
+        };
+        field.check();
+        self.fields.push(field);
+        self
+    }
+
+    fn get(&self, key: &K) -> &Field<K> {
+        self.fields
+            .iter()
+            .find(|field| &field.key == key)
+            .expect("no such field in form")
+    }
+
+    fn get_mut(&mut self, key: &K) -> &mut Field<K> {
+        self.fields
+            .iter_mut()
+            .find(|field| &field.key == key)
+            .expect("no such field in form")
+    }
+
+    /// Set the value of a field and validate it.
+    ///
+    /// Panics if the form has no such field.
+    pub fn set<S: Into<String>>(&mut self, key: &K, value: S) {
+        let field = self.get_mut(key);
+        field.value = value.into();
+        field.touched = true;
+        field.check();
+    }
+
+    /// Get the value of a field.
+    ///
+    /// Panics if the form has no such field.
+    pub fn value(&self, key: &K) -> &str {
+        &self.get(key).value
+    }
+
+    /// Get the error to show for a field, if it's invalid and it's been
+    /// edited or [`validate()`][validate] has been called.
+    ///
+    /// Panics if the form has no such field.
+    ///
+    /// [validate]: #method.validate
+    pub fn error(&self, key: &K) -> Option<String> {
+        let field = self.get(key);
+        if field.touched {
+            field.error.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Test whether every field is valid, whether or not it's been edited.
+    pub fn is_valid(&self) -> bool {
+        self.fields.iter().all(|field| field.error.is_none())
+    }
+
+    /// Show errors for every field, including the ones which haven't been
+    /// edited, and test whether every field is valid.
+    ///
+    /// Call this when the form is submitted.
+    pub fn validate(&mut self) -> bool {
+        for field in &mut self.fields {
+            field.touched = true;
+        }
+        self.is_valid()
+    }
+
+    /// Get the keys of the invalid fields, in the order they were added.
+    pub fn invalid_fields(&self) -> impl Iterator<Item = &K> {
+        self.fields
+            .iter()
+            .filter(|field| field.error.is_some())
+            .map(|field| &field.key)
+    }
+
+    /// Clear every field's value, and stop showing errors until they've been
+    /// edited again.
+    pub fn reset(&mut self) {
+        for field in &mut self.fields {
+            field.value.clear();
+            field.touched = false;
+            field.check();
+        }
+    }
+}
+
+/// Configures what happens to invalid widgets when a form is submitted.
+///
+/// The default is to scroll the first invalid widget into view, give it the
+/// keyboard focus, and add the `error` style class to every invalid widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorReveal {
+    /// Scroll the first invalid widget into view.
+    pub scroll: bool,
+    /// Give the keyboard focus to the first invalid widget.
+    pub focus: bool,
+    /// A style class to add to every invalid widget, if any.
+    pub style_class: Option<&'static str>,
+}
+
+impl Default for ErrorReveal {
+    fn default() -> Self {
+        ErrorReveal {
+            scroll: true,
+            focus: true,
+            style_class: Some("error"),
+        }
+    }
+}
+
+impl ErrorReveal {
+    /// Don't scroll to the first invalid widget.
+    pub fn without_scroll(mut self) -> Self {
+        self.scroll = false;
+        self
+    }
+
+    /// Don't move the focus to the first invalid widget.
+    pub fn without_focus(mut self) -> Self {
+        self.focus = false;
+        self
+    }
+
+    /// Use a different style class for invalid widgets, or none at all.
+    pub fn with_style_class(mut self, style_class: Option<&'static str>) -> Self {
+        self.style_class = style_class;
+        self
+    }
+}
+
+/// Reveal a list of invalid widgets to the user, in the order given.
+///
+/// The first widget is scrolled into view and focused according to `reveal`, and
+/// every widget gets the configured error style class. Any collapsed
+/// [`Section`][Section] containing the first widget is expanded. Returns
+/// `true` if there were any invalid widgets.
+///
+/// [Section]: ../widgets/struct.Section.html
+pub fn reveal_errors<W, I>(invalid: I, reveal: &ErrorReveal) -> bool
+where
+    W: IsA<Widget>,
+    I: IntoIterator<Item = W>,
+{
+    let mut first = true;
+    for widget in invalid {
+        let widget = widget.upcast_ref::<Widget>();
+        affinity::assert_owner(widget, "vgtk::form::reveal_errors");
+        if let Some(class) = reveal.style_class {
+            widget.get_style_context().add_class(class);
+        }
+        if first {
+            first = false;
+            let mut ancestor = widget.get_parent();
+            while let Some(current) = ancestor {
+                if let Some(section) = current.downcast_ref::<Section>() {
+                    section.set_expanded(true);
+                }
+                ancestor = current.get_parent();
+            }
+            if reveal.scroll {
+                scroll_into_view(widget);
+            }
+            if reveal.focus {
+                widget.grab_focus();
+            }
+        }
+    }
+    !first
+}
+
+/// Remove the error style class added by [`reveal_errors()`][reveal_errors]
+/// from a widget.
+///
+/// [reveal_errors]: fn.reveal_errors.html
+pub fn clear_error<W: IsA<Widget>>(widget: &W, reveal: &ErrorReveal) {
+    affinity::assert_owner(widget.as_ref(), "vgtk::form::clear_error");
+    if let Some(class) = reveal.style_class {
+        widget.as_ref().get_style_context().remove_class(class);
+    }
+}
+
+/// Scroll every [`ScrolledWindow`][ScrolledWindow] containing a widget so that
+/// the widget becomes visible.
+///
+/// [ScrolledWindow]: ../../gtk/struct.ScrolledWindow.html
+pub fn scroll_into_view<W: IsA<Widget>>(widget: &W) {
+    let widget = widget.as_ref();
+    affinity::assert_owner(widget, "vgtk::form::scroll_into_view");
+    let mut ancestor = widget.get_parent();
+    while let Some(current) = ancestor {
+        if let Some(scrolled) = current.downcast_ref::<ScrolledWindow>() {
+            // Adjustments are measured in the coordinates of the scrolled
+            // content, which is the viewport's child if there's a viewport.
+            let content = scrolled.get_child().map(|child| {
+                match child.downcast_ref::<Viewport>().and_then(BinExt::get_child) {
+                    Some(inner) => inner,
+                    None => child,
+                }
+            });
+            if let Some((x, y)) =
+                content.and_then(|content| widget.translate_coordinates(&content, 0, 0))
+            {
+                let (width, height) = (widget.get_allocated_width(), widget.get_allocated_height());
+                if let Some(adjustment) = scrolled.get_hadjustment() {
+                    adjustment.clamp_page(x as f64, (x + width) as f64);
+                }
+                if let Some(adjustment) = scrolled.get_vadjustment() {
+                    adjustment.clamp_page(y as f64, (y + height) as f64);
+                }
+            }
+        }
+        ancestor = current.get_parent();
+    }
+}
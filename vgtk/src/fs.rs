@@ -0,0 +1,281 @@
+//! Reading, writing and watching files without blocking the UI.
+//!
+//! These are thin wrappers around GIO's asynchronous [`File`][File] API,
+//! which runs on the same GLib main loop as everything else in vgtk, so a
+//! component can load and save files in the futures it passes to
+//! [`UpdateAction::defer()`][defer] without pulling in another async runtime
+//! or tying up a thread of its own:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::*;
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Loaded(Result<String, String>),
+//!     Save,
+//!     Saved(Result<(), String>),
+//! }
+//!
+//! #[derive(Default)]
+//! struct Notes {
+//!     text: String,
+//! }
+//!
+//! impl Component for Notes {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn init(&mut self) -> UpdateAction<Self> {
+//!         UpdateAction::defer(async {
+//!             let text = vgtk::fs::read_to_string("notes.txt").await;
+//!             Message::Loaded(text.map_err(|error| error.to_string()))
+//!         })
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Loaded(Ok(text)) => {
+//!                 self.text = text;
+//!                 UpdateAction::Render
+//!             }
+//!             Message::Save => {
+//!                 let text = self.text.clone();
+//!                 UpdateAction::defer(async move {
+//!                     let saved = vgtk::fs::write("notes.txt", text).await;
+//!                     Message::Saved(saved.map_err(|error| error.to_string()))
+//!                 })
+//!             }
+//!             _ => UpdateAction::None,
+//!         }
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         // ...
+//! #       gtk! { <Label label=self.text.clone() /> }
+//!     }
+//! }
+//! ```
+//!
+//! [`watch()`][watch] turns changes to a file or directory into a
+//! [`Stream`][Stream], which can be handed to a
+//! [`SignalGovernor`][SignalGovernor] to deliver them to a component as
+//! messages.
+//!
+//! Like the rest of GIO, these work on paths in any location GIO can reach,
+//! and have to be used on the main thread.
+//!
+//! [File]: ../../gio/struct.File.html
+//! [defer]: ../enum.UpdateAction.html#method.defer
+//! [watch]: fn.watch.html
+//! [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+//! [SignalGovernor]: ../governor/struct.SignalGovernor.html
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use futures::stream::{Stream, StreamExt};
+use gio::prelude::*;
+use gio::{
+    Cancellable, File, FileCreateFlags, FileMonitor, FileMonitorEvent, FileMonitorFlags,
+    FileQueryInfoFlags, FileType, IOErrorEnum,
+};
+
+use crate::affinity;
+
+/// How many directory entries to read at a time.
+const BATCH: i32 = 64;
+
+/// Read the whole of a file.
+pub async fn read<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, glib::Error> {
+    let file = File::new_for_path(path);
+    let (contents, _etag) = file.load_contents_async_future().await?;
+    Ok(contents)
+}
+
+/// Read the whole of a file into a string, which fails if it isn't valid
+/// UTF-8.
+pub async fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, glib::Error> {
+    let path = path.as_ref().to_path_buf();
+    let contents = read(&path).await?;
+    String::from_utf8(contents).map_err(|_| {
+        glib::Error::new(
+            IOErrorEnum::InvalidData,
+            &format!("{} isn't valid UTF-8", path.display()),
+        )
+    })
+}
+
+/// Replace the contents of a file, creating it if it doesn't exist.
+///
+/// The new contents are written to a temporary file first, which then
+/// takes the place of the old one, so the file is never left half written.
+pub async fn write<P, C>(path: P, contents: C) -> Result<(), glib::Error>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]> + Send + 'static,
+{
+    let file = File::new_for_path(path);
+    file.replace_contents_async_future(contents, None, false, FileCreateFlags::NONE)
+        .await
+        .map(|_| ())
+        .map_err(|(_, error)| error)
+}
+
+/// An entry in a directory, as listed by [`read_dir()`][read_dir].
+///
+/// [read_dir]: fn.read_dir.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    /// The entry's path, which is the directory's path followed by its name.
+    pub path: PathBuf,
+    /// The entry's name within the directory.
+    pub name: PathBuf,
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+    /// Whether the entry should be hidden from the user, like dot files on
+    /// Unix.
+    pub is_hidden: bool,
+    /// The size of the entry in bytes, if it's a file.
+    pub size: u64,
+}
+
+/// List the entries in a directory, in no particular order.
+pub async fn read_dir<P: AsRef<Path>>(path: P) -> Result<Vec<DirEntry>, glib::Error> {
+    let path = path.as_ref();
+    let directory = File::new_for_path(path);
+    // gio-rs doesn't have the asynchronous version of this. Opening the
+    // directory is quick, though, and it's reading it which can take a while.
+    let enumerator = directory.enumerate_children(
+        "standard::name,standard::type,standard::is-hidden,standard::size",
+        FileQueryInfoFlags::NONE,
+        None::<&Cancellable>,
+    )?;
+    let mut entries = Vec::new();
+    loop {
+        let batch = enumerator
+            .next_files_async_future(BATCH, glib::PRIORITY_DEFAULT)
+            .await?;
+        if batch.is_empty() {
+            break;
+        }
+        entries.extend(batch.into_iter().filter_map(|info| {
+            let name = info.get_name()?;
+            Some(DirEntry {
+                path: path.join(&name),
+                name,
+                is_dir: info.get_file_type() == FileType::Directory,
+                is_hidden: info.get_is_hidden(),
+                size: info.get_size().max(0) as u64,
+            })
+        }));
+    }
+    enumerator
+        .close_async_future(glib::PRIORITY_DEFAULT)
+        .await?;
+    Ok(entries)
+}
+
+/// A change to a file, or to something in a directory, reported by a
+/// [`Watch`][Watch].
+///
+/// [Watch]: struct.Watch.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A file was created, or moved in from somewhere else.
+    Created(PathBuf),
+    /// A file was written to, and has been closed, or hasn't been written
+    /// to for a while.
+    Changed(PathBuf),
+    /// A file was deleted, or moved out to somewhere else.
+    Deleted(PathBuf),
+    /// A file was renamed from the first path to the second.
+    Renamed(PathBuf, PathBuf),
+}
+
+impl Change {
+    fn from_event(file: &File, other: Option<&File>, event: FileMonitorEvent) -> Option<Self> {
+        let path = file.get_path()?;
+        match event {
+            FileMonitorEvent::Created | FileMonitorEvent::MovedIn => Some(Change::Created(path)),
+            // Every write sends a `Changed`, and the hint follows once
+            // they're done, which is when the file is worth reading again.
+            FileMonitorEvent::ChangesDoneHint => Some(Change::Changed(path)),
+            FileMonitorEvent::Deleted | FileMonitorEvent::MovedOut => Some(Change::Deleted(path)),
+            FileMonitorEvent::Renamed | FileMonitorEvent::Moved => {
+                let other = other.and_then(|other| other.get_path())?;
+                Some(Change::Renamed(path, other))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A [`Stream`][Stream] of changes to a file or directory, made by
+/// [`watch()`][watch].
+///
+/// The file stops being watched when this is dropped.
+///
+/// [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+/// [watch]: fn.watch.html
+#[derive(Debug)]
+pub struct Watch {
+    monitor: FileMonitor,
+    changes: UnboundedReceiver<Change>,
+}
+
+impl Stream for Watch {
+    type Item = Change;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Change>> {
+        self.changes.poll_next_unpin(ctx)
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.monitor.cancel();
+    }
+}
+
+/// Watch a file, or the files in a directory, for changes.
+///
+/// The path doesn't have to exist yet, so a file can be watched for being
+/// created. Subdirectories of a watched directory aren't watched.
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use vgtk::fs::Change;
+/// # use vgtk::governor::{Policy, SignalGovernor};
+/// # #[derive(Default)] struct Editor;
+/// # #[derive(Clone, Debug)] enum Message { FilesChanged(Vec<Change>) }
+/// # impl vgtk::Component for Editor { type Message = Message; type Properties = ();
+/// # fn create(_props: ()) -> Self { Self::default() }
+/// # fn view(&self) -> vgtk::VNode<Self> { unimplemented!() } }
+/// # fn example(scope: vgtk::Scope<Editor>) -> Result<(), vgtk::lib::glib::Error> {
+/// let changes = vgtk::fs::watch("src")?;
+/// let governor = SignalGovernor::new(scope);
+/// governor.subscribe(
+///     changes,
+///     Policy::Batch(Duration::from_millis(200), 100),
+///     Message::FilesChanged,
+/// );
+/// # Ok(()) }
+/// ```
+pub fn watch<P: AsRef<Path>>(path: P) -> Result<Watch, glib::Error> {
+    affinity::assert_main_thread("vgtk::fs::watch");
+    let file = File::new_for_path(path);
+    let monitor = file.monitor(FileMonitorFlags::WATCH_MOVES, None::<&Cancellable>)?;
+    let (sender, changes) = unbounded();
+    monitor.connect_changed(move |_, file, other, event| {
+        if let Some(change) = Change::from_event(file, other, event) {
+            let _ = sender.unbounded_send(change);
+        }
+    });
+    Ok(Watch { monitor, changes })
+}
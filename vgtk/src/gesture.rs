@@ -0,0 +1,186 @@
+//! Gesture controllers behind the gesture signals of widgets.
+//!
+//! This backs the `gesture_swipe`, `long_press` and `pinch` signals in
+//! `ext::WidgetExtHelpers`.
+//!
+//! GTK recognises gestures with `GtkGesture` controllers, which aren't
+//! widgets and have their own signals, so they can't be written in a view.
+//! Instead, each widget gets the controllers it needs when a handler is
+//! first connected, which then pass what they recognise on to the widget as
+//! signals of its own. The vdom connects, blocks and disconnects handlers
+//! for these like any other signal, while the controllers stay with the
+//! widget until it's destroyed, so patching a view never recreates them
+//! halfway through a gesture.
+
+use glib::{Cast, IsA, ObjectExt, ParamFlags, ParamSpec, SignalHandlerId};
+use gtk::{EventControllerExt, GestureLongPress, GestureSwipe, GestureZoom, Widget, WidgetExt};
+
+const GESTURE_KEY: &str = "vgtk-gestures";
+const SWIPE_SIGNAL: &str = "notify::vgtk-swipe";
+const LONG_PRESS_SIGNAL: &str = "notify::vgtk-long-press";
+const PINCH_SIGNAL: &str = "notify::vgtk-pinch";
+
+#[derive(Default, Clone)]
+struct GestureState {
+    swipe: Option<GestureSwipe>,
+    long_press: Option<GestureLongPress>,
+    pinch: Option<GestureZoom>,
+    // The last velocity of a swipe, in pixels per second.
+    velocity: (f64, f64),
+    // Where the last long press was, relative to the widget.
+    point: (f64, f64),
+    // The last pinch's scale, relative to when it started.
+    scale: f64,
+}
+
+fn state(widget: &Widget) -> Option<GestureState> {
+    #[allow(unsafe_code)]
+    unsafe { widget.get_data::<GestureState>(GESTURE_KEY) }.cloned()
+}
+
+fn set_state(widget: &Widget, state: GestureState) {
+    #[allow(unsafe_code)]
+    unsafe {
+        widget.set_data(GESTURE_KEY, state);
+    }
+}
+
+/// Change the widget's gesture state, setting it up first if it has none.
+fn update<F: FnOnce(&mut GestureState)>(widget: &Widget, f: F) {
+    let mut current = match state(widget) {
+        Some(current) => current,
+        None => {
+            // The controllers hold on to the widget, so they have to go
+            // before it's finalised.
+            widget.connect_destroy(|widget| {
+                #[allow(unsafe_code)]
+                let state = unsafe { widget.steal_data::<GestureState>(GESTURE_KEY) };
+                drop(state);
+            });
+            GestureState {
+                scale: 1.0,
+                ..GestureState::default()
+            }
+        }
+    };
+    f(&mut current);
+    set_state(widget, current);
+}
+
+/// Emit one of the widget's gesture signals, once the values it passes on
+/// are stored.
+fn emit(widget: &Widget, signal: &str, name: &str, update_state: impl FnOnce(&mut GestureState)) {
+    update(widget, update_state);
+    let pspec = ParamSpec::double(
+        name,
+        name,
+        "The last gesture of its kind",
+        f64::MIN,
+        f64::MAX,
+        0.0,
+        ParamFlags::READABLE,
+    );
+    let _ = widget.emit(signal, &[&pspec]);
+}
+
+fn connect<F: Fn(&Widget, GestureState) + 'static>(
+    widget: &Widget,
+    signal: &str,
+    f: F,
+) -> SignalHandlerId {
+    widget
+        .connect_local(signal, false, move |values| {
+            let widget = values[0]
+                .get::<glib::Object>()
+                .ok()
+                .flatten()
+                .and_then(|object| object.downcast::<Widget>().ok());
+            if let Some(widget) = widget {
+                if let Some(state) = state(&widget) {
+                    f(&widget, state);
+                }
+            }
+            None
+        })
+        .unwrap_or_else(|_| panic!("unable to connect to {} gestures", signal))
+}
+
+pub(crate) fn connect_swipe<W, F>(widget: &W, f: F) -> SignalHandlerId
+where
+    W: IsA<Widget>,
+    F: Fn(&W, f64, f64) + 'static,
+{
+    let widget = widget.upcast_ref();
+    update(widget, |state| {
+        if state.swipe.is_none() {
+            let gesture = GestureSwipe::new(widget);
+            gesture.connect_swipe(|gesture, x, y| {
+                if let Some(widget) = gesture.get_widget() {
+                    emit(&widget, SWIPE_SIGNAL, "vgtk-swipe", |state| {
+                        state.velocity = (x, y)
+                    });
+                }
+            });
+            state.swipe = Some(gesture);
+        }
+    });
+    connect(widget, SWIPE_SIGNAL, move |widget, state| {
+        if let Some(widget) = widget.downcast_ref::<W>() {
+            let (x, y) = state.velocity;
+            f(widget, x, y)
+        }
+    })
+}
+
+pub(crate) fn connect_long_press<W, F>(widget: &W, f: F) -> SignalHandlerId
+where
+    W: IsA<Widget>,
+    F: Fn(&W, f64, f64) + 'static,
+{
+    let widget = widget.upcast_ref();
+    update(widget, |state| {
+        if state.long_press.is_none() {
+            let gesture = GestureLongPress::new(widget);
+            gesture.connect_pressed(|gesture, x, y| {
+                if let Some(widget) = gesture.get_widget() {
+                    emit(&widget, LONG_PRESS_SIGNAL, "vgtk-long-press", |state| {
+                        state.point = (x, y)
+                    });
+                }
+            });
+            state.long_press = Some(gesture);
+        }
+    });
+    connect(widget, LONG_PRESS_SIGNAL, move |widget, state| {
+        if let Some(widget) = widget.downcast_ref::<W>() {
+            let (x, y) = state.point;
+            f(widget, x, y)
+        }
+    })
+}
+
+pub(crate) fn connect_pinch<W, F>(widget: &W, f: F) -> SignalHandlerId
+where
+    W: IsA<Widget>,
+    F: Fn(&W, f64) + 'static,
+{
+    let widget = widget.upcast_ref();
+    update(widget, |state| {
+        if state.pinch.is_none() {
+            let gesture = GestureZoom::new(widget);
+            gesture.connect_scale_changed(|gesture, scale| {
+                if let Some(widget) = gesture.get_widget() {
+                    emit(&widget, PINCH_SIGNAL, "vgtk-pinch", |state| {
+                        state.scale = scale
+                    });
+                }
+            });
+            state.pinch = Some(gesture);
+        }
+    });
+    connect(widget, PINCH_SIGNAL, move |widget, state| {
+        if let Some(widget) = widget.downcast_ref::<W>() {
+            f(widget, state.scale)
+        }
+    })
+}
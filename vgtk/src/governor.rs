@@ -0,0 +1,239 @@
+//! Rate limiting for external event sources.
+//!
+//! File watchers, sockets and sensors can produce events far faster than a UI
+//! needs to hear about them, and sending every one of them straight to a
+//! [`Scope`][Scope] means a full update and render cycle per event. A
+//! [`SignalGovernor`][SignalGovernor] sits between such a [`Stream`][Stream]
+//! and a component, and only lets events through according to a
+//! [`Policy`][Policy] you choose for each subscription.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use std::time::Duration;
+//! # use vgtk::governor::{Policy, SignalGovernor};
+//! # use vgtk::Scope;
+//! # #[derive(Default)] struct Editor;
+//! # #[derive(Clone, Debug)] enum Message { FilesChanged(Vec<String>) }
+//! # impl vgtk::Component for Editor { type Message = Message; type Properties = ();
+//! # fn create(_props: ()) -> Self { Self::default() }
+//! # fn view(&self) -> vgtk::VNode<Self> { unimplemented!() } }
+//! # fn watch_files() -> futures::channel::mpsc::UnboundedReceiver<String> { unimplemented!() }
+//! # fn example(scope: Scope<Editor>) {
+//! let governor = SignalGovernor::new(scope);
+//! // Deliver changed files at most every half second, in batches of up to 100.
+//! let subscription = governor.subscribe(
+//!     watch_files(),
+//!     Policy::Batch(Duration::from_millis(500), 100),
+//!     Message::FilesChanged,
+//! );
+//! // Later, when you're no longer interested:
+//! subscription.cancel();
+//! # }
+//! ```
+//!
+//! [Scope]: ../struct.Scope.html
+//! [SignalGovernor]: struct.SignalGovernor.html
+//! [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+//! [Policy]: enum.Policy.html
+
+use std::fmt::{Debug, Error, Formatter};
+use std::time::Duration;
+
+use futures::future::{select, AbortHandle, Abortable, Either};
+use futures::stream::{Stream, StreamExt};
+use glib::MainContext;
+
+use colored::Colorize;
+use log::debug;
+
+use crate::affinity;
+use crate::clock::{self, Sleep};
+use crate::component::Component;
+use crate::scope::Scope;
+
+/// How a [`SignalGovernor`][SignalGovernor] lets events through.
+///
+/// Every policy hands the subscription's mapping function a `Vec` of events.
+/// For [`Sample`][Policy::Sample] and [`Debounce`][Policy::Debounce], it
+/// always holds exactly one event, the latest one.
+///
+/// [SignalGovernor]: struct.SignalGovernor.html
+/// [Policy::Sample]: #variant.Sample
+/// [Policy::Debounce]: #variant.Debounce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Deliver at most one event per interval.
+    ///
+    /// The first event is delivered right away. Events arriving within the
+    /// interval after it replace each other, and the latest is delivered
+    /// when the interval runs out.
+    Sample(Duration),
+    /// Deliver the latest event once no new events have arrived for the given
+    /// duration.
+    Debounce(Duration),
+    /// Collect events and deliver them together, at most the given interval
+    /// after the first of them arrived, or as soon as the given number of
+    /// events have been collected.
+    Batch(Duration, usize),
+}
+
+/// A handle to a running [`SignalGovernor`][SignalGovernor] subscription.
+///
+/// Dropping the handle leaves the subscription running. It ends when you call
+/// [`cancel()`][cancel], when its stream ends, or when its component goes away.
+///
+/// [SignalGovernor]: struct.SignalGovernor.html
+/// [cancel]: #method.cancel
+#[derive(Debug, Clone)]
+pub struct Subscription(AbortHandle);
+
+impl Subscription {
+    /// Stop delivering events from this subscription. Events which haven't been
+    /// delivered yet are dropped.
+    pub fn cancel(&self) {
+        self.0.abort();
+    }
+}
+
+/// Delivers events from external streams to a component, rate limited
+/// according to a [`Policy`][Policy].
+///
+/// Subscriptions run on the thread default [`MainContext`][MainContext], so they
+/// must be made from the main thread.
+///
+/// [Policy]: enum.Policy.html
+/// [MainContext]: ../../glib/struct.MainContext.html
+pub struct SignalGovernor<C: Component> {
+    scope: Scope<C>,
+}
+
+impl<C: Component> Clone for SignalGovernor<C> {
+    fn clone(&self) -> Self {
+        SignalGovernor {
+            scope: self.scope.clone(),
+        }
+    }
+}
+
+impl<C: Component> Debug for SignalGovernor<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "SignalGovernor({:?})", self.scope)
+    }
+}
+
+impl<C: 'static + Component> SignalGovernor<C> {
+    /// Create a governor which delivers messages to the given scope.
+    pub fn new(scope: Scope<C>) -> Self {
+        SignalGovernor { scope }
+    }
+
+    /// Start delivering events from `stream` according to `policy`, using
+    /// `to_message` to turn them into messages for the component.
+    ///
+    /// Any events still held back when the stream ends are delivered before
+    /// the subscription finishes.
+    pub fn subscribe<S, F>(&self, stream: S, policy: Policy, to_message: F) -> Subscription
+    where
+        S: Stream + Unpin + 'static,
+        F: Fn(Vec<S::Item>) -> C::Message + 'static,
+    {
+        affinity::assert_main_thread("vgtk::governor::SignalGovernor::subscribe");
+        let scope = self.scope.clone();
+        let deliver = move |events: Vec<S::Item>| {
+            let count = events.len();
+            let result = scope.try_send(to_message(events));
+            if result.is_err() {
+                debug!(
+                    "{} {}: dropped {} events",
+                    "SignalGovernor".bright_red(),
+                    scope.name().magenta().bold(),
+                    count
+                );
+            }
+            result.is_ok()
+        };
+        let (handle, registration) = AbortHandle::new_pair();
+        MainContext::ref_thread_default().spawn_local(async move {
+            let _ = Abortable::new(govern(stream, policy, deliver), registration).await;
+        });
+        Subscription(handle)
+    }
+}
+
+fn timer(duration: Duration) -> Sleep {
+    clock::sleep(duration)
+}
+
+/// Run a stream through a policy until the stream ends or `deliver` returns
+/// `false`.
+async fn govern<S, F>(mut stream: S, policy: Policy, mut deliver: F)
+where
+    S: Stream + Unpin,
+    F: FnMut(Vec<S::Item>) -> bool,
+{
+    let mut pending = Vec::new();
+    let mut deadline: Option<Sleep> = None;
+    loop {
+        let next = match deadline.as_mut() {
+            Some(timeout) => match select(stream.next(), timeout).await {
+                Either::Left((next, _)) => Some(next),
+                Either::Right(_) => None,
+            },
+            None => Some(stream.next().await),
+        };
+        let alive = match next {
+            // The deadline passed.
+            None => {
+                deadline = None;
+                if pending.is_empty() {
+                    true
+                } else {
+                    if let Policy::Sample(interval) = policy {
+                        // Keep sampling until an interval passes in silence.
+                        deadline = Some(timer(interval));
+                    }
+                    deliver(pending.split_off(0))
+                }
+            }
+            // The stream ended.
+            Some(None) => {
+                if !pending.is_empty() {
+                    deliver(pending);
+                }
+                return;
+            }
+            Some(Some(event)) => match policy {
+                Policy::Sample(interval) => {
+                    if deadline.is_none() {
+                        deadline = Some(timer(interval));
+                        deliver(vec![event])
+                    } else {
+                        pending = vec![event];
+                        true
+                    }
+                }
+                Policy::Debounce(quiet) => {
+                    deadline = Some(timer(quiet));
+                    pending = vec![event];
+                    true
+                }
+                Policy::Batch(interval, max) => {
+                    pending.push(event);
+                    if pending.len() >= max {
+                        deadline = None;
+                        deliver(pending.split_off(0))
+                    } else {
+                        if deadline.is_none() {
+                            deadline = Some(timer(interval));
+                        }
+                        true
+                    }
+                }
+            },
+        };
+        if !alive {
+            return;
+        }
+    }
+}
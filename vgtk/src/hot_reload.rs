@@ -0,0 +1,274 @@
+//! Reloading view functions while the app is running.
+//!
+//! With the `hot-reload` feature enabled, a component's view can be loaded
+//! from a shared library which is watched for changes, so you can rebuild
+//! the library and see your changes without restarting the app or losing
+//! its state.
+//!
+//! Put the components you're working on in a library crate which is built
+//! both as an `rlib`, which your app links as usual, and as a `cdylib`:
+//!
+//! ```toml
+//! [lib]
+//! crate-type = ["rlib", "cdylib"]
+//! ```
+//!
+//! Move the body of the component's `view()` into a function of its own,
+//! have `view()` call it through [`view()`][view], and export it from the
+//! library with [`hot_view!`][hot_view]. The component needs to implement
+//! `serde`'s `Serialize` and `Deserialize`:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, hot_view, Component, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Default, Serialize, Deserialize)]
+//! pub struct Counter {
+//!     count: usize,
+//! }
+//!
+//! impl Counter {
+//!     fn render(&self) -> VNode<Self> {
+//!         gtk! { <Label label=format!("Clicked {} times", self.count) /> }
+//!     }
+//! }
+//!
+//! impl Component for Counter {
+//! #   type Message = (); type Properties = ();
+//! #   fn create(_props: ()) -> Self { Self::default() }
+//!     // ...
+//!     fn view(&self) -> VNode<Self> {
+//!         vgtk::hot_reload::view(self, "counter_view", Self::render)
+//!     }
+//! }
+//!
+//! hot_view!(counter_view, Counter, Counter::render);
+//! ```
+//!
+//! Then call [`watch()`][watch] with the path of the built `cdylib` before
+//! you start the app. Each time it's rebuilt, it's loaded again, and every
+//! mounted component renders its view again.
+//!
+//! When a reloaded library renders a view, it's given a `serde` snapshot of
+//! the component's state rather than the state itself, which lets you add
+//! fields to the component as long as they have a `#[serde(default)]`. The
+//! component's state lives on in the app, and its message type and
+//! `update()` aren't reloaded, so changes to those still need a restart.
+//!
+//! The library runs its own copy of `vgtk`, so reloaded views can use
+//! widgets, attributes and signal handlers, but not subcomponents, which
+//! only work in views built by the app itself. Views for which the library
+//! has nothing, or which it fails to render, fall back to the app's own
+//! function.
+//!
+//! [view]: fn.view.html
+//! [watch]: fn.watch.html
+//! [hot_view]: ../macro.hot_view.html
+
+use std::cell::RefCell;
+use std::ffi::{c_void, CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use gio::prelude::*;
+use gio::{FileMonitor, FileMonitorEvent, FileMonitorFlags};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use colored::Colorize;
+use log::{info, warn};
+
+use crate::component::rerender_all;
+use crate::{Component, VNode};
+
+/// What a view function exported with [`hot_view!`][hot_view] looks like.
+///
+/// [hot_view]: ../macro.hot_view.html
+type ViewFn<C> = fn(&str) -> Option<VNode<C>>;
+
+#[derive(Default)]
+struct Reloader {
+    library: PathBuf,
+    generation: usize,
+    // The most recently loaded library. Libraries are never unloaded, as
+    // widgets built from an older one can still be calling into it.
+    handle: Option<usize>,
+    monitor: Option<FileMonitor>,
+}
+
+thread_local! {
+    static RELOADER: RefCell<Reloader> = RefCell::new(Default::default());
+}
+
+fn dlerror() -> String {
+    #[allow(unsafe_code)]
+    unsafe {
+        let error = libc::dlerror();
+        if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(error).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// Load the current version of the library.
+///
+/// It's loaded from a copy with a new name each time, because the dynamic
+/// linker won't load a path it's already loaded again.
+fn reload() {
+    let (library, generation) = RELOADER.with(|reloader| {
+        let mut reloader = reloader.borrow_mut();
+        reloader.generation += 1;
+        (reloader.library.clone(), reloader.generation)
+    });
+    let copy = std::env::temp_dir().join(format!(
+        "vgtk-hot-reload-{}-{}.so",
+        std::process::id(),
+        generation
+    ));
+    if let Err(error) = std::fs::copy(&library, &copy) {
+        warn!(
+            "{} {}: {}",
+            "Unable to copy hot reload library".bright_red(),
+            library.display().to_string().bright_white().bold(),
+            error
+        );
+        return;
+    }
+    let path = CString::new(copy.as_os_str().as_bytes()).unwrap();
+    #[allow(unsafe_code)]
+    let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    let _ = std::fs::remove_file(&copy);
+    if handle.is_null() {
+        warn!(
+            "{} {}: {}",
+            "Unable to load hot reload library".bright_red(),
+            library.display().to_string().bright_white().bold(),
+            dlerror()
+        );
+        return;
+    }
+    RELOADER.with(|reloader| reloader.borrow_mut().handle = Some(handle as usize));
+    info!(
+        "{} {}",
+        "Hot reloaded".bright_green(),
+        library.display().to_string().bright_white().bold()
+    );
+    rerender_all();
+}
+
+/// Watch a `cdylib` for changes, loading view functions from it whenever it
+/// changes.
+///
+/// If the library already exists, it's loaded straight away. Calling this
+/// again replaces the library being watched.
+pub fn watch<P: AsRef<Path>>(library: P) -> Result<(), glib::Error> {
+    let library = library.as_ref().to_path_buf();
+    let monitor = gio::File::new_for_path(&library)
+        .monitor_file(FileMonitorFlags::NONE, None::<&gio::Cancellable>)?;
+    // Wait for the linker to finish writing the library before loading it.
+    monitor.connect_changed(|_, _, _, event| {
+        if event == FileMonitorEvent::ChangesDoneHint {
+            reload();
+        }
+    });
+    let exists = library.exists();
+    RELOADER.with(|reloader| {
+        let mut reloader = reloader.borrow_mut();
+        reloader.library = library;
+        reloader.handle = None;
+        reloader.monitor = Some(monitor);
+    });
+    if exists {
+        reload();
+    }
+    Ok(())
+}
+
+fn lookup<C: Component>(symbol: &str) -> Option<ViewFn<C>> {
+    let handle = RELOADER.with(|reloader| reloader.borrow().handle)?;
+    let symbol = CString::new(symbol).ok()?;
+    #[allow(unsafe_code)]
+    unsafe {
+        let function = libc::dlsym(handle as *mut c_void, symbol.as_ptr());
+        if function.is_null() {
+            None
+        } else {
+            Some(std::mem::transmute::<*mut c_void, ViewFn<C>>(function))
+        }
+    }
+}
+
+/// Render a component's view using the function exported as `symbol` by the
+/// library being watched, or using `fallback` if there is no such function.
+///
+/// Call this from your component's `view()`.
+pub fn view<C>(component: &C, symbol: &str, fallback: fn(&C) -> VNode<C>) -> VNode<C>
+where
+    C: Component + Serialize,
+{
+    let reloaded = match lookup::<C>(symbol) {
+        Some(reloaded) => reloaded,
+        None => return fallback(component),
+    };
+    let state = match serde_json::to_string(component) {
+        Ok(state) => state,
+        Err(error) => {
+            warn!(
+                "{} {}: {}",
+                "Unable to snapshot component state for".bright_red(),
+                std::any::type_name::<C>().magenta().bold(),
+                error
+            );
+            return fallback(component);
+        }
+    };
+    match reloaded(&state) {
+        Some(view) => view,
+        None => {
+            warn!(
+                "{} {}",
+                "Hot reloaded view couldn't restore the state of".bright_red(),
+                std::any::type_name::<C>().magenta().bold()
+            );
+            fallback(component)
+        }
+    }
+}
+
+/// Render a view from a snapshot of a component's state.
+///
+/// This is used by the functions [`hot_view!`][hot_view] exports, and
+/// you'll not normally need to call it yourself.
+///
+/// [hot_view]: ../macro.hot_view.html
+pub fn restore<C>(state: &str, render: fn(&C) -> VNode<C>) -> Option<VNode<C>>
+where
+    C: Component + DeserializeOwned,
+{
+    serde_json::from_str(state)
+        .ok()
+        .map(|component| render(&component))
+}
+
+/// Export a component's view function from a library for
+/// [`hot_reload::view()`][view] to load.
+///
+/// `hot_view!(counter_view, Counter, Counter::render)` exports
+/// `Counter::render` under the name `counter_view`, which needs to be
+/// unique across the library.
+///
+/// See the [`hot_reload`][hot_reload] module for details.
+///
+/// [view]: hot_reload/fn.view.html
+/// [hot_reload]: hot_reload/index.html
+#[macro_export]
+macro_rules! hot_view {
+    ($symbol:ident, $component:ty, $render:path) => {
+        #[no_mangle]
+        pub fn $symbol(state: &str) -> Option<$crate::VNode<$component>> {
+            $crate::hot_reload::restore::<$component>(state, $render)
+        }
+    };
+}
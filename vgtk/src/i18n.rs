@@ -0,0 +1,235 @@
+//! Translation using gettext.
+//!
+//! Call [`init()`][init] with your gettext domain when your application starts,
+//! then use the [`i18n!`][i18n!] macro wherever you need a translated string,
+//! including inside the [`gtk!`][gtk!] macro:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, i18n, VNode};
+//! # use vgtk::lib::gtk::{Box, Button, ButtonExt, Label, LabelExt};
+//! # fn view(files: usize) -> VNode<()> {
+//! gtk! {
+//!     <Box>
+//!         <Label label=i18n!("{} file selected", "{} files selected"; files) />
+//!         <Button label=i18n!("Save") />
+//!     </Box>
+//! }
+//! # }
+//! ```
+//!
+//! Because translations are looked up when a view is rendered, changing the
+//! language with [`set_language()`][set_language] re-renders every component so
+//! the new translations take effect immediately.
+//!
+//! [init]: fn.init.html
+//! [set_language]: fn.set_language.html
+//! [i18n!]: ../macro.i18n.html
+//! [gtk!]: ../macro.gtk.html
+
+use std::ffi::{CStr, CString};
+use std::fmt::Display;
+use std::os::raw::c_char;
+use std::path::Path;
+
+use colored::Colorize;
+use log::debug;
+
+use crate::affinity;
+use crate::component::rerender_all;
+
+#[cfg_attr(not(target_env = "gnu"), link(name = "intl"))]
+extern "C" {
+    fn bindtextdomain(domain: *const c_char, dir: *const c_char) -> *mut c_char;
+    fn bind_textdomain_codeset(domain: *const c_char, codeset: *const c_char) -> *mut c_char;
+    fn textdomain(domain: *const c_char) -> *mut c_char;
+}
+
+fn c_string(value: &str) -> CString {
+    CString::new(value).expect("string contains a null byte")
+}
+
+/// Set up gettext to translate strings from the given domain, using the
+/// message catalogues in `locale_dir`, and the language of the user's locale.
+pub fn init<P: AsRef<Path>>(domain: &str, locale_dir: P) {
+    let domain = c_string(domain);
+    let locale_dir = c_string(
+        locale_dir
+            .as_ref()
+            .to_str()
+            .expect("locale directory must be valid UTF-8"),
+    );
+    let utf8 = c_string("UTF-8");
+    let empty = c_string("");
+    #[allow(unsafe_code)]
+    unsafe {
+        libc::setlocale(libc::LC_ALL, empty.as_ptr());
+        bindtextdomain(domain.as_ptr(), locale_dir.as_ptr());
+        bind_textdomain_codeset(domain.as_ptr(), utf8.as_ptr());
+        textdomain(domain.as_ptr());
+    }
+}
+
+/// Switch to translating into a different language, given as a language code
+/// like `"de"` or `"pt_BR"`, and re-render every component on this thread.
+///
+/// This sets the `LANGUAGE` environment variable, which gettext looks at
+/// before the locale, so only translations are affected, not the formatting
+/// of dates and numbers. Note that gettext ignores `LANGUAGE` altogether if
+/// the locale is `C`.
+pub fn set_language(language: &str) {
+    affinity::assert_main_thread("vgtk::i18n::set_language");
+    debug!(
+        "{} {}",
+        "Changing language to".bright_blue(),
+        language.magenta().bold()
+    );
+    std::env::set_var("LANGUAGE", language);
+    let empty = c_string("");
+    // Setting the locale makes gettext drop the translations it has cached.
+    #[allow(unsafe_code)]
+    unsafe {
+        libc::setlocale(libc::LC_MESSAGES, empty.as_ptr());
+    }
+    rerender_all();
+}
+
+fn translated(message: *const c_char) -> String {
+    #[allow(unsafe_code)]
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Translate a message.
+pub fn gettext(message: &str) -> String {
+    let message = c_string(message);
+    #[allow(unsafe_code)]
+    let result = unsafe { glib_sys::g_dgettext(std::ptr::null(), message.as_ptr()) };
+    translated(result)
+}
+
+/// Translate a message from another gettext domain, like GTK's own `gtk30`.
+pub(crate) fn dgettext(domain: &str, message: &str) -> String {
+    let (domain, message) = (c_string(domain), c_string(message));
+    #[allow(unsafe_code)]
+    let result = unsafe { glib_sys::g_dgettext(domain.as_ptr(), message.as_ptr()) };
+    translated(result)
+}
+
+/// Translate a message with singular and plural forms, picking the right
+/// form for `n` in the current language.
+pub fn ngettext(singular: &str, plural: &str, n: u64) -> String {
+    let (singular, plural) = (c_string(singular), c_string(plural));
+    #[allow(unsafe_code)]
+    let result = unsafe {
+        glib_sys::g_dngettext(std::ptr::null(), singular.as_ptr(), plural.as_ptr(), n as _)
+    };
+    translated(result)
+}
+
+/// Translate a message which needs disambiguating with a context, like a
+/// `"View"` which is a menu name rather than a verb.
+pub fn pgettext(context: &str, message: &str) -> String {
+    let (context, message) = (c_string(context), c_string(message));
+    #[allow(unsafe_code)]
+    let result =
+        unsafe { glib_sys::g_dpgettext2(std::ptr::null(), context.as_ptr(), message.as_ptr()) };
+    translated(result)
+}
+
+/// Substitute arguments into a translated format string.
+///
+/// Unlike `format!()`, the format string is only known at runtime. It
+/// supports `{}` for the next argument, `{0}`, `{1}` etc. for arguments
+/// by position, so translators can reorder them, and `{{` and `}}` for
+/// literal braces. Placeholders which don't match an argument are left in
+/// the output as they are.
+pub fn format(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                let index = if !closed {
+                    None
+                } else if placeholder.is_empty() {
+                    next += 1;
+                    Some(next - 1)
+                } else {
+                    placeholder.parse().ok()
+                };
+                // Anything which doesn't stand for an argument is written
+                // back out as it was.
+                match index.and_then(|index| args.get(index)) {
+                    Some(arg) => out.push_str(&arg.to_string()),
+                    None => {
+                        out.push('{');
+                        out.push_str(&placeholder);
+                        if closed {
+                            out.push('}');
+                        }
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Translate a string using gettext.
+///
+/// With a single string literal, this translates it. With further arguments,
+/// the translated string is used as a format string for them, as described
+/// under [`i18n::format()`][format]. To translate a string with plural forms,
+/// give the singular and plural forms followed by a `;` and the number to pick
+/// a form for, which is also the first format argument.
+///
+/// Make sure you run `xgettext` with `--keyword=i18n --keyword=i18n:1,2` so it
+/// will find the strings you use this macro with.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use vgtk::i18n;
+/// # let (name, count, folder) = ("Alice", 3, "Pictures");
+/// let save = i18n!("Save");
+/// let greeting = i18n!("Hello, {}!", name);
+/// let summary = i18n!("{} file in {}", "{} files in {}"; count, folder);
+/// ```
+///
+/// [format]: i18n/fn.format.html
+#[macro_export]
+macro_rules! i18n {
+    ($message:literal) => {
+        $crate::i18n::gettext($message)
+    };
+    ($message:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format(&$crate::i18n::gettext($message), &[$(&$arg),+])
+    };
+    ($singular:literal, $plural:literal; $n:expr $(, $arg:expr)* $(,)?) => {{
+        let n = $n;
+        $crate::i18n::format(
+            &$crate::i18n::ngettext($singular, $plural, n as u64),
+            &[&n $(, &$arg)*],
+        )
+    }};
+}
@@ -0,0 +1,223 @@
+//! Loading images into `Image` widgets from assets, files and URLs.
+//!
+//! This backs the `resource`, `file`, `from_url`, `size` and
+//! `placeholder` attributes in `ext::ImageExtHelpers`.
+
+use gdk::prelude::GdkPixbufExt;
+use gdk_pixbuf::Pixbuf;
+use gio::FileExt;
+use glib::{Cast, MainContext, ObjectExt, ParamFlags, ParamSpec, SignalHandlerId};
+use gtk::{IconSize, Image, ImageExt, WidgetExt};
+
+use colored::Colorize;
+use log::warn;
+
+use crate::{affinity, assets};
+
+const IMAGE_KEY: &str = "vgtk-image";
+const LOAD_ERROR_SIGNAL: &str = "notify::vgtk-load-error";
+
+/// Where an image comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Source {
+    Resource(String),
+    File(String),
+    Url(String),
+}
+
+#[derive(Default, Clone)]
+struct ImageState {
+    source: Option<Source>,
+    size: Option<i32>,
+    placeholder: Option<String>,
+    error: Option<glib::Error>,
+    loading: bool,
+    // Bumped on every load, so a slow load can't overwrite a newer one.
+    generation: usize,
+    watching: bool,
+}
+
+fn state(image: &Image) -> ImageState {
+    #[allow(unsafe_code)]
+    unsafe { image.get_data::<ImageState>(IMAGE_KEY) }
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn set_state(image: &Image, state: ImageState) {
+    #[allow(unsafe_code)]
+    unsafe {
+        image.set_data(IMAGE_KEY, state);
+    }
+}
+
+fn show(image: &Image, pixbuf: &Pixbuf) {
+    let scale = image.get_scale_factor();
+    match pixbuf.create_surface(scale, None::<&gdk::Window>) {
+        Some(surface) => image.set_from_surface(Some(&surface)),
+        None => image.set_from_pixbuf(Some(pixbuf)),
+    }
+}
+
+fn show_placeholder(image: &Image, placeholder: Option<&str>) {
+    match placeholder {
+        Some(icon) => image.set_from_icon_name(Some(icon), IconSize::Dialog),
+        None => image.clear(),
+    }
+}
+
+fn fail(image: &Image, source: &Source, error: glib::Error) {
+    warn!(
+        "{} {}: {}",
+        "Unable to load image".bright_red(),
+        format!("{:?}", source).bright_white().bold(),
+        error
+    );
+    let mut state = state(image);
+    show_placeholder(image, state.placeholder.as_deref());
+    state.error = Some(error);
+    set_state(image, state);
+    let pspec = ParamSpec::string(
+        "vgtk-load-error",
+        "Load error",
+        "The last image load failed",
+        None,
+        ParamFlags::READABLE,
+    );
+    let _ = image.emit(LOAD_ERROR_SIGNAL, &[&pspec]);
+}
+
+async fn load_uri(uri: &str, size: Option<i32>, scale: i32) -> Result<Pixbuf, glib::Error> {
+    let file = gio::File::new_for_uri(uri);
+    let stream = file.read_async_future(glib::PRIORITY_DEFAULT).await?;
+    match size {
+        Some(size) => {
+            Pixbuf::from_stream_at_scale_async_future(&stream, size * scale, size * scale, true)
+                .await
+        }
+        None => Pixbuf::from_stream_async_future(&stream).await,
+    }
+}
+
+fn load(image: &Image) {
+    let mut state = state(image);
+    let source = match state.source.clone() {
+        Some(source) => source,
+        None => return,
+    };
+    state.generation += 1;
+    state.error = None;
+    state.loading = false;
+    let generation = state.generation;
+    let size = state.size;
+    let placeholder = state.placeholder.clone();
+    set_state(image, state);
+
+    let scale = image.get_scale_factor().max(1);
+    let uri = match &source {
+        Source::Resource(name) => {
+            match assets::load_pixbuf(name, size, scale) {
+                Ok(pixbuf) => show(image, &pixbuf),
+                Err(error) => fail(image, &source, error),
+            }
+            return;
+        }
+        Source::File(path) => gio::File::new_for_path(path).get_uri().to_string(),
+        Source::Url(url) => url.clone(),
+    };
+    show_placeholder(image, placeholder.as_deref());
+    let mut state = self::state(image);
+    state.loading = true;
+    set_state(image, state);
+    let weak = image.downgrade();
+    MainContext::ref_thread_default().spawn_local(async move {
+        let result = load_uri(&uri, size, scale).await;
+        let image = match weak.upgrade() {
+            Some(image) => image,
+            None => return,
+        };
+        let mut state = self::state(&image);
+        if state.generation != generation {
+            return;
+        }
+        state.loading = false;
+        set_state(&image, state);
+        match result {
+            Ok(pixbuf) => show(&image, &pixbuf),
+            Err(error) => fail(&image, &source, error),
+        }
+    });
+}
+
+fn update<F: FnOnce(&mut ImageState) -> bool>(image: &Image, f: F) {
+    affinity::assert_owner(image, "vgtk::ext::ImageExtHelpers");
+    let mut state = state(image);
+    let reload = f(&mut state);
+    let watch = !state.watching;
+    state.watching = true;
+    set_state(image, state);
+    if watch {
+        image.connect_property_scale_factor_notify(load);
+    }
+    if reload {
+        load(image);
+    }
+}
+
+pub(crate) fn source(image: &Image) -> Option<Source> {
+    state(image).source
+}
+
+pub(crate) fn set_source(image: &Image, source: Source) {
+    update(image, |state| {
+        state.source = Some(source);
+        true
+    });
+}
+
+pub(crate) fn size(image: &Image) -> i32 {
+    state(image).size.unwrap_or(-1)
+}
+
+pub(crate) fn set_size(image: &Image, size: i32) {
+    update(image, |state| {
+        state.size = if size < 0 { None } else { Some(size) };
+        true
+    });
+}
+
+pub(crate) fn placeholder(image: &Image) -> Option<String> {
+    state(image).placeholder
+}
+
+pub(crate) fn set_placeholder(image: &Image, placeholder: Option<&str>) {
+    update(image, |state| {
+        state.placeholder = placeholder.map(str::to_string);
+        false
+    });
+    let state = state(image);
+    if state.loading || state.error.is_some() {
+        show_placeholder(image, placeholder);
+    }
+}
+
+pub(crate) fn connect_load_error<F: Fn(&Image, &glib::Error) + 'static>(
+    image: &Image,
+    f: F,
+) -> SignalHandlerId {
+    image
+        .connect_local(LOAD_ERROR_SIGNAL, false, move |values| {
+            let image = values[0]
+                .get::<glib::Object>()
+                .ok()
+                .flatten()
+                .and_then(|object| object.downcast::<Image>().ok());
+            if let Some(image) = image {
+                if let Some(error) = state(&image).error {
+                    f(&image, &error);
+                }
+            }
+            None
+        })
+        .expect("unable to connect to Image load errors")
+}
@@ -0,0 +1,431 @@
+//! Running components in a separate process.
+//!
+//! If your app hosts user interfaces written by someone else, like plugins,
+//! a panic or a crash in one of them would normally take your whole app down
+//! with it. The [`Isolated`][Isolated] component avoids this by running a
+//! plugin as a helper process of its own, and embedding the plugin's window
+//! in your widget tree using GTK's [`Socket`][Socket] and [`Plug`][Plug]. If
+//! the plugin dies, all you lose is the plugin, and the user is offered the
+//! chance to restart it.
+//!
+//! This is only available with the `plugin-isolation` feature enabled, and
+//! when GTK was built with the X11 backend, which is where `Socket` and `Plug`
+//! come from. On other backends the feature does nothing.
+//!
+//! Properties and messages cross the process boundary as strings, one per
+//! line, so serialise them however you like, eg. with `serde_json`:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::isolate::Isolated;
+//! # #[derive(Clone, Debug)] enum Message { Plugin(String), Crashed }
+//! # #[derive(Default)] struct Host { settings: String }
+//! # impl Component for Host {
+//! #     type Message = Message; type Properties = ();
+//! #     fn create(_props: ()) -> Self { Self::default() }
+//! fn view(&self) -> VNode<Self> {
+//!     gtk! {
+//!         <@Isolated command=vec!["./my-plugin".to_string()]
+//!                    props=self.settings.clone()
+//!                    on message=|message| Message::Plugin(message)
+//!                    on exit=|_| Message::Crashed />
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! The plugin is an ordinary vgtk application whose top level window is a
+//! [`Plug`][Plug] for the socket it's given by [`socket_id()`][socket_id].
+//! It receives its properties through [`serve()`][serve], and sends messages
+//! back to the host with [`send()`][send]:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::*;
+//! # use vgtk::lib::gio::{ApplicationFlags, prelude::ApplicationExtManual};
+//! # use vgtk::isolate;
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Props(String),
+//!     Clicked,
+//! }
+//!
+//! #[derive(Default)]
+//! struct Plugin {
+//!     settings: String,
+//! }
+//!
+//! impl Component for Plugin {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Props(settings) => {
+//!                 self.settings = settings;
+//!                 UpdateAction::Render
+//!             }
+//!             Message::Clicked => {
+//!                 isolate::send("clicked");
+//!                 UpdateAction::None
+//!             }
+//!         }
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         gtk! {
+//!             <Application::new_unwrap(None, ApplicationFlags::NON_UNIQUE)>
+//!                 <Plug::new(isolate::socket_id())>
+//!                     <Button label=self.settings.clone() on clicked=|_| (Message::Clicked, ()) />
+//!                 </Plug>
+//!             </Application>
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let (app, scope) = vgtk::start::<Plugin>();
+//!     isolate::serve(scope, Message::Props);
+//!     std::process::exit(app.run(&[]));
+//! }
+//! ```
+//!
+//! The plugin's standard output is reserved for messages, so make sure its
+//! logging goes to standard error.
+//!
+//! [Isolated]: struct.Isolated.html
+//! [Socket]: ../../gtk/struct.Socket.html
+//! [Plug]: ../../gtk/struct.Plug.html
+//! [socket_id]: fn.socket_id.html
+//! [serve]: fn.serve.html
+//! [send]: fn.send.html
+
+use std::ffi::OsStr;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use gio::prelude::*;
+use gio::{DataInputStream, OutputStream, Subprocess, SubprocessFlags, SubprocessLauncher};
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Button, Label, Orientation, Socket};
+
+use colored::Colorize;
+use log::warn;
+
+use crate::{Callback, Component, Scope, UpdateAction, VNode};
+
+/// The environment variable the host passes the plugin's socket ID in.
+pub const SOCKET_ENV: &str = "VGTK_PLUG_SOCKET";
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                text.push('\n');
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                text.push('\\');
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+#[derive(Debug)]
+struct Process {
+    subprocess: Subprocess,
+    stdin: OutputStream,
+    stdout: DataInputStream,
+}
+
+impl Process {
+    fn spawn(command: &[String], socket: u64) -> Result<Self, glib::Error> {
+        let launcher =
+            SubprocessLauncher::new(SubprocessFlags::STDIN_PIPE | SubprocessFlags::STDOUT_PIPE);
+        launcher.setenv(SOCKET_ENV, socket.to_string(), true);
+        let argv: Vec<&OsStr> = command.iter().map(OsStr::new).collect();
+        let subprocess = launcher.spawnv(&argv)?;
+        let stdin = subprocess
+            .get_stdin_pipe()
+            .expect("plugin process has no stdin pipe");
+        let stdout = subprocess
+            .get_stdout_pipe()
+            .expect("plugin process has no stdout pipe");
+        Ok(Process {
+            subprocess,
+            stdin,
+            stdout: DataInputStream::new(&stdout),
+        })
+    }
+
+    fn write(&self, line: &str) {
+        let line = format!("{}\n", escape(line));
+        if let Err(error) = self
+            .stdin
+            .write_all(line.as_bytes(), None::<&gio::Cancellable>)
+        {
+            // The plugin has most likely died, which we'll hear about shortly.
+            warn!(
+                "{} {}",
+                "Unable to send properties to plugin:".bright_red(),
+                error
+            );
+        }
+    }
+
+    fn read(&self) -> UpdateAction<Isolated> {
+        let line = self
+            .stdout
+            .read_line_utf8_async_future(glib::PRIORITY_DEFAULT);
+        UpdateAction::defer(async move {
+            match line.await {
+                Ok(Some(line)) => IsolatedMessage::Received(unescape(&line)),
+                Ok(None) => IsolatedMessage::Closed,
+                Err(error) => {
+                    warn!("{} {}", "Unable to read from plugin:".bright_red(), error);
+                    IsolatedMessage::Closed
+                }
+            }
+        })
+    }
+
+    fn wait(&self) -> UpdateAction<Isolated> {
+        let subprocess = self.subprocess.clone();
+        UpdateAction::defer(async move {
+            let _ = subprocess.wait_async_future().await;
+            IsolatedMessage::Exited(if subprocess.get_if_exited() {
+                Some(subprocess.get_exit_status())
+            } else {
+                None
+            })
+        })
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        self.subprocess.force_exit();
+    }
+}
+
+/// A component running in a helper process.
+///
+/// See the [module documentation][module] for details.
+///
+/// [module]: index.html
+#[derive(Clone, Debug, Default)]
+pub struct Isolated {
+    /// The command to run the plugin with, followed by its arguments.
+    ///
+    /// This is only read when the plugin starts.
+    pub command: Vec<String>,
+    /// The properties to send to the plugin, sent again whenever they change.
+    pub props: String,
+    /// Called with each message the plugin sends.
+    pub on_message: Callback<String>,
+    /// Called when the plugin exits, with its exit status, or `None` if it
+    /// was killed by a signal.
+    pub on_exit: Callback<Option<i32>>,
+    process: Option<Rc<Process>>,
+    stopped: bool,
+}
+
+/// Messages for [`Isolated`][Isolated].
+///
+/// [Isolated]: struct.Isolated.html
+#[derive(Clone, Debug)]
+pub enum IsolatedMessage {
+    /// The socket was realised, and has this ID.
+    Realized(u64),
+    /// The plug was removed from the socket.
+    Unplugged,
+    /// The plugin sent a message.
+    Received(String),
+    /// The plugin closed its standard output.
+    Closed,
+    /// The plugin exited.
+    Exited(Option<i32>),
+    /// The user asked for the plugin to be restarted.
+    Restart,
+}
+
+impl Component for Isolated {
+    type Message = IsolatedMessage;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        if props.props != self.props {
+            if let Some(process) = &self.process {
+                process.write(&props.props);
+            }
+        }
+        self.command = props.command;
+        self.props = props.props;
+        self.on_message = props.on_message;
+        self.on_exit = props.on_exit;
+        UpdateAction::None
+    }
+
+    fn update(&mut self, message: Self::Message) -> UpdateAction<Self> {
+        match message {
+            IsolatedMessage::Realized(socket) if self.process.is_none() => {
+                match Process::spawn(&self.command, socket) {
+                    Ok(process) => {
+                        process.write(&self.props);
+                        let read = process.read();
+                        self.process = Some(Rc::new(process));
+                        read
+                    }
+                    Err(error) => {
+                        warn!(
+                            "{} {}: {}",
+                            "Unable to start plugin".bright_red(),
+                            format!("{:?}", self.command).bright_white().bold(),
+                            error
+                        );
+                        self.stopped = true;
+                        UpdateAction::Render
+                    }
+                }
+            }
+            IsolatedMessage::Received(message) => {
+                self.on_message.send(message);
+                match &self.process {
+                    Some(process) => process.read(),
+                    None => UpdateAction::None,
+                }
+            }
+            IsolatedMessage::Closed => match &self.process {
+                Some(process) => process.wait(),
+                None => UpdateAction::None,
+            },
+            IsolatedMessage::Exited(status) => {
+                if status != Some(0) {
+                    warn!(
+                        "{} {}: {:?}",
+                        "Plugin stopped".bright_red(),
+                        format!("{:?}", self.command).bright_white().bold(),
+                        status
+                    );
+                }
+                self.process = None;
+                self.stopped = true;
+                self.on_exit.send(status);
+                UpdateAction::Render
+            }
+            IsolatedMessage::Restart => {
+                self.stopped = false;
+                UpdateAction::Render
+            }
+            IsolatedMessage::Realized(_) | IsolatedMessage::Unplugged => UpdateAction::None,
+        }
+    }
+
+    fn view(&self) -> VNode<Self> {
+        if self.stopped {
+            gtk! {
+                <GtkBox orientation=Orientation::Vertical spacing=6 valign=Align::Center>
+                    <Label label="This plugin has stopped." />
+                    <Button label="Restart" halign=Align::Center
+                            on clicked=|_| (IsolatedMessage::Restart, ()) />
+                </GtkBox>
+            }
+        } else {
+            gtk! {
+                <Socket hexpand=true vexpand=true
+                        on realize=|socket| (IsolatedMessage::Realized(socket.get_id() as u64), ())
+                        // Keep the socket around if the plug goes away,
+                        // rather than letting GTK destroy it.
+                        on plug_removed=|_| (IsolatedMessage::Unplugged, true) />
+            }
+        }
+    }
+}
+
+/// Get the ID of the socket a plugin should plug itself into.
+///
+/// Returns 0 if the process wasn't started by an [`Isolated`][Isolated]
+/// component, in which case [`Plug::new()`][Plug::new] creates a plug that
+/// isn't embedded anywhere.
+///
+/// [Isolated]: struct.Isolated.html
+/// [Plug::new]: ../../gtk/struct.Plug.html#method.new
+pub fn socket_id() -> u64 {
+    std::env::var(SOCKET_ENV)
+        .ok()
+        .and_then(|id| id.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Test whether this process is a plugin started by an
+/// [`Isolated`][Isolated] component.
+///
+/// [Isolated]: struct.Isolated.html
+pub fn is_hosted() -> bool {
+    socket_id() != 0
+}
+
+/// Deliver the properties the host sends to a plugin's component.
+///
+/// Each time the host's properties change, `message` is called with them to
+/// make a message for the component with the given `Scope`. When the host
+/// goes away, the plugin's [`Application`][Application] quits.
+///
+/// [Application]: ../../gtk/struct.Application.html
+pub fn serve<C, F>(scope: Scope<C>, message: F)
+where
+    C: 'static + Component,
+    C::Message: Send,
+    F: Fn(String) -> C::Message + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if scope.try_send(message(unescape(&line))).is_err() {
+                return;
+            }
+        }
+        glib::idle_add(|| {
+            crate::quit();
+            glib::Continue(false)
+        });
+    });
+}
+
+/// Send a message from a plugin to its host.
+///
+/// The host's [`Isolated`][Isolated] component passes it on to its
+/// `on message` callback.
+///
+/// [Isolated]: struct.Isolated.html
+pub fn send(message: &str) {
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    if writeln!(stdout, "{}", escape(message))
+        .and_then(|_| stdout.flush())
+        .is_err()
+    {
+        warn!("{}", "Unable to send message to plugin host.".bright_red());
+    }
+}
@@ -37,6 +37,10 @@
 //!     type Message = Message;
 //!     type Properties = ();
 //!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
 //!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
 //!         match message {
 //!             Message::Inc => {
@@ -149,7 +153,7 @@
 //! # use vgtk::lib::gtk::{Button, ButtonExt};
 //! # #[derive(Clone, Debug)] enum Message { ButtonWasClicked }
 //! # #[derive(Default)] struct Comp;
-//! # impl Component for Comp { type Message = Message; type Properties = (); fn view(&self) -> VNode<Self> {
+//! # impl Component for Comp { type Message = Message; type Properties = (); fn create(_props: ()) -> Self { Self::default() } fn view(&self) -> VNode<Self> {
 //! gtk! {
 //!     <Button label="Click me" on clicked=|_| Message::ButtonWasClicked />
 //! }
@@ -159,6 +163,31 @@
 //! This will cause a `Message::ButtonWasClicked` message to be sent to your component's
 //! [`update`][Component::update] function when the user clicks the button.
 //!
+//! To find out when a property changes, handle `notify_` followed by the property's name. Changes
+//! made by patching the widget to match the view don't send any messages, so a property which
+//! both the user and the view can change, like the position of a [`Paned`][Paned]'s divider,
+//! can be kept in the component's state without the two feeding back into each other:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode, Component};
+//! # use vgtk::lib::gtk::{Box, Paned, PanedExt};
+//! # #[derive(Clone, Debug)] enum Message { Split(i32) }
+//! # #[derive(Default)] struct Comp { split: i32 }
+//! # impl Component for Comp { type Message = Message; type Properties = (); fn create(_props: ()) -> Self { Self::default() } fn view(&self) -> VNode<Self> {
+//! gtk! {
+//!     <Paned position=self.split on notify_position=|paned| Message::Split(paned.get_position())>
+//!         <Box />
+//!         <Box />
+//!     </Paned>
+//! }
+//! # }}
+//! ```
+//!
+//! The `key_press`, `key_release`, `button_press` and `button_release` signals hand their
+//! handlers decoded keyboard and mouse events, and their handlers take patterns as arguments, so
+//! that a handler like `on key_press=|_, KeyEvent { key: Key::Escape, .. }| ...` only handles the
+//! events it matches. See [`vgtk::event`][event].
+//!
 //! Signal handlers can also be declared as `async`, which will cause the framework to wrap the handler
 //! in an `async {}` block and `await` the
 //! message result before passing it on to your update function. For instance, this very contrived
@@ -170,7 +199,7 @@
 //! # use vgtk::lib::gtk::{Button, ButtonExt, DialogFlags, MessageType, ButtonsType};
 //! # #[derive(Clone, Debug)] enum Message { ButtonWasClicked }
 //! # #[derive(Default)] struct Comp;
-//! # impl Component for Comp { type Message = Message; type Properties = (); fn view(&self) -> VNode<Self> {
+//! # impl Component for Comp { type Message = Message; type Properties = (); fn create(_props: ()) -> Self { Self::default() } fn view(&self) -> VNode<Self> {
 //! gtk! {
 //!     <Button label="Click me" on clicked=async |_| {
 //!         vgtk::message_dialog(
@@ -184,6 +213,30 @@
 //! # }}
 //! ```
 //!
+//! Some signals fire far more often than a component needs to hear about them, like a slider
+//! being dragged. A handler can be given a `throttle` modifier, to send at most one message per
+//! interval, or a `debounce` modifier, to send a message only once the signal has gone quiet for
+//! a whole interval. Either way, the message that gets through is the latest one, and the signal
+//! gets its return value straight away:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode, Component};
+//! # use vgtk::lib::gtk::{Scale, RangeExt, Entry, EntryExt, EditableSignals};
+//! # #[derive(Clone, Debug)] enum Message { Volume(f64), Search(String) }
+//! # #[derive(Default)] struct Comp;
+//! # impl Component for Comp { type Message = Message; type Properties = (); fn create(_props: ()) -> Self { Self::default() } fn view(&self) -> VNode<Self> {
+//! gtk! {
+//!     <Scale on value_changed(throttle=50ms)=|scale| (Message::Volume(scale.get_value()), ()) />
+//! }
+//! # }}
+//! # fn search() -> VNode<Comp> { gtk! {
+//! <Entry on changed(debounce=300ms)=|entry| (Message::Search(entry.get_text().to_string()), ()) />
+//! # }}
+//! ```
+//!
+//! To collapse a burst of messages once they've been sent, whatever sent them, see
+//! [`Component::coalesce()`][Component::coalesce].
+//!
 //! ## The `gtk!` Syntax
 //!
 //! The syntax for the [`gtk!`][vgtk::gtk!] macro is similar to [JSX], but with a number of necessary
@@ -271,6 +324,97 @@
 //! # }}
 //! ```
 //!
+//! Numeric properties can be animated whenever their value changes, by adding an `animate`
+//! modifier with the duration of the animation to the attribute. The widget starts out with
+//! the initial value as normal, and eases towards each new value from then on.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt, WidgetExt};
+//! # fn view(visible: bool) -> VNode<()> {
+//! gtk! {
+//!     <Label label="Now you see me" opacity(animate=250ms)={ if visible { 1.0 } else { 0.0 } } />
+//! }
+//! # }
+//! ```
+//!
+//! An attribute with an `optional` modifier takes an `Option` of the property's value. The
+//! property is only set when it's `Some`, and when it turns into `None`, the property goes
+//! back to its default, so there's no need for a separate branch of the view without it.
+//! A `None` leaves a property which isn't a GObject property, and so has no default, as it
+//! is.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt, WidgetExt};
+//! # fn view(status: Option<String>, hint: Option<&str>) -> VNode<()> {
+//! gtk! {
+//!     <Label label(optional)=status tooltip_text(optional)=hint />
+//! }
+//! # }
+//! ```
+//!
+//! Tooltips of plain text or Pango markup are set with the `tooltip_text` and `tooltip_markup`
+//! properties. For anything more, a `tooltip` attribute takes a node of its own, which is
+//! shown as the widget's tooltip. It's built along with the widget, and patched like the rest
+//! of the view from then on, so it's kept up to date even while it's showing:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::*;
+//! # fn view(progress: f64) -> VNode<()> {
+//! gtk! {
+//!     <Button label="Download"
+//!             tooltip=gtk! {
+//!                 <Box orientation=Orientation::Vertical spacing=6>
+//!                     <Label label="<b>Downloading</b>" use_markup=true />
+//!                     <ProgressBar fraction=progress />
+//!                 </Box>
+//!             } />
+//! }
+//! # }
+//! ```
+//!
+//! Some properties can only be set when a widget is created, like a window's `type`. Mark
+//! these with a `construct` modifier, and they're passed to the widget as it's constructed.
+//! They can't be changed on an existing widget, so if the value changes, the widget is
+//! thrown away and built again in its place. Construct properties can't be child
+//! properties, and can't be used on a widget with an explicit constructor.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{GtkWindowExt, Window, WindowType};
+//! # fn view() -> VNode<()> {
+//! gtk! {
+//!     <Window type(construct)=WindowType::Popup default_width=200 />
+//! }
+//! # }
+//! ```
+//!
+//! Properties which are only known at runtime, like a set of style toggles, can be spread
+//! over a widget with a `{..value}` attribute, where the value is a
+//! [`PropertySet`][PropertySet] or anything else which implements [`Spread`][Spread].
+//! Spread properties are patched like any other, and a property which a spread stops setting
+//! goes back to its default value. On a subcomponent, `{..value}` gives it a whole
+//! [`Component::Properties`][Component::Properties] value, and the other attributes
+//! replace parts of it, like Rust's struct update syntax.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt};
+//! # use vgtk::properties::PropertySet;
+//! # fn view(dimmed: bool) -> VNode<()> {
+//! let style = if dimmed {
+//!     PropertySet::new().with("opacity", 0.5).with("sensitive", false)
+//! } else {
+//!     PropertySet::new()
+//! };
+//! gtk! {
+//!     <Label label="Maybe dimmed" {..style} />
+//! }
+//! # }
+//! ```
+//!
 //! ### Interpolation
 //!
 //! The `gtk!` macro's parser tries to be smart about recognising Rust expressions as attribute
@@ -299,6 +443,82 @@
 //! # }
 //! ```
 //!
+//! A code block can return anything you can iterate over to get nodes, so you can use any
+//! iterator adapters you like, and flatten nested lists with `flat_map()`.
+//!
+//! When a list changes, its widgets are patched in order by default, so if an item is removed
+//! from the front of the list, every widget after it is patched to show the item after the
+//! one it showed before. If widgets have state of their own, like the text a user is typing
+//! into an `Entry`, or if they're subcomponents, give each of them a `key` attribute which
+//! identifies its item, and they'll be kept with their items instead, and moved around when
+//! the items are. A key can be any value which implements [`Hash`][Hash], and only has to be
+//! different from the keys of its siblings:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt, ListBox};
+//! # struct Item { id: usize, name: String }
+//! # fn view(items: &[Item]) -> VNode<()> {
+//! gtk! {
+//!     <ListBox>
+//!         {
+//!             items.iter().enumerate().map(|(index, item)| gtk! {
+//!                 <Label key=item.id label=format!("{}. {}", index + 1, item.name) />
+//!             })
+//!         }
+//!     </ListBox>
+//! }
+//! # }
+//! ```
+//!
+//! When a keyed list is replaced wholesale, like with a new set of search results, none of its
+//! keys match and every widget is built again. If the widgets don't have state of their own which
+//! matters, mark their keys with the `recycle` modifier, as in `key(recycle)=item.id`, and the
+//! widgets of the items which have gone are patched to show the new items instead, as long as
+//! they're the same type and were built the same way.
+//!
+//! You can also put `let` bindings in place of child widgets, to compute a value once and use it
+//! in the siblings that follow. A binding is only in scope inside its parent element.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt, Box};
+//! # fn view(items: &[String]) -> VNode<()> {
+//! gtk! {
+//!     <Box>
+//!         let count = items.len();
+//!         <Label label=format!("{} items", count) />
+//!         <Label label={ if count == 0 { "Nothing to do" } else { "Keep going" } } />
+//!     </Box>
+//! }
+//! # }
+//! ```
+//!
+//! A `match` expression can go in place of child widgets too, with an element or a code block
+//! for each arm. When the view switches to another arm, the widgets and subcomponents of the
+//! old arm are thrown away and the new arm's are built in their place, even if they're of the
+//! same types, so each subcomponent's [`mounted()`][Component::mounted] and
+//! [`unmounted()`][Component::unmounted] are called as the arms come and go. While the view
+//! stays on one arm, it's patched as usual.
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Label, LabelExt, Box, Spinner};
+//! # enum Page { Loading, Ready(String), Failed(String) }
+//! # fn view(page: &Page) -> VNode<()> {
+//! gtk! {
+//!     <Box>
+//!         match page {
+//!             Page::Loading => <Spinner />,
+//!             Page::Ready(text) => <Label label=text.clone() />,
+//!             Page::Failed(error) if error.is_empty() => { VNode::empty() }
+//!             Page::Failed(error) => <Label label=format!("Failed: {}", error) />,
+//!         }
+//!     </Box>
+//! }
+//! # }
+//! ```
+//!
 //! ## Subcomponents
 //!
 //! Components are designed to be composable, so you can place one component inside
@@ -387,11 +607,13 @@
 //! # impl Component for MyButton {
 //! #     type Message = ();
 //! #     type Properties = Self;
+//! #     fn create(props: Self) -> Self { props }
 //! #     fn view(&self) -> VNode<Self> { todo!() }
 //! # }
 //! # #[derive(Clone, Debug)] enum ParentMessage { ButtonClicked }
 //! # #[derive(Default)] struct Parent;
 //! # impl Component for Parent { type Message = ParentMessage; type Properties = ();
+//! # fn create(_props: ()) -> Self { Self::default() }
 //! fn view(&self) -> VNode<Self> {
 //!     gtk! {
 //!         <Box>
@@ -410,6 +632,39 @@
 //! parent component it lives within inside its type signature. It'll just work, with nary a
 //! profunctor in sight.
 //!
+//! A subcomponent can be generic, so that a component like a list can be written once
+//! for any type of item. Give its type arguments in the tag, with or without a
+//! turbofish, and the attributes fill in the properties of that instance of it:
+//!
+//! ```rust,ignore
+//! <@List<Task> items=self.tasks.clone() />
+//! <@List::<(String, usize)> items=self.counts.clone() />
+//! ```
+//!
+//! A component like that usually needs its parent to say how to draw each item. A
+//! [`Render`][Render] property takes a closure which returns part of the parent's view,
+//! for the subcomponent to put into its own.
+//!
+//! ## Without The Macro
+//!
+//! If you'd rather write a view in plain Rust, or need to build one from data, the builders
+//! made by [`VNode::widget()`][VNode::widget] and [`VNode::component()`][VNode::component]
+//! make the same virtual tree as the `gtk!` macro, and can be mixed freely with it:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Button, Box, Label, LabelExt, Orientation};
+//! # fn view() -> VNode<()> {
+//! VNode::widget::<Box>()
+//!     .prop("orientation", Orientation::Vertical)
+//!     .child(gtk! { <Label label="Hello" /> })
+//!     .child(VNode::widget::<Button>().prop("label", "OK").on_clicked(|_| ()))
+//!     .build()
+//! # }
+//! ```
+//!
+//! See [`WidgetBuilder`][WidgetBuilder] and [`ComponentBuilder`][ComponentBuilder] for the rest.
+//!
 //! ## Logging
 //!
 //! `vgtk` uses the [`log`][log] crate for debug output. You'll need to provide your own logger for this;
@@ -454,11 +709,19 @@
 //! [Component]: trait.Component.html
 //! [Component::view]: trait.Component.html#tymethod.view
 //! [Component::update]: trait.Component.html#method.update
-//! [Component::create]: trait.Component.html#method.create
+//! [Component::create]: trait.Component.html#tymethod.create
 //! [Component::change]: trait.Component.html#method.change
 //! [Component::Message]: trait.Component.html#associatedtype.Message
 //! [Component::Properties]: trait.Component.html#associatedtype.Properties
+//! [Component::mounted]: trait.Component.html#method.mounted
+//! [Component::unmounted]: trait.Component.html#method.unmounted
+//! [Component::coalesce]: trait.Component.html#method.coalesce
 //! [Callback]: struct.Callback.html
+//! [Render]: struct.Render.html
+//! [VNode::widget]: enum.VNode.html#method.widget
+//! [VNode::component]: enum.VNode.html#method.component
+//! [WidgetBuilder]: struct.WidgetBuilder.html
+//! [ComponentBuilder]: struct.ComponentBuilder.html
 //! [UpdateAction]: enum.UpdateAction.html
 //! [UpdateAction::None]: enum.UpdateAction.html#variant.None
 //! [UpdateAction::Render]: enum.UpdateAction.html#variant.Render
@@ -473,40 +736,120 @@
 //! [Container]: ../gtk/struct.Container.html
 //! [MenuButton]: ../gtk/struct.MenuButton.html
 //! [MenuButtonExt]: ../gtk/trait.MenuButtonExt.html
+//! [Paned]: ../gtk/struct.Paned.html
+//! [event]: event/index.html
 //! [WidgetExt]: ../gtk/trait.WidgetExt.html
 //! [Window]: ../gtk/struct.Window.html
 //! [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
+//! [Hash]: https://doc.rust-lang.org/std/hash/trait.Hash.html
+//! [PropertySet]: properties/struct.PropertySet.html
+//! [Spread]: properties/trait.Spread.html
 
+#![recursion_limit = "1024"]
 #![forbid(rust_2018_idioms)]
 #![deny(nonstandard_style, unsafe_code)]
 #![warn(unreachable_pub, missing_docs)]
 #![allow(clippy::needless_doctest_main)]
 
+// Lets the `gtk!` macro's `vgtk::` paths resolve inside this crate too.
+extern crate self as vgtk;
+
+// Log something that happened to a component, using its scope's name as the
+// target, so it can be filtered per component with `debug::set_level()`.
+// This has to come before the modules which use it.
+macro_rules! component_log {
+    ($level:ident, $scope:expr, $($arg:tt)+) => {{
+        let target: &str = $scope.name();
+        if $crate::debug::enabled(target, log::Level::$level) {
+            log::log!(target: target, log::Level::$level, $($arg)+);
+        }
+    }};
+}
+
+// Being exported, `gtk!` can't be referred to by path from inside this crate,
+// so it has to come before the modules which use it too.
+/// Generate a virtual component tree.
+///
+/// See the [top level documentation][toplevel] for a description of its syntax.
+///
+/// [toplevel]: index.html
+#[proc_macro_hack(support_nested)]
+pub use vgtk_macros::gtk;
+
+pub mod a11y;
+mod adjustment;
+pub mod affinity;
+pub mod assets;
+pub mod bench;
 mod callback;
+pub mod clock;
 mod component;
+pub mod components;
+pub mod config;
+pub mod debug;
+pub mod dialogs;
+pub mod display;
+mod embed;
+mod error;
+pub mod event;
+pub mod export;
 pub mod ext;
+pub mod form;
+pub mod fs;
+pub mod governor;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod i18n;
+mod gesture;
+mod image;
+#[cfg(all(feature = "plugin-isolation", gdk_backend = "x11"))]
+pub mod isolate;
+pub mod links;
+pub mod machine;
+pub mod mailbox;
+pub mod memory;
+pub mod middleware;
+#[cfg(feature = "plugins")]
+pub mod plugin;
 mod menu_builder;
+#[cfg(feature = "profiling")]
+pub mod profile;
 #[doc(hidden)]
 pub mod properties;
+mod query;
+mod render;
+pub mod router;
 #[doc(hidden)]
 pub mod scope;
+pub mod session;
+mod shutdown;
+pub mod splash;
+pub mod strict;
+#[cfg(feature = "template")]
+pub mod template;
+pub mod test;
+pub mod theme;
+pub mod toast;
 pub mod types;
+pub mod undo;
 mod vdom;
 #[doc(hidden)]
 pub mod vnode;
+pub mod widgets;
+pub mod zoom;
 
 use proc_macro_hack::proc_macro_hack;
 
-/// Generate a virtual component tree.
+/// Embed every file in a directory as an asset, and register them.
 ///
-/// See the [top level documentation][toplevel] for a description of its syntax.
+/// See the [`assets`][assets] module for details.
 ///
-/// [toplevel]: index.html
-#[proc_macro_hack(support_nested)]
-pub use vgtk_macros::gtk;
+/// [assets]: assets/index.html
+#[proc_macro_hack]
+pub use vgtk_macros::include_assets;
 
 use gio::prelude::*;
-use gio::Cancellable;
+use gio::{ApplicationFlags, Cancellable};
 use glib::MainContext;
 use gtk::prelude::*;
 use gtk::{
@@ -514,7 +857,9 @@ use gtk::{
 };
 
 use futures::channel::oneshot::{self, Canceled};
+use std::cell::Cell;
 use std::future::Future;
+use std::rc::Rc;
 
 use colored::Colorize;
 use log::debug;
@@ -522,16 +867,25 @@ use log::debug;
 use crate::component::{ComponentMessage, ComponentTask, PartialComponentTask};
 
 pub use crate::callback::Callback;
-pub use crate::component::{current_object, current_window, Component, UpdateAction};
+pub use crate::component::{
+    current_object, current_window, Component, Moved, ShutdownAction, UpdateAction,
+};
+pub use crate::config::ComponentConfig;
+pub use crate::embed::{mount, mount_with_state, MountHandle};
+pub use crate::error::Error;
 pub use crate::menu_builder::{menu, MenuBuilder};
-pub use crate::scope::Scope;
-pub use crate::vnode::{VNode, VNodeIterator};
+pub use crate::query::{query, Queryable, StateSnapshot};
+pub use crate::render::Render;
+pub use crate::scope::{Scope, WeakScope};
+pub use crate::vnode::{ComponentBuilder, VNode, VNodeIterator, WidgetBuilder};
 
 /// Re-exports of GTK and its associated libraries.
 ///
 /// It is recommended that you use these rather than pulling them in as
 /// dependencies of your own project, to avoid versioning conflicts.
 pub mod lib {
+    pub use ::atk;
+    pub use ::cairo;
     pub use ::gdk;
     pub use ::gdk_pixbuf;
     pub use ::gio;
@@ -539,6 +893,15 @@ pub mod lib {
     pub use ::gtk;
 }
 
+/// A parser and formatter for the [`gtk!`][gtk!] macro's syntax, for tools
+/// like formatters, linters and editor plugins.
+///
+/// This needs the `syntax` feature.
+///
+/// [gtk!]: macro.gtk.html
+#[cfg(feature = "syntax")]
+pub use vgtk_syntax as syntax;
+
 /// Run an [`Application`][Application] component until termination.
 ///
 /// This is generally the function you'll call to get everything up and running.
@@ -571,12 +934,101 @@ pub mod lib {
 /// [start]: fn.start.html
 /// [Application::quit]: ../gio/trait.ApplicationExt.html#tymethod.quit
 /// [Application::run]: ../gio/trait.ApplicationExt.html#tymethod.run
-pub fn run<C: 'static + Component>() -> i32 {
-    let (app, _) = start::<C>();
+pub fn run<C: 'static + Component + Default>() -> i32 {
+    run_with_state(C::default())
+}
+
+/// Run an [`Application`][Application] component until termination, starting
+/// from the given state.
+///
+/// This is [`vgtk::run()`][run] for components which can't sensibly implement
+/// [`Default`][default], like ones which need arguments from the command line
+/// or a configuration file to start with.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use vgtk::{ext::*, gtk, Component, VNode};
+/// # use vgtk::lib::gtk::*;
+/// # use vgtk::lib::gio::ApplicationFlags;
+/// struct Viewer {
+///     path: std::path::PathBuf,
+/// }
+///
+/// impl Component for Viewer {
+///     type Message = ();
+///     type Properties = ();
+///
+///     fn create(_props: ()) -> Self {
+///         unreachable!("a Viewer is only launched with vgtk::run_with_state()")
+///     }
+///
+///     // ...
+/// #     fn view(&self) -> VNode<Self> {
+/// #         gtk! { <Application::new_unwrap(None, ApplicationFlags::empty()) /> }
+/// #     }
+/// }
+///
+/// let path = std::env::args().nth(1).expect("no file given").into();
+/// std::process::exit(vgtk::run_with_state(Viewer { path }));
+/// ```
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [default]: https://doc.rust-lang.org/std/default/trait.Default.html
+/// [run]: fn.run.html
+pub fn run_with_state<C: 'static + Component>(initial: C) -> i32 {
+    let (app, _) = start_with_state(initial);
     let args: Vec<String> = std::env::args().collect();
     app.run(&args)
 }
 
+/// Run an [`Application`][Application] component until termination, like
+/// [`vgtk::run()`][run], but return an [`Error`][Error] if it can't be
+/// started, or if the component panics, instead of panicking.
+///
+/// A panic in the top level component ends the application, and is returned
+/// as [`Error::Panic`][Panic]. In debug builds, a panic in its `update()` or
+/// `view()` is still shown in the window instead, as usual, see the
+/// [`debug`][debug] module.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # type MyComponent = ();
+/// match vgtk::try_run::<MyComponent>() {
+///     Ok(code) => std::process::exit(code),
+///     Err(error) => {
+///         eprintln!("{}", error);
+///         std::process::exit(1);
+///     }
+/// }
+/// ```
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [run]: fn.run.html
+/// [Error]: enum.Error.html
+/// [Panic]: enum.Error.html#variant.Panic
+/// [debug]: debug/index.html
+pub fn try_run<C: 'static + Component + Default>() -> Result<i32, Error> {
+    try_run_with_state(C::default())
+}
+
+/// Run an [`Application`][Application] component until termination,
+/// starting from the given state, and return an [`Error`][Error] if it
+/// can't be started or panics. See [`vgtk::try_run()`][try_run].
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [Error]: enum.Error.html
+/// [try_run]: fn.try_run.html
+pub fn try_run_with_state<C: 'static + Component>(initial: C) -> Result<i32, Error> {
+    let (app, _) = try_start_with_state(initial)?;
+    let args: Vec<String> = std::env::args().collect();
+    match error::catching(|| app.run(&args)) {
+        (_, Some(message)) => Err(Error::Panic(message)),
+        (code, None) => Ok(code),
+    }
+}
+
 /// Start an [`Application`][Application] component.
 ///
 /// This will instantiate the component, construct the [`Application`][Application]
@@ -608,37 +1060,206 @@ pub fn run<C: 'static + Component>() -> i32 {
 /// [Application::quit]: ../gio/trait.ApplicationExt.html#tymethod.quit
 /// [Application::run]: ../gio/trait.ApplicationExt.html#tymethod.run
 /// [Scope]: struct.Scope.html
-pub fn start<C: 'static + Component>() -> (Application, Scope<C>) {
-    gtk::init().expect("GTK failed to initialise");
-    let partial_task = PartialComponentTask::<C, ()>::new(Default::default(), None, None);
-    let app: Application = partial_task.object().downcast().unwrap_or_else(|_| {
-        panic!(
-            "The top level object must be an Application, but {} was found.",
-            partial_task.object().get_type()
-        )
-    });
+pub fn start<C: 'static + Component + Default>() -> (Application, Scope<C>) {
+    start_with_state(C::default())
+}
+
+/// Start an [`Application`][Application] component, starting from the given
+/// state.
+///
+/// This is [`vgtk::start()`][start] for components which don't implement
+/// [`Default`][default]. See [`vgtk::run_with_state()`][run_with_state].
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [default]: https://doc.rust-lang.org/std/default/trait.Default.html
+/// [start]: fn.start.html
+/// [run_with_state]: fn.run_with_state.html
+pub fn start_with_state<C: 'static + Component>(initial: C) -> (Application, Scope<C>) {
+    try_start_with_state(initial).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Start an [`Application`][Application] component, like
+/// [`vgtk::start()`][start], but return an [`Error`][Error] if it can't be
+/// started instead of panicking.
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [start]: fn.start.html
+/// [Error]: enum.Error.html
+pub fn try_start<C: 'static + Component + Default>() -> Result<(Application, Scope<C>), Error> {
+    try_start_with_state(C::default())
+}
+
+/// Start an [`Application`][Application] component, starting from the given
+/// state, and return an [`Error`][Error] if it can't be started. See
+/// [`vgtk::try_start()`][try_start].
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [Error]: enum.Error.html
+/// [try_start]: fn.try_start.html
+pub fn try_start_with_state<C: 'static + Component>(
+    initial: C,
+) -> Result<(Application, Scope<C>), Error> {
+    launch(initial, ApplicationFlags::empty())
+}
+
+/// Run an [`Application`][Application] component until termination, as the
+/// only instance of the application.
+///
+/// If the application is already running, launching it again passes its
+/// command line arguments to the running instance and exits, with the exit
+/// code the running instance returns. The running instance's top level
+/// component gets the arguments through
+/// [`Component::activated()`][activated], as does the first instance, with
+/// its own arguments, when it starts, and the application's window is
+/// brought to the front.
+///
+/// Applications are told apart by their application ID, so the top level
+/// [`Application`][Application] needs one.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use vgtk::{ext::*, gtk, Component, VNode};
+/// # use vgtk::lib::gtk::*;
+/// # use vgtk::lib::gio::ApplicationFlags;
+/// #[derive(Clone, Debug)]
+/// enum Message {
+///     Activated(Vec<String>),
+/// }
+///
+/// # #[derive(Default)] struct Editor;
+/// impl Component for Editor {
+///     // ...
+/// #   type Message = Message; type Properties = ();
+/// #   fn create(_props: ()) -> Self { Self::default() }
+/// #   fn view(&self) -> VNode<Self> {
+/// #       gtk! { <Application::new_unwrap(Some("com.example.Editor"), ApplicationFlags::empty()) /> }
+/// #   }
+///     fn activated(args: &[String]) -> Option<Message> {
+///         Some(Message::Activated(args.to_vec()))
+///     }
+/// }
+///
+/// std::process::exit(vgtk::run_single_instance::<Editor>());
+/// ```
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [activated]: trait.Component.html#method.activated
+pub fn run_single_instance<C: 'static + Component + Default>() -> i32 {
+    run_single_instance_with_state(C::default())
+}
+
+/// Run an [`Application`][Application] component until termination, as the
+/// only instance of the application, starting from the given state. See
+/// [`vgtk::run_single_instance()`][run_single_instance].
+///
+/// [Application]: ../gtk/struct.Application.html
+/// [run_single_instance]: fn.run_single_instance.html
+pub fn run_single_instance_with_state<C: 'static + Component>(initial: C) -> i32 {
+    let (app, _) = launch(initial, ApplicationFlags::HANDLES_COMMAND_LINE)
+        .unwrap_or_else(|error| panic!("{}", error));
+    let args: Vec<String> = std::env::args().collect();
+    app.run(&args)
+}
+
+// Start an application component, with `flags` added to its application's.
+fn launch<C: 'static + Component>(
+    initial: C,
+    flags: ApplicationFlags,
+) -> Result<(Application, Scope<C>), Error> {
+    gtk::init().map_err(|error| Error::Init(error.to_string()))?;
+    affinity::claim();
+    theme::watch();
+    let partial_task =
+        error::catch(|| PartialComponentTask::<C, ()>::with_state(initial, None, None))?;
+    let app: Application = match partial_task.object().downcast() {
+        Ok(app) => app,
+        Err(object) => return Err(Error::NotAnApplication(object.get_type().to_string())),
+    };
     app.set_default();
+    app.set_flags(app.get_flags() | flags);
     app.register(None as Option<&Cancellable>)
-        .expect("unable to register Application");
+        .map_err(|error| Error::Register(error.to_string()))?;
+    zoom::install(&app);
 
     let scope = partial_task.scope();
     let const_app = app.clone();
 
-    let constructor = once(move |_| {
-        let (channel, task) = partial_task.finalise();
+    let splash_app = app.clone();
+
+    let build = move || {
+        let (channel, task) = match error::catch_root(|| partial_task.finalise()) {
+            Some(finalised) => finalised,
+            None => return,
+        };
         MainContext::ref_thread_default().spawn_local(task);
         channel.unbounded_send(ComponentMessage::Mounted).unwrap();
+        let ask = channel.clone();
+        shutdown::install(&const_app, move || {
+            let _ = ask.unbounded_send(ComponentMessage::Quit);
+        });
         const_app.connect_shutdown(move |_| {
+            session::save_on_quit();
             channel.unbounded_send(ComponentMessage::Unmounted).unwrap();
         });
+        splash::hold_back(&const_app);
+    };
+    let constructor = Cell::new(Some(move || match C::splash() {
+        None => build(),
+        Some(splash) => {
+            // Get the splash window up before building the view.
+            splash::show(&splash_app, splash);
+            let build = Cell::new(Some(build));
+            glib::idle_add_local(move || {
+                if let Some(build) = build.take() {
+                    build();
+                }
+                glib::Continue(false)
+            });
+        }
+    }));
+    // The first activation starts the component, and any after that, like
+    // when the application is launched again while it's running, bring it
+    // back to the front.
+    let start = Rc::new(move |app: &Application| match constructor.take() {
+        Some(construct) => construct(),
+        None => {
+            if let Some(window) = app.get_active_window() {
+                window.present();
+            }
+        }
     });
 
-    app.connect_activate(move |_| {
+    let activate = start.clone();
+    app.connect_activate(move |app| {
         debug!("{}", "Application has activated.".bright_blue());
-        constructor(());
+        activate(app);
     });
 
-    (app, scope)
+    let open = scope.clone();
+    let open_start = start.clone();
+    app.connect_open(move |app, files, _| {
+        debug!("{}", "Application has been asked to open links.".bright_blue());
+        open_start(app);
+        links::open(&open, files);
+    });
+
+    let activated = scope.clone();
+    app.connect_command_line(move |app, command_line| {
+        let args: Vec<String> = command_line
+            .get_arguments()
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        debug!("{} {:?}", "Application has activated with:".bright_blue(), args);
+        start(app);
+        if let Some(message) = C::activated(&args) {
+            activated.send_message(message);
+        }
+        0
+    });
+
+    Ok((app, scope))
 }
 
 /// Launch a [`Dialog`][Dialog] component as a modal dialog.
@@ -656,10 +1277,10 @@ pub fn start<C: 'static + Component>() -> (Application, Scope<C>) {
 /// [ResponseType]: ../gtk/enum.ResponseType.html
 /// [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
 /// [Canceled]: https://docs.rs/futures/latest/futures/channel/oneshot/struct.Canceled.html
-pub fn run_dialog<C: 'static + Component>(
+pub fn run_dialog<C: 'static + Component + Default>(
     parent: Option<&Window>,
 ) -> (impl Future<Output = Result<ResponseType, Canceled>>, Scope<C>) {
-    run_dialog_props::<C>(parent, Default::default())
+    run_dialog_with_state(parent, C::default())
 }
 
 /// Launch a [`Dialog`][Dialog] component as a modal dialog, creating its component with the given initial
@@ -674,7 +1295,23 @@ pub fn run_dialog_props<C: 'static + Component>(
     parent: Option<&Window>,
     props: C::Properties,
 ) -> (impl Future<Output = Result<ResponseType, Canceled>>, Scope<C>) {
-    let (channel, task) = ComponentTask::<C, ()>::new(props, None, None);
+    run_dialog_with_state(parent, C::create(props))
+}
+
+/// Launch a [`Dialog`][Dialog] component as a modal dialog, starting from the given state.
+///
+/// This is for dialog components which don't implement [`Default`][default].
+///
+/// See [`run_dialog`][run_dialog].
+///
+/// [Dialog]: ../gtk/struct.Dialog.html
+/// [default]: https://doc.rust-lang.org/std/default/trait.Default.html
+pub fn run_dialog_with_state<C: 'static + Component>(
+    parent: Option<&Window>,
+    initial: C,
+) -> (impl Future<Output = Result<ResponseType, Canceled>>, Scope<C>) {
+    affinity::assert_main_thread("vgtk::run_dialog");
+    let (channel, task) = ComponentTask::<C, ()>::with_state(initial, None, None);
     let dialog: Dialog = task
         .object()
         .unwrap()
@@ -717,10 +1354,20 @@ fn once<A, F: FnOnce(A)>(f: F) -> impl Fn(A) {
 /// [`Application`][Application]. It will cause the [`vgtk::run()`][run] in
 /// charge of that [`Application`][Application] to terminate.
 ///
+/// If the application was started with [`vgtk::run()`][run] or
+/// [`vgtk::start()`][start], its top level component's
+/// [`Component::before_quit()`][before_quit] gets to decide first.
+///
 /// [Application]: ../gtk/struct.Application.html
 /// [Application::quit]: ../gio/trait.ApplicationExt.html#tymethod.quit
 /// [run]: fn.run.html
+/// [start]: fn.start.html
+/// [before_quit]: trait.Component.html#method.before_quit
 pub fn quit() {
+    affinity::assert_main_thread("vgtk::quit");
+    if shutdown::request() {
+        return;
+    }
     gio::Application::get_default()
         .expect("no default Application!")
         .quit();
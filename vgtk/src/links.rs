@@ -0,0 +1,215 @@
+//! Opening the application at a particular place from a URI.
+//!
+//! An application can handle URIs of its own scheme, like
+//! `myapp://item/42`, so other applications, web pages, notifications and
+//! the desktop can link straight to something inside it. To do this, give
+//! the [`Application`][Application] the `HANDLES_OPEN` flag, and implement
+//! [`Component::open_link()`][open_link] on the top level component to turn
+//! each [`Link`][Link] into a message for it, usually one which navigates
+//! its [`Router`][Router]:
+//!
+//! ```rust,no_run
+//! # use vgtk::{ext::*, gtk, Component, VNode};
+//! # use vgtk::links::Link;
+//! # use vgtk::router::Navigation;
+//! # use vgtk::lib::gio::ApplicationFlags;
+//! # use vgtk::lib::gtk::*;
+//! # #[derive(Clone, Debug, PartialEq)] enum Screen { Home, Item(u64) }
+//! # #[derive(Clone, Debug)] enum Message { Navigate(Navigation<Screen>) }
+//! # #[derive(Default)] struct App;
+//! impl Component for App {
+//!     // ...
+//! #   type Message = Message; type Properties = ();
+//! #   fn create(_props: ()) -> Self { Self::default() }
+//!
+//!     fn open_link(link: &Link) -> Option<Message> {
+//!         match link.path().as_slice() {
+//!             ["item", id] => {
+//!                 let id = id.parse().ok()?;
+//!                 Some(Message::Navigate(Navigation::Push(Screen::Item(id))))
+//!             }
+//!             _ => None,
+//!         }
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         gtk! {
+//!             <Application::new_unwrap(Some("com.example.MyApp"), ApplicationFlags::HANDLES_OPEN)>
+//!                 // ...
+//! #               <Window />
+//!             </Application>
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Applications are single instance, so opening a link while the
+//! application is already running delivers it to the running instance,
+//! which brings its window to the front. If it isn't running yet, it starts
+//! up as usual first.
+//!
+//! The desktop needs to know the application handles its scheme, which it
+//! learns from the `MimeType=x-scheme-handler/myapp;` line in the
+//! application's installed desktop file, or at runtime from
+//! [`register_scheme()`][register_scheme].
+//!
+//! [Application]: ../../gtk/struct.Application.html
+//! [open_link]: ../trait.Component.html#method.open_link
+//! [Link]: struct.Link.html
+//! [Router]: ../router/struct.Router.html
+//! [register_scheme]: fn.register_scheme.html
+
+use std::fmt::{Display, Error, Formatter};
+
+use gio::prelude::*;
+use gio::File;
+
+use colored::Colorize;
+use log::{debug, warn};
+
+use crate::component::Component;
+use crate::scope::Scope;
+
+/// A URI the application was asked to open, split into its parts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    uri: String,
+    scheme: String,
+    path: Vec<String>,
+    query: Vec<(String, String)>,
+}
+
+impl Link {
+    /// Parse a URI like `myapp://item/42?tab=comments`, or `None` if it
+    /// isn't one.
+    ///
+    /// The host, `item`, counts as the first part of the path, so that the
+    /// path of the above is `["item", "42"]`, same as for `myapp:item/42`.
+    /// Escaped characters in the path and query are unescaped.
+    pub fn parse(uri: &str) -> Option<Link> {
+        let scheme = glib::uri_parse_scheme(uri)?.to_string();
+        let rest = &uri[scheme.len() + 1..];
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (path, query) = match rest.find('?') {
+            Some(index) => (&rest[..index], &rest[index + 1..]),
+            None => (rest, ""),
+        };
+        let path = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(unescape)
+            .collect::<Option<_>>()?;
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                let key = unescape(parts.next().unwrap_or_default())?;
+                let value = unescape(parts.next().unwrap_or_default())?;
+                Some((key, value))
+            })
+            .collect::<Option<_>>()?;
+        Some(Link {
+            uri: uri.to_string(),
+            scheme: scheme.to_lowercase(),
+            path,
+            query,
+        })
+    }
+
+    /// Get the whole URI.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Get the scheme, in lower case, like `myapp`.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Get the parts of the path, which can be matched on as a slice of
+    /// string slices.
+    pub fn path(&self) -> Vec<&str> {
+        self.path.iter().map(String::as_str).collect()
+    }
+
+    /// Get the value of a query parameter, if it's there.
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Display for Link {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str(&self.uri)
+    }
+}
+
+fn unescape(text: &str) -> Option<String> {
+    glib::uri_unescape_string(&text.replace('+', " "), None).map(String::from)
+}
+
+/// Make the running application the desktop's handler for URIs of the
+/// given scheme, if it isn't already.
+///
+/// This only works once the application's desktop file, named after its ID,
+/// has been installed, and only on desktops which use desktop files, which
+/// excludes Windows and macOS. It's better to name the scheme in the
+/// desktop file to begin with, where possible.
+pub fn register_scheme(scheme: &str) -> Result<(), glib::Error> {
+    let id = gio::Application::get_default()
+        .and_then(|app| app.get_application_id())
+        .ok_or_else(|| {
+            glib::Error::new(
+                gio::IOErrorEnum::NotFound,
+                "the application has no ID to register a scheme for",
+            )
+        })?;
+    register_desktop_file(&format!("{}.desktop", id), scheme)
+}
+
+#[cfg(all(not(windows), not(target_os = "macos")))]
+fn register_desktop_file(desktop_id: &str, scheme: &str) -> Result<(), glib::Error> {
+    let info = gio::DesktopAppInfo::new(desktop_id).ok_or_else(|| {
+        glib::Error::new(
+            gio::IOErrorEnum::NotFound,
+            &format!("no desktop file named {} is installed", desktop_id),
+        )
+    })?;
+    let current = gio::AppInfo::get_default_for_uri_scheme(scheme);
+    if current.and_then(|current| current.get_id()).as_deref() == Some(desktop_id) {
+        return Ok(());
+    }
+    info.set_as_default_for_type(&format!("x-scheme-handler/{}", scheme))
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn register_desktop_file(_desktop_id: &str, scheme: &str) -> Result<(), glib::Error> {
+    Err(glib::Error::new(
+        gio::IOErrorEnum::NotSupported,
+        &format!("unable to register the {} scheme on this platform", scheme),
+    ))
+}
+
+/// Deliver the files the application was asked to open to the top level
+/// component as messages.
+pub(crate) fn open<C: 'static + Component>(scope: &Scope<C>, files: &[File]) {
+    for file in files {
+        let uri = file.get_uri();
+        let message = Link::parse(&uri).and_then(|link| C::open_link(&link));
+        match message {
+            Some(message) => {
+                debug!("{} {}", "Opening link:".bright_blue(), uri);
+                scope.send_message(message);
+            }
+            None => warn!(
+                "{} {}",
+                "Component doesn't handle link:".bright_red(),
+                uri.as_str().bright_white().bold()
+            ),
+        }
+    }
+}
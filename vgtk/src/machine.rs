@@ -0,0 +1,258 @@
+//! State machines for components with distinct modes.
+//!
+//! Many components move between a handful of modes, like idle, loading,
+//! loaded and failed, where each message is only meaningful in some of them.
+//! A [`Machine`][Machine] lets you write these rules down as a table of
+//! transitions instead of a nest of `match` expressions, with actions to run
+//! on entering and leaving each state:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::*;
+//! # use vgtk::machine::Machine;
+//! #[derive(Clone, Debug, PartialEq)]
+//! enum Mode {
+//!     Idle,
+//!     Loading,
+//!     Loaded(String),
+//!     Failed,
+//! }
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Load,
+//!     Done(Result<String, ()>),
+//!     Reset,
+//! }
+//!
+//! struct Document {
+//!     mode: Machine<Mode, Document>,
+//! }
+//!
+//! # async fn fetch() -> Result<String, ()> { Ok(String::new()) }
+//! impl Default for Document {
+//!     fn default() -> Self {
+//!         Document {
+//!             mode: Machine::new(Mode::Idle)
+//!                 .on(Mode::Idle, |msg| matches!(msg, Message::Load), Mode::Loading)
+//!                 .on(Mode::Failed, |msg| matches!(msg, Message::Load), Mode::Loading)
+//!                 .on_with(Mode::Loading, |msg| matches!(msg, Message::Done(_)), |_, msg| {
+//!                     match msg {
+//!                         Message::Done(Ok(text)) => Mode::Loaded(text.clone()),
+//!                         _ => Mode::Failed,
+//!                     }
+//!                 })
+//!                 .on(Mode::Loaded(String::new()), |msg| matches!(msg, Message::Reset), Mode::Idle)
+//!                 .on_enter(Mode::Loading, |_| {
+//!                     UpdateAction::defer(async { Message::Done(fetch().await) })
+//!                 }),
+//!         }
+//!     }
+//! }
+//!
+//! impl Component for Document {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         self.mode.handle(&message).unwrap_or(UpdateAction::None)
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         let text = match self.mode.state() {
+//!             Mode::Loaded(text) => text.clone(),
+//!             Mode::Loading => "Loading...".to_string(),
+//!             _ => String::new(),
+//!         };
+//!         gtk! {
+//!             <Label label=text />
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! States are matched by their enum variant, ignoring any data they carry, so
+//! `Mode::Loaded(String::new())` above stands for every `Mode::Loaded`.
+//!
+//! [Machine]: struct.Machine.html
+
+use std::fmt::{Debug, Error, Formatter};
+use std::mem::{discriminant, Discriminant};
+
+use colored::Colorize;
+use log::trace;
+
+use crate::component::{Component, UpdateAction};
+
+type Predicate<T> = Box<dyn Fn(&T) -> bool>;
+type Target<S, M> = Box<dyn Fn(&S, &M) -> S>;
+type Action<S, C> = Box<dyn Fn(&S) -> UpdateAction<C>>;
+
+struct Transition<S, M> {
+    from: Discriminant<S>,
+    accepts: Predicate<M>,
+    guard: Option<Box<dyn Fn(&S, &M) -> bool>>,
+    to: Target<S, M>,
+}
+
+/// A state machine with states of type `S`, driven by the messages of
+/// component `C`.
+///
+/// See the [module documentation][module] for an example.
+///
+/// [module]: index.html
+pub struct Machine<S, C: Component> {
+    state: S,
+    transitions: Vec<Transition<S, C::Message>>,
+    on_enter: Vec<(Discriminant<S>, Action<S, C>)>,
+    on_exit: Vec<(Discriminant<S>, Action<S, C>)>,
+}
+
+impl<S: Debug, C: Component> Debug for Machine<S, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_struct("Machine")
+            .field("state", &self.state)
+            .field("transitions", &self.transitions.len())
+            .finish()
+    }
+}
+
+impl<S: Debug + 'static, C: Component> Machine<S, C> {
+    /// Construct a machine in its initial state, with no transitions.
+    ///
+    /// The initial state's entry actions aren't run.
+    pub fn new(initial: S) -> Self {
+        Machine {
+            state: initial,
+            transitions: Vec::new(),
+            on_enter: Vec::new(),
+            on_exit: Vec::new(),
+        }
+    }
+
+    /// Add a transition from any state of the same variant as `from` to the
+    /// state `to`, on a message `accepts` returns `true` for.
+    pub fn on<F>(self, from: S, accepts: F, to: S) -> Self
+    where
+        S: Clone,
+        F: Fn(&C::Message) -> bool + 'static,
+    {
+        self.on_with(from, accepts, move |_, _| to.clone())
+    }
+
+    /// Add a transition from any state of the same variant as `from`, on a
+    /// message `accepts` returns `true` for, to the state returned by `to`,
+    /// which is given the current state and the message.
+    pub fn on_with<F, T>(mut self, from: S, accepts: F, to: T) -> Self
+    where
+        F: Fn(&C::Message) -> bool + 'static,
+        T: Fn(&S, &C::Message) -> S + 'static,
+    {
+        self.transitions.push(Transition {
+            from: discriminant(&from),
+            accepts: Box::new(accepts),
+            guard: None,
+            to: Box::new(to),
+        });
+        self
+    }
+
+    /// Add a guard to the most recently added transition, which is only taken
+    /// if `guard` returns `true` for the current state and the message.
+    ///
+    /// Panics if there are no transitions yet.
+    pub fn guard<G>(mut self, guard: G) -> Self
+    where
+        G: Fn(&S, &C::Message) -> bool + 'static,
+    {
+        self.transitions
+            .last_mut()
+            .expect("Machine::guard() called before adding a transition")
+            .guard = Some(Box::new(guard));
+        self
+    }
+
+    /// Add an action to perform on entering any state of the same variant as
+    /// `state`.
+    pub fn on_enter<F>(mut self, state: S, action: F) -> Self
+    where
+        F: Fn(&S) -> UpdateAction<C> + 'static,
+    {
+        self.on_enter.push((discriminant(&state), Box::new(action)));
+        self
+    }
+
+    /// Add an action to perform on leaving any state of the same variant as
+    /// `state`.
+    pub fn on_exit<F>(mut self, state: S, action: F) -> Self
+    where
+        F: Fn(&S) -> UpdateAction<C> + 'static,
+    {
+        self.on_exit.push((discriminant(&state), Box::new(action)));
+        self
+    }
+
+    /// Get the current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Test whether the current state is of the same variant as `state`.
+    pub fn is(&self, state: &S) -> bool {
+        discriminant(&self.state) == discriminant(state)
+    }
+
+    fn find(&self, message: &C::Message) -> Option<&Transition<S, C::Message>> {
+        let current = discriminant(&self.state);
+        self.transitions.iter().find(|transition| {
+            transition.from == current
+                && (transition.accepts)(message)
+                && transition
+                    .guard
+                    .as_ref()
+                    .is_none_or(|guard| guard(&self.state, message))
+        })
+    }
+
+    /// Test whether a message would cause a transition from the current
+    /// state.
+    pub fn accepts(&self, message: &C::Message) -> bool {
+        self.find(message).is_some()
+    }
+
+    /// Handle a message, moving to a new state if there's a transition for
+    /// it.
+    ///
+    /// If a transition was taken, returns the exit actions of the old state,
+    /// followed by a render, followed by the entry actions of the new state.
+    /// Returns `None` if the message doesn't cause a transition from the
+    /// current state, so you can handle it yourself.
+    pub fn handle(&mut self, message: &C::Message) -> Option<UpdateAction<C>> {
+        let next = {
+            let transition = self.find(message)?;
+            (transition.to)(&self.state, message)
+        };
+        trace!(
+            "{} {:?} -> {:?}",
+            "Machine:".bright_black(),
+            self.state,
+            next
+        );
+        let mut actions = Vec::new();
+        let from = discriminant(&self.state);
+        for (_, action) in self.on_exit.iter().filter(|(state, _)| *state == from) {
+            actions.push(action(&self.state));
+        }
+        self.state = next;
+        actions.push(UpdateAction::Render);
+        let to = discriminant(&self.state);
+        for (_, action) in self.on_enter.iter().filter(|(state, _)| *state == to) {
+            actions.push(action(&self.state));
+        }
+        Some(UpdateAction::batch(actions))
+    }
+}
@@ -0,0 +1,223 @@
+//! Holding on to messages for components which aren't mounted.
+//!
+//! Messages sent to a component which has unmounted are normally lost. For
+//! a component which comes and goes, like a page the user can navigate away
+//! from and back to, that means missing whatever happened in the meantime,
+//! like the result of a download it started.
+//!
+//! A mailbox is a named place to send a component's messages, which delivers
+//! them straight away while the component is mounted, and holds on to them
+//! while it isn't. The component opens its mailbox with [`open()`][open] when
+//! it mounts, which also delivers any messages held for it, and closes it
+//! with [`close()`][close] when it unmounts:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::Box;
+//! # use vgtk::mailbox::{self, Mailbox};
+//! # #[derive(Clone, Debug)] enum Message { Downloaded(Vec<u8>) }
+//! # #[derive(Default)] struct Downloads;
+//! impl Component for Downloads {
+//! #   type Message = Message; type Properties = ();
+//! #   fn create(_props: ()) -> Self { Self::default() }
+//!     // ...
+//!     fn mounted(&mut self) {
+//!         mailbox::open::<Self>("downloads", Mailbox::default());
+//!     }
+//!
+//!     fn unmounted(&mut self) {
+//!         mailbox::close::<Self>("downloads");
+//!     }
+//! #   fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! }
+//!
+//! // Somewhere else entirely:
+//! # let data = Vec::new();
+//! mailbox::send::<Downloads>("downloads", Message::Downloaded(data));
+//! ```
+//!
+//! A mailbox only holds so many messages, as configured by its
+//! [`Mailbox`][Mailbox] settings, after which it starts dropping them.
+//!
+//! [open]: fn.open.html
+//! [close]: fn.close.html
+//! [Mailbox]: struct.Mailbox.html
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use colored::Colorize;
+use log::warn;
+
+use crate::component::current_scope;
+use crate::{Component, Scope};
+
+/// Which message to drop when a mailbox is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Make room for the new message by dropping the oldest one.
+    DropOldest,
+    /// Drop the new message.
+    DropNewest,
+}
+
+/// How a mailbox holds messages while its component isn't mounted.
+///
+/// The default is to hold up to 64 messages, dropping the oldest ones to
+/// make room for new ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mailbox {
+    /// The most messages to hold.
+    pub capacity: usize,
+    /// Which message to drop when there are too many.
+    pub overflow: Overflow,
+}
+
+impl Default for Mailbox {
+    fn default() -> Self {
+        Mailbox {
+            capacity: 64,
+            overflow: Overflow::DropOldest,
+        }
+    }
+}
+
+impl Mailbox {
+    /// Hold up to `capacity` messages.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Choose which message to drop when there are too many.
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+struct Slot<C: Component> {
+    settings: Mailbox,
+    scope: Option<Scope<C>>,
+    held: VecDeque<C::Message>,
+}
+
+impl<C: 'static + Component> Slot<C> {
+    fn hold(&mut self, key: &str, message: C::Message) {
+        if self.held.len() >= self.settings.capacity {
+            warn!(
+                "{} {} {}",
+                "Mailbox is full, dropping a message for".bright_red(),
+                std::any::type_name::<C>().magenta().bold(),
+                key.bright_white().bold()
+            );
+            match self.settings.overflow {
+                Overflow::DropOldest => {
+                    self.held.pop_front();
+                }
+                Overflow::DropNewest => return,
+            }
+        }
+        if self.settings.capacity > 0 {
+            self.held.push_back(message);
+        }
+    }
+}
+
+thread_local! {
+    static MAILBOXES: RefCell<HashMap<(TypeId, String), Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+fn with_slot<C, F, R>(key: &str, f: F) -> R
+where
+    C: 'static + Component,
+    F: FnOnce(&mut Slot<C>) -> R,
+{
+    MAILBOXES.with(|mailboxes| {
+        let mut mailboxes = mailboxes.borrow_mut();
+        let slot = mailboxes
+            .entry((TypeId::of::<C>(), key.to_string()))
+            .or_insert_with(|| {
+                Box::new(Slot::<C> {
+                    settings: Mailbox::default(),
+                    scope: None,
+                    held: VecDeque::new(),
+                })
+            });
+        f(slot.downcast_mut().unwrap())
+    })
+}
+
+/// Open the mailbox with the given key for the current component, and
+/// deliver any messages it's holding.
+///
+/// Call this from the component's [`Component::mounted()`][mounted]. The
+/// messages held are delivered together, as a single batch.
+///
+/// [mounted]: ../trait.Component.html#method.mounted
+pub fn open<C: 'static + Component>(key: &str, settings: Mailbox) {
+    let scope = current_scope::<C>().unwrap_or_else(|| {
+        panic!(
+            "vgtk::mailbox::open::<{}>() must be called from inside that component",
+            std::any::type_name::<C>()
+        )
+    });
+    let held = with_slot::<C, _, _>(key, |slot| {
+        slot.settings = settings;
+        slot.scope = Some(scope.clone());
+        std::mem::take(&mut slot.held)
+    });
+    if scope.send_batched(held).is_err() {
+        with_slot::<C, _, _>(key, |slot| slot.scope = None);
+    }
+}
+
+/// Close the mailbox with the given key, so it holds messages until it's
+/// opened again.
+///
+/// Call this from the component's [`Component::unmounted()`][unmounted].
+///
+/// [unmounted]: ../trait.Component.html#method.unmounted
+pub fn close<C: 'static + Component>(key: &str) {
+    with_slot::<C, _, _>(key, |slot| slot.scope = None);
+}
+
+/// Throw away the mailbox with the given key, along with any messages it's
+/// holding, for when its component is gone for good.
+pub fn discard<C: 'static + Component>(key: &str) {
+    MAILBOXES.with(|mailboxes| {
+        mailboxes
+            .borrow_mut()
+            .remove(&(TypeId::of::<C>(), key.to_string()))
+    });
+}
+
+/// Send a message to the component with the given mailbox key, or hold on to
+/// it until the component opens its mailbox if it isn't mounted.
+///
+/// You can send to a mailbox before it's been opened, in which case it holds
+/// messages according to the default [`Mailbox`][Mailbox] settings.
+///
+/// [Mailbox]: struct.Mailbox.html
+pub fn send<C: 'static + Component>(key: &str, message: C::Message) {
+    with_slot::<C, _, _>(key, |slot| {
+        let message = match &slot.scope {
+            Some(scope) => match scope.try_send(message) {
+                Ok(()) => return,
+                Err(error) => error.into_inner(),
+            },
+            None => message,
+        };
+        // The component unmounted without closing its mailbox.
+        slot.scope = None;
+        slot.hold(key, message);
+    })
+}
+
+/// Get a function which sends messages to the component with the given
+/// mailbox key, for handing to code which doesn't know about mailboxes.
+pub fn sender<C: 'static + Component>(key: &str) -> impl Fn(C::Message) + Clone {
+    let key = key.to_string();
+    move |message| send::<C>(&key, message)
+}
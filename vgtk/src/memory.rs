@@ -0,0 +1,112 @@
+//! Trimming of caches managed by `vgtk`.
+//!
+//! Long running applications tend to accumulate cached data, like icon
+//! [`Pixbuf`][Pixbuf]s or recycled widgets. `vgtk`'s own caches, of asset
+//! images, named size groups and property animations, are registered here,
+//! and you can ask for all of them to be trimmed using
+//! [`trim_now()`][trim_now] or [`trim_when_idle()`][trim_when_idle]. With the
+//! `memory-monitor` feature enabled, [`trim_on_low_memory()`][trim_on_low_memory]
+//! will also do it automatically when the system runs low on memory.
+//!
+//! You can register your own caches using [`register_cache()`][register_cache].
+//!
+//! [Pixbuf]: ../../gdk_pixbuf/struct.Pixbuf.html
+//! [trim_now]: fn.trim_now.html
+//! [trim_when_idle]: fn.trim_when_idle.html
+//! [trim_on_low_memory]: fn.trim_on_low_memory.html
+//! [register_cache]: fn.register_cache.html
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use colored::Colorize;
+use log::debug;
+
+use crate::{affinity, assets, ext, properties};
+
+type Trim = Rc<dyn Fn() -> usize>;
+
+thread_local! {
+    static CACHES: RefCell<Vec<(&'static str, Trim)>> = RefCell::new(vec![
+        ("asset images", Rc::new(assets::trim_pixbufs) as Trim),
+        ("size groups", Rc::new(ext::trim_size_groups)),
+        ("animations", Rc::new(properties::trim_animations)),
+    ]);
+}
+
+/// The outcome of trimming a single cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reclaimed {
+    /// The name the cache was registered with.
+    pub cache: &'static str,
+    /// The number of entries which were dropped from the cache.
+    pub entries: usize,
+}
+
+/// Register a cache to be trimmed along with `vgtk`'s own caches.
+///
+/// The `trim` function should drop whatever it can from the cache and return
+/// the number of entries it dropped.
+pub fn register_cache<F>(name: &'static str, trim: F)
+where
+    F: Fn() -> usize + 'static,
+{
+    CACHES.with(|caches| caches.borrow_mut().push((name, Rc::new(trim))));
+}
+
+/// Trim every registered cache right away.
+///
+/// Returns what was reclaimed from each cache, which is also logged at the
+/// `debug` level.
+pub fn trim_now() -> Vec<Reclaimed> {
+    affinity::assert_main_thread("vgtk::memory::trim_now");
+    // A cache may register another while it's being trimmed.
+    let caches = CACHES.with(|caches| caches.borrow().clone());
+    caches
+        .into_iter()
+        .map(|(cache, trim)| {
+            let entries = trim();
+            debug!(
+                "{} {}: {} entries",
+                "Trimmed cache".bright_blue(),
+                cache.magenta().bold(),
+                entries
+            );
+            Reclaimed { cache, entries }
+        })
+        .collect()
+}
+
+/// Trim every registered cache the next time the main loop is idle.
+pub fn trim_when_idle() {
+    glib::idle_add_local(|| {
+        trim_now();
+        glib::Continue(false)
+    });
+}
+
+/// Trim every registered cache whenever the system warns that it's running
+/// low on memory.
+///
+/// This uses GLib's [`MemoryMonitor`][MemoryMonitor], which requires GLib 2.64
+/// and the `memory-monitor` feature. Returns `false` if no memory monitor is
+/// available on this system.
+///
+/// [MemoryMonitor]: ../../gio/struct.MemoryMonitor.html
+#[cfg(feature = "memory-monitor")]
+pub fn trim_on_low_memory() -> bool {
+    use gio::MemoryMonitorExt;
+
+    match gio::MemoryMonitor::dup_default() {
+        Some(monitor) => {
+            monitor.connect_low_memory_warning(|_, level| {
+                debug!("{} {}", "Low memory warning:".bright_red(), level);
+                trim_now();
+            });
+            // The default monitor is a singleton, so it stays alive after
+            // we drop our reference.
+            true
+        }
+        None => false,
+    }
+}
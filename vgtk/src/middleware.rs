@@ -0,0 +1,122 @@
+//! Hooks which see every message on its way to a component.
+//!
+//! Some things need to happen for every message in the app, like logging
+//! them, counting them for analytics, recording them for undo, or dropping
+//! the ones which shouldn't be allowed through. Rather than add them to every
+//! component's `update()`, install a middleware, which is called with the
+//! name of the component each message is for, the message, and a `next`
+//! function which passes the message on. A middleware which doesn't call
+//! `next` stops the message from being delivered:
+//!
+//! ```rust,no_run
+//! vgtk::middleware::install(|component, message, next| {
+//!     log::info!("{} received {:?}", component, message);
+//!     next();
+//! });
+//! ```
+//!
+//! Messages are all different types, so a middleware sees them as an
+//! [`AnyMessage`][AnyMessage], which you can downcast to the message type of
+//! a component you're interested in:
+//!
+//! ```rust,no_run
+//! # #[derive(Clone, Debug)] enum Message { Delete(usize) }
+//! # let read_only = true;
+//! vgtk::middleware::install(move |_, message, next| {
+//!     match message.downcast_ref::<Message>() {
+//!         Some(Message::Delete(_)) if read_only => {}
+//!         _ => next(),
+//!     }
+//! });
+//! ```
+//!
+//! Middleware run in the order they were installed, so the first one
+//! installed sees each message first, and only sees what's left after it
+//! once it calls `next`. They only see messages sent to components through
+//! their [`Scope`][Scope]s, including the ones their signal handlers
+//! produce, and not new properties from their parent components.
+//!
+//! [AnyMessage]: trait.AnyMessage.html
+//! [Scope]: ../scope/struct.Scope.html
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+use std::rc::Rc;
+
+/// A message for any type of component.
+pub trait AnyMessage: Any + Debug {
+    /// Get the message as an `Any`, to downcast it to its own type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<M: Any + Debug> AnyMessage for M {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl dyn AnyMessage {
+    /// Test whether the message is an `M`.
+    pub fn is<M: Any>(&self) -> bool {
+        self.as_any().is::<M>()
+    }
+
+    /// Get the message as an `M`, if that's what it is.
+    pub fn downcast_ref<M: Any>(&self) -> Option<&M> {
+        self.as_any().downcast_ref::<M>()
+    }
+}
+
+/// Pass a message on to the next middleware, or to its component if there
+/// are no more.
+pub type Next<'a> = Box<dyn FnOnce() + 'a>;
+
+type Middleware = Rc<dyn Fn(&'static str, &dyn AnyMessage, Next<'_>)>;
+
+thread_local! {
+    static MIDDLEWARE: RefCell<Vec<Middleware>> = RefCell::new(Vec::new());
+}
+
+/// Install a middleware for every component on this thread.
+///
+/// It's called with the name of the component each message is for, the
+/// message, and a function which passes the message on.
+pub fn install<F>(middleware: F)
+where
+    F: Fn(&'static str, &dyn AnyMessage, Next<'_>) + 'static,
+{
+    MIDDLEWARE.with(|installed| installed.borrow_mut().push(Rc::new(middleware)));
+}
+
+/// Remove every middleware installed on this thread.
+pub fn clear() {
+    MIDDLEWARE.with(|installed| installed.borrow_mut().clear());
+}
+
+fn run(chain: &[Middleware], component: &'static str, message: &dyn AnyMessage, last: Next<'_>) {
+    match chain.split_first() {
+        None => last(),
+        Some((middleware, rest)) => middleware(
+            component,
+            message,
+            Box::new(move || run(rest, component, message, last)),
+        ),
+    }
+}
+
+/// Pass a message through the middleware, returning it if it made it out the
+/// other end.
+pub(crate) fn dispatch<M: Any + Debug>(component: &'static str, message: M) -> Option<M> {
+    let chain = MIDDLEWARE.with(|installed| installed.borrow().clone());
+    if chain.is_empty() {
+        return Some(message);
+    }
+    let passed = Cell::new(false);
+    run(&chain, component, &message, Box::new(|| passed.set(true)));
+    if passed.get() {
+        Some(message)
+    } else {
+        None
+    }
+}
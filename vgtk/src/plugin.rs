@@ -0,0 +1,627 @@
+//! Components loaded from shared libraries.
+//!
+//! With the `plugins` feature enabled, an app can load components from
+//! shared libraries at runtime, and mount them by name, so that third
+//! parties can add panels to it without it being rebuilt. Unlike the
+//! [`isolate`][isolate] module, plugins run in the app's own process, so
+//! they're cheaper to host, but a plugin which crashes takes the app with
+//! it.
+//!
+//! A plugin is a `cdylib` crate with its own dependency on `vgtk`, which
+//! lists its components with [`export_plugin!`][export_plugin]. A plugin's
+//! component takes [`PluginProps`][PluginProps] as its properties, which
+//! carry the host's properties as a string, and a [`Host`][Host] to send it
+//! messages with, also as strings, so serialise them however you like, eg.
+//! with `serde_json`:
+//!
+//! ```rust,no_run
+//! # use vgtk::{Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::Button;
+//! use vgtk::plugin::PluginProps;
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Clicked,
+//! }
+//!
+//! #[derive(Default)]
+//! struct Visualiser {
+//!     props: PluginProps,
+//! }
+//!
+//! impl Component for Visualiser {
+//!     type Message = Message;
+//!     type Properties = PluginProps;
+//!
+//!     fn create(props: PluginProps) -> Self {
+//!         Visualiser { props }
+//!     }
+//!
+//!     fn change(&mut self, props: PluginProps) -> UpdateAction<Self> {
+//!         self.props = props;
+//!         UpdateAction::Render
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Clicked => self.props.host.send("clicked"),
+//!         }
+//!         UpdateAction::None
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         VNode::widget::<Button>()
+//!             .prop("label", &self.props.props)
+//!             .on_clicked(|_| Message::Clicked)
+//!             .build()
+//!     }
+//! }
+//!
+//! vgtk::export_plugin! {
+//!     "media.visualiser" => Visualiser,
+//! }
+//! ```
+//!
+//! The host loads the library with [`load()`][load], and mounts its
+//! components with the [`Plugin`][Plugin] component:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::plugin::Plugin;
+//! # #[derive(Clone, Debug)] enum Message { Plugin(String) }
+//! # #[derive(Default)] struct Host { settings: String }
+//! # impl Component for Host {
+//! #     type Message = Message; type Properties = ();
+//! #     fn create(_props: ()) -> Self { Self::default() }
+//! fn view(&self) -> VNode<Self> {
+//!     gtk! {
+//!         <@Plugin name="media.visualiser"
+//!                  props=self.settings.clone()
+//!                  on message=|message| Message::Plugin(message) />
+//!     }
+//! }
+//! # }
+//! # fn main() {
+//! vgtk::plugin::load("./libvisualiser.so").expect("unable to load plugin");
+//! # }
+//! ```
+//!
+//! The two sides only talk through a table of C functions, passing GTK
+//! widgets and C strings, so the plugin doesn't have to be built with the
+//! same compiler or the same version of `vgtk` as the app, as long as both
+//! use the same [`ABI_VERSION`][ABI_VERSION] and the same GTK. The plugin
+//! runs its own copy of `vgtk`, so the state of the app's components,
+//! subcomponents and `vgtk`'s global settings don't reach into it. Its
+//! component's top level widget needs to keep the same type for as long as
+//! it's mounted.
+//!
+//! Libraries are never unloaded, as their code can still be running in
+//! widgets they've built.
+//!
+//! [isolate]: ../isolate/index.html
+//! [export_plugin]: ../macro.export_plugin.html
+//! [PluginProps]: struct.PluginProps.html
+//! [Host]: struct.Host.html
+//! [load]: fn.load.html
+//! [Plugin]: struct.Plugin.html
+//! [ABI_VERSION]: constant.ABI_VERSION.html
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_void, CStr, CString};
+use std::fmt::{Debug, Display, Formatter};
+use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use futures::channel::mpsc::UnboundedSender;
+use glib::gobject_sys::GObject;
+use glib::translate::{from_glib_none, ToGlibPtr};
+use glib::{Cast, MainContext, Object};
+use gtk::prelude::*;
+use gtk::{Box as GtkBox, Label, Orientation, Widget};
+
+use colored::Colorize;
+use log::warn;
+
+use crate::component::{ComponentMessage, ComponentTask};
+use crate::{Callback, Component, UpdateAction, VNode};
+
+/// The version of the interface between plugins and their hosts.
+///
+/// A host only loads plugins built for the same version.
+pub const ABI_VERSION: u32 = 1;
+
+/// The symbol [`export_plugin!`][export_plugin] exports a plugin's
+/// component table under.
+///
+/// [export_plugin]: ../macro.export_plugin.html
+pub const ENTRY_POINT: &str = "vgtk_plugin_entry";
+
+/// A plugin's table of components, as exported by
+/// [`export_plugin!`][export_plugin].
+///
+/// [export_plugin]: ../macro.export_plugin.html
+#[doc(hidden)]
+#[repr(C)]
+pub struct RawPlugin {
+    pub abi: u32,
+    pub components: *const RawComponent,
+    pub count: usize,
+}
+
+/// The functions which make and drive one of a plugin's components.
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RawComponent {
+    pub name: *const c_char,
+    /// Make an instance of the component, given its host and properties.
+    /// Returns null if it fails.
+    pub create: extern "C" fn(host: RawHost, props: *const c_char) -> *mut c_void,
+    /// Get an instance's top level widget, without adding a reference.
+    pub widget: extern "C" fn(instance: *mut c_void) -> *mut GObject,
+    pub set_props: extern "C" fn(instance: *mut c_void, props: *const c_char),
+    pub destroy: extern "C" fn(instance: *mut c_void),
+}
+
+/// What a plugin's component gets to talk back to its host with. The
+/// plugin calls `drop` when it's done with it.
+#[doc(hidden)]
+#[repr(C)]
+pub struct RawHost {
+    pub data: *mut c_void,
+    pub send: extern "C" fn(data: *mut c_void, message: *const c_char),
+    pub drop: extern "C" fn(data: *mut c_void),
+}
+
+fn to_c_string(text: &str) -> CString {
+    CString::new(text.replace('\0', "")).unwrap()
+}
+
+#[allow(unsafe_code)]
+fn from_c_string(text: *const c_char) -> String {
+    unsafe { CStr::from_ptr(text) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+// The plugin's side.
+
+/// The properties of a plugin's component.
+#[derive(Clone, Debug, Default)]
+pub struct PluginProps {
+    /// The properties the host's [`Plugin`][Plugin] component was given.
+    ///
+    /// [Plugin]: struct.Plugin.html
+    pub props: String,
+    /// The host, which messages are sent to.
+    pub host: Host,
+}
+
+struct HostHandle(RawHost);
+
+impl Drop for HostHandle {
+    fn drop(&mut self) {
+        (self.0.drop)(self.0.data)
+    }
+}
+
+/// A plugin's link to the [`Plugin`][Plugin] component hosting it.
+///
+/// The default `Host` isn't linked to anything, and ignores what's sent to
+/// it.
+///
+/// [Plugin]: struct.Plugin.html
+#[derive(Clone, Default)]
+pub struct Host(Option<Rc<HostHandle>>);
+
+impl Host {
+    /// Send a message to the host, which it receives through its `Plugin`
+    /// component's `on message` callback.
+    pub fn send(&self, message: &str) {
+        if let Some(handle) = &self.0 {
+            let message = to_c_string(message);
+            (handle.0.send)(handle.0.data, message.as_ptr());
+        }
+    }
+}
+
+impl Debug for Host {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Host({})",
+            if self.0.is_some() {
+                "linked"
+            } else {
+                "unlinked"
+            }
+        )
+    }
+}
+
+struct Instance<C: Component> {
+    channel: UnboundedSender<ComponentMessage<C>>,
+    root: Object,
+    host: Host,
+}
+
+impl RawComponent {
+    /// Make the table of functions for a plugin's component. `name` must
+    /// end with a NUL.
+    #[doc(hidden)]
+    pub fn new<C>(name: &'static str) -> Self
+    where
+        C: 'static + Component<Properties = PluginProps>,
+    {
+        assert!(
+            name.ends_with('\0'),
+            "plugin component name must end with a NUL"
+        );
+        RawComponent {
+            name: name.as_ptr() as *const c_char,
+            create: create::<C>,
+            widget: widget::<C>,
+            set_props: set_props::<C>,
+            destroy: destroy::<C>,
+        }
+    }
+}
+
+impl RawPlugin {
+    /// Make a plugin's table of components, which lasts for as long as the
+    /// library is loaded.
+    #[doc(hidden)]
+    pub fn new(components: Vec<RawComponent>) -> *const RawPlugin {
+        let components = Box::leak(components.into_boxed_slice());
+        Box::leak(Box::new(RawPlugin {
+            abi: ABI_VERSION,
+            components: components.as_ptr(),
+            count: components.len(),
+        }))
+    }
+}
+
+extern "C" fn create<C>(host: RawHost, props: *const c_char) -> *mut c_void
+where
+    C: 'static + Component<Properties = PluginProps>,
+{
+    let host = Host(Some(Rc::new(HostHandle(host))));
+    let props = PluginProps {
+        props: from_c_string(props),
+        host: host.clone(),
+    };
+    // Don't let a panic unwind into the host, which may not even be Rust.
+    catch_unwind(AssertUnwindSafe(move || {
+        let (channel, task) = ComponentTask::<C, ()>::new(props, None, None);
+        let root = task
+            .object()
+            .expect("plugin component has no top level object");
+        MainContext::ref_thread_default().spawn_local(task);
+        channel.unbounded_send(ComponentMessage::Mounted).unwrap();
+        Box::into_raw(Box::new(Instance {
+            channel,
+            root,
+            host,
+        })) as *mut c_void
+    }))
+    .unwrap_or(std::ptr::null_mut())
+}
+
+#[allow(unsafe_code)]
+fn instance<'a, C: Component>(instance: *mut c_void) -> &'a Instance<C> {
+    unsafe { &*(instance as *const Instance<C>) }
+}
+
+extern "C" fn widget<C: Component>(raw: *mut c_void) -> *mut GObject {
+    let pointer: *mut GObject = instance::<C>(raw).root.to_glib_none().0;
+    pointer
+}
+
+extern "C" fn set_props<C>(raw: *mut c_void, props: *const c_char)
+where
+    C: Component<Properties = PluginProps>,
+{
+    let instance = instance::<C>(raw);
+    let props = PluginProps {
+        props: from_c_string(props),
+        host: instance.host.clone(),
+    };
+    // The task may have stopped if it panicked, which has been reported.
+    let _ = instance
+        .channel
+        .unbounded_send(ComponentMessage::Props(props));
+}
+
+extern "C" fn destroy<C: Component>(raw: *mut c_void) {
+    #[allow(unsafe_code)]
+    let instance = unsafe { Box::from_raw(raw as *mut Instance<C>) };
+    let _ = instance.channel.unbounded_send(ComponentMessage::Unmounted);
+}
+
+/// Export a plugin's components for [`vgtk::plugin::load()`][load] to find.
+///
+/// `export_plugin! { "media.visualiser" => Visualiser }` exports the
+/// component `Visualiser` under the name `media.visualiser`, which should be
+/// unique across all the plugins an app loads. A library can only use this
+/// once, so list all of its components in the same place.
+///
+/// See the [`plugin`][plugin] module for details.
+///
+/// [load]: plugin/fn.load.html
+/// [plugin]: plugin/index.html
+#[macro_export]
+macro_rules! export_plugin {
+    ($($name:literal => $component:ty),* $(,)?) => {
+        #[no_mangle]
+        pub extern "C" fn vgtk_plugin_entry() -> *const $crate::plugin::RawPlugin {
+            $crate::plugin::RawPlugin::new(vec![
+                $($crate::plugin::RawComponent::new::<$component>(concat!($name, "\0"))),*
+            ])
+        }
+    };
+}
+
+// The host's side.
+
+/// A plugin which couldn't be loaded.
+#[derive(Debug, Clone)]
+pub struct Error {
+    /// The library's path.
+    pub path: PathBuf,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+thread_local! {
+    static REGISTRY: RefCell<HashMap<String, RawComponent>> = RefCell::new(HashMap::new());
+}
+
+fn dlerror() -> String {
+    #[allow(unsafe_code)]
+    unsafe {
+        let error = libc::dlerror();
+        if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(error).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// Load a plugin library, and register its components so that
+/// [`Plugin`][Plugin] components can mount them.
+///
+/// Returns the names of the components it registered. A component with the
+/// same name as one which was already registered replaces it, for
+/// `Plugin`s mounted from then on.
+///
+/// [Plugin]: struct.Plugin.html
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<String>, Error> {
+    let path = path.as_ref();
+    let error = |message: String| Error {
+        path: path.to_path_buf(),
+        message,
+    };
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| error("path contains a NUL".to_string()))?;
+    #[allow(unsafe_code)]
+    let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if handle.is_null() {
+        return Err(error(dlerror()));
+    }
+    let symbol = CString::new(ENTRY_POINT).unwrap();
+    #[allow(unsafe_code)]
+    let entry = unsafe { libc::dlsym(handle, symbol.as_ptr()) };
+    if entry.is_null() {
+        return Err(error(format!(
+            "not a vgtk plugin, as it has no {} function",
+            ENTRY_POINT
+        )));
+    }
+    #[allow(unsafe_code)]
+    let plugin = unsafe {
+        let entry = std::mem::transmute::<*mut c_void, extern "C" fn() -> *const RawPlugin>(entry);
+        &*entry()
+    };
+    if plugin.abi != ABI_VERSION {
+        return Err(error(format!(
+            "built for plugin ABI version {}, but this is version {}",
+            plugin.abi, ABI_VERSION
+        )));
+    }
+    #[allow(unsafe_code)]
+    let components = unsafe { std::slice::from_raw_parts(plugin.components, plugin.count) };
+    let names = REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        components
+            .iter()
+            .map(|component| {
+                let name = from_c_string(component.name);
+                if registry.insert(name.clone(), *component).is_some() {
+                    warn!(
+                        "{} {} {} {}",
+                        "Plugin component".bright_red(),
+                        name.bright_white().bold(),
+                        "was replaced by".bright_red(),
+                        path.display().to_string().bright_white().bold()
+                    );
+                }
+                name
+            })
+            .collect()
+    });
+    Ok(names)
+}
+
+/// Get the names of the components registered by the plugins loaded so
+/// far, in alphabetical order.
+pub fn registered() -> Vec<String> {
+    let mut names: Vec<String> =
+        REGISTRY.with(|registry| registry.borrow().keys().cloned().collect());
+    names.sort();
+    names
+}
+
+extern "C" fn host_send(data: *mut c_void, message: *const c_char) {
+    #[allow(unsafe_code)]
+    let on_message = unsafe { &*(data as *const RefCell<Callback<String>>) };
+    on_message.borrow().send(from_c_string(message));
+}
+
+extern "C" fn host_drop(data: *mut c_void) {
+    #[allow(unsafe_code)]
+    unsafe {
+        Rc::from_raw(data as *const RefCell<Callback<String>>);
+    }
+}
+
+/// A mounted instance of a plugin's component.
+struct Mounted {
+    component: RawComponent,
+    raw: *mut c_void,
+    widget: Widget,
+    on_message: Rc<RefCell<Callback<String>>>,
+}
+
+impl Mounted {
+    fn new(name: &str, props: &str, on_message: Callback<String>) -> Option<Self> {
+        let component = REGISTRY.with(|registry| registry.borrow().get(name).copied())?;
+        let on_message = Rc::new(RefCell::new(on_message));
+        let host = RawHost {
+            data: Rc::into_raw(on_message.clone()) as *mut c_void,
+            send: host_send,
+            drop: host_drop,
+        };
+        let props = to_c_string(props);
+        let raw = (component.create)(host, props.as_ptr());
+        if raw.is_null() {
+            warn!(
+                "{} {}",
+                "Unable to create plugin component".bright_red(),
+                name.bright_white().bold()
+            );
+            return None;
+        }
+        #[allow(unsafe_code)]
+        let object: Object = unsafe { from_glib_none((component.widget)(raw)) };
+        let widget = match object.downcast::<Widget>() {
+            Ok(widget) => widget,
+            Err(object) => {
+                warn!(
+                    "{} {} {} {}",
+                    "Plugin component".bright_red(),
+                    name.bright_white().bold(),
+                    "has a top level object which isn't a widget:".bright_red(),
+                    object.get_type()
+                );
+                (component.destroy)(raw);
+                return None;
+            }
+        };
+        Some(Mounted {
+            component,
+            raw,
+            widget,
+            on_message,
+        })
+    }
+}
+
+impl Drop for Mounted {
+    fn drop(&mut self) {
+        (self.component.destroy)(self.raw)
+    }
+}
+
+/// A component which mounts a component from a plugin.
+///
+/// See the [module documentation][module] for details.
+///
+/// [module]: index.html
+#[derive(Clone, Default)]
+pub struct Plugin {
+    /// The name the plugin registered the component under.
+    pub name: String,
+    /// The properties to send to the plugin's component, sent again whenever
+    /// they change.
+    pub props: String,
+    /// Called with each message the plugin's component sends.
+    pub on_message: Callback<String>,
+    mounted: Option<Rc<Mounted>>,
+}
+
+impl Debug for Plugin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin")
+            .field("name", &self.name)
+            .field("props", &self.props)
+            .field("on_message", &self.on_message)
+            .field("mounted", &self.mounted.is_some())
+            .finish()
+    }
+}
+
+impl Component for Plugin {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(mut props: Self) -> Self {
+        props.mounted =
+            Mounted::new(&props.name, &props.props, props.on_message.clone()).map(Rc::new);
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        if props.name != self.name {
+            *self = Self::create(props);
+            return UpdateAction::Render;
+        }
+        if let Some(mounted) = &self.mounted {
+            if props.props != self.props {
+                let c_props = to_c_string(&props.props);
+                (mounted.component.set_props)(mounted.raw, c_props.as_ptr());
+            }
+            *mounted.on_message.borrow_mut() = props.on_message.clone();
+        }
+        self.props = props.props;
+        self.on_message = props.on_message;
+        UpdateAction::None
+    }
+
+    fn view(&self) -> VNode<Self> {
+        match &self.mounted {
+            Some(mounted) => {
+                let widget = mounted.widget.clone();
+                // The holder is keyed on the instance, so that mounting
+                // another one builds a new holder for it.
+                VNode::widget::<GtkBox>()
+                    .key(&(mounted.raw as usize))
+                    .constructor(move || {
+                        let holder = GtkBox::new(Orientation::Vertical, 0);
+                        holder.pack_start(&widget, true, true, 0);
+                        widget.show();
+                        holder
+                    })
+                    .build()
+            }
+            None => VNode::widget::<Label>()
+                .prop(
+                    "label",
+                    format!("The plugin {} isn't available.", self.name),
+                )
+                .build(),
+        }
+    }
+}
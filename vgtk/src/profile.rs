@@ -0,0 +1,200 @@
+//! Finding the slow components in your app.
+//!
+//! With the `profiling` feature enabled, every component keeps track of how
+//! long its `view()` takes, how long it takes to patch the widget tree to
+//! match the new view, or to build it again when it can't be patched, and
+//! how long its messages wait in its queue before they're delivered to
+//! `update()`. Ask for a [`report()`][report] to see which components are
+//! costing you the most:
+//!
+//! ```rust,no_run
+//! println!("{}", vgtk::profile::report());
+//! ```
+//!
+//! Timings are collected per component type, so if you have a hundred rows
+//! in a list, you'll see the total for all of them, along with how many
+//! renders that adds up to.
+//!
+//! If the `tracing` feature is enabled too, every view and patch runs
+//! inside a [`tracing`][tracing] span named after what it's doing, with a
+//! `component` field naming the component, so you can see them on a
+//! timeline in whatever your `tracing` subscriber feeds them to.
+//!
+//! [report]: fn.report.html
+//! [tracing]: https://docs.rs/tracing/
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter};
+use std::time::{Duration, Instant};
+
+use crate::component::Phase;
+
+/// Timings for one kind of work, eg. calls to `view()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    /// How many times it's happened.
+    pub count: usize,
+    /// The time it's taken altogether.
+    pub total: Duration,
+    /// The longest it's taken.
+    pub max: Duration,
+}
+
+impl Timing {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// The time it's taken on average.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::default()
+        } else {
+            self.total / self.count as u32
+        }
+    }
+}
+
+/// Timings for one type of component.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ComponentTimings {
+    /// The component's type name.
+    pub component: &'static str,
+    /// Calls to the component's `view()`.
+    pub view: Timing,
+    /// Patching the widget tree to match a new view.
+    pub patch: Timing,
+    /// Building the widget tree again from scratch, when it couldn't be
+    /// patched.
+    pub rebuild: Timing,
+    /// How long messages waited before being delivered to `update()`.
+    pub latency: Timing,
+}
+
+impl ComponentTimings {
+    /// The time spent rendering altogether, including `view()`, patching
+    /// and rebuilding.
+    pub fn render_time(&self) -> Duration {
+        self.view.total + self.patch.total + self.rebuild.total
+    }
+}
+
+/// The timings for every component which has done anything yet, as returned
+/// by [`report()`][report].
+///
+/// Display it for a table of them, slowest first.
+///
+/// [report]: fn.report.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The components, with the ones which have spent the most time
+    /// rendering first.
+    pub components: Vec<ComponentTimings>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(
+            f,
+            "{:<40} {:>8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "component",
+            "renders",
+            "rebuilds",
+            "view ms",
+            "view max",
+            "patch ms",
+            "patch max",
+            "wait ms",
+            "wait max"
+        )?;
+        for timings in &self.components {
+            writeln!(
+                f,
+                "{:<40} {:>8} {:>8} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                timings.component,
+                timings.view.count,
+                timings.rebuild.count,
+                millis(timings.view.total),
+                millis(timings.view.max),
+                millis(timings.patch.total + timings.rebuild.total),
+                millis(timings.patch.max.max(timings.rebuild.max)),
+                millis(timings.latency.mean()),
+                millis(timings.latency.max)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static TIMINGS: RefCell<HashMap<&'static str, ComponentTimings>> = RefCell::new(HashMap::new());
+}
+
+/// Get the timings collected on this thread so far.
+pub fn report() -> Report {
+    let mut components: Vec<_> =
+        TIMINGS.with(|timings| timings.borrow().values().cloned().collect());
+    components.sort_by(|a, b| {
+        b.render_time()
+            .cmp(&a.render_time())
+            .then(a.component.cmp(b.component))
+    });
+    Report { components }
+}
+
+/// Forget the timings collected so far, eg. to profile a single interaction.
+pub fn reset() {
+    TIMINGS.with(|timings| timings.borrow_mut().clear());
+}
+
+fn with_timings<F>(component: &'static str, f: F)
+where
+    F: FnOnce(&mut ComponentTimings),
+{
+    TIMINGS.with(|timings| {
+        f(timings
+            .borrow_mut()
+            .entry(component)
+            .or_insert_with(|| ComponentTimings {
+                component,
+                ..Default::default()
+            }))
+    })
+}
+
+/// Run `f`, recording how long it took.
+pub(crate) fn time<F, R>(phase: Phase, component: &'static str, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    #[cfg(feature = "tracing")]
+    let span = match phase {
+        Phase::View => tracing::trace_span!("view", component),
+        Phase::Patch => tracing::trace_span!("patch", component),
+        Phase::Rebuild => tracing::trace_span!("rebuild", component),
+    };
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = f();
+    let duration = start.elapsed();
+    with_timings(component, |timings| match phase {
+        Phase::View => timings.view.record(duration),
+        Phase::Patch => timings.patch.record(duration),
+        Phase::Rebuild => timings.rebuild.record(duration),
+    });
+    result
+}
+
+/// Record that a message sent at `sent` has been delivered.
+pub(crate) fn delivered(component: &'static str, sent: Instant) {
+    let latency = sent.elapsed();
+    with_timings(component, |timings| timings.latency.record(latency));
+}
@@ -1,10 +1,19 @@
-//! Property conversion traits.
+//! Property conversion traits, and sets of properties to spread over
+//! widgets.
 
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 
-use glib::{Cast, GString};
+use glib::{Cast, GString, IsA, Object, ObjectExt, ObjectType, ToValue, Value, WeakRef};
 use gtk::{IconSize, Image, ImageExt, Widget};
 
+use crate::types::Adjustment;
+use crate::vnode::VProperty;
+
 pub struct PropertyValue<'a, A, Get, Set>
 where
     A: PropertyValueCompare<'a, Get> + PropertyValueCoerce<'a, Set> + 'a,
@@ -31,10 +40,20 @@ where
     pub fn coerce(&'a self) -> Set {
         A::property_coerce(&self.value)
     }
+
+    pub fn update(&self, value: Get) -> bool {
+        A::property_update(value, &self.value)
+    }
 }
 
 pub trait PropertyValueCompare<'a, A> {
     fn property_compare(left: A, right: &Self) -> bool;
+
+    // Update the current value in place to match, instead of setting the
+    // property to a new one, if that's possible. Returns whether it was.
+    fn property_update(_left: A, _right: &Self) -> bool {
+        false
+    }
 }
 
 pub trait PropertyValueCoerce<'a, A> {
@@ -175,6 +194,38 @@ impl<'a> PropertyValueCoerce<'a, Option<&'a Image>> for Image {
     }
 }
 
+impl<'a> PropertyValueCompare<'a, gtk::Adjustment> for Adjustment {
+    fn property_compare(left: gtk::Adjustment, right: &Adjustment) -> bool {
+        right.matches(&left)
+    }
+
+    fn property_update(left: gtk::Adjustment, right: &Adjustment) -> bool {
+        right.apply_to(&left);
+        true
+    }
+}
+
+impl<'a> PropertyValueCompare<'a, Option<gtk::Adjustment>> for Adjustment {
+    fn property_compare(left: Option<gtk::Adjustment>, right: &Adjustment) -> bool {
+        left.is_some_and(|left| right.matches(&left))
+    }
+
+    fn property_update(left: Option<gtk::Adjustment>, right: &Adjustment) -> bool {
+        if let Some(left) = left {
+            right.apply_to(&left);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<'a> PropertyValueCoerce<'a, Option<&'a Adjustment>> for Adjustment {
+    fn property_coerce(value: &'a Adjustment) -> Option<&'a Adjustment> {
+        Some(value)
+    }
+}
+
 impl PropertyValueCompare<'_, Vec<GString>> for &'_ [&'_ str] {
     fn property_compare(left: Vec<GString>, right: &&[&str]) -> bool {
         left == *right
@@ -243,3 +294,370 @@ where
         PropertyValue::new(Image::from_icon_name(Some(self), IconSize::Button))
     }
 }
+
+/// Property values which can be animated by the `animate` attribute modifier.
+pub trait Interpolate: Copy + 'static {
+    fn to_f64(self) -> f64;
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_interpolate {
+    ($($float:ty),* ; $($int:ty),*) => {
+        $(impl Interpolate for $float {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                value as $float
+            }
+        })*
+        $(impl Interpolate for $int {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                value.round() as $int
+            }
+        })*
+    };
+}
+
+impl_interpolate!(f32, f64; i8, u8, i16, u16, i32, u32, i64, u64);
+
+/// Roughly one frame at 60 frames per second.
+const FRAME_INTERVAL_MS: u32 = 16;
+
+thread_local! {
+    // The generation and target value of the running animation for each
+    // animated property, and the object it belongs to, keyed by object
+    // address and property name.
+    static ANIMATIONS: RefCell<HashMap<(usize, &'static str), (u64, f64, WeakRef<Object>)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Forget the animations of objects which are gone, returning how many
+/// there were.
+pub(crate) fn trim_animations() -> usize {
+    ANIMATIONS.with(|animations| {
+        let mut animations = animations.borrow_mut();
+        let before = animations.len();
+        animations.retain(|_, (_, _, object)| object.upgrade().is_some());
+        before - animations.len()
+    })
+}
+
+/// Animate a property from one value to another over the given duration.
+///
+/// Starting a new animation on a property supersedes any animation still
+/// running on it, unless that animation is already headed for the same value.
+pub fn animate<O, T, F>(
+    object: &O,
+    property: &'static str,
+    duration: Duration,
+    from: T,
+    to: T,
+    set: F,
+) where
+    O: IsA<Object>,
+    T: Interpolate,
+    F: Fn(&O, T) + 'static,
+{
+    crate::affinity::assert_owner(object, "vgtk::properties::animate");
+    let key = (object.upcast_ref::<Object>().as_ptr() as usize, property);
+    let (from, target) = (from.to_f64(), to.to_f64());
+    let generation = ANIMATIONS.with(|animations| {
+        let mut animations = animations.borrow_mut();
+        match animations.get(&key) {
+            Some((_, running, _)) if *running == target => None,
+            running => {
+                let generation = running.map_or(0, |(generation, ..)| generation + 1);
+                let weak = object.upcast_ref::<Object>().downgrade();
+                animations.insert(key, (generation, target, weak));
+                Some(generation)
+            }
+        }
+    });
+    let generation = match generation {
+        Some(generation) => generation,
+        None => return,
+    };
+    if duration == Duration::from_millis(0) {
+        set(object, to);
+        ANIMATIONS.with(|animations| animations.borrow_mut().remove(&key));
+        return;
+    }
+
+    let object = object.downgrade();
+    let start = Instant::now();
+    glib::timeout_add_local(FRAME_INTERVAL_MS, move || {
+        let current = ANIMATIONS.with(|animations| {
+            let animations = animations.borrow();
+            animations.get(&key).map(|(current, ..)| *current)
+        });
+        if current != Some(generation) {
+            // A newer animation has taken over.
+            return glib::Continue(false);
+        }
+        let object = match object.upgrade() {
+            Some(object) => object,
+            None => {
+                ANIMATIONS.with(|animations| animations.borrow_mut().remove(&key));
+                return glib::Continue(false);
+            }
+        };
+        let progress = (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.0);
+        // Ease out cubic.
+        let eased = 1.0 - (1.0 - progress).powi(3);
+        set(&object, T::from_f64(from + (target - from) * eased));
+        if progress < 1.0 {
+            glib::Continue(true)
+        } else {
+            ANIMATIONS.with(|animations| animations.borrow_mut().remove(&key));
+            glib::Continue(false)
+        }
+    });
+}
+
+/// Properties to set on a widget, which can be spread over it with a
+/// `{..value}` attribute in the [`gtk!`][gtk!] macro.
+///
+/// The properties are set by name, like they would be in a Gtk Builder
+/// file, so the properties a widget gets don't have to be known when you
+/// write its view:
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, VNode};
+/// # use vgtk::lib::gtk::{Button, ButtonExt};
+/// # fn view(label: &str, pressed: bool) -> VNode<()> {
+/// use vgtk::properties::PropertySet;
+///
+/// let pressed = if pressed {
+///     PropertySet::new().with("relief", vgtk::lib::gtk::ReliefStyle::Normal)
+/// } else {
+///     PropertySet::new().with("relief", vgtk::lib::gtk::ReliefStyle::None)
+/// };
+/// gtk! {
+///     <Button label=label {..pressed} />
+/// }
+/// # }
+/// ```
+///
+/// To spread a struct of your own over a widget, implement
+/// [`Spread`][Spread] for it.
+///
+/// [gtk!]: ../macro.gtk.html
+/// [Spread]: trait.Spread.html
+#[derive(Clone, Debug, Default)]
+pub struct PropertySet {
+    properties: Vec<(&'static str, Value)>,
+}
+
+impl PropertySet {
+    /// Make an empty property set.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a property to the set, replacing any value it already had.
+    pub fn with<V: ToValue>(mut self, name: &'static str, value: V) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Add a property to the set, replacing any value it already had.
+    pub fn insert<V: ToValue>(&mut self, name: &'static str, value: V) {
+        let value = value.to_value();
+        match self.properties.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, current)) => *current = value,
+            None => self.properties.push((name, value)),
+        }
+    }
+
+    /// Remove a property from the set.
+    pub fn remove(&mut self, name: &str) {
+        self.properties.retain(|(key, _)| *key != name);
+    }
+
+    /// Add every property in `other` to the set, replacing the values of
+    /// any it already had.
+    pub fn merge(mut self, other: PropertySet) -> Self {
+        for (name, value) in other.properties {
+            self.insert(name, value);
+        }
+        self
+    }
+
+    /// Test whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+}
+
+/// Values which can be spread over a widget with a `{..value}` attribute in
+/// the [`gtk!`][gtk!] macro.
+///
+/// [gtk!]: ../macro.gtk.html
+pub trait Spread {
+    /// Get the names and values of the properties to set.
+    fn spread(&self) -> Vec<(&'static str, Value)>;
+}
+
+impl Spread for PropertySet {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        self.properties.clone()
+    }
+}
+
+impl<S: Spread + ?Sized> Spread for &S {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        (**self).spread()
+    }
+}
+
+impl<S: Spread> Spread for Option<S> {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        self.as_ref().map(Spread::spread).unwrap_or_default()
+    }
+}
+
+impl Spread for [(&'static str, Value)] {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        self.to_vec()
+    }
+}
+
+impl Spread for Vec<(&'static str, Value)> {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        self.clone()
+    }
+}
+
+impl<S> Spread for HashMap<&'static str, Value, S> {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        self.iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .collect()
+    }
+}
+
+impl Spread for BTreeMap<&'static str, Value> {
+    fn spread(&self) -> Vec<(&'static str, Value)> {
+        self.iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .collect()
+    }
+}
+
+// Test whether a property already has a value. Values we can't compare
+// count as different, so they're always set.
+fn unchanged(current: &Value, value: &Value) -> bool {
+    if let (Ok(Some(current)), Ok(Some(value))) = (current.get::<Object>(), value.get::<Object>()) {
+        return current == value;
+    }
+    // A float property may not store exactly the value it's given, like a
+    // `f64` given to a `f32` property, so allow for rounding.
+    if let (Some(current), Some(value)) = (float(current), float(value)) {
+        return (current - value).abs() <= f64::from(f32::EPSILON) * current.abs().max(1.0);
+    }
+    match (current.transform::<String>(), value.transform::<String>()) {
+        (Some(current), Some(value)) => current.get::<String>() == value.get::<String>(),
+        _ => false,
+    }
+}
+
+fn float(value: &Value) -> Option<f64> {
+    match value.type_() {
+        glib::Type::F32 => value.get::<f32>().ok().flatten().map(f64::from),
+        glib::Type::F64 => value.get::<f64>().ok().flatten(),
+        _ => None,
+    }
+}
+
+/// Wraps a property's value to work out its fingerprint, which is a hash of
+/// it if it can be hashed, and otherwise nothing.
+///
+/// Call `fingerprint()` on a double reference to one, with both `Fingerprint`
+/// and `NoFingerprint` in scope, and the compiler picks the hash if it can.
+#[doc(hidden)]
+pub struct Fingerprinted<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait Fingerprint {
+    fn fingerprint(&self) -> Option<u64>;
+}
+
+impl<T: Hash + ?Sized> Fingerprint for &Fingerprinted<'_, T> {
+    fn fingerprint(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+#[doc(hidden)]
+pub trait NoFingerprint {
+    fn fingerprint(&self) -> Option<u64>;
+}
+
+impl<T: ?Sized> NoFingerprint for Fingerprinted<'_, T> {
+    fn fingerprint(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Turn a `{..value}` attribute into properties.
+#[doc(hidden)]
+pub fn spread<S: Spread + ?Sized>(value: &S) -> Vec<VProperty> {
+    value
+        .spread()
+        .into_iter()
+        .map(|(name, value)| VProperty {
+            name,
+            fingerprint: None,
+            set: Box::new(move |object: &Object, _parent, force| {
+                if !force {
+                    if let Ok(current) = object.get_property(name) {
+                        if unchanged(&current, &value) {
+                            return false;
+                        }
+                    }
+                }
+                object.set_property(name, &value).unwrap_or_else(|error| {
+                    panic!(
+                        "can't set property {} on {}: {}",
+                        name,
+                        object.get_type(),
+                        error
+                    )
+                });
+                true
+            }),
+        })
+        .collect()
+}
+
+/// Put a property back to its default value, for an `optional` attribute
+/// whose value is `None`, returning whether it changed.
+///
+/// A widget which is being built is left alone, as it starts out with its
+/// defaults, or with whatever its constructor gave it. Properties which
+/// aren't GObject properties, or don't have a default, are left as they are.
+#[doc(hidden)]
+pub fn reset(object: &Object, name: &str, building: bool) -> bool {
+    if building {
+        return false;
+    }
+    let default = match object
+        .find_property(name)
+        .and_then(|pspec| pspec.get_default_value())
+    {
+        Some(default) => default,
+        None => return false,
+    };
+    if let Ok(current) = object.get_property(name) {
+        if unchanged(&current, &default) {
+            return false;
+        }
+    }
+    object.set_property(name, &default).is_ok()
+}
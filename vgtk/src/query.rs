@@ -0,0 +1,205 @@
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use crate::component::{self, Component};
+
+/// A published snapshot, along with the `Exposures` of the component
+/// instance which published it, if it was one.
+struct Published {
+    owner: Option<usize>,
+    snapshot: Box<dyn Any>,
+}
+
+thread_local! {
+    static SNAPSHOTS: RefCell<HashMap<(TypeId, String), Published>> = RefCell::new(HashMap::new());
+    static NEXT_OWNER: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The snapshot type a [`Queryable`][Queryable] component exposes.
+///
+/// [Queryable]: trait.Queryable.html
+pub type StateSnapshot<C> = <C as Queryable>::Snapshot;
+
+/// A [`Component`][Component] which can expose a read-only snapshot of its state.
+///
+/// This lets loosely coupled parts of your UI inspect each other without routing
+/// every value through a shared parent: a status bar could summarise the state of
+/// an editor, for instance. The component decides what goes into the snapshot
+/// and when to start publishing it, usually from [`Component::init()`][init],
+/// and anyone can read the latest published snapshot using
+/// [`vgtk::query()`][query].
+///
+/// Once a component has exposed a snapshot from one of its own methods, it's
+/// taken again after every update and change of properties, and withdrawn
+/// when the component is destroyed, so it never goes stale.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, Component, Queryable, UpdateAction, VNode};
+/// # use vgtk::lib::gtk::Box;
+/// #[derive(Default)]
+/// struct Editor {
+///     text: String,
+///     dirty: bool,
+/// }
+///
+/// impl Queryable for Editor {
+///     type Snapshot = (usize, bool);
+///
+///     fn snapshot(&self) -> Self::Snapshot {
+///         (self.text.len(), self.dirty)
+///     }
+/// }
+///
+/// impl Component for Editor {
+///     type Message = ();
+///     type Properties = ();
+///
+///     fn create(_props: ()) -> Self {
+///         Self::default()
+///     }
+///
+///     fn init(&mut self) -> UpdateAction<Self> {
+///         self.expose("main-editor");
+///         UpdateAction::None
+///     }
+///
+///     fn view(&self) -> VNode<Self> {
+///         gtk! { <Box/> }
+///     }
+/// }
+///
+/// // Somewhere else entirely:
+/// if let Some((length, dirty)) = vgtk::query::<Editor>("main-editor") {
+///     println!("{} characters, {}", length, if dirty { "unsaved" } else { "saved" });
+/// }
+/// ```
+///
+/// [Component]: trait.Component.html
+/// [init]: trait.Component.html#method.init
+/// [query]: fn.query.html
+pub trait Queryable: Component {
+    /// The type of the snapshot. It should contain only the fields you're
+    /// happy to share.
+    type Snapshot: Clone + 'static;
+
+    /// Take a snapshot of the current state.
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Publish a snapshot of the current state under the given instance key,
+    /// replacing any snapshot previously published under that key.
+    ///
+    /// Called from one of the component's own methods, this also keeps the
+    /// snapshot up to date for as long as the component is around.
+    fn expose(&self, key: &str)
+    where
+        Self: 'static,
+    {
+        let mut owner = None;
+        component::with_exposures(|exposures: &mut Exposures<Self>| {
+            exposures.add(key, |state| Box::new(state.snapshot()));
+            owner = Some(exposures.owner);
+        });
+        publish::<Self>(key, owner, Box::new(self.snapshot()));
+    }
+
+    /// Remove the snapshot published under the given instance key, if any.
+    ///
+    /// Called from one of the component's own methods, this also stops it
+    /// being kept up to date.
+    fn withdraw(key: &str)
+    where
+        Self: 'static,
+    {
+        unpublish::<Self>(key, None);
+        component::with_exposures(|exposures: &mut Exposures<Self>| exposures.remove(key));
+    }
+}
+
+fn publish<C: 'static>(key: &str, owner: Option<usize>, snapshot: Box<dyn Any>) {
+    SNAPSHOTS.with(|snapshots| {
+        snapshots.borrow_mut().insert(
+            (TypeId::of::<C>(), key.to_string()),
+            Published { owner, snapshot },
+        )
+    });
+}
+
+/// Remove the snapshot published under `key`, but only if `owner` published
+/// it, or regardless if there's no `owner`.
+fn unpublish<C: 'static>(key: &str, owner: Option<usize>) {
+    SNAPSHOTS.with(|snapshots| {
+        let mut snapshots = snapshots.borrow_mut();
+        let key = (TypeId::of::<C>(), key.to_string());
+        if owner.is_none() || snapshots.get(&key).map(|published| published.owner) == Some(owner) {
+            snapshots.remove(&key);
+        }
+    });
+}
+
+/// The keys a component has exposed snapshots under, and how to take them,
+/// kept by its task.
+///
+/// Each has an owner id of its own, so that when an instance goes away it
+/// only withdraws the snapshots which another instance exposing the same
+/// keys hasn't replaced since.
+pub(crate) struct Exposures<C> {
+    owner: usize,
+    keys: Vec<(String, fn(&C) -> Box<dyn Any>)>,
+}
+
+impl<C> Default for Exposures<C> {
+    fn default() -> Self {
+        let owner = NEXT_OWNER.with(|next| next.replace(next.get() + 1));
+        Exposures {
+            owner,
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl<C: 'static> Exposures<C> {
+    fn add(&mut self, key: &str, snapshot: fn(&C) -> Box<dyn Any>) {
+        if self.keys.iter().all(|(exposed, _)| exposed != key) {
+            self.keys.push((key.to_string(), snapshot));
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.keys.retain(|(exposed, _)| exposed != key);
+    }
+
+    /// Publish fresh snapshots of `state` under every key.
+    pub(crate) fn refresh(&self, state: &C) {
+        for (key, snapshot) in &self.keys {
+            publish::<C>(key, Some(self.owner), snapshot(state));
+        }
+    }
+
+    /// Withdraw the snapshots published under every key which are still
+    /// this instance's own.
+    pub(crate) fn withdraw_all(&mut self) {
+        for (key, _) in self.keys.drain(..) {
+            unpublish::<C>(&key, Some(self.owner));
+        }
+    }
+}
+
+/// Get the latest snapshot a [`Queryable`][Queryable] component has published
+/// under the given instance key.
+///
+/// Returns `None` if no component of type `C` has published a snapshot with
+/// this key, or if it has since been withdrawn.
+///
+/// [Queryable]: trait.Queryable.html
+pub fn query<C: 'static + Queryable>(key: &str) -> Option<StateSnapshot<C>> {
+    SNAPSHOTS.with(|snapshots| {
+        snapshots
+            .borrow()
+            .get(&(TypeId::of::<C>(), key.to_string()))
+            .and_then(|published| published.snapshot.downcast_ref::<StateSnapshot<C>>())
+            .cloned()
+    })
+}
@@ -0,0 +1,131 @@
+use std::fmt::{Debug, Error, Formatter};
+use std::rc::Rc;
+
+use futures::channel::mpsc::unbounded;
+
+use crate::scope::Scope;
+use crate::{Component, VNode};
+
+/// A render property for sub-[`Component`][Component]s.
+///
+/// A `Render` lets a parent component tell a subcomponent how to draw part
+/// of its view, like a list which is written once and shown with a
+/// different widget for each item in every place it's used. The property
+/// value is a closure which takes a reference to a value from the
+/// subcomponent and returns a [`VNode`][VNode] of the parent component, so
+/// its signal handlers send messages to the parent, just like the rest of
+/// its view:
+///
+/// ```rust,no_run
+/// # use vgtk::{gtk, Component, Render, UpdateAction, VNode};
+/// # use vgtk::lib::gtk::*;
+/// #[derive(Clone, Default)]
+/// pub struct List<T: 'static> {
+///     pub items: Vec<T>,
+///     pub render_item: Render<T>,
+/// }
+///
+/// impl<T: 'static + Clone + Default + Unpin> Component for List<T> {
+///     type Message = ();
+///     type Properties = Self;
+///
+///     fn create(props: Self) -> Self {
+///         props
+///     }
+///
+///     fn change(&mut self, props: Self) -> UpdateAction<Self> {
+///         *self = props;
+///         UpdateAction::Render
+///     }
+///
+///     fn view(&self) -> VNode<Self> {
+///         gtk! {
+///             <ListBox>
+///                 { self.items.iter().filter_map(|item| self.render_item.render(item)) }
+///             </ListBox>
+///         }
+///     }
+/// }
+///
+/// # #[derive(Clone, Debug, Default)] struct Task { id: usize, name: String }
+/// # #[derive(Clone, Debug)] enum Message { Done(usize) }
+/// # #[derive(Default)] struct App { tasks: Vec<Task> }
+/// # impl Component for App { type Message = Message; type Properties = ();
+/// # fn create(_props: ()) -> Self { Self::default() }
+/// // In the parent component:
+/// fn view(&self) -> VNode<Self> {
+///     gtk! {
+///         <@List<Task> items=self.tasks.clone() render_item=|task: &Task| {
+///             let id = task.id;
+///             gtk! { <Button label=task.name.clone() on clicked=|_| Message::Done(id) /> }
+///         } />
+///     }
+/// }
+/// # }
+/// ```
+///
+/// The closure's argument needs a type annotation, as it's a reference. The
+/// subcomponent patches what the closure returns like any other part of its
+/// view, and any subcomponents in it belong to the parent component.
+///
+/// Like a [`Callback`][Callback], the [`Default`][Default] implementation
+/// for `Render` is empty, and renders nothing.
+///
+/// [Component]: trait.Component.html
+/// [VNode]: enum.VNode.html
+/// [Callback]: struct.Callback.html
+/// [Default]: https://doc.rust-lang.org/std/default/trait.Default.html
+// The closure's nodes have already been handed over to the parent's scope,
+// so they're kept as nodes of the null component until we know which
+// component's view they're going into.
+pub struct Render<A>(pub(crate) Option<Rc<dyn Fn(&A) -> VNode<()>>>);
+
+thread_local! {
+    // Nodes which were handed over to their parent's scope ignore the scope
+    // they're built with, but they still need one.
+    static DETACHED: Scope<()> = Scope::new("()", unbounded().0);
+}
+
+impl<A> Render<A> {
+    /// Build the view for `value`, as part of the view of the component `C`.
+    ///
+    /// If the `Render` is empty, this returns `None`.
+    pub fn render<C: 'static + Component>(&self, value: &A) -> Option<VNode<C>> {
+        let render = self.0.as_ref()?;
+        let node = render(value);
+        Some(DETACHED.with(|scope| node.retarget(scope)))
+    }
+
+    /// Test whether a `Render` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+impl<A> Default for Render<A> {
+    fn default() -> Self {
+        Render(None)
+    }
+}
+
+impl<A> Clone for Render<A> {
+    fn clone(&self) -> Self {
+        Render(self.0.clone())
+    }
+}
+
+impl<A> PartialEq for Render<A> {
+    fn eq(&self, other: &Self) -> bool {
+        if let (Some(left), Some(right)) = (&self.0, &other.0) {
+            Rc::ptr_eq(left, right)
+        } else {
+            false
+        }
+    }
+}
+
+impl<A> Debug for Render<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "Render()")
+    }
+}
@@ -0,0 +1,312 @@
+//! Navigating between the screens of an application.
+//!
+//! Applications with more than one screen usually keep a list of the
+//! screens the user came through, so they can go back. A
+//! [`Router`][Router] keeps that list for you, as routes of a type you
+//! define, usually an enum with a variant for each screen whose fields are
+//! the screen's parameters. It shows them in a [`Stack`][Stack], keeping the
+//! screens further back alive, so they're just as the user left them when
+//! they come back to them.
+//!
+//! The router lives in the component which owns the screens, which hands it
+//! the messages it's told to navigate with. Anything can then navigate
+//! using [`push()`][push], [`pop()`][pop] and friends, including the screens
+//! themselves, without knowing where the router is:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::*;
+//! # use vgtk::router::{self, Navigation, Router};
+//! # #[derive(Clone, Default)] struct Home;
+//! # impl Component for Home { type Message = (); type Properties = Self;
+//! # fn create(props: Self) -> Self { props }
+//! #     fn view(&self) -> VNode<Self> { todo!() } }
+//! # #[derive(Clone, Default)] struct Profile { user: u64 }
+//! # impl Component for Profile { type Message = (); type Properties = Self;
+//! #     fn create(props: Self) -> Self { props }
+//! #     fn view(&self) -> VNode<Self> { todo!() } }
+//! #[derive(Clone, Debug, PartialEq)]
+//! enum Screen {
+//!     Home,
+//!     Profile(u64),
+//! }
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Navigate(Navigation<Screen>),
+//! }
+//!
+//! struct App {
+//!     router: Router<Screen, App>,
+//! }
+//!
+//! impl Default for App {
+//!     fn default() -> Self {
+//!         App {
+//!             router: Router::new(Screen::Home, Message::Navigate),
+//!         }
+//!     }
+//! }
+//!
+//! impl Component for App {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Navigate(navigation) => self.router.navigate(navigation),
+//!         }
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         gtk! {
+//!             <Window>
+//!                 {
+//!                     self.router.view(|screen| match screen {
+//!                         Screen::Home => gtk! { <@Home /> },
+//!                         Screen::Profile(user) => gtk! { <@Profile user={*user} /> },
+//!                     })
+//!                 }
+//!             </Window>
+//!         }
+//!     }
+//! }
+//!
+//! // Elsewhere, like in `Home::update()`:
+//! router::push(Screen::Profile(1337));
+//! // And to go back:
+//! router::pop::<Screen>();
+//! ```
+//!
+//! There can only be one router for each type of route showing at a time.
+//! If more than one is, they're navigated in whichever one rendered last.
+//!
+//! [Router]: struct.Router.html
+//! [Stack]: ../../gtk/struct.Stack.html
+//! [push]: fn.push.html
+//! [pop]: fn.pop.html
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Error, Formatter};
+use std::rc::Rc;
+
+use gtk::{Stack, StackExt, StackTransitionType, Widget};
+
+use colored::Colorize;
+use log::{trace, warn};
+
+use crate::component::{current_scope, Component, UpdateAction};
+use crate::ext::StackExtHelpers;
+use crate::vnode::builder::child_prop;
+use crate::vnode::VNode;
+
+/// A way to move through a [`Router`][Router]'s history.
+///
+/// [Router]: struct.Router.html
+#[derive(Clone, Debug, PartialEq)]
+pub enum Navigation<R> {
+    /// Go to a new route, which the user can come back from.
+    Push(R),
+    /// Go back to the previous route.
+    Pop,
+    /// Go to a new route in place of the current one.
+    Replace(R),
+    /// Go all the way back to the first route.
+    Home,
+}
+
+/// The history of routes of type `R` shown by component `C`.
+///
+/// See the [module documentation][module] for an example.
+///
+/// [module]: index.html
+pub struct Router<R, C: Component> {
+    history: Vec<R>,
+    message: fn(Navigation<R>) -> C::Message,
+    transition: StackTransitionType,
+}
+
+impl<R: Debug, C: Component> Debug for Router<R, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_struct("Router")
+            .field("history", &self.history)
+            .finish()
+    }
+}
+
+impl<R, C> Router<R, C>
+where
+    R: 'static + Clone + Debug + PartialEq,
+    C: 'static + Component,
+{
+    /// Construct a router showing the route `home`, which is handed its
+    /// component's messages made by `message` to navigate with.
+    pub fn new(home: R, message: fn(Navigation<R>) -> C::Message) -> Self {
+        Router {
+            history: vec![home],
+            message,
+            transition: StackTransitionType::SlideLeftRight,
+        }
+    }
+
+    /// Set the transition the [`Stack`][Stack] uses to move between routes.
+    /// The default is `SlideLeftRight`.
+    ///
+    /// [Stack]: ../../gtk/struct.Stack.html
+    pub fn transition(mut self, transition: StackTransitionType) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// Get the current route.
+    pub fn current(&self) -> &R {
+        self.history.last().unwrap()
+    }
+
+    /// Get the routes the user came through to the current one, starting
+    /// with the first, and ending with the current one.
+    pub fn history(&self) -> &[R] {
+        &self.history
+    }
+
+    /// Test whether there's a route to go back to.
+    pub fn can_go_back(&self) -> bool {
+        self.history.len() > 1
+    }
+
+    /// Navigate, asking for a render if the route changed.
+    pub fn navigate(&mut self, navigation: Navigation<R>) -> UpdateAction<C> {
+        let previous = self.current().clone();
+        let depth = self.history.len();
+        match navigation {
+            Navigation::Push(route) => {
+                if route != previous {
+                    self.history.push(route);
+                }
+            }
+            Navigation::Pop => {
+                if self.can_go_back() {
+                    self.history.pop();
+                }
+            }
+            Navigation::Replace(route) => *self.history.last_mut().unwrap() = route,
+            Navigation::Home => self.history.truncate(1),
+        }
+        if *self.current() == previous && self.history.len() == depth {
+            return UpdateAction::None;
+        }
+        trace!(
+            "{} {:?} {} {:?}",
+            "Route:".magenta(),
+            previous,
+            "->".bright_black(),
+            self.current()
+        );
+        UpdateAction::Render
+    }
+
+    /// Show the routes in a [`Stack`][Stack], using `page` to turn each one
+    /// into the node for its screen, and making this the router [`push()`][push]
+    /// and friends navigate.
+    ///
+    /// Call this from the component's `view()`.
+    ///
+    /// [Stack]: ../../gtk/struct.Stack.html
+    /// [push]: fn.push.html
+    pub fn view<F>(&self, page: F) -> VNode<C>
+    where
+        F: Fn(&R) -> VNode<C>,
+    {
+        self.register();
+        let visible = (self.history.len() - 1).to_string();
+        let pages = self.history.iter().enumerate().map(|(index, route)| {
+            let mut node = page(route);
+            let name = child_prop(
+                "name",
+                |stack: &Stack, page: &Widget| stack.get_child_name(page).map(String::from),
+                |stack: &Stack, page: &Widget, name: Option<String>| {
+                    stack.set_child_name(page, name.as_deref())
+                },
+                Some(index.to_string()),
+            );
+            match &mut node {
+                VNode::Object(object) => object.child_props.push(name),
+                VNode::Component(component) => component.child_props.push(name),
+            }
+            node
+        });
+        gtk! {
+            <Stack transition_type=self.transition visible_page=visible>
+                { pages }
+            </Stack>
+        }
+    }
+
+    // Make this the router which navigates routes of type `R`.
+    fn register(&self) {
+        let scope = match current_scope::<C>() {
+            Some(scope) => scope,
+            None => return,
+        };
+        let message = self.message;
+        let navigate: Navigate<R> =
+            Box::new(move |navigation| scope.try_send(message(navigation)).is_ok());
+        ROUTERS.with(|routers| {
+            routers
+                .borrow_mut()
+                .insert(TypeId::of::<R>(), Rc::new(navigate))
+        });
+    }
+}
+
+type Navigate<R> = Box<dyn Fn(Navigation<R>) -> bool>;
+
+thread_local! {
+    // The `Navigate<R>` of the router showing each type of route.
+    static ROUTERS: RefCell<HashMap<TypeId, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Navigate the [`Router`][Router] showing routes of type `R`.
+///
+/// If there's no such router, this logs a warning and does nothing.
+///
+/// [Router]: struct.Router.html
+pub fn navigate<R: 'static + Clone + Debug>(navigation: Navigation<R>) {
+    let router = ROUTERS.with(|routers| routers.borrow().get(&TypeId::of::<R>()).cloned());
+    let delivered = match router
+        .as_ref()
+        .and_then(|router| router.downcast_ref::<Navigate<R>>())
+    {
+        Some(navigate) => navigate(navigation.clone()),
+        None => false,
+    };
+    if !delivered {
+        warn!("{} {:?}", "No router to navigate:".bright_red(), navigation);
+    }
+}
+
+/// Go to a new route, which the user can come back from.
+pub fn push<R: 'static + Clone + Debug>(route: R) {
+    navigate(Navigation::Push(route))
+}
+
+/// Go back to the previous route.
+pub fn pop<R: 'static + Clone + Debug>() {
+    navigate::<R>(Navigation::Pop)
+}
+
+/// Go to a new route in place of the current one.
+pub fn replace<R: 'static + Clone + Debug>(route: R) {
+    navigate(Navigation::Replace(route))
+}
+
+/// Go all the way back to the first route.
+pub fn home<R: 'static + Clone + Debug>() {
+    navigate::<R>(Navigation::Home)
+}
@@ -1,33 +1,50 @@
 use std::any::TypeId;
+#[cfg(feature = "profiling")]
+use std::collections::VecDeque;
 use std::fmt::{Debug, Error, Formatter};
 use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 use std::sync::{
-    atomic::{AtomicPtr, AtomicUsize, Ordering},
-    Arc,
+    atomic::{AtomicPtr, Ordering},
+    Arc, Mutex, Weak,
 };
+#[cfg(feature = "profiling")]
+use std::time::Instant;
 
 use colored::Colorize;
-use log::debug;
 
 use futures::channel::mpsc::{TrySendError, UnboundedSender};
 
-use crate::component::{Component, ComponentTask};
+use crate::component::{hold_reentrant, Component, ComponentTask};
+use crate::config::Backlog;
+
+type DropCallback = Box<dyn FnOnce() + Send>;
 
 /// A channel for sending messages to a [`Component`][Component].
 ///
 /// [Component]: trait.Component.html
 pub struct Scope<C: Component> {
     name: &'static str,
-    muted: Arc<AtomicUsize>,
-    channel: UnboundedSender<C::Message>,
+    // Shared, so that a `WeakScope` can tell when every `Scope` is gone.
+    channel: Arc<UnboundedSender<C::Message>>,
+    backlog: Arc<Backlog>,
+    // Callbacks to run once the component is torn down, or `None` once it
+    // has been.
+    on_drop: Arc<Mutex<Option<Vec<DropCallback>>>>,
+    // When each message waiting in the channel was sent.
+    #[cfg(feature = "profiling")]
+    sent: Arc<Mutex<VecDeque<Instant>>>,
 }
 
 impl<C: Component> Scope<C> {
     pub(crate) fn new(name: &'static str, channel: UnboundedSender<C::Message>) -> Self {
         Scope {
             name,
-            muted: Default::default(),
-            channel,
+            channel: Arc::new(channel),
+            backlog: Arc::new(Backlog::new(C::config())),
+            on_drop: Arc::new(Mutex::new(Some(Vec::new()))),
+            #[cfg(feature = "profiling")]
+            sent: Default::default(),
         }
     }
 }
@@ -36,8 +53,11 @@ impl<C: Component> Clone for Scope<C> {
     fn clone(&self) -> Self {
         Scope {
             name: self.name,
-            muted: self.muted.clone(),
             channel: self.channel.clone(),
+            backlog: self.backlog.clone(),
+            on_drop: self.on_drop.clone(),
+            #[cfg(feature = "profiling")]
+            sent: self.sent.clone(),
         }
     }
 }
@@ -74,48 +94,72 @@ impl<C: 'static + Component> Scope<C> {
     ) -> Scope<Child> {
         Scope {
             name,
-            muted: self.muted.clone(),
-            channel,
+            channel: Arc::new(channel),
+            backlog: Arc::new(Backlog::new(Child::config())),
+            on_drop: Arc::new(Mutex::new(Some(Vec::new()))),
+            #[cfg(feature = "profiling")]
+            sent: Default::default(),
         }
     }
 
-    pub(crate) fn is_muted(&self) -> bool {
-        self.muted.load(Ordering::SeqCst) > 0
-    }
-
-    pub(crate) fn mute(&self) {
-        self.muted.fetch_add(1, Ordering::SeqCst);
-    }
-
-    pub(crate) fn unmute(&self) {
-        self.muted.fetch_sub(1, Ordering::SeqCst);
-    }
-
     pub(crate) fn current_parent() -> Self {
         ComponentTask::<_, C>::current_parent_scope()
     }
 
+    /// Get this scope as the parent of a subcomponent.
+    pub(crate) fn as_parent(&self) -> ParentScope {
+        let scope = self.clone();
+        Rc::new(move || scope.clone().into())
+    }
+
     #[inline(always)]
     fn log(&self, message: &C::Message) {
-        debug!(
+        component_log!(
+            Debug,
+            self,
             "{} {}: {}",
-            format!(
-                "Scope::send_message{}",
-                if self.is_muted() { " [muted]" } else { "" }
-            )
-            .green(),
+            "Scope::send_message".green(),
             self.name.magenta().bold(),
             format!("{:?}", message).bright_white().bold()
         );
     }
 
+    /// Note the time a message was sent, for the profiler.
+    #[inline(always)]
+    fn sent(&self) {
+        #[cfg(feature = "profiling")]
+        self.sent.lock().unwrap().push_back(Instant::now());
+    }
+
+    /// Account for the oldest message sent having arrived, returning `false`
+    /// if it was dropped to make room for a newer one, and tell the profiler
+    /// if it's been delivered.
+    #[inline(always)]
+    pub(crate) fn delivered(&self) -> bool {
+        let keep = self.backlog.received();
+        #[cfg(feature = "profiling")]
+        {
+            if let Some(sent) = self.sent.lock().unwrap().pop_front() {
+                if keep {
+                    crate::profile::delivered(self.name, sent);
+                }
+            }
+        }
+        keep
+    }
+
     #[doc(hidden)]
     pub fn send_message(&self, message: C::Message) {
         self.log(&message);
-        if !self.is_muted() {
+        let message = match hold_reentrant(self, message) {
+            Some(message) => message,
+            None => return,
+        };
+        if self.backlog.admit(self.name) {
             self.channel
                 .unbounded_send(message)
                 .expect("channel has gone unexpectedly out of scope!");
+            self.sent();
         }
     }
 
@@ -127,7 +171,17 @@ impl<C: 'static + Component> Scope<C> {
     /// unmounted and the scope has become invalid.
     ///
     /// If the message is sent successfully, it will show up at your
-    /// component's [`Component::update()`][update] method presently.
+    /// component's [`Component::update()`][update] method presently. If the
+    /// component's message queue is full, what happens to it depends on the
+    /// overflow policy in its [`Component::config()`][config], and a message
+    /// dropped because of it doesn't count as an error.
+    ///
+    /// A message a component sends itself while it's busy, like from inside
+    /// its own [`Component::update()`][update], skips the queue: it's
+    /// delivered as soon as the component is done with the message it's
+    /// handling, ahead of any others waiting and before it renders. Several
+    /// such messages are delivered in the order they were sent, including
+    /// any sent while handling those.
     ///
     /// Never call this from inside a signal handler. It's important that you
     /// follow the usual pattern of returning messages from signal handler
@@ -135,17 +189,183 @@ impl<C: 'static + Component> Scope<C> {
     /// loops.
     ///
     /// [update]: ../trait.Component.html#method.update
+    /// [config]: ../trait.Component.html#method.config
     pub fn try_send(&self, message: C::Message) -> Result<(), TrySendError<C::Message>> {
         self.log(&message);
-        self.channel.unbounded_send(message)
+        let message = match hold_reentrant(self, message) {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        if !self.backlog.admit(self.name) {
+            return Ok(());
+        }
+        if let Err(error) = self.channel.unbounded_send(message) {
+            self.backlog.cancel();
+            return Err(error);
+        }
+        self.sent();
+        Ok(())
+    }
+
+    /// Attempt to send several messages to the component this `Scope` belongs
+    /// to, which will be delivered together in a single call to its
+    /// [`Component::update_batch()`][update_batch] method.
+    ///
+    /// This stops at the first message which fails to send, in which case
+    /// you'll receive an error, as with [`Scope::try_send()`][try_send].
+    ///
+    /// [update_batch]: ../trait.Component.html#method.update_batch
+    /// [try_send]: #method.try_send
+    pub fn send_batched<I>(&self, messages: I) -> Result<(), TrySendError<C::Message>>
+    where
+        I: IntoIterator<Item = C::Message>,
+    {
+        for message in messages {
+            self.try_send(message)?;
+        }
+        Ok(())
+    }
+
+    /// Run `callback` once the component this `Scope` belongs to has been
+    /// torn down for good.
+    ///
+    /// This happens after the component's widget tree has been removed, all
+    /// of its subcomponents have been torn down, and its
+    /// [`Component::destroyed()`][destroyed] method has been called, so
+    /// it's a good way for something the component started, like a
+    /// background thread holding on to a socket, to learn that it should
+    /// let go. Callbacks run in the order they were added. If the component
+    /// is already gone, `callback` runs straight away.
+    ///
+    /// [destroyed]: ../trait.Component.html#method.destroyed
+    pub fn on_drop<F>(&self, callback: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut on_drop = self.on_drop.lock().unwrap();
+        match *on_drop {
+            Some(ref mut callbacks) => callbacks.push(Box::new(callback)),
+            None => {
+                drop(on_drop);
+                callback();
+            }
+        }
+    }
+
+    /// Run the `on_drop()` callbacks, now the component's been torn down.
+    pub(crate) fn dropped(&self) {
+        let callbacks = self.on_drop.lock().unwrap().take();
+        for callback in callbacks.into_iter().flatten() {
+            callback();
+        }
     }
 
     /// Get the name of the component this `Scope` belongs to.
     pub fn name(&self) -> &'static str {
-        &self.name
+        self.name
+    }
+
+    /// Get a [`WeakScope`][WeakScope] for the component this `Scope`
+    /// belongs to.
+    ///
+    /// A `Scope` keeps the component's message channel open for as long as
+    /// it's around, so one held by something which outlives the component,
+    /// like a future waiting on a slow network request, or a background
+    /// thread, keeps the channel alive long after the component has gone,
+    /// only to find it can't deliver its message in the end. A
+    /// `WeakScope` doesn't, and sending to one once the component has gone
+    /// just hands the message back.
+    ///
+    /// [WeakScope]: struct.WeakScope.html
+    pub fn downgrade(&self) -> WeakScope<C> {
+        WeakScope {
+            name: self.name,
+            channel: Arc::downgrade(&self.channel),
+            backlog: self.backlog.clone(),
+            on_drop: self.on_drop.clone(),
+            #[cfg(feature = "profiling")]
+            sent: self.sent.clone(),
+        }
+    }
+}
+
+/// A [`Scope`][Scope] which doesn't keep its component's message channel
+/// open.
+///
+/// Get one with [`Scope::downgrade()`][downgrade].
+///
+/// [Scope]: struct.Scope.html
+/// [downgrade]: struct.Scope.html#method.downgrade
+pub struct WeakScope<C: Component> {
+    name: &'static str,
+    channel: Weak<UnboundedSender<C::Message>>,
+    backlog: Arc<Backlog>,
+    on_drop: Arc<Mutex<Option<Vec<DropCallback>>>>,
+    #[cfg(feature = "profiling")]
+    sent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl<C: Component> Clone for WeakScope<C> {
+    fn clone(&self) -> Self {
+        WeakScope {
+            name: self.name,
+            channel: self.channel.clone(),
+            backlog: self.backlog.clone(),
+            on_drop: self.on_drop.clone(),
+            #[cfg(feature = "profiling")]
+            sent: self.sent.clone(),
+        }
+    }
+}
+
+impl<C: Component> Debug for WeakScope<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "WeakScope[{}]", self.name)
     }
 }
 
+impl<C: 'static + Component> WeakScope<C> {
+    /// Get a [`Scope`][Scope] for the component, if it's still running.
+    ///
+    /// [Scope]: struct.Scope.html
+    pub fn upgrade(&self) -> Option<Scope<C>> {
+        let channel = self.channel.upgrade()?;
+        if channel.is_closed() || self.on_drop.lock().unwrap().is_none() {
+            return None;
+        }
+        Some(Scope {
+            name: self.name,
+            channel,
+            backlog: self.backlog.clone(),
+            on_drop: self.on_drop.clone(),
+            #[cfg(feature = "profiling")]
+            sent: self.sent.clone(),
+        })
+    }
+
+    /// Send a message to the component, if it's still running, or hand it
+    /// back if it isn't.
+    ///
+    /// Otherwise, this works like [`Scope::try_send()`][try_send].
+    ///
+    /// [try_send]: struct.Scope.html#method.try_send
+    pub fn send_message(&self, message: C::Message) -> Result<(), C::Message> {
+        match self.upgrade() {
+            Some(scope) => scope.try_send(message).map_err(TrySendError::into_inner),
+            None => Err(message),
+        }
+    }
+
+    /// Get the name of the component this `WeakScope` belongs to.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// The scope of a subcomponent's parent, which can be handed to the
+/// subcomponent's task when it moves to another parent.
+pub(crate) type ParentScope = Rc<dyn Fn() -> AnyScope>;
+
 pub(crate) struct AnyScope {
     type_id: TypeId,
     ptr: AtomicPtr<()>,
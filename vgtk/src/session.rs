@@ -0,0 +1,277 @@
+//! Reopening the app where the user left off.
+//!
+//! Call [`enable()`][enable] before you start your app, and implement
+//! [`Component::save_state()`][save_state] and
+//! [`Component::restore_state()`][restore_state] for the components whose
+//! state should survive a restart:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::lib::gtk::Box;
+//! #[derive(Default)]
+//! struct Browser {
+//!     location: String,
+//! }
+//!
+//! impl Component for Browser {
+//! #   type Message = (); type Properties = ();
+//! #   fn create(_props: ()) -> Self { Self::default() }
+//!     // ...
+//!     fn save_state(&self) -> Option<String> {
+//!         Some(self.location.clone())
+//!     }
+//!
+//!     fn restore_state(&mut self, state: &str) {
+//!         self.location = state.to_string();
+//!     }
+//! #   fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! }
+//!
+//! vgtk::session::enable();
+//! # type App = Browser;
+//! std::process::exit(vgtk::run::<App>());
+//! ```
+//!
+//! A component's state is saved every time it updates, and written to disk
+//! when the app quits. When the app starts again, each component is given
+//! the state it saved last time, right after it's created and before it
+//! renders its first view. The state is a string, so serialise it however
+//! you like, eg. with `serde_json`.
+//!
+//! Components are told apart by their type and the order in which they were
+//! created, which means they get their own state back as long as the app
+//! builds the same components in the same order when it starts, as it
+//! usually will. For state which doesn't belong to a component, or needs a
+//! stable name, use [`get()`][get] and [`set()`][set].
+//!
+//! [enable]: fn.enable.html
+//! [save_state]: ../trait.Component.html#method.save_state
+//! [restore_state]: ../trait.Component.html#method.restore_state
+//! [get]: fn.get.html
+//! [set]: fn.set.html
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use glib::KeyFile;
+
+use colored::Colorize;
+use log::{debug, warn};
+
+const COMPONENTS: &str = "components";
+const VALUES: &str = "values";
+
+#[derive(Default)]
+struct Session {
+    enabled: bool,
+    path: Option<PathBuf>,
+    loaded: bool,
+    components: HashMap<String, String>,
+    values: HashMap<String, String>,
+    // How many of each type of component have been created so far.
+    instances: HashMap<&'static str, usize>,
+}
+
+thread_local! {
+    static SESSION: RefCell<Session> = RefCell::new(Default::default());
+}
+
+/// Where the session is stored if you don't say otherwise: a file named
+/// `session.ini` in a directory named after the program, in the user's data
+/// directory.
+fn default_path() -> Option<PathBuf> {
+    let program = glib::get_prgname()?;
+    Some(glib::get_user_data_dir()?.join(program).join("session.ini"))
+}
+
+fn read_group(file: &KeyFile, group: &str) -> HashMap<String, String> {
+    let keys = match file.get_keys(group) {
+        Ok((keys, _)) => keys,
+        Err(_) => return HashMap::new(),
+    };
+    keys.into_iter()
+        .filter_map(|key| {
+            let value = file.get_string(group, &key).ok()?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Read the saved session, the first time we need it.
+fn load(session: &mut Session) {
+    if session.loaded || !session.enabled {
+        return;
+    }
+    session.loaded = true;
+    if session.path.is_none() {
+        session.path = default_path();
+    }
+    let path = match &session.path {
+        Some(path) if path.exists() => path,
+        _ => return,
+    };
+    let file = KeyFile::new();
+    match file.load_from_file(path, glib::KeyFileFlags::NONE) {
+        Ok(_) => {
+            debug!(
+                "{} {}",
+                "Restoring session from".bright_blue(),
+                path.display().to_string().bright_white().bold()
+            );
+            session.components = read_group(&file, COMPONENTS);
+            session.values = read_group(&file, VALUES);
+        }
+        Err(error) => warn!(
+            "{} {}: {}",
+            "Unable to read session from".bright_red(),
+            path.display().to_string().bright_white().bold(),
+            error
+        ),
+    }
+}
+
+/// Save the state of components and restore it when the app starts again,
+/// using a file in the user's data directory.
+pub fn enable() {
+    SESSION.with(|session| session.borrow_mut().enabled = true);
+}
+
+/// Like [`enable()`][enable], but store the session in the given file.
+///
+/// [enable]: fn.enable.html
+pub fn enable_at<P: AsRef<Path>>(path: P) {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        session.enabled = true;
+        session.path = Some(path.as_ref().to_path_buf());
+    });
+}
+
+/// Test whether sessions have been enabled.
+pub fn is_enabled() -> bool {
+    SESSION.with(|session| session.borrow().enabled)
+}
+
+/// Get a value stored in the session with [`set()`][set].
+///
+/// [set]: fn.set.html
+pub fn get(key: &str) -> Option<String> {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        load(&mut session);
+        session.values.get(key).cloned()
+    })
+}
+
+/// Store a value in the session, to get back with [`get()`][get] the next
+/// time the app starts.
+///
+/// Unless sessions have been enabled, the value is forgotten when the app
+/// quits.
+///
+/// [get]: fn.get.html
+pub fn set(key: &str, value: &str) {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        load(&mut session);
+        session.values.insert(key.to_string(), value.to_string());
+    })
+}
+
+/// Remove a value stored in the session with [`set()`][set].
+///
+/// [set]: fn.set.html
+pub fn remove(key: &str) {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        load(&mut session);
+        session.values.remove(key);
+    })
+}
+
+/// Write the session to disk now, rather than waiting for the app to quit.
+///
+/// This does nothing if sessions haven't been enabled.
+pub fn save() -> Result<(), glib::Error> {
+    let file = KeyFile::new();
+    let path = SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        if !session.enabled {
+            return None;
+        }
+        load(&mut session);
+        for (key, value) in &session.components {
+            file.set_string(COMPONENTS, key, value);
+        }
+        for (key, value) in &session.values {
+            file.set_string(VALUES, key, value);
+        }
+        session.path.clone()
+    });
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    file.save_to_file(&path)
+}
+
+/// Forget everything in the session, including what's been saved to disk,
+/// so the app starts from scratch next time.
+pub fn clear() {
+    let path = SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        load(&mut session);
+        session.components.clear();
+        session.values.clear();
+        session.path.clone()
+    });
+    if let Some(path) = path {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Save the session when the app quits.
+pub(crate) fn save_on_quit() {
+    if let Err(error) = save() {
+        warn!("{} {}", "Unable to save session:".bright_red(), error);
+    }
+}
+
+/// Get the key to store the state of a newly created component of the given
+/// type under, if sessions are enabled.
+pub(crate) fn component_key(type_name: &'static str) -> Option<String> {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        if !session.enabled {
+            return None;
+        }
+        let instance = session.instances.entry(type_name).or_insert(0);
+        let key = format!("{}#{}", type_name, instance);
+        *instance += 1;
+        Some(key)
+    })
+}
+
+/// Get the state saved for a component last time.
+pub(crate) fn component_state(key: &str) -> Option<String> {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        load(&mut session);
+        session.components.get(key).cloned()
+    })
+}
+
+/// Remember a component's current state.
+pub(crate) fn store_component_state(key: &str, state: Option<String>) {
+    SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        match state {
+            Some(state) => session.components.insert(key.to_string(), state),
+            None => session.components.remove(key),
+        };
+    })
+}
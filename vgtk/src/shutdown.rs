@@ -0,0 +1,56 @@
+//! Asking the top level component before the application quits.
+
+use std::cell::{Cell, RefCell};
+
+use gio::prelude::*;
+use gtk::{Application, GtkApplicationExt, GtkWindowExt, Inhibit, WidgetExt};
+
+use colored::Colorize;
+use log::debug;
+
+thread_local! {
+    // Sends the top level component the question.
+    static REQUEST: RefCell<Option<Box<dyn Fn()>>> = const { RefCell::new(None) };
+    // Whether the answer was yes, so there's no need to ask again.
+    static QUITTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Make `ask` the way to ask whether `app` should quit, and ask whenever
+/// the user closes the last of `app`'s windows.
+pub(crate) fn install(app: &Application, ask: impl Fn() + 'static) {
+    REQUEST.with(|request| *request.borrow_mut() = Some(Box::new(ask)));
+    app.connect_window_added(|_, window| {
+        // This runs after any `delete_event` handler the view gave the
+        // window, which can still keep it open on its own terms.
+        window.connect_delete_event(|window, _| {
+            let last = window
+                .get_application()
+                .is_some_and(|app| app.get_windows().len() == 1);
+            Inhibit(last && request())
+        });
+    });
+}
+
+/// Ask the top level component whether to quit, returning `false` if
+/// there's nobody to ask, or it's already said yes.
+pub(crate) fn request() -> bool {
+    if QUITTING.with(Cell::get) {
+        return false;
+    }
+    REQUEST.with(|request| match *request.borrow() {
+        Some(ref request) => {
+            debug!("{}", "Asking whether to quit.".bright_blue());
+            request();
+            true
+        }
+        None => false,
+    })
+}
+
+/// Quit the application, without asking.
+pub(crate) fn quit_now() {
+    QUITTING.with(|quitting| quitting.set(true));
+    if let Some(app) = gio::Application::get_default() {
+        app.quit();
+    }
+}
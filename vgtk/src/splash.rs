@@ -0,0 +1,232 @@
+//! Splash screens for applications which take a while to start.
+//!
+//! An application which has to load a lot before its main window is any
+//! use, like opening a database or fetching an account's details, can show
+//! a small splash window straight away while it does, instead of nothing at
+//! all. Return a [`Splash`][Splash] from the top level component's
+//! [`Component::splash()`][splash] to show one as soon as the application
+//! starts. The component's view is only built once the splash window is on
+//! screen, and its windows are held back until the component calls
+//! [`splash::done()`][done], usually once the work it started in
+//! [`Component::init()`][init] has finished, at which point they replace
+//! the splash window:
+//!
+//! ```rust,no_run
+//! # use vgtk::{ext::*, gtk, Component, UpdateAction, VNode};
+//! # use vgtk::splash::{self, Splash};
+//! # use vgtk::lib::gio::ApplicationFlags;
+//! # use vgtk::lib::gtk::*;
+//! # #[derive(Debug)] struct Library;
+//! # async fn open_library() -> Library { Library }
+//! #[derive(Debug)]
+//! enum Message {
+//!     Loaded(Library),
+//! }
+//!
+//! #[derive(Default)]
+//! struct App {
+//!     library: Option<Library>,
+//! }
+//!
+//! impl Component for App {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn splash() -> Option<Splash> {
+//!         let splash = Splash::new("Music").icon_name("multimedia-player");
+//!         Some(splash.text("Opening your library…"))
+//!     }
+//!
+//!     fn init(&mut self) -> UpdateAction<Self> {
+//!         UpdateAction::defer(async { Message::Loaded(open_library().await) })
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Loaded(library) => {
+//!                 self.library = Some(library);
+//!                 splash::done();
+//!                 UpdateAction::Render
+//!             }
+//!         }
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         gtk! {
+//!             <Application::new_unwrap(None, ApplicationFlags::empty())>
+//!                 <Window title="Music" />
+//!             </Application>
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Only the windows the view has when it's first built are held back.
+//!
+//! [Splash]: struct.Splash.html
+//! [splash]: ../trait.Component.html#method.splash
+//! [init]: ../trait.Component.html#method.init
+//! [done]: fn.done.html
+
+use std::cell::RefCell;
+
+use gdk::WindowTypeHint;
+use gtk::prelude::*;
+use gtk::{
+    Application, Box as GtkBox, IconSize, Image, Label, Orientation, Spinner, Window,
+    WindowPosition, WindowType,
+};
+
+use colored::Colorize;
+use log::debug;
+
+/// A splash window to show while the application starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Splash {
+    /// The application's name, shown in large print.
+    pub title: String,
+    /// The name of an icon to show above the title.
+    pub icon_name: Option<String>,
+    /// Some text to show below the title, like what the application is
+    /// busy doing.
+    pub text: Option<String>,
+}
+
+impl Splash {
+    /// Construct a splash window with a title and nothing else.
+    pub fn new<S: Into<String>>(title: S) -> Self {
+        Splash {
+            title: title.into(),
+            icon_name: None,
+            text: None,
+        }
+    }
+
+    /// Show the named icon above the title.
+    pub fn icon_name<S: Into<String>>(mut self, icon_name: S) -> Self {
+        self.icon_name = Some(icon_name.into());
+        self
+    }
+
+    /// Show some text below the title.
+    pub fn text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    fn build(&self) -> Window {
+        let window = Window::new(WindowType::Toplevel);
+        window.set_title(&self.title);
+        window.set_type_hint(WindowTypeHint::Splashscreen);
+        window.set_decorated(false);
+        window.set_resizable(false);
+        window.set_position(WindowPosition::Center);
+        window.get_style_context().add_class("vgtk-splash");
+
+        let content = GtkBox::new(Orientation::Vertical, 12);
+        content.set_border_width(32);
+        if let Some(icon_name) = &self.icon_name {
+            let icon = Image::from_icon_name(Some(icon_name), IconSize::Dialog);
+            icon.set_pixel_size(128);
+            content.add(&icon);
+        }
+        let title = Label::new(None);
+        title.set_markup(&format!(
+            "<span size=\"xx-large\" weight=\"bold\">{}</span>",
+            glib::markup_escape_text(&self.title)
+        ));
+        content.add(&title);
+        if let Some(text) = &self.text {
+            let label = Label::new(Some(text));
+            label.get_style_context().add_class("dim-label");
+            content.add(&label);
+        }
+        let spinner = Spinner::new();
+        spinner.start();
+        content.add(&spinner);
+        window.add(&content);
+        window
+    }
+}
+
+struct Showing {
+    window: Window,
+    held: Vec<Window>,
+    built: bool,
+    done: bool,
+}
+
+thread_local! {
+    static SHOWING: RefCell<Option<Showing>> = const { RefCell::new(None) };
+}
+
+/// Show the splash window in `app`.
+pub(crate) fn show(app: &Application, splash: Splash) {
+    let window = splash.build();
+    app.add_window(&window);
+    window.show_all();
+    SHOWING.with(|showing| {
+        *showing.borrow_mut() = Some(Showing {
+            window,
+            held: Vec::new(),
+            built: false,
+            done: false,
+        })
+    });
+}
+
+/// Hide the windows the top level component's view has just built, until
+/// the splash window is done, unless it already is.
+pub(crate) fn hold_back(app: &Application) {
+    let finished = SHOWING.with(|showing| match &mut *showing.borrow_mut() {
+        Some(showing) => {
+            showing.built = true;
+            for window in app.get_windows() {
+                if window != showing.window {
+                    window.hide();
+                    showing.held.push(window);
+                }
+            }
+            showing.done
+        }
+        None => false,
+    });
+    if finished {
+        done();
+    }
+}
+
+/// Close the splash window, and show the application's windows in its
+/// place.
+///
+/// If the application's view hasn't been built yet, this happens as soon as
+/// it has. If there's no splash window, this does nothing.
+pub fn done() {
+    let showing = SHOWING.with(|showing| {
+        let mut showing = showing.borrow_mut();
+        match &mut *showing {
+            // The view hasn't been built yet.
+            Some(state) if !state.built => {
+                state.done = true;
+                None
+            }
+            _ => showing.take(),
+        }
+    });
+    if let Some(showing) = showing {
+        debug!("{}", "Splash screen done.".bright_blue());
+        // Show the new windows before the splash window goes, so the
+        // application always has a window and doesn't quit.
+        for window in &showing.held {
+            window.present();
+        }
+        #[allow(unsafe_code)]
+        unsafe {
+            showing.window.destroy();
+        }
+    }
+}
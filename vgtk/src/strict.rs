@@ -0,0 +1,161 @@
+//! Strict mode, for catching components which bend the rules.
+//!
+//! A component's `view()` is supposed to be a pure function of its state,
+//! and only `update()` and `change()` are supposed to change that state.
+//! Rust won't let `view()` change a component through `&self`, unless the
+//! component has a `Cell` or a `RefCell` in it, in which case nothing stops
+//! it, and the component ends up showing something other than what its
+//! state says.
+//!
+//! Strict mode checks for this. Turn it on for a component with
+//! [`enable()`][enable], and in debug builds, its state is hashed before and
+//! after every call to `view()`, and a warning is logged if it changed. The
+//! component has to implement [`Hash`][Hash] for this:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::lib::gtk::Box;
+//! #[derive(Default, Hash)]
+//! struct App {
+//!     items: Vec<String>,
+//! }
+//! # impl Component for App {
+//! #   type Message = (); type Properties = ();
+//! #   fn create(_props: ()) -> Self { Self::default() }
+//! #   fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! # }
+//!
+//! vgtk::strict::enable::<App>();
+//! std::process::exit(vgtk::run::<App>());
+//! ```
+//!
+//! Strict mode also warns about wasted renders, where a component asked to
+//! render and the new view turned out to be the same as the old one, so the
+//! patch changed nothing. That's harmless, but it means either `update()` is
+//! asking for renders it doesn't need, or the component could use
+//! [`Component::should_render()`][should_render] to skip them. To look for
+//! wasted renders in every component, not just the ones you've enabled strict
+//! mode for, use [`warn_wasted_renders()`][warn_wasted_renders].
+//!
+//! Subcomponents are given their properties again whenever their parent
+//! renders, so they don't count as a change, but widgets whose properties
+//! changed do.
+//!
+//! Release builds don't check anything.
+//!
+//! [enable]: fn.enable.html
+//! [warn_wasted_renders]: fn.warn_wasted_renders.html
+//! [Hash]: https://doc.rust-lang.org/std/hash/trait.Hash.html
+//! [should_render]: ../trait.Component.html#method.should_render
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use colored::Colorize;
+
+use crate::debug::{Change, NodeDiff, PatchDiff};
+use crate::{Component, Scope};
+
+type StateHasher = fn(&dyn Any) -> u64;
+
+thread_local! {
+    static ENABLED: RefCell<HashMap<TypeId, StateHasher>> = RefCell::new(HashMap::new());
+    static WASTED_RENDERS: Cell<bool> = const { Cell::new(false) };
+}
+
+fn hash_state<C: 'static + Hash>(state: &dyn Any) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state
+        .downcast_ref::<C>()
+        .expect("strict mode hasher given the wrong type of component")
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Turn on strict mode for every component of type `C`.
+pub fn enable<C: 'static + Component + Hash>() {
+    ENABLED.with(|enabled| {
+        enabled
+            .borrow_mut()
+            .insert(TypeId::of::<C>(), hash_state::<C>)
+    });
+}
+
+/// Turn strict mode off again for components of type `C`.
+pub fn disable<C: 'static + Component>() {
+    ENABLED.with(|enabled| enabled.borrow_mut().remove(&TypeId::of::<C>()));
+}
+
+/// Warn about wasted renders in every component, whether strict mode is
+/// enabled for it or not.
+pub fn warn_wasted_renders(enabled: bool) {
+    WASTED_RENDERS.with(|wasted| wasted.set(enabled));
+}
+
+/// Test whether a patch left every widget as it was.
+fn changed_nothing(node: &NodeDiff) -> bool {
+    node.change == Change::Patched
+        && node.properties.is_empty()
+        && node.children.iter().all(changed_nothing)
+}
+
+/// The checks to make on a component's render.
+pub(crate) struct Check {
+    // The component's hasher, and the hash of its state before `view()`.
+    state: Option<(StateHasher, u64)>,
+}
+
+impl Check {
+    /// Get ready to check a component's render, if there's anything to
+    /// check.
+    pub(crate) fn before_view<C: 'static + Component>(component: &C) -> Option<Self> {
+        if !cfg!(debug_assertions) {
+            return None;
+        }
+        let hasher = ENABLED.with(|enabled| enabled.borrow().get(&TypeId::of::<C>()).copied());
+        let state = hasher.map(|hasher| (hasher, hasher(component)));
+        if state.is_none() && !WASTED_RENDERS.with(Cell::get) {
+            return None;
+        }
+        Some(Check { state })
+    }
+
+    /// Check whether `view()` changed the component's state.
+    pub(crate) fn check_view<C: 'static + Component>(&self, scope: &Scope<C>, component: &C) {
+        if let Some((hasher, before)) = self.state {
+            if hasher(component) != before {
+                component_log!(
+                    Warn,
+                    scope,
+                    "{} {} {}",
+                    "Strict mode:".bright_red(),
+                    scope.name().magenta().bold(),
+                    "changed its own state in view()".bright_red()
+                );
+            }
+        }
+    }
+
+    /// Check whether the render was worth doing. Renders forced on every
+    /// component, like when the zoom changes, don't count.
+    pub(crate) fn check_patch<C: 'static + Component>(
+        &self,
+        scope: &Scope<C>,
+        diff: &PatchDiff,
+        forced: bool,
+    ) {
+        if !forced && changed_nothing(&diff.root) {
+            component_log!(
+                Warn,
+                scope,
+                "{} {} {}",
+                "Strict mode:".bright_red(),
+                scope.name().magenta().bold(),
+                "rendered a view identical to the last one".bright_red()
+            );
+        }
+    }
+}
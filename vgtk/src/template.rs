@@ -0,0 +1,757 @@
+//! Views loaded from data files at runtime.
+//!
+//! With the `template` feature enabled, a view can be described in a data
+//! file instead of with the [`gtk!`][gtk!] macro, and loaded while the app
+//! is running, which is handy for data driven UIs, themes which change a
+//! view's layout, or views provided by plugins.
+//!
+//! A template is a tree of widgets, each of which is an object like this:
+//!
+//! ```json
+//! {
+//!     "widget": "Box",
+//!     "properties": { "orientation": "vertical", "spacing": 6 },
+//!     "children": [
+//!         {
+//!             "widget": "Label",
+//!             "properties": { "label": { "bind": "greeting" } },
+//!             "child_properties": { "expand": true }
+//!         },
+//!         {
+//!             "widget": "Button",
+//!             "properties": { "label": "Say hello" },
+//!             "on": { "clicked": "hello" }
+//!         },
+//!         { "slot": "items" }
+//!     ]
+//! }
+//! ```
+//!
+//! * `widget` is the name of a GTK class, like `GtkBox`, with or without the
+//!   `Gtk` prefix.
+//! * `properties` sets GObject properties by name. Enums and flags are given
+//!   by their nicknames, like `"vertical"` or `"top|bottom"`.
+//! * `child_properties` sets the widget's properties in its parent:
+//!   `expand`, `fill`, `padding` and `pack-type` in a `Box`, and `left`,
+//!   `top`, `width` and `height` in a `Grid`.
+//! * `on` maps signal names to the names of messages to send. For a signal
+//!   which needs a return value, like `delete-event`, give it as
+//!   `{ "message": "quit", "return": true }`. Rendering fails if the widget
+//!   has no such signal, or the handler doesn't give it the return value it
+//!   needs.
+//! * `key` identifies the widget among its siblings, like the `key`
+//!   attribute in the `gtk!` macro.
+//! * `children` is a list of widgets, in which `{ "slot": "name" }` stands
+//!   for nodes the app fills in when it renders the template.
+//!
+//! A property's value can be `{ "bind": "name" }` instead of a literal, for
+//! the app to fill in. The app gives the values, messages and slots the
+//! template refers to by name as [`Bindings`][Bindings] when it renders it
+//! with [`render()`][Template::render]:
+//!
+//! ```rust,no_run
+//! # use vgtk::{Component, VNode};
+//! # use vgtk::template::{Bindings, Template};
+//! # #[derive(Clone, Debug)] enum Message { Hello }
+//! # #[derive(Default)] struct Model { greeting: String, items: Vec<String>, template: Option<Template> }
+//! # impl Component for Model { type Message = Message; type Properties = ();
+//! # fn create(_props: ()) -> Self { Self::default() }
+//! fn view(&self) -> VNode<Model> {
+//!     let bindings = Bindings::new()
+//!         .value("greeting", &self.greeting)
+//!         .on("hello", |_| Message::Hello)
+//!         .slot(
+//!             "items",
+//!             self.items.iter().map(|item| {
+//!                 VNode::widget::<vgtk::lib::gtk::Label>()
+//!                     .prop("label", item)
+//!                     .build()
+//!             }),
+//!         );
+//!     let template = self.template.as_ref().unwrap();
+//!     template
+//!         .render(bindings)
+//!         .unwrap_or_else(|error| panic!("bad template: {}", error))
+//! }
+//! # }
+//! ```
+//!
+//! Templates are read as JSON by [`Template::load()`][Template::load] and
+//! [`Template::from_json()`][Template::from_json]. [`Template`][Template]
+//! implements `serde`'s `Deserialize`, so other formats work too, through
+//! their own `serde` crates:
+//!
+//! ```rust,ignore
+//! let template: Template = ron::from_str(&source)?;
+//! let template: Template = serde_yaml::from_str(&source)?;
+//! ```
+//!
+//! Mistakes in the structure of a template are found when it's loaded, and
+//! unknown widgets, properties and bindings when it's rendered. Either way,
+//! the [`Error`][Error] says where in the template the problem is.
+//!
+//! Like in the `gtk!` macro, a signal handler is connected when its widget
+//! is built, and keeps the message binding it was first rendered with.
+//!
+//! [gtk!]: ../macro.gtk.html
+//! [Bindings]: struct.Bindings.html
+//! [Template]: struct.Template.html
+//! [Template::render]: struct.Template.html#method.render
+//! [Template::load]: struct.Template.html#method.load
+//! [Template::from_json]: struct.Template.html#method.from_json
+//! [Error]: struct.Error.html
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::rc::Rc;
+
+use glib::object::{IsClassFor, ObjectClass};
+use glib::translate::{from_glib, ToGlib, ToGlibPtr};
+use glib::{gobject_sys, EnumClass, FlagsClass, Object, ObjectExt, ToValue, Type, Value};
+use gtk::prelude::*;
+use gtk::{Box as GtkBox, Grid, PackType, Widget};
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde_json::{Map, Value as Json};
+
+use crate::ext::GridExtHelpers;
+use crate::properties;
+use crate::vnode::builder::child_prop;
+use crate::vnode::{key, VHandler, VNode, VObject, VProperty};
+use crate::Component;
+
+/// A problem with a template, found when loading or rendering it.
+#[derive(Debug, Clone)]
+pub struct Error {
+    /// Where in the template the problem is, like `children[1].properties.label`.
+    pub path: String,
+    /// What the problem is.
+    pub message: String,
+}
+
+impl Error {
+    fn new(path: &str, message: impl Into<String>) -> Self {
+        Error {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            self.message.fmt(f)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A view description loaded from a data file.
+///
+/// See the [module documentation][template] for the format.
+///
+/// [template]: index.html
+#[derive(Debug, Clone)]
+pub struct Template {
+    root: Node,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    widget: String,
+    key: Option<String>,
+    properties: Vec<(String, Source)>,
+    child_properties: Vec<(String, Source)>,
+    handlers: Vec<(String, Handler)>,
+    children: Vec<Child>,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Literal(Json),
+    Bound(String),
+}
+
+#[derive(Debug, Clone)]
+struct Handler {
+    message: String,
+    ret: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+enum Child {
+    Node(Node),
+    Slot(String),
+}
+
+impl Template {
+    /// Read a template from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| Error::new("", format!("can't read {}: {}", path.display(), error)))?;
+        Self::from_json(&source)
+    }
+
+    /// Read a template from a string of JSON.
+    pub fn from_json(source: &str) -> Result<Self, Error> {
+        let value =
+            serde_json::from_str(source).map_err(|error| Error::new("", error.to_string()))?;
+        Self::from_value(value)
+    }
+
+    /// Read a template from a JSON value.
+    pub fn from_value(value: Json) -> Result<Self, Error> {
+        Ok(Template {
+            root: Node::parse(&value, "")?,
+        })
+    }
+
+    /// Build the template's view, filling in its bindings.
+    pub fn render<Model: 'static + Component>(
+        &self,
+        mut bindings: Bindings<Model>,
+    ) -> Result<VNode<Model>, Error> {
+        let resolver = gtk::Builder::new();
+        self.root.render(&resolver, &mut bindings, "")
+    }
+}
+
+impl<'de> Deserialize<'de> for Template {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Json::deserialize(deserializer)?;
+        Template::from_value(value).map_err(D::Error::custom)
+    }
+}
+
+/// The values, messages and child nodes a [`Template`][Template] refers to
+/// by name.
+///
+/// [Template]: struct.Template.html
+pub struct Bindings<Model: Component> {
+    values: HashMap<String, Value>,
+    messages: HashMap<String, Rc<dyn Fn(&Object) -> Model::Message>>,
+    slots: HashMap<String, Vec<VNode<Model>>>,
+}
+
+impl<Model: Component> Default for Bindings<Model> {
+    fn default() -> Self {
+        Bindings {
+            values: HashMap::new(),
+            messages: HashMap::new(),
+            slots: HashMap::new(),
+        }
+    }
+}
+
+impl<Model: Component> Bindings<Model> {
+    /// Make an empty set of bindings.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Give the value for properties bound to `name`.
+    pub fn value<V: ToValue + ?Sized>(mut self, name: &str, value: &V) -> Self {
+        self.values.insert(name.to_string(), value.to_value());
+        self
+    }
+
+    /// Give the message for signals bound to `name`, as a function of the
+    /// widget which emitted the signal.
+    pub fn on(mut self, name: &str, message: impl Fn(&Object) -> Model::Message + 'static) -> Self {
+        self.messages.insert(name.to_string(), Rc::new(message));
+        self
+    }
+
+    /// Give the nodes for the slot called `name`.
+    pub fn slot(mut self, name: &str, nodes: impl IntoIterator<Item = VNode<Model>>) -> Self {
+        self.slots
+            .insert(name.to_string(), nodes.into_iter().collect());
+        self
+    }
+}
+
+fn join(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else if field.starts_with('[') {
+        format!("{}{}", path, field)
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn object<'a>(value: &'a Json, path: &str) -> Result<&'a Map<String, Json>, Error> {
+    value
+        .as_object()
+        .ok_or_else(|| Error::new(path, "expected an object"))
+}
+
+fn string<'a>(value: &'a Json, path: &str) -> Result<&'a str, Error> {
+    value
+        .as_str()
+        .ok_or_else(|| Error::new(path, "expected a string"))
+}
+
+impl Node {
+    fn parse(value: &Json, path: &str) -> Result<Self, Error> {
+        let fields = object(value, path)?;
+        let mut node = Node {
+            widget: String::new(),
+            key: None,
+            properties: Vec::new(),
+            child_properties: Vec::new(),
+            handlers: Vec::new(),
+            children: Vec::new(),
+        };
+        for (field, value) in fields {
+            let path = join(path, field);
+            match field.as_str() {
+                "widget" => node.widget = string(value, &path)?.to_string(),
+                "key" => node.key = Some(string(value, &path)?.to_string()),
+                "properties" => node.properties = Self::parse_properties(value, &path)?,
+                "child_properties" => node.child_properties = Self::parse_properties(value, &path)?,
+                "on" => {
+                    for (signal, value) in object(value, &path)? {
+                        let path = join(&path, signal);
+                        node.handlers
+                            .push((signal.clone(), Handler::parse(value, &path)?));
+                    }
+                }
+                "children" => {
+                    let children = value
+                        .as_array()
+                        .ok_or_else(|| Error::new(&path, "expected a list"))?;
+                    for (index, child) in children.iter().enumerate() {
+                        let path = join(&path, &format!("[{}]", index));
+                        node.children.push(Child::parse(child, &path)?);
+                    }
+                }
+                _ => return Err(Error::new(&path, format!("unknown field `{}`", field))),
+            }
+        }
+        if node.widget.is_empty() {
+            return Err(Error::new(path, "missing field `widget`"));
+        }
+        Ok(node)
+    }
+
+    fn parse_properties(value: &Json, path: &str) -> Result<Vec<(String, Source)>, Error> {
+        object(value, path)?
+            .iter()
+            .map(|(name, value)| {
+                let source = match value.as_object() {
+                    Some(fields) if fields.contains_key("bind") => {
+                        let path = join(path, name);
+                        if fields.len() > 1 {
+                            return Err(Error::new(
+                                &path,
+                                "a binding can only have a `bind` field",
+                            ));
+                        }
+                        Source::Bound(string(&fields["bind"], &join(&path, "bind"))?.to_string())
+                    }
+                    _ => Source::Literal(value.clone()),
+                };
+                Ok((name.clone(), source))
+            })
+            .collect()
+    }
+
+    fn render<Model: 'static + Component>(
+        &self,
+        resolver: &gtk::Builder,
+        bindings: &mut Bindings<Model>,
+        path: &str,
+    ) -> Result<VNode<Model>, Error> {
+        let object_type = widget_type(resolver, &self.widget).ok_or_else(|| {
+            Error::new(
+                &join(path, "widget"),
+                format!("unknown widget `{}`", self.widget),
+            )
+        })?;
+        let class = ObjectClass::from_type(object_type).ok_or_else(|| {
+            Error::new(
+                &join(path, "widget"),
+                format!("`{}` isn't a GObject class", self.widget),
+            )
+        })?;
+
+        let mut values = Vec::new();
+        for (name, source) in &self.properties {
+            let path = join(&join(path, "properties"), name);
+            let pspec = class.find_property(name.as_str()).ok_or_else(|| {
+                Error::new(&path, format!("{} has no property `{}`", object_type, name))
+            })?;
+            let value = source.resolve(pspec.get_value_type(), bindings, &path)?;
+            values.push((intern(&pspec.get_name()), value));
+        }
+
+        let mut child_props = Vec::new();
+        for (name, source) in &self.child_properties {
+            let path = join(&join(path, "child_properties"), name);
+            child_props.push(child_property(name, source, bindings, &path)?);
+        }
+
+        let mut handlers = Vec::new();
+        for (signal, handler) in &self.handlers {
+            let path = join(&join(path, "on"), signal);
+            let return_type = signal_return_type(object_type, signal).ok_or_else(|| {
+                Error::new(&path, format!("{} has no signal `{}`", object_type, signal))
+            })?;
+            match (return_type, handler.ret) {
+                (Type::Unit, None) | (Type::Bool, Some(_)) => {}
+                (Type::Unit, Some(_)) => {
+                    return Err(Error::new(&path, "this signal doesn't take a return value"))
+                }
+                (Type::Bool, None) => {
+                    return Err(Error::new(
+                        &path,
+                        "this signal needs a return value, given as `\"return\": true` or `false`",
+                    ))
+                }
+                (return_type, _) => {
+                    return Err(Error::new(
+                        &path,
+                        format!("can't return a {} from a template", return_type),
+                    ))
+                }
+            }
+            let message = bindings.messages.get(&handler.message).ok_or_else(|| {
+                Error::new(&path, format!("no message bound to `{}`", handler.message))
+            })?;
+            handlers.push(handler.connect(intern(signal), message.clone()));
+        }
+
+        let mut children = Vec::new();
+        for (index, child) in self.children.iter().enumerate() {
+            let path = join(&join(path, "children"), &format!("[{}]", index));
+            match child {
+                Child::Node(node) => children.push(node.render(resolver, bindings, &path)?),
+                Child::Slot(name) => {
+                    children.extend(bindings.slots.remove(name).ok_or_else(|| {
+                        Error::new(
+                            &path,
+                            format!("no nodes for slot `{}`, or it's used twice", name),
+                        )
+                    })?)
+                }
+            }
+        }
+
+        Ok(VNode::Object(VObject {
+            object_type,
+            constructor: None,
+            properties: properties::spread(&values),
+            child_props,
+            construct_props: Vec::new(),
+            handlers,
+            children,
+            key: self.key.as_ref().map(key),
+            recycle: false,
+            tooltip: None,
+        }))
+    }
+}
+
+impl Handler {
+    fn parse(value: &Json, path: &str) -> Result<Self, Error> {
+        if let Some(message) = value.as_str() {
+            return Ok(Handler {
+                message: message.to_string(),
+                ret: None,
+            });
+        }
+        let mut handler = Handler {
+            message: String::new(),
+            ret: None,
+        };
+        for (field, value) in object(value, path)? {
+            let path = join(path, field);
+            match field.as_str() {
+                "message" => handler.message = string(value, &path)?.to_string(),
+                "return" => {
+                    handler.ret = Some(
+                        value
+                            .as_bool()
+                            .ok_or_else(|| Error::new(&path, "expected true or false"))?,
+                    )
+                }
+                _ => return Err(Error::new(&path, format!("unknown field `{}`", field))),
+            }
+        }
+        if handler.message.is_empty() {
+            return Err(Error::new(path, "missing field `message`"));
+        }
+        Ok(handler)
+    }
+
+    fn connect<Model: 'static + Component>(
+        &self,
+        signal: &'static str,
+        message: Rc<dyn Fn(&Object) -> Model::Message>,
+    ) -> VHandler<Model> {
+        let ret = self.ret;
+        VHandler {
+            name: signal,
+            id: Cow::Owned(format!("template:{}", self.message)),
+            set: Box::new(move |object, scope| {
+                let (message, scope) = (message.clone(), scope.clone());
+                object
+                    .connect_local(signal, false, move |args| {
+                        let object = args[0]
+                            .get::<Object>()
+                            .ok()
+                            .flatten()
+                            .expect("signal emitted without an object");
+                        scope.send_message(message(&object));
+                        ret.map(|ret| ret.to_value())
+                    })
+                    .unwrap_or_else(|error| {
+                        panic!(
+                            "can't connect to signal {} on {}: {}",
+                            signal,
+                            object.get_type(),
+                            error
+                        )
+                    })
+            }),
+        }
+    }
+}
+
+impl Child {
+    fn parse(value: &Json, path: &str) -> Result<Self, Error> {
+        match value.get("slot") {
+            Some(name) if object(value, path)?.len() == 1 => {
+                Ok(Child::Slot(string(name, &join(path, "slot"))?.to_string()))
+            }
+            Some(_) => Err(Error::new(path, "a slot can only have a `slot` field")),
+            None => Node::parse(value, path).map(Child::Node),
+        }
+    }
+}
+
+impl Source {
+    /// Get the value of a property of type `value_type`.
+    fn resolve<Model: Component>(
+        &self,
+        value_type: Type,
+        bindings: &Bindings<Model>,
+        path: &str,
+    ) -> Result<Value, Error> {
+        match self {
+            Source::Literal(json) => convert(json, value_type).ok_or_else(|| {
+                Error::new(
+                    path,
+                    format!("can't use {} as a value of type {}", json, value_type),
+                )
+            }),
+            Source::Bound(name) => {
+                let value = bindings
+                    .values
+                    .get(name)
+                    .ok_or_else(|| Error::new(path, format!("no value bound to `{}`", name)))?;
+                if value.type_().is_a(&value_type) {
+                    Ok(value.clone())
+                } else {
+                    Err(Error::new(
+                        path,
+                        format!(
+                            "`{}` is bound to a {}, but the property is a {}",
+                            name,
+                            value.type_(),
+                            value_type
+                        ),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Convert a literal to a value of a GObject type.
+fn convert(json: &Json, value_type: Type) -> Option<Value> {
+    fn int<T: TryFrom<i64> + ToValue>(json: &Json) -> Option<Value> {
+        T::try_from(json.as_i64()?).ok().map(|n| n.to_value())
+    }
+    fn uint<T: TryFrom<u64> + ToValue>(json: &Json) -> Option<Value> {
+        T::try_from(json.as_u64()?).ok().map(|n| n.to_value())
+    }
+    match value_type {
+        Type::Bool => json.as_bool().map(|b| b.to_value()),
+        Type::String => json.as_str().map(|s| s.to_value()),
+        Type::I8 => int::<i8>(json),
+        Type::I32 => int::<i32>(json),
+        Type::I64 => int::<i64>(json),
+        Type::U8 => uint::<u8>(json),
+        Type::U32 => uint::<u32>(json),
+        Type::U64 => uint::<u64>(json),
+        Type::F32 => json.as_f64().map(|n| (n as f32).to_value()),
+        Type::F64 => json.as_f64().map(|n| n.to_value()),
+        _ if value_type.is_a(&Type::BaseEnum) => {
+            let class = EnumClass::new(value_type)?;
+            match json {
+                Json::String(name) => class
+                    .to_value_by_nick(name)
+                    .or_else(|| class.to_value_by_name(name)),
+                _ => class.to_value(i32::try_from(json.as_i64()?).ok()?),
+            }
+        }
+        _ if value_type.is_a(&Type::BaseFlags) => {
+            let class = FlagsClass::new(value_type)?;
+            match json {
+                Json::String(names) => {
+                    let mut bits = 0;
+                    for name in names
+                        .split('|')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                    {
+                        let flag = class
+                            .get_value_by_nick(name)
+                            .or_else(|| class.get_value_by_name(name))?;
+                        bits |= flag.get_value();
+                    }
+                    class.to_value(bits)
+                }
+                _ => class.to_value(u32::try_from(json.as_u64()?).ok()?),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Make a child property, for the handful of them templates support.
+fn child_property<Model: Component>(
+    name: &str,
+    source: &Source,
+    bindings: &Bindings<Model>,
+    path: &str,
+) -> Result<VProperty, Error> {
+    let value = |value_type: Type| source.resolve(value_type, bindings, path);
+    Ok(match name {
+        "expand" => {
+            let value = value(Type::Bool)?.get_some::<bool>().unwrap_or_default();
+            child_prop(
+                "expand",
+                GtkBox::get_child_expand::<Widget>,
+                GtkBox::set_child_expand::<Widget>,
+                value,
+            )
+        }
+        "fill" => {
+            let value = value(Type::Bool)?.get_some::<bool>().unwrap_or_default();
+            child_prop(
+                "fill",
+                GtkBox::get_child_fill::<Widget>,
+                GtkBox::set_child_fill::<Widget>,
+                value,
+            )
+        }
+        "padding" => {
+            let value = value(Type::U32)?.get_some::<u32>().unwrap_or_default();
+            child_prop(
+                "padding",
+                GtkBox::get_child_padding::<Widget>,
+                GtkBox::set_child_padding::<Widget>,
+                value,
+            )
+        }
+        "pack-type" | "pack_type" => {
+            let value = value(PackType::static_type())?
+                .get_some::<PackType>()
+                .unwrap_or(PackType::Start);
+            child_prop(
+                "pack_type",
+                GtkBox::get_child_pack_type::<Widget>,
+                GtkBox::set_child_pack_type::<Widget>,
+                value,
+            )
+        }
+        "left" | "top" | "width" | "height" => {
+            let value = value(Type::I32)?.get_some::<i32>().unwrap_or_default();
+            let (get, set): (fn(&Grid, &Widget) -> i32, fn(&Grid, &Widget, i32)) = match name {
+                "left" => (Grid::get_child_left, Grid::set_child_left::<Widget>),
+                "top" => (Grid::get_child_top, Grid::set_child_top::<Widget>),
+                "width" => (Grid::get_child_width, Grid::set_child_width::<Widget>),
+                _ => (Grid::get_child_height, Grid::set_child_height::<Widget>),
+            };
+            child_prop(intern(name), get, set, value)
+        }
+        _ => {
+            return Err(Error::new(
+                path,
+                format!("unsupported child property `{}`", name),
+            ))
+        }
+    })
+}
+
+/// Find a GTK class by name, with or without its `Gtk` prefix.
+fn widget_type(resolver: &gtk::Builder, name: &str) -> Option<Type> {
+    let prefixed = format!("Gtk{}", name);
+    [name, prefixed.as_str()].iter().find_map(|name| {
+        // Classes are only registered once they're first used, which the
+        // builder takes care of by looking up their type functions.
+        Type::from_name(*name)
+            .or_else(|| Some(resolver.get_type_from_name(name)))
+            .filter(|object_type| object_type.is_a(&Object::static_type()))
+    })
+}
+
+thread_local! {
+    static NAMES: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+}
+
+/// Find the type of value a signal returns, or `None` if `object_type` has
+/// no such signal.
+fn signal_return_type(object_type: Type, signal: &str) -> Option<Type> {
+    let (mut id, mut detail) = (0, 0);
+    #[allow(unsafe_code)]
+    unsafe {
+        let found: bool = from_glib(gobject_sys::g_signal_parse_name(
+            signal.to_glib_none().0,
+            object_type.to_glib(),
+            &mut id,
+            &mut detail,
+            true.to_glib(),
+        ));
+        if !found {
+            return None;
+        }
+        let mut query = std::mem::MaybeUninit::zeroed();
+        gobject_sys::g_signal_query(id, query.as_mut_ptr());
+        let query: gobject_sys::GSignalQuery = query.assume_init();
+        // Leave out the G_SIGNAL_TYPE_STATIC_SCOPE flag.
+        Some(from_glib(
+            query.return_type & !gobject_sys::G_TYPE_FLAG_RESERVED_ID_BIT,
+        ))
+    }
+}
+
+/// Get a `'static` copy of a property or signal name, which virtual nodes
+/// need.
+///
+/// Each distinct name is leaked once, which is fine for the few dozen a set
+/// of templates uses.
+fn intern(name: &str) -> &'static str {
+    NAMES.with(|names| {
+        let mut names = names.borrow_mut();
+        match names.get(name) {
+            Some(name) => name,
+            None => {
+                let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+                names.insert(name);
+                name
+            }
+        }
+    })
+}
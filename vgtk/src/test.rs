@@ -0,0 +1,1030 @@
+//! Driving a component's asynchronous behaviour step by step in tests.
+//!
+//! A component which defers work with [`UpdateAction::defer()`][defer],
+//! waits on timers, or has `throttle`d or `debounce`d signal handlers
+//! doesn't do anything until the main loop runs, and then it does it in
+//! real time, which makes for slow and flaky tests. An
+//! [`Executor`][Executor] runs the main loop for a test instead, and puts
+//! vgtk's timers on a virtual clock which only moves when the test calls
+//! [`advance()`][advance], so a five second debounce takes no time at all,
+//! and always fires in the same order relative to everything else.
+//!
+//! ```rust,no_run
+//! use std::cell::Cell;
+//! use std::rc::Rc;
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//!
+//! use vgtk::clock;
+//! use vgtk::test::Executor;
+//!
+//! let executor = Executor::new();
+//! let done = Rc::new(Cell::new(false));
+//! let finished = done.clone();
+//! executor.spawn(async move {
+//!     clock::sleep(Duration::from_secs(5)).await;
+//!     finished.set(true);
+//! });
+//!
+//! executor.advance(Duration::from_secs(4));
+//! assert!(!done.get());
+//! executor.advance(Duration::from_secs(1));
+//! assert!(done.get());
+//! ```
+//!
+//! Components mounted with [`vgtk::mount()`][mount] run on the same main
+//! loop, so the executor drives their deferred actions and renders too. Only
+//! timers started through vgtk, including [`clock::sleep()`][sleep], run on
+//! the virtual clock. Anything else, like a `glib::timeout_future()` or I/O,
+//! runs in real time, and finishes whenever it finishes.
+//!
+//! To poke at a component the way a user would, [`find()`][find] its
+//! widgets by their type, properties or `key` attributes, and emit their
+//! signals, which run the component's handlers exactly as the real thing
+//! would:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::lib::gtk::*;
+//! use vgtk::test::{self, Executor};
+//!
+//! # #[derive(Clone, Default)] struct Dialog;
+//! # impl Component for Dialog {
+//! #     type Message = (); type Properties = Self;
+//! #     fn create(props: Self) -> Self { props }
+//! #     fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! # }
+//! # fn main() {
+//! # gtk::init().unwrap();
+//! let executor = Executor::new();
+//! let window = Window::new(WindowType::Toplevel);
+//! let dialog = vgtk::mount::<Dialog>(&window, Dialog::default());
+//! executor.run_until_stalled();
+//!
+//! test::find::<Button>(&dialog.widget()).with_label("OK").click();
+//! executor.run_until_stalled();
+//! # }
+//! ```
+//!
+//! For what a view actually looks like, with the theme applied and laid out,
+//! mount it in an [`Offscreen`][Offscreen] window, take a
+//! [`screenshot()`][screenshot], and compare it with one saved earlier using
+//! [`assert_screenshot()`][assert_screenshot]:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::lib::gtk::*;
+//! use vgtk::test::{self, Offscreen};
+//!
+//! # #[derive(Clone, Default)] struct Dialog;
+//! # impl Component for Dialog {
+//! #     type Message = (); type Properties = Self;
+//! #     fn create(props: Self) -> Self { props }
+//! #     fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! # }
+//! let offscreen = Offscreen::new(400, 300);
+//! let _dialog = offscreen.mount::<Dialog>(Dialog::default());
+//! test::assert_screenshot(&offscreen.screenshot(), "tests/screenshots/dialog.png", 0.01);
+//! ```
+//!
+//! The first time, there's nothing to compare with, so the screenshot is
+//! saved instead, to check by eye and commit. Run the tests with
+//! `VGTK_UPDATE_SCREENSHOTS=1` to save new ones after changing a view on
+//! purpose. Screenshots depend on the GTK theme and fonts, so they're best
+//! taken somewhere those don't change, like a CI container, and under
+//! `xvfb-run` or with `GDK_BACKEND=broadway` on a machine without a display.
+//!
+//! To check a view works with screen readers, get its
+//! [`accessible_tree()`][accessible_tree], which is what assistive
+//! technologies see of it, and check every control has a name they can read
+//! out:
+//!
+//! ```rust,no_run
+//! # use vgtk::lib::gtk::*;
+//! # fn test(root: &Widget) {
+//! let tree = vgtk::test::accessible_tree(root);
+//! assert!(tree.unlabelled().is_empty(), "unlabelled controls:\n{}", tree);
+//! # }
+//! ```
+//!
+//! Finally, [`fuzz()`][fuzz] throws random sequences of messages at a
+//! component, and checks that it never panics, that its widget tree can
+//! always be patched to match its view, and that patching it ends up with
+//! exactly the widgets building the view from scratch would. Its messages
+//! have to implement [`Arbitrary`][Arbitrary] for it to make them up:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, VNode};
+//! # use vgtk::lib::gtk::*;
+//! use vgtk::test::{self, Arbitrary, Rng};
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Add(String),
+//!     Remove(usize),
+//!     Clear,
+//! }
+//!
+//! impl Arbitrary for Message {
+//!     fn arbitrary(rng: &mut Rng) -> Self {
+//!         match rng.below(3) {
+//!             0 => Message::Add(String::arbitrary(rng)),
+//!             1 => Message::Remove(rng.below(10) as usize),
+//!             _ => Message::Clear,
+//!         }
+//!     }
+//! }
+//! # #[derive(Default)] struct TodoList;
+//! # impl Component for TodoList {
+//! #     type Message = Message; type Properties = ();
+//! #     fn create(_props: ()) -> Self { Self::default() }
+//! #     fn view(&self) -> VNode<Self> { gtk! { <Box/> } }
+//! # }
+//!
+//! # fn main() {
+//! test::fuzz::<TodoList>();
+//! # }
+//! ```
+//!
+//! [defer]: ../enum.UpdateAction.html#method.defer
+//! [Executor]: struct.Executor.html
+//! [advance]: struct.Executor.html#method.advance
+//! [mount]: ../fn.mount.html
+//! [sleep]: ../clock/fn.sleep.html
+//! [find]: fn.find.html
+//! [Offscreen]: struct.Offscreen.html
+//! [screenshot]: struct.Offscreen.html#method.screenshot
+//! [assert_screenshot]: fn.assert_screenshot.html
+//! [accessible_tree]: fn.accessible_tree.html
+//! [fuzz]: fn.fuzz.html
+//! [Arbitrary]: trait.Arbitrary.html
+
+use std::any::type_name;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::fs;
+use std::future::Future;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use atk::{
+    AtkObjectExt, Object as AtkObject, RelationExt, RelationSetExt, RelationType, Role,
+    StateSetExt, StateType,
+};
+use futures::channel::mpsc::unbounded;
+use gdk_pixbuf::Pixbuf;
+use glib::{Cast, IsA, MainContext, Object, ObjectExt, ToValue, Value};
+use gtk::prelude::WidgetExtManual;
+use gtk::{
+    Container, ContainerExt, GtkWindowExt, MenuButton, MenuButtonExt, OffscreenWindow,
+    OffscreenWindowExt, Widget, WidgetExt, Window,
+};
+
+use crate::clock;
+use crate::component::Component;
+use crate::debug::export_ui_xml;
+use crate::embed::{mount, MountHandle};
+use crate::error::catch;
+use crate::scope::Scope;
+use crate::vdom::key_of;
+use crate::vdom::State;
+use crate::vnode::key;
+use crate::vnode::VNode;
+
+/// Runs the main loop for a test, on a virtual clock.
+///
+/// See the [module documentation](index.html).
+#[derive(Debug)]
+pub struct Executor {
+    context: MainContext,
+    // The clock belongs to the thread the executor was created on.
+    _thread: PhantomData<Rc<()>>,
+}
+
+impl Executor {
+    /// Put this thread's timers on a virtual clock, starting at zero, until
+    /// the executor is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's already an executor on this thread.
+    pub fn new() -> Self {
+        assert!(
+            clock::start_virtual(),
+            "There can only be one vgtk::test::Executor on a thread at a time."
+        );
+        Executor {
+            context: MainContext::ref_thread_default(),
+            _thread: PhantomData,
+        }
+    }
+
+    /// Run a future on the main loop, the way a component's deferred
+    /// actions run.
+    ///
+    /// It doesn't start until the executor next runs.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.context.spawn_local(future);
+    }
+
+    /// Run the main loop until there's nothing left for it to do without
+    /// the clock moving.
+    pub fn run_until_stalled(&self) {
+        while self.context.pending() {
+            self.context.iteration(false);
+        }
+    }
+
+    /// Move the virtual clock forward by `duration`, firing each timer due
+    /// in that time in turn, and running the main loop until it stalls after
+    /// each.
+    pub fn advance(&self, duration: Duration) {
+        let until = self.elapsed() + duration;
+        self.run_until_stalled();
+        while clock::fire_next(until) {
+            self.run_until_stalled();
+        }
+        clock::set_virtual_now(until);
+    }
+
+    /// Keep moving the virtual clock forward to the next timer, and firing
+    /// it, until there are no timers left, or until `limit` has passed, in
+    /// case some timer keeps firing for ever.
+    pub fn run_timers(&self, limit: Duration) {
+        let until = self.elapsed() + limit;
+        self.run_until_stalled();
+        while clock::fire_next(until) {
+            self.run_until_stalled();
+        }
+    }
+
+    /// Get how far the virtual clock has moved since the executor was
+    /// created.
+    pub fn elapsed(&self) -> Duration {
+        clock::virtual_now().unwrap_or_default()
+    }
+
+    /// Get how many timers are waiting to fire.
+    pub fn pending_timers(&self) -> usize {
+        clock::virtual_pending()
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Executor {
+    fn drop(&mut self) {
+        clock::stop_virtual();
+    }
+}
+
+/// Find the widgets of type `W` in the widget tree under `root`, including
+/// `root` itself.
+///
+/// This returns a [`Query`][Query], which can narrow the search down further
+/// before getting the widgets it found, or emitting signals on them.
+///
+/// [Query]: struct.Query.html
+pub fn find<W: IsA<Widget>>(root: &impl IsA<Widget>) -> Query<W> {
+    let mut found = Vec::new();
+    collect(root.as_ref(), &mut found);
+    Query {
+        widgets: found
+            .into_iter()
+            .filter_map(|widget| widget.downcast().ok())
+            .collect(),
+        description: format!("{}", W::static_type()),
+    }
+}
+
+/// Add `widget` and every widget under it to `found`, in depth first order.
+fn collect(widget: &Widget, found: &mut Vec<Widget>) {
+    found.push(widget.clone());
+    if let Some(window) = widget.downcast_ref::<Window>() {
+        if let Some(titlebar) = window.get_titlebar() {
+            collect(&titlebar, found);
+        }
+    }
+    if let Some(button) = widget.downcast_ref::<MenuButton>() {
+        if let Some(popover) = button.get_popover() {
+            collect(popover.upcast_ref(), found);
+        }
+    }
+    if let Some(container) = widget.downcast_ref::<Container>() {
+        for child in container.get_children() {
+            collect(&child, found);
+        }
+    }
+}
+
+/// The widgets of type `W` found by [`find()`][find].
+///
+/// [find]: fn.find.html
+pub struct Query<W> {
+    widgets: Vec<W>,
+    // What the query looks for, to say what wasn't found.
+    description: String,
+}
+
+impl<W: IsA<Widget>> Debug for Query<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{} ({} found)", self.description, self.widgets.len())
+    }
+}
+
+impl<W: IsA<Widget>> Query<W> {
+    /// Keep only the widgets `predicate` returns `true` for.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&W) -> bool,
+    {
+        self.widgets.retain(|widget| predicate(widget));
+        self.description += " matching a filter";
+        self
+    }
+
+    /// Keep only the widgets whose property `name`, converted to a string,
+    /// is `value`.
+    pub fn with_property(mut self, name: &str, value: &str) -> Self {
+        self.widgets.retain(|widget| {
+            widget
+                .get_property(name)
+                .ok()
+                .and_then(|property| property.transform::<String>())
+                .and_then(|property| property.get::<String>().ok().flatten())
+                .as_deref()
+                == Some(value)
+        });
+        self.description += &format!(" with {}={:?}", name, value);
+        self
+    }
+
+    /// Keep only the widgets whose `label` property is `label`.
+    pub fn with_label(self, label: &str) -> Self {
+        self.with_property("label", label)
+    }
+
+    /// Keep only the widgets whose name, as set with the `widget_name`
+    /// property, is `name`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.widgets
+            .retain(|widget| widget.get_widget_name().as_str() == name);
+        self.description += &format!(" named {:?}", name);
+        self
+    }
+
+    /// Keep only the widgets built from a node whose `key` attribute is
+    /// `value`.
+    pub fn with_key<K: Hash + ?Sized>(mut self, value: &K) -> Self {
+        let wanted = key(value);
+        self.widgets
+            .retain(|widget| key_of(widget.as_ref().upcast_ref::<Object>()) == Some(wanted));
+        self.description += " with the given key";
+        self
+    }
+
+    /// Get every widget found.
+    pub fn all(self) -> Vec<W> {
+        self.widgets
+    }
+
+    /// Get the first widget found, if any.
+    pub fn first(self) -> Option<W> {
+        self.widgets.into_iter().next()
+    }
+
+    /// Get the only widget found.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't exactly one.
+    pub fn get(self) -> W {
+        match self.widgets.len() {
+            1 => self.widgets.into_iter().next().unwrap(),
+            0 => panic!("No {} found.", self.description),
+            count => panic!("Expected one {}, but found {}.", self.description, count),
+        }
+    }
+
+    /// Emit the signal `signal` on the only widget found, with `args`, and
+    /// return what its handlers returned.
+    ///
+    /// This runs the same handlers as when GTK emits the signal itself, so
+    /// messages they send are delivered as usual, once the main loop runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't exactly one widget, or if it has no such signal,
+    /// or the arguments don't fit it.
+    pub fn emit(self, signal: &str, args: &[&dyn ToValue]) -> Option<Value> {
+        let description = self.description.clone();
+        self.get()
+            .emit(signal, args)
+            .unwrap_or_else(|error| panic!("Can't emit {} on {}: {}", signal, description, error))
+    }
+
+    /// Click the only widget found, by emitting its `clicked` signal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't exactly one widget, or if it can't be clicked.
+    pub fn click(self) {
+        self.emit("clicked", &[]);
+    }
+}
+
+/// An offscreen window to mount components in, and take screenshots of.
+///
+/// The window is destroyed when this is dropped.
+#[derive(Debug)]
+pub struct Offscreen {
+    window: OffscreenWindow,
+}
+
+impl Offscreen {
+    /// Create an offscreen window of the given size.
+    ///
+    /// This initialises GTK if nothing else has yet.
+    pub fn new(width: i32, height: i32) -> Self {
+        if !gtk::is_initialized() {
+            gtk::init().expect("GTK failed to initialise");
+        }
+        let window = OffscreenWindow::new();
+        window.set_default_size(width, height);
+        window.show();
+        Offscreen { window }
+    }
+
+    /// Get the window.
+    pub fn window(&self) -> &OffscreenWindow {
+        &self.window
+    }
+
+    /// Mount a component in the window, the same as
+    /// [`vgtk::mount()`][mount].
+    ///
+    /// [mount]: ../fn.mount.html
+    pub fn mount<C: 'static + Component>(&self, props: C::Properties) -> MountHandle<C> {
+        let handle = mount(&self.window, props);
+        self.window.show_all();
+        handle
+    }
+
+    /// Let the window finish rendering, and take a screenshot of it.
+    pub fn screenshot(&self) -> Pixbuf {
+        while gtk::events_pending() {
+            gtk::main_iteration_do(false);
+        }
+        self.window
+            .get_pixbuf()
+            .expect("unable to take a screenshot of an offscreen window")
+    }
+}
+
+impl Drop for Offscreen {
+    fn drop(&mut self) {
+        #[allow(unsafe_code)]
+        unsafe {
+            self.window.destroy();
+        }
+    }
+}
+
+/// How far apart two channels of a pixel can be before the pixels count as
+/// different, which allows for small differences in antialiasing.
+const CHANNEL_TOLERANCE: u8 = 16;
+
+/// Get the fraction of pixels which differ between two screenshots, from 0
+/// for none to 1 for all of them, or if they're not the same size.
+pub fn difference(expected: &Pixbuf, actual: &Pixbuf) -> f64 {
+    let (width, height) = (actual.get_width(), actual.get_height());
+    if (expected.get_width(), expected.get_height()) != (width, height) {
+        return 1.0;
+    }
+    if width == 0 || height == 0 {
+        return 0.0;
+    }
+    // Neither pixbuf is written to while these are around.
+    #[allow(unsafe_code)]
+    let (left, right): (&[u8], &[u8]) = unsafe { (expected.get_pixels(), actual.get_pixels()) };
+    let channels = (
+        expected.get_n_channels() as usize,
+        actual.get_n_channels() as usize,
+    );
+    let strides = (
+        expected.get_rowstride() as usize,
+        actual.get_rowstride() as usize,
+    );
+    // Compare the colours, and the alpha channel only if both have one.
+    let compared = channels.0.min(channels.1);
+    let mut differing = 0usize;
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let left = &left[y * strides.0 + x * channels.0..][..compared];
+            let right = &right[y * strides.1 + x * channels.1..][..compared];
+            if left.iter().zip(right).any(|(left, right)| {
+                (*left as i16 - *right as i16).abs() > CHANNEL_TOLERANCE as i16
+            }) {
+                differing += 1;
+            }
+        }
+    }
+    differing as f64 / (width as f64 * height as f64)
+}
+
+/// Compare a screenshot with the one saved at `path`, allowing up to
+/// `tolerance` of its pixels to differ, as a fraction from 0 to 1.
+///
+/// If there's no screenshot saved at `path` yet, or the
+/// `VGTK_UPDATE_SCREENSHOTS` environment variable is set, this saves
+/// `actual` there as a PNG instead.
+///
+/// # Panics
+///
+/// Panics if the screenshots differ by more than `tolerance`, after saving
+/// `actual` next to the saved one, with `.actual` added to its name, to
+/// compare them by eye. Also panics if a screenshot can't be read or saved.
+pub fn assert_screenshot(actual: &Pixbuf, path: impl AsRef<Path>, tolerance: f64) {
+    let path = path.as_ref();
+    if !path.exists() || std::env::var_os("VGTK_UPDATE_SCREENSHOTS").is_some() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .unwrap_or_else(|error| panic!("can't create {}: {}", parent.display(), error));
+        }
+        save(actual, path);
+        return;
+    }
+    let expected = Pixbuf::from_file(path)
+        .unwrap_or_else(|error| panic!("can't read {}: {}", path.display(), error));
+    let difference = difference(&expected, actual);
+    if difference > tolerance {
+        let saved = path.with_extension("actual.png");
+        save(actual, &saved);
+        panic!(
+            "{:.1}% of the screenshot differs from {}, more than the {:.1}% allowed. It was saved \
+             to {} to compare.",
+            difference * 100.0,
+            path.display(),
+            tolerance * 100.0,
+            saved.display()
+        );
+    }
+}
+
+fn save(pixbuf: &Pixbuf, path: &Path) {
+    pixbuf
+        .savev(path, "png", &[])
+        .unwrap_or_else(|error| panic!("can't save {}: {}", path.display(), error));
+}
+
+/// A node in the accessibility tree, as returned by
+/// [`accessible_tree()`][accessible_tree].
+///
+/// Displaying a node draws the tree under it, one node per line, which
+/// makes for readable test failures.
+///
+/// [accessible_tree]: fn.accessible_tree.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibleNode {
+    /// What kind of thing this is, like a `PushButton`.
+    pub role: Role,
+    /// The name a screen reader reads out for it.
+    pub name: Option<String>,
+    /// A longer description of it.
+    pub description: Option<String>,
+    /// Whether it can take the keyboard focus.
+    pub focusable: bool,
+    /// Its relations to other nodes, with the names of the nodes they're
+    /// to, like being `LabelledBy` a label.
+    pub relations: Vec<(RelationType, Vec<Option<String>>)>,
+    /// The nodes inside it.
+    pub children: Vec<AccessibleNode>,
+}
+
+/// Get the accessibility tree of `root` and the widgets under it.
+pub fn accessible_tree(root: &impl IsA<Widget>) -> AccessibleNode {
+    let accessible = root
+        .get_accessible()
+        .expect("the widget has no accessible object");
+    accessible_node(&accessible)
+}
+
+fn accessible_node(accessible: &AtkObject) -> AccessibleNode {
+    let relations = accessible
+        .ref_relation_set()
+        .map(|set| {
+            (0..set.get_n_relations())
+                .filter_map(|index| set.get_relation(index))
+                .map(|relation| {
+                    let targets = relation
+                        .get_target()
+                        .iter()
+                        .filter_map(|target| target.downcast_ref::<AtkObject>())
+                        .map(|target| target.get_name().map(String::from))
+                        .collect();
+                    (relation.get_relation_type(), targets)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    AccessibleNode {
+        role: accessible.get_role(),
+        name: accessible.get_name().map(String::from),
+        description: accessible.get_description().map(String::from),
+        focusable: accessible
+            .ref_state_set()
+            .is_some_and(|states| states.contains_state(StateType::Focusable)),
+        relations,
+        children: (0..accessible.get_n_accessible_children())
+            .filter_map(|index| accessible.ref_accessible_child(index))
+            .map(|child| accessible_node(&child))
+            .collect(),
+    }
+}
+
+impl AccessibleNode {
+    /// Get this node and every node under it, in depth first order.
+    pub fn iter(&self) -> Vec<&AccessibleNode> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.iter());
+        }
+        nodes
+    }
+
+    /// Get the nodes with the given role, in depth first order.
+    pub fn find_role(&self, role: Role) -> Vec<&AccessibleNode> {
+        self.iter()
+            .into_iter()
+            .filter(|node| node.role == role)
+            .collect()
+    }
+
+    /// Get the first node with the given name, if any.
+    pub fn find_name(&self, name: &str) -> Option<&AccessibleNode> {
+        self.iter()
+            .into_iter()
+            .find(|node| node.name.as_deref() == Some(name))
+    }
+
+    /// Test whether this is a control the user interacts with, like a
+    /// button or an entry, which needs a name to be usable with a screen
+    /// reader.
+    pub fn is_interactive(&self) -> bool {
+        matches!(
+            self.role,
+            Role::PushButton
+                | Role::ToggleButton
+                | Role::CheckBox
+                | Role::RadioButton
+                | Role::MenuItem
+                | Role::CheckMenuItem
+                | Role::RadioMenuItem
+                | Role::Entry
+                | Role::PasswordText
+                | Role::ComboBox
+                | Role::Slider
+                | Role::SpinButton
+                | Role::Link
+        )
+    }
+
+    /// Test whether this node has a name, or is labelled by another node
+    /// with one.
+    pub fn is_labelled(&self) -> bool {
+        let named = |name: &Option<String>| name.as_deref().is_some_and(|name| !name.is_empty());
+        named(&self.name)
+            || self.relations.iter().any(|(relation, targets)| {
+                *relation == RelationType::LabelledBy && targets.iter().any(named)
+            })
+    }
+
+    /// Get the interactive nodes which aren't labelled, which a screen
+    /// reader has no way to describe.
+    pub fn unlabelled(&self) -> Vec<&AccessibleNode> {
+        self.iter()
+            .into_iter()
+            .filter(|node| node.is_interactive() && !node.is_labelled())
+            .collect()
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, depth: usize) -> Result<(), Error> {
+        write!(f, "{:indent$}{:?}", "", self.role, indent = depth * 2)?;
+        if let Some(name) = &self.name {
+            write!(f, " {:?}", name)?;
+        }
+        if let Some(description) = &self.description {
+            write!(f, " ({})", description)?;
+        }
+        if self.focusable {
+            write!(f, " focusable")?;
+        }
+        for (relation, targets) in &self.relations {
+            write!(f, " {:?}", relation)?;
+            for target in targets {
+                write!(f, " {:?}", target.as_deref().unwrap_or(""))?;
+            }
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AccessibleNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.write(f, 0)
+    }
+}
+
+/// A small, fast, deterministic random number generator, for making up
+/// [`Arbitrary`][Arbitrary] values.
+///
+/// The same seed always gives the same numbers.
+///
+/// [Arbitrary]: trait.Arbitrary.html
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Construct a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at zero.
+        Rng(seed ^ 0x9e37_79b9_7f4a_7c15 | 1)
+    }
+
+    /// Get the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        // Xorshift64*.
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Get a random number below `limit`, which must be more than zero.
+    pub fn below(&mut self, limit: u64) -> u64 {
+        self.next_u64() % limit
+    }
+
+    /// Get `true` with a probability of `chance`, from 0 to 1.
+    pub fn chance(&mut self, chance: f64) -> bool {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 <= chance
+    }
+
+    /// Pick one of `items` at random, or `None` if there aren't any.
+    pub fn choose<'a, A>(&mut self, items: &'a [A]) -> Option<&'a A> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.below(items.len() as u64) as usize)
+        }
+    }
+}
+
+/// A type [`fuzz()`][fuzz] can make up random values of.
+///
+/// [fuzz]: fn.fuzz.html
+pub trait Arbitrary: Sized {
+    /// Make up a random value.
+    fn arbitrary(rng: &mut Rng) -> Self;
+}
+
+impl Arbitrary for () {
+    fn arbitrary(_rng: &mut Rng) -> Self {}
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.chance(0.5)
+    }
+}
+
+macro_rules! arbitrary_integers {
+    ($($type:ty)*) => {$(
+        impl Arbitrary for $type {
+            fn arbitrary(rng: &mut Rng) -> Self {
+                // Small numbers and the extremes find the most bugs.
+                match rng.below(8) {
+                    0 => <$type>::MIN,
+                    1 => <$type>::MAX,
+                    2..=5 => rng.below(16) as $type,
+                    _ => rng.next_u64() as $type,
+                }
+            }
+        }
+    )*};
+}
+
+arbitrary_integers! { u8 u16 u32 u64 usize i8 i16 i32 i64 isize }
+
+impl Arbitrary for f64 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        match rng.below(4) {
+            0 => 0.0,
+            1 => -(rng.below(1000) as f64) / 10.0,
+            _ => rng.below(1000) as f64 / 10.0,
+        }
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        const CHARS: &[char] = &['a', 'b', 'Z', '0', ' ', '&', '<', '\n', 'é', '🦀'];
+        (0..rng.below(12))
+            .filter_map(|_| rng.choose(CHARS).copied())
+            .collect()
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Option<A> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        if rng.chance(0.25) {
+            None
+        } else {
+            Some(A::arbitrary(rng))
+        }
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Vec<A> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (0..rng.below(8)).map(|_| A::arbitrary(rng)).collect()
+    }
+}
+
+/// Settings for [`fuzz_with()`][fuzz_with].
+///
+/// [fuzz_with]: fn.fuzz_with.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fuzz {
+    /// How many times to start the component from scratch.
+    pub runs: usize,
+    /// How many messages to send it each time.
+    pub steps: usize,
+    /// The seed for the random number generator, to repeat a failure. The
+    /// default is the `VGTK_FUZZ_SEED` environment variable, if it's set, or
+    /// else a different seed each time.
+    pub seed: Option<u64>,
+}
+
+impl Default for Fuzz {
+    fn default() -> Self {
+        Fuzz {
+            runs: 50,
+            steps: 40,
+            seed: std::env::var("VGTK_FUZZ_SEED")
+                .ok()
+                .and_then(|seed| seed.parse().ok()),
+        }
+    }
+}
+
+/// Send random messages to component `C`, starting from its default state,
+/// and check it holds up, using the default [`Fuzz`][Fuzz] settings.
+///
+/// Each time, its widget tree is patched to match its new view, which has
+/// to succeed, and then compared with trees built from scratch from that
+/// view and from a second call to `view()`, which all have to be the same.
+/// Messages are handled straight away, without going through a
+/// [`Scope`][Scope], and any futures `update()` defers are dropped without
+/// being run.
+///
+/// This initialises GTK if nothing else has yet.
+///
+/// # Panics
+///
+/// Panics as soon as the component panics, or fails one of these checks,
+/// with the seed and the messages which led up to it, to repeat it with.
+/// Also panics if the component's top level object isn't a widget.
+///
+/// [Fuzz]: struct.Fuzz.html
+/// [Scope]: ../struct.Scope.html
+pub fn fuzz<C>()
+where
+    C: 'static + Component + Default,
+    C::Message: Arbitrary,
+{
+    fuzz_with::<C>(Fuzz::default())
+}
+
+/// Like [`fuzz()`][fuzz], with the given settings.
+///
+/// [fuzz]: fn.fuzz.html
+pub fn fuzz_with<C>(settings: Fuzz)
+where
+    C: 'static + Component + Default,
+    C::Message: Arbitrary,
+{
+    if !gtk::is_initialized() {
+        gtk::init().expect("GTK failed to initialise");
+    }
+    let seed = settings.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    });
+    let mut rng = Rng::new(seed);
+    for _ in 0..settings.runs {
+        let mut history = Vec::new();
+        if let Err(failure) = fuzz_run::<C>(&mut rng, settings.steps, &mut history) {
+            panic!(
+                "{} {} after these messages:\n{}\nRun again with VGTK_FUZZ_SEED={} to repeat \
+                 this.",
+                type_name::<C>(),
+                failure,
+                history.join("\n"),
+                seed
+            );
+        }
+    }
+}
+
+/// Send `steps` random messages to a new component, recording them in
+/// `history`, and return what went wrong, if anything.
+fn fuzz_run<C>(rng: &mut Rng, steps: usize, history: &mut Vec<String>) -> Result<(), String>
+where
+    C: 'static + Component + Default,
+    C::Message: Arbitrary,
+{
+    let scope: Scope<C> = Scope::new(type_name::<C>(), unbounded().0);
+    let mut component = C::default();
+    let view = catch(|| {
+        let _ = component.init();
+        component.view()
+    })
+    .map_err(|error| format!("panicked in init() or view(): {}", error))?;
+    let mut state = catch(|| State::build(&view, None, &scope))
+        .map_err(|error| format!("panicked building its view: {}", error))?;
+    settle();
+    let result = (|| {
+        for _ in 0..steps {
+            let message = C::Message::arbitrary(rng);
+            history.push(format!("{:?}", message));
+            let (view, again) = catch(|| {
+                let _ = component.update(message);
+                (component.view(), component.view())
+            })
+            .map_err(|error| format!("panicked in update() or view(): {}", error))?;
+            catch(|| state.patch(&view, None, &scope))
+                .map_err(|error| format!("panicked patching its view: {}", error))?
+                .map_err(|error| format!("failed to patch its view: {}", error))?;
+            settle();
+            let patched = dump(&state);
+            if dump_built(&view, &scope)? != patched {
+                return Err(format!(
+                    "patched its widgets into something else than building them from \
+                     scratch would, which was:\n{}",
+                    patched
+                ));
+            }
+            if dump_built(&again, &scope)? != patched {
+                return Err("made a different view the second time for the same state".to_string());
+            }
+        }
+        Ok(())
+    })();
+    state.unmount();
+    settle();
+    result
+}
+
+/// Run the main loop until there's nothing left for it to do, so
+/// subcomponents get to render.
+fn settle() {
+    while gtk::events_pending() {
+        gtk::main_iteration_do(false);
+    }
+}
+
+/// Describe the widget tree of a state.
+fn dump<C: 'static + Component>(state: &State<C>) -> String {
+    let widget = state.widget().unwrap_or_else(|| {
+        panic!(
+            "{} can't be fuzzed, because its top level object isn't a widget.",
+            type_name::<C>()
+        )
+    });
+    export_ui_xml(&widget)
+}
+
+/// Describe the widget tree built from scratch from `view`.
+fn dump_built<C: 'static + Component>(view: &VNode<C>, scope: &Scope<C>) -> Result<String, String> {
+    let state = catch(|| State::build(view, None, scope))
+        .map_err(|error| format!("panicked building its view: {}", error))?;
+    settle();
+    let built = dump(&state);
+    state.unmount();
+    settle();
+    Ok(built)
+}
@@ -0,0 +1,158 @@
+//! Notifications about changes to the desktop theme.
+//!
+//! Most widgets follow the GTK theme on their own, but custom drawing, images
+//! and colours picked in code need to know when the user switches between a
+//! light and a dark theme. There are two ways to find out: every component's
+//! [`Component::theme_changed()`][theme_changed] method is called when the
+//! theme changes, and [`changes()`][changes] gives you a [`Stream`][Stream]
+//! of the same notifications for use outside of components.
+//!
+//! [theme_changed]: ../trait.Component.html#method.theme_changed
+//! [changes]: fn.changes.html
+//! [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+
+use std::cell::RefCell;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use gtk::{IconTheme, IconThemeExt, Settings, SettingsExt};
+
+use colored::Colorize;
+use log::debug;
+
+use crate::component::wake_all;
+
+/// The current desktop theme.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Theme {
+    /// The name of the GTK theme, like `Adwaita`.
+    pub name: String,
+    /// The name of the icon theme.
+    pub icon_theme: String,
+    /// Whether the user has asked applications to use the dark variant of
+    /// the theme.
+    pub prefer_dark: bool,
+}
+
+impl Theme {
+    /// Test whether the theme is dark, either because the dark variant was
+    /// asked for or because the theme is a dark theme by name.
+    pub fn is_dark(&self) -> bool {
+        self.prefer_dark || self.name.to_lowercase().ends_with("-dark")
+    }
+}
+
+#[derive(Default)]
+struct Watch {
+    started: bool,
+    generation: usize,
+    current: Theme,
+    subscribers: Vec<UnboundedSender<Theme>>,
+}
+
+thread_local! {
+    static WATCH: RefCell<Watch> = RefCell::new(Default::default());
+}
+
+fn read_theme() -> Theme {
+    let settings = Settings::get_default();
+    let setting = |get: fn(&Settings) -> Option<glib::GString>| {
+        settings
+            .as_ref()
+            .and_then(get)
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    };
+    Theme {
+        name: setting(|settings| settings.get_property_gtk_theme_name()),
+        icon_theme: setting(|settings| settings.get_property_gtk_icon_theme_name()),
+        prefer_dark: settings
+            .as_ref()
+            .map(|settings| settings.get_property_gtk_application_prefer_dark_theme())
+            .unwrap_or(false),
+    }
+}
+
+/// Check whether the theme has changed, and notify everyone if it has.
+///
+/// With `force`, notify them even if the theme settings are the same, because
+/// something like the contents of the icon theme changed.
+fn refresh(force: bool) {
+    let theme = read_theme();
+    let changed = WATCH.with(|watch| {
+        let mut watch = watch.borrow_mut();
+        if !force && watch.current == theme {
+            return false;
+        }
+        debug!("{} {:?}", "Theme changed:".bright_blue(), theme);
+        watch.generation += 1;
+        watch.current = theme.clone();
+        watch
+            .subscribers
+            .retain(|subscriber| subscriber.unbounded_send(theme.clone()).is_ok());
+        true
+    });
+    if changed {
+        wake_all();
+    }
+}
+
+/// Start listening for theme changes, if we aren't already.
+pub(crate) fn watch() {
+    let started = WATCH.with(|watch| {
+        let mut watch = watch.borrow_mut();
+        let started = watch.started;
+        if !started {
+            watch.started = true;
+            watch.current = read_theme();
+        }
+        started
+    });
+    if started {
+        return;
+    }
+    if let Some(settings) = Settings::get_default() {
+        settings.connect_property_gtk_theme_name_notify(|_| refresh(false));
+        settings.connect_property_gtk_icon_theme_name_notify(|_| refresh(false));
+        settings.connect_property_gtk_application_prefer_dark_theme_notify(|_| refresh(false));
+    }
+    if let Some(icon_theme) = IconTheme::get_default() {
+        icon_theme.connect_changed(|_| refresh(true));
+    }
+}
+
+/// Get the current theme.
+pub fn current() -> Theme {
+    watch();
+    WATCH.with(|watch| watch.borrow().current.clone())
+}
+
+/// Get a [`Stream`][Stream] which produces the new [`Theme`][Theme] every
+/// time the theme changes.
+///
+/// [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+/// [Theme]: struct.Theme.html
+pub fn changes() -> UnboundedReceiver<Theme> {
+    watch();
+    let (sender, receiver) = unbounded();
+    WATCH.with(|watch| watch.borrow_mut().subscribers.push(sender));
+    receiver
+}
+
+/// The number of times the theme has changed.
+pub(crate) fn generation() -> usize {
+    WATCH.with(|watch| watch.borrow().generation)
+}
+
+/// Get the current theme if it has changed since `generation`, updating
+/// `generation` to match.
+pub(crate) fn changed_since(generation: &mut usize) -> Option<Theme> {
+    WATCH.with(|watch| {
+        let watch = watch.borrow();
+        if watch.generation == *generation {
+            None
+        } else {
+            *generation = watch.generation;
+            Some(watch.current.clone())
+        }
+    })
+}
@@ -0,0 +1,221 @@
+//! Toasts: brief in-app notifications.
+//!
+//! A toast slides in at the top of the current window for a few seconds to
+//! tell the user something happened, optionally with a button to act on it,
+//! like undoing what just happened. Toasts are shown by the
+//! [`Toaster`][Toaster] widget, so wrap your window's content in one, then
+//! call [`show()`][show] from your component's `update()`:
+//!
+//! ```rust,no_run
+//! # use vgtk::{Component, UpdateAction, VNode};
+//! # use vgtk::toast::{self, Toast};
+//! # #[derive(Clone, Debug)] enum Message { Delete, Undo }
+//! # #[derive(Default)] struct Files;
+//! # impl Component for Files {
+//! #     type Message = Message; type Properties = ();
+//! #     fn create(_props: ()) -> Self { Self::default() }
+//! fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!     match message {
+//!         Message::Delete => {
+//!             // ...
+//!             toast::show(Toast::new("File deleted").action("_Undo", Message::Undo));
+//!             UpdateAction::Render
+//!         }
+//!         Message::Undo => {
+//!             // ...
+//! #           UpdateAction::None
+//!         }
+//!     }
+//! }
+//! # fn view(&self) -> VNode<Self> { unimplemented!() }
+//! # }
+//! ```
+//!
+//! Clicking the action button sends its message to the component which
+//! showed the toast, and dismisses the toast.
+//!
+//! [Toaster]: ../widgets/struct.Toaster.html
+//! [show]: fn.show.html
+
+use std::cell::Cell;
+use std::time::Duration;
+
+use glib::{Cast, ObjectExt};
+use gtk::prelude::*;
+use gtk::{
+    Box as GtkBox, Button, IconSize, Label, Orientation, ReliefStyle, Revealer,
+    RevealerTransitionType, Widget,
+};
+
+use colored::Colorize;
+use log::warn;
+
+use crate::clock;
+use crate::component::{current_sender, current_window};
+use crate::widgets::Toaster;
+
+/// How long toasts stay up for by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The most toasts a window shows at once. Older ones are dismissed to make
+/// room for new ones.
+pub const MAX_TOASTS: usize = 3;
+
+/// A toast to show.
+#[derive(Clone, Debug)]
+pub struct Toast<M = ()> {
+    /// The text to show.
+    pub text: String,
+    /// How long to show the toast for, or `None` to show it until the user
+    /// dismisses it.
+    pub timeout: Option<Duration>,
+    /// The label of the action button, and the message it sends.
+    pub action: Option<(String, M)>,
+}
+
+impl Toast<()> {
+    /// Construct a toast without an action, shown for the
+    /// [`DEFAULT_TIMEOUT`][DEFAULT_TIMEOUT].
+    ///
+    /// [DEFAULT_TIMEOUT]: constant.DEFAULT_TIMEOUT.html
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Toast {
+            text: text.into(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            action: None,
+        }
+    }
+}
+
+impl<M> Toast<M> {
+    /// Set how long to show the toast for, or `None` to show it until the
+    /// user dismisses it.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add an action button with the given label, which sends `message` to
+    /// the component showing the toast.
+    ///
+    /// Prefix a letter with `_` to make it the button's mnemonic.
+    pub fn action<S: Into<String>, N>(self, label: S, message: N) -> Toast<N> {
+        Toast {
+            text: self.text,
+            timeout: self.timeout,
+            action: Some((label.into(), message)),
+        }
+    }
+}
+
+fn dismiss(revealer: &Revealer) {
+    if !revealer.get_reveal_child() {
+        return;
+    }
+    revealer.set_reveal_child(false);
+    revealer.connect_property_child_revealed_notify(|revealer| {
+        if !revealer.get_child_revealed() {
+            #[allow(unsafe_code)]
+            unsafe {
+                revealer.destroy();
+            }
+        }
+    });
+}
+
+/// Show a toast in the current window's [`Toaster`][Toaster].
+///
+/// Call this from inside a component, so the toast knows which window to
+/// appear in and where to send its action's message. Its message type must
+/// be the component's.
+///
+/// [Toaster]: ../widgets/struct.Toaster.html
+pub fn show<M: 'static>(toast: Toast<M>) {
+    let toaster = current_window().and_then(|window| Toaster::find(window.upcast_ref()));
+    let toaster = match toaster {
+        Some(toaster) => toaster,
+        None => {
+            warn!(
+                "{} {}",
+                "Unable to show toast, because the current window has no Toaster:".bright_red(),
+                toast.text.bright_white().bold()
+            );
+            return;
+        }
+    };
+    let toasts = toaster.toasts();
+
+    let content = GtkBox::new(Orientation::Horizontal, 12);
+    content.get_style_context().add_class("app-notification");
+    let label = Label::new(Some(&toast.text));
+    label.set_line_wrap(true);
+    content.add(&label);
+    let revealer = Revealer::new();
+    revealer.set_transition_type(RevealerTransitionType::SlideDown);
+    revealer.add(&content);
+
+    if let Some((text, message)) = toast.action {
+        match current_sender::<M>() {
+            Some(send) => {
+                let button = Button::with_mnemonic(&text);
+                let message = Cell::new(Some(message));
+                let weak = revealer.downgrade();
+                button.connect_clicked(move |_| {
+                    if let Some(message) = message.take() {
+                        send(message);
+                    }
+                    if let Some(revealer) = weak.upgrade() {
+                        dismiss(&revealer);
+                    }
+                });
+                content.add(&button);
+            }
+            None => warn!(
+                "{} {}",
+                "Toast action message isn't for the current component:".bright_red(),
+                toast.text.bright_white().bold()
+            ),
+        }
+    }
+
+    let close = Button::from_icon_name(Some("window-close-symbolic"), IconSize::Button);
+    close.set_relief(ReliefStyle::None);
+    let weak = revealer.downgrade();
+    close.connect_clicked(move |_| {
+        if let Some(revealer) = weak.upgrade() {
+            dismiss(&revealer);
+        }
+    });
+    content.add(&close);
+
+    // Make room for the new toast.
+    let shown: Vec<Widget> = toasts
+        .get_children()
+        .into_iter()
+        .filter(|child| {
+            child
+                .downcast_ref::<Revealer>()
+                .is_some_and(Revealer::get_reveal_child)
+        })
+        .collect();
+    for old in shown
+        .iter()
+        .take((shown.len() + 1).saturating_sub(MAX_TOASTS))
+    {
+        dismiss(old.downcast_ref::<Revealer>().unwrap());
+    }
+
+    toasts.add(&revealer);
+    revealer.show_all();
+    revealer.set_reveal_child(true);
+
+    if let Some(timeout) = toast.timeout {
+        let weak = revealer.downgrade();
+        clock::timeout_add(timeout, move || {
+            if let Some(revealer) = weak.upgrade() {
+                dismiss(&revealer);
+            }
+            glib::Continue(false)
+        });
+    }
+}
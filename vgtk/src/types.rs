@@ -1,5 +1,7 @@
 //! Useful types for GTK extensions.
 
+pub use crate::adjustment::Adjustment;
+
 /// Specifies the position of a widget in a [`Grid`][Grid].
 ///
 /// The primary use of this struct is to fetch the current
@@ -0,0 +1,191 @@
+//! Undo and redo for a component's state.
+//!
+//! A [`History`][History] keeps snapshots of some part of a component's
+//! state, usually the document it's editing. Record a snapshot before each
+//! change the user might want to undo, by making the change through
+//! [`History::edit()`][edit], and handle undo and redo as messages carrying
+//! an [`Action`][Action], which you can send from menus, buttons or key
+//! bindings like any other message. [`can_undo()`][can_undo] and
+//! [`can_redo()`][can_redo] tell you whether to make those sensitive:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, Component, UpdateAction, VNode};
+//! # use vgtk::lib::gtk::*;
+//! use vgtk::undo::{Action, History};
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Add(String),
+//!     History(Action),
+//! }
+//!
+//! #[derive(Default)]
+//! struct Editor {
+//!     lines: Vec<String>,
+//!     history: History<Vec<String>>,
+//! }
+//!
+//! impl Component for Editor {
+//!     type Message = Message;
+//!     type Properties = ();
+//!
+//!     fn create(_props: ()) -> Self {
+//!         Self::default()
+//!     }
+//!
+//!     fn update(&mut self, message: Message) -> UpdateAction<Self> {
+//!         match message {
+//!             Message::Add(line) => self.history.edit(&mut self.lines, |lines| lines.push(line)),
+//!             Message::History(action) => {
+//!                 if !self.history.apply(action, &mut self.lines) {
+//!                     return UpdateAction::None;
+//!                 }
+//!             }
+//!         }
+//!         UpdateAction::Render
+//!     }
+//!
+//!     fn view(&self) -> VNode<Self> {
+//!         gtk! {
+//!             <Box>
+//!                 <Button label="Undo" sensitive=self.history.can_undo()
+//!                         on clicked=|_| Message::History(Action::Undo) />
+//!                 <Button label="Redo" sensitive=self.history.can_redo()
+//!                         on clicked=|_| Message::History(Action::Redo) />
+//!             </Box>
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Snapshots are whole copies of the state, so keep the state they cover
+//! small, or cheap to clone, eg. by keeping large parts of it in an `Rc`.
+//! By default, a history keeps the last 100 snapshots.
+//!
+//! [History]: struct.History.html
+//! [Action]: enum.Action.html
+//! [edit]: struct.History.html#method.edit
+//! [can_undo]: struct.History.html#method.can_undo
+//! [can_redo]: struct.History.html#method.can_redo
+
+use std::collections::VecDeque;
+
+/// Something to do to a [`History`][History], for sending as part of a
+/// component's messages.
+///
+/// [History]: struct.History.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Go back to the state before the last change.
+    Undo,
+    /// Make the last change which was undone again.
+    Redo,
+}
+
+/// Snapshots of a state, for undoing changes to it and redoing them.
+#[derive(Clone, Debug)]
+pub struct History<S> {
+    past: VecDeque<S>,
+    future: Vec<S>,
+    limit: usize,
+}
+
+impl<S> Default for History<S> {
+    fn default() -> Self {
+        History::with_limit(100)
+    }
+}
+
+impl<S> History<S> {
+    /// Make an empty history, which keeps the last 100 snapshots.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Make an empty history, which keeps at most `limit` snapshots, and
+    /// forgets the oldest ones to make room for new ones.
+    pub fn with_limit(limit: usize) -> Self {
+        History {
+            past: VecDeque::new(),
+            future: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Test whether there's a change to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    /// Test whether there's an undone change to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    /// Forget every snapshot, eg. when a new document is opened.
+    pub fn clear(&mut self) {
+        self.past.clear();
+        self.future.clear();
+    }
+
+    /// Put `current` back the way it was before the last change, returning
+    /// `false` if there's nothing to undo.
+    pub fn undo(&mut self, current: &mut S) -> bool {
+        match self.past.pop_back() {
+            Some(previous) => {
+                self.future.push(std::mem::replace(current, previous));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Make the last change which was undone to `current` again, returning
+    /// `false` if there's nothing to redo.
+    pub fn redo(&mut self, current: &mut S) -> bool {
+        match self.future.pop() {
+            Some(next) => {
+                let previous = std::mem::replace(current, next);
+                self.push(previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Undo or redo a change to `current`, returning `false` if there was
+    /// nothing to undo or redo.
+    pub fn apply(&mut self, action: Action, current: &mut S) -> bool {
+        match action {
+            Action::Undo => self.undo(current),
+            Action::Redo => self.redo(current),
+        }
+    }
+
+    fn push(&mut self, snapshot: S) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.past.len() == self.limit {
+            self.past.pop_front();
+        }
+        self.past.push_back(snapshot);
+    }
+}
+
+impl<S: Clone> History<S> {
+    /// Record a snapshot of `current`, before changing it.
+    ///
+    /// This forgets any changes which were undone, as they can't be redone
+    /// once something else has changed.
+    pub fn record(&mut self, current: &S) {
+        self.future.clear();
+        self.push(current.clone());
+    }
+
+    /// Record a snapshot of `current`, then change it with `f`.
+    pub fn edit<R>(&mut self, current: &mut S, f: impl FnOnce(&mut S) -> R) -> R {
+        self.record(current);
+        f(current)
+    }
+}
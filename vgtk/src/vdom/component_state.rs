@@ -1,26 +1,80 @@
-use futures::channel::mpsc::UnboundedSender;
+use futures::channel::{mpsc::UnboundedSender, oneshot};
 use glib::prelude::*;
 use glib::{MainContext, Object};
 use gtk::{prelude::*, Widget};
 
 use std::any::TypeId;
+use std::cell::RefCell;
 use std::marker::PhantomData;
+use std::rc::Rc;
 
+use super::diff::{self, Reason};
+use super::error::{set_property, PatchError, PatchErrorKind, PatchOutcome};
 use crate::component::{Component, ComponentMessage, ComponentTask};
-use crate::scope::Scope;
+use crate::scope::{ParentScope, Scope};
 use crate::vnode::component::AnyProps;
 use crate::vnode::{VComponent, VProperty};
 
+thread_local! {
+    // While a component is being torn down, the subcomponents it has to wait
+    // for.
+    static TEARDOWN: RefCell<Option<Vec<oneshot::Receiver<()>>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, returning channels which close once each subcomponent it
+/// unmounted has been torn down.
+pub(crate) fn teardown(f: impl FnOnce()) -> Vec<oneshot::Receiver<()>> {
+    let outer = TEARDOWN.with(|teardown| teardown.replace(Some(Vec::new())));
+    f();
+    TEARDOWN
+        .with(|teardown| teardown.replace(outer))
+        .unwrap_or_default()
+}
+
 trait PropertiesReceiver {
     fn update(&mut self, props: &AnyProps);
     fn unmounting(&self);
+    fn reparent(&self, parent: ParentScope);
+}
+
+/// The subcomponent's running task, as seen from the state holding it.
+pub(super) struct Task {
+    object: Rc<RefCell<Object>>,
+    state: Box<dyn PropertiesReceiver>,
+    done: Option<oneshot::Receiver<()>>,
+    unmounted: bool,
+    // The parent to send the task's messages to from the next time it's
+    // patched, if it's moved.
+    reparent: Option<ParentScope>,
+}
+
+impl Task {
+    /// Get the task's top level object.
+    pub(super) fn object(&self) -> Object {
+        self.object.borrow().clone()
+    }
+
+    /// Test whether the task has been unmounted.
+    pub(super) fn unmounted(&self) -> bool {
+        self.unmounted
+    }
+
+    /// Send the task's messages to another parent, along with the next
+    /// properties it's given.
+    pub(super) fn reparent_later(&mut self, parent: ParentScope) {
+        self.reparent = Some(parent);
+    }
 }
 
 pub struct ComponentState<Model: Component> {
     parent: PhantomData<Model>,
-    pub(crate) object: Object,
     model_type: TypeId,
-    state: Box<dyn PropertiesReceiver>,
+    name: &'static str,
+    // The key it can be moved elsewhere by, see `VNode::movable_key()`.
+    pub(super) movable_key: Option<u64>,
+    // Shared, so that a keyed subcomponent which moves here from somewhere
+    // else can take the place of one built for it, see `transfer`.
+    task: Rc<RefCell<Task>>,
 }
 
 impl<Model: 'static + Component> ComponentState<Model> {
@@ -30,39 +84,151 @@ impl<Model: 'static + Component> ComponentState<Model> {
         child_props: &[VProperty],
         scope: &Scope<Model>,
     ) -> Self {
-        let (sub_state, object) =
+        let (sub_state, object, done) =
             SubcomponentState::<Child>::new(props, parent, child_props, scope);
         ComponentState {
             parent: PhantomData,
-            object,
             model_type: TypeId::of::<Child>(),
-            state: Box::new(sub_state),
+            name: std::any::type_name::<Child>(),
+            movable_key: None,
+            task: Rc::new(RefCell::new(Task {
+                object,
+                state: Box::new(sub_state),
+                done: Some(done),
+                unmounted: false,
+                reparent: None,
+            })),
+        }
+    }
+
+    /// Take over a subcomponent's task which was taken out of somewhere
+    /// else, giving it the properties it would have been built with here.
+    pub(super) fn adopted(
+        task: Rc<RefCell<Task>>,
+        spec: &VComponent<Model>,
+        parent: Option<&Object>,
+        scope: &Scope<Model>,
+    ) -> Self {
+        task.borrow_mut().reparent_later((spec.parent_scope)(scope));
+        let object = task.borrow().object();
+        for prop in &spec.child_props {
+            (prop.set)(object.upcast_ref(), parent, true);
         }
+        let mut state = ComponentState {
+            parent: PhantomData,
+            model_type: spec.model_type,
+            name: spec.name,
+            movable_key: None,
+            task,
+        };
+        state.update(&spec.props);
+        state
     }
 
-    pub fn patch(
+    // Pass new properties on to the subcomponent, and tell it first if it's
+    // been moved to another parent, so that callbacks in them go there.
+    fn update(&mut self, props: &AnyProps) {
+        let mut task = self.task.borrow_mut();
+        if let Some(parent) = task.reparent.take() {
+            task.state.reparent(parent);
+        }
+        task.state.update(props);
+    }
+
+    pub(crate) fn patch(
         &mut self,
         spec: &VComponent<Model>,
         parent: Option<&Object>,
         _scope: &Scope<Model>,
-    ) -> bool {
+    ) -> Result<PatchOutcome, PatchError> {
         if self.model_type == spec.model_type {
             // Components have same type; update props
+            let object = self.object();
+            let mut changed = Vec::new();
+            let mut failure = None;
             for prop in &spec.child_props {
-                (prop.set)(self.object.upcast_ref(), parent, false);
+                match set_property(&object, prop, parent) {
+                    Ok(true) => changed.push(prop.name),
+                    Ok(false) => {}
+                    Err(error) => {
+                        failure.get_or_insert(error);
+                    }
+                }
             }
-            self.state.update(&spec.props);
-            true
+            // The component's own properties are always passed on to it, so
+            // they count as one more change.
+            let outcome = PatchOutcome {
+                properties: changed.len() + 1,
+                ..PatchOutcome::default()
+            };
+            diff::component(self.name, changed);
+            self.update(&spec.props);
+            failure.map_or(Ok(outcome), Err)
         } else {
             // Component type changed; need to rebuild
-            self.state.unmounting();
-            false
+            self.task.borrow().state.unmounting();
+            let reason = Reason::ComponentChanged {
+                from: self.name.to_string(),
+                to: spec.name.to_string(),
+            };
+            Err(PatchError::new(self.name, PatchErrorKind::Rebuild(reason)))
+        }
+    }
+
+    /// Test whether the subcomponent can be patched to match a spec, rather
+    /// than having to be built again.
+    pub(crate) fn can_patch(&self, spec: &VComponent<Model>) -> bool {
+        self.model_type == spec.model_type
+    }
+
+    /// Hand the subcomponent over to a view of another component, see
+    /// `VNode::retarget()`.
+    pub(crate) fn retarget<C: Component>(self) -> ComponentState<C> {
+        ComponentState {
+            parent: PhantomData,
+            model_type: self.model_type,
+            name: self.name,
+            movable_key: self.movable_key,
+            task: self.task,
         }
     }
 
+    /// Get the type of the subcomponent.
+    pub(crate) fn model_type(&self) -> TypeId {
+        self.model_type
+    }
+
+    /// Get a handle on the subcomponent's task, which stays with this state
+    /// if the task is swapped for another.
+    pub(super) fn task(&self) -> Rc<RefCell<Task>> {
+        self.task.clone()
+    }
+
+    /// Get the type name of the subcomponent.
+    pub(crate) fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the subcomponent's top level object.
+    ///
+    /// This can change over the subcomponent's lifetime, if it has to rebuild
+    /// its widget tree from scratch.
+    pub(crate) fn object(&self) -> Object {
+        self.task.borrow().object()
+    }
+
     pub fn unmount(self) {
-        self.state.unmounting();
-        if let Ok(widget) = self.object.downcast::<Widget>() {
+        let mut task = self.task.borrow_mut();
+        task.unmounted = true;
+        task.state.unmounting();
+        if let Some(done) = task.done.take() {
+            TEARDOWN.with(|teardown| {
+                if let Some(ref mut children) = *teardown.borrow_mut() {
+                    children.push(done);
+                }
+            });
+        }
+        if let Ok(widget) = task.object().downcast::<Widget>() {
             #[allow(unsafe_code)]
             unsafe {
                 widget.destroy();
@@ -81,15 +247,16 @@ impl<Model: 'static + Component> SubcomponentState<Model> {
         parent: Option<&Object>,
         child_props: &[VProperty],
         parent_scope: &Scope<P>,
-    ) -> (Self, Object) {
+    ) -> (Self, Rc<RefCell<Object>>, oneshot::Receiver<()>) {
         let props: Model::Properties = props.unwrap();
-        let (channel, task) = ComponentTask::new(props, parent, Some(parent_scope));
-        let object = task.object().unwrap();
+        let (channel, mut task) = ComponentTask::new(props, parent, Some(parent_scope));
+        let object = task.share_root();
+        let done = task.finished();
         for prop in child_props {
-            (prop.set)(object.upcast_ref(), parent, true);
+            (prop.set)(object.borrow().upcast_ref(), parent, true);
         }
         MainContext::ref_thread_default().spawn_local(task);
-        (SubcomponentState { channel }, object)
+        (SubcomponentState { channel }, object, done)
     }
 }
 
@@ -106,4 +273,10 @@ impl<Model: 'static + Component> PropertiesReceiver for SubcomponentState<Model>
             .unbounded_send(ComponentMessage::Unmounted)
             .expect("failed to send unmount message over system channel")
     }
+
+    fn reparent(&self, parent: ParentScope) {
+        self.channel
+            .unbounded_send(ComponentMessage::Reparent(parent))
+            .expect("failed to send reparent message over system channel")
+    }
 }
@@ -0,0 +1,324 @@
+//! Recording what a patch did to the widget tree, for
+//! `debug::set_log_patches()` and `debug::patches()`.
+
+use std::cell::RefCell;
+use std::fmt::{Display, Error, Formatter};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+
+use glib::ObjectExt;
+
+use super::State;
+use crate::component::Component;
+use crate::vnode::VNode;
+
+/// Why a widget was thrown away and built again instead of being patched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// The view wants a different type of widget in its place.
+    TypeChanged {
+        /// The type of the widget which was there.
+        from: String,
+        /// The type of the widget the view wants.
+        to: String,
+    },
+    /// The view wants a different type of component in its place.
+    ComponentChanged {
+        /// The type of the component which was there.
+        from: String,
+        /// The type of the component the view wants.
+        to: String,
+    },
+    /// A construct-only property of the widget changed, and those can only
+    /// be set when the widget is created.
+    ConstructPropertyChanged {
+        /// The name of the property.
+        property: &'static str,
+    },
+    /// The view wants a component where there was a widget.
+    BecameComponent,
+    /// The view wants a widget where there was a component.
+    BecameWidget,
+    /// An earlier sibling had to be built again, and every child after it
+    /// is built again along with it.
+    SiblingRebuilt,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Reason::TypeChanged { from, to } => write!(f, "type changed from {} to {}", from, to),
+            Reason::ComponentChanged { from, to } => {
+                write!(f, "component changed from {} to {}", from, to)
+            }
+            Reason::ConstructPropertyChanged { property } => {
+                write!(f, "construct-only property {} changed", property)
+            }
+            Reason::BecameComponent => write!(f, "widget became a component"),
+            Reason::BecameWidget => write!(f, "component became a widget"),
+            Reason::SiblingRebuilt => write!(f, "an earlier sibling was rebuilt"),
+        }
+    }
+}
+
+/// What a patch did to a node in the widget tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The node was kept, and patched to match the view.
+    Patched,
+    /// The node is new.
+    Added,
+    /// The node is gone.
+    Removed,
+    /// The node was thrown away and built again.
+    Rebuilt(Reason),
+    /// The node was kept, but its `key` put it in a different place among
+    /// its siblings, and it was patched to match the view.
+    Moved {
+        /// Where it was among its siblings before.
+        from: usize,
+    },
+}
+
+/// What a patch did to one node in the widget tree, and to its children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeDiff {
+    /// The type of the widget, or of the component.
+    pub name: String,
+    /// Whether the node is a component rather than a widget.
+    pub component: bool,
+    /// What happened to the node.
+    pub change: Change,
+    /// The properties which were changed, if it was patched. For a
+    /// component, these are its child properties, and its own properties are
+    /// always passed on to it.
+    pub properties: Vec<&'static str>,
+    /// What happened to the node's children, if it was patched.
+    pub children: Vec<NodeDiff>,
+}
+
+impl NodeDiff {
+    fn new(name: String, component: bool, change: Change) -> Self {
+        NodeDiff {
+            name,
+            component,
+            change,
+            properties: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Test whether the patch left this node and everything under it alone.
+    ///
+    /// Components always count as changed, as they're always given their
+    /// properties again.
+    pub fn is_unchanged(&self) -> bool {
+        !self.component
+            && self.change == Change::Patched
+            && self.properties.is_empty()
+            && self.children.iter().all(NodeDiff::is_unchanged)
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, depth: usize) -> Result<(), Error> {
+        write!(f, "{:indent$}{}", "", self.name, indent = depth * 2)?;
+        if self.component {
+            write!(f, " (component)")?;
+        }
+        match &self.change {
+            Change::Patched if self.properties.is_empty() => {}
+            Change::Patched => write!(f, ": changed {}", self.properties.join(", "))?,
+            Change::Added => write!(f, ": added")?,
+            Change::Removed => write!(f, ": removed")?,
+            Change::Rebuilt(reason) => write!(f, ": rebuilt because {}", reason)?,
+            Change::Moved { from } if self.properties.is_empty() => {
+                write!(f, ": moved from position {}", from)?
+            }
+            Change::Moved { from } => write!(
+                f,
+                ": moved from position {}, changed {}",
+                from,
+                self.properties.join(", ")
+            )?,
+        }
+        writeln!(f)?;
+        let unchanged = self.children.iter().filter(|c| c.is_unchanged()).count();
+        for child in self.children.iter().filter(|c| !c.is_unchanged()) {
+            child.write(f, depth + 1)?;
+        }
+        if unchanged > 0 {
+            writeln!(
+                f,
+                "{:indent$}... {} unchanged",
+                "",
+                unchanged,
+                indent = (depth + 1) * 2
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// What patching a component's widget tree to match a new view did to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchDiff {
+    /// The component's type.
+    pub component: &'static str,
+    /// What happened to the component's top level widget, and everything
+    /// under it.
+    pub root: NodeDiff,
+}
+
+impl Display for PatchDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.root.write(f, 0)
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    log: bool,
+    recording: bool,
+    // The nodes being patched, from the root down.
+    stack: Vec<NodeDiff>,
+    root: Option<NodeDiff>,
+    subscribers: Vec<UnboundedSender<PatchDiff>>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Recorder> = RefCell::new(Default::default());
+}
+
+fn with<R>(f: impl FnOnce(&mut Recorder) -> R) -> R {
+    RECORDER.with(|recorder| f(&mut recorder.borrow_mut()))
+}
+
+pub(crate) fn set_log(enabled: bool) {
+    with(|recorder| recorder.log = enabled);
+}
+
+pub(crate) fn subscribe() -> UnboundedReceiver<PatchDiff> {
+    let (sender, receiver) = unbounded();
+    with(|recorder| recorder.subscribers.push(sender));
+    receiver
+}
+
+/// Test whether a patch is being recorded.
+pub(crate) fn is_recording() -> bool {
+    RECORDER.with(|recorder| recorder.borrow().recording)
+}
+
+/// Start recording a patch, if anyone wants to know about it, or if `always`
+/// is set.
+pub(crate) fn begin(always: bool) {
+    with(|recorder| {
+        recorder.subscribers.retain(|s| !s.is_closed());
+        recorder.recording = always || recorder.log || !recorder.subscribers.is_empty();
+        recorder.stack.clear();
+        recorder.root = None;
+    })
+}
+
+/// Stop recording, and send the diff to any subscribers. Returns the diff, if
+/// one was recorded, along with whether it should be logged.
+pub(crate) fn end(component: &'static str) -> Option<(PatchDiff, bool)> {
+    with(|recorder| {
+        if !std::mem::take(&mut recorder.recording) {
+            return None;
+        }
+        let diff = PatchDiff {
+            component,
+            root: recorder.root.take()?,
+        };
+        recorder
+            .subscribers
+            .retain(|subscriber| subscriber.unbounded_send(diff.clone()).is_ok());
+        Some((diff, recorder.log))
+    })
+}
+
+fn attach(recorder: &mut Recorder, node: NodeDiff) {
+    match recorder.stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => recorder.root = Some(node),
+    }
+}
+
+/// Start patching a widget.
+pub(crate) fn enter(name: impl FnOnce() -> String) {
+    if is_recording() {
+        let node = NodeDiff::new(name(), false, Change::Patched);
+        with(|recorder| recorder.stack.push(node));
+    }
+}
+
+/// Finish patching the widget we last entered.
+pub(crate) fn leave() {
+    if is_recording() {
+        with(|recorder| {
+            if let Some(node) = recorder.stack.pop() {
+                attach(recorder, node);
+            }
+        });
+    }
+}
+
+/// Note that a property of the node being patched was changed.
+pub(crate) fn property(name: &'static str) {
+    if is_recording() {
+        with(|recorder| {
+            if let Some(node) = recorder.stack.last_mut() {
+                node.properties.push(name);
+            }
+        });
+    }
+}
+
+/// Note what happened to a child which wasn't patched like a widget, or to
+/// a top level object which couldn't be patched, given its type name and
+/// whether it's a component.
+pub(crate) fn node(name: impl FnOnce() -> (String, bool), change: Change) {
+    if is_recording() {
+        let (name, component) = name();
+        let node = NodeDiff::new(name, component, change);
+        with(|recorder| attach(recorder, node));
+    }
+}
+
+/// Note that the child which was just patched had been at position `from`
+/// among its siblings, and was moved to match its `key`.
+pub(crate) fn moved(from: usize) {
+    if is_recording() {
+        with(|recorder| {
+            let parent = recorder.stack.last_mut();
+            if let Some(node) = parent.and_then(|parent| parent.children.last_mut()) {
+                node.change = Change::Moved { from };
+            }
+        });
+    }
+}
+
+/// Note that a component was given new properties, along with which of its
+/// child properties changed.
+pub(crate) fn component(name: &'static str, properties: Vec<&'static str>) {
+    if is_recording() {
+        let mut node = NodeDiff::new(name.to_string(), true, Change::Patched);
+        node.properties = properties;
+        with(|recorder| attach(recorder, node));
+    }
+}
+
+/// The type name of what a `VNode` describes, and whether it's a component.
+pub(crate) fn spec_name<Model: Component>(spec: &VNode<Model>) -> (String, bool) {
+    match spec {
+        VNode::Object(object) => (object.object_type.name(), false),
+        VNode::Component(component) => (component.name.to_string(), true),
+    }
+}
+
+/// The type name of what a `State` holds, and whether it's a component.
+pub(crate) fn state_name<Model: 'static + Component>(state: &State<Model>) -> (String, bool) {
+    match state {
+        State::Gtk(state) => (state.object.get_type().name(), false),
+        State::Component(state) => (state.name().to_string(), true),
+    }
+}
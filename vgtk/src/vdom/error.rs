@@ -0,0 +1,147 @@
+//! What patching a widget tree did, or why it couldn't be done.
+
+use std::fmt::{Display, Error, Formatter};
+use std::ops::AddAssign;
+use std::panic::{self, AssertUnwindSafe};
+
+use glib::{Object, ObjectExt};
+
+use super::diff::Reason;
+use crate::error::panic_message;
+use crate::vnode::VProperty;
+
+/// What patching a widget tree did to it, when it worked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PatchOutcome {
+    /// How many properties were set, including child properties.
+    pub(crate) properties: usize,
+    /// How many widgets and components were built and added.
+    pub(crate) added: usize,
+    /// How many widgets and components were taken out and torn down.
+    pub(crate) removed: usize,
+}
+
+impl PatchOutcome {
+    /// Test whether the patch left the widget tree alone.
+    pub(crate) fn is_unchanged(&self) -> bool {
+        *self == PatchOutcome::default()
+    }
+}
+
+impl AddAssign for PatchOutcome {
+    fn add_assign(&mut self, other: Self) {
+        self.properties += other.properties;
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+}
+
+/// Why a widget couldn't be patched to match a component's view.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchErrorKind {
+    /// The widget can't be patched into what the view wants, and has to be
+    /// built again. A component does this by itself.
+    Rebuild(Reason),
+    /// The widget had to be built again, but it's the top level widget of a
+    /// component and has no window or container to put a new one in, like a
+    /// `Popover`, so it was left as it was.
+    RebuildFailed(Reason),
+    /// A property couldn't be set to its new value.
+    PropertyRejected(String),
+    /// A new child couldn't be added to the widget.
+    ChildAddFailed(String),
+    /// A child couldn't be taken out of the widget.
+    ChildRemoveFailed(String),
+}
+
+impl Display for PatchErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            PatchErrorKind::Rebuild(reason) => write!(f, "has to be rebuilt because {}", reason),
+            PatchErrorKind::RebuildFailed(reason) => write!(
+                f,
+                "has to be rebuilt because {}, but has nowhere to put a new one",
+                reason
+            ),
+            PatchErrorKind::PropertyRejected(reason) => write!(f, "was rejected: {}", reason),
+            PatchErrorKind::ChildAddFailed(reason) => {
+                write!(f, "couldn't add a child: {}", reason)
+            }
+            PatchErrorKind::ChildRemoveFailed(reason) => {
+                write!(f, "couldn't remove a child: {}", reason)
+            }
+        }
+    }
+}
+
+/// A widget which couldn't be patched to match a component's view, as given
+/// to [`Component::on_patch_error()`][on_patch_error].
+///
+/// When this happens, the rest of the widget tree is still patched, and the
+/// widget is left as it was, short of whatever failed.
+///
+/// [on_patch_error]: ../trait.Component.html#method.on_patch_error
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchError {
+    /// The type of the widget, or of the component.
+    pub widget: String,
+    /// The property which couldn't be set, if it was a property.
+    pub property: Option<&'static str>,
+    /// What went wrong.
+    pub kind: PatchErrorKind,
+}
+
+impl PatchError {
+    pub(crate) fn new(widget: impl Into<String>, kind: PatchErrorKind) -> Self {
+        PatchError {
+            widget: widget.into(),
+            property: None,
+            kind,
+        }
+    }
+
+    pub(crate) fn property(
+        widget: impl Into<String>,
+        property: &'static str,
+        reason: impl Into<String>,
+    ) -> Self {
+        PatchError {
+            widget: widget.into(),
+            property: Some(property),
+            kind: PatchErrorKind::PropertyRejected(reason.into()),
+        }
+    }
+
+    /// Test whether the widget just has to be built again, rather than
+    /// something having gone wrong.
+    pub fn needs_rebuild(&self) -> bool {
+        matches!(self.kind, PatchErrorKind::Rebuild(_))
+    }
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.property {
+            Some(property) => write!(f, "{} property {} {}", self.widget, property, self.kind),
+            None => write!(f, "{} {}", self.widget, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Set a property of `object` while patching it, returning whether it
+/// changed, or an error if its setter panicked, like when it was given an
+/// object of the wrong type.
+pub(crate) fn set_property(
+    object: &Object,
+    property: &VProperty,
+    parent: Option<&Object>,
+) -> Result<bool, PatchError> {
+    panic::catch_unwind(AssertUnwindSafe(|| (property.set)(object, parent, false))).map_err(
+        |payload| {
+            let name = object.get_type().name();
+            PatchError::property(name, property.name, panic_message(payload))
+        },
+    )
+}
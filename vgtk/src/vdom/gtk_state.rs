@@ -1,28 +1,186 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::os::raw::c_ulong;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
 
-use gio::{Action, ActionExt, ActionMapExt};
-use glib::{prelude::*, Object, SignalHandlerId};
+use gio::{Action, ActionExt, ActionMapExt, ApplicationExt};
+use glib::translate::{ToGlib, ToGlibPtr};
+use glib::{gobject_sys, prelude::*, GString, Object, ParamSpec, SignalHandlerId, Value};
 use gtk::{
     self, prelude::*, Application, ApplicationWindow, Bin, Box as GtkBox, Builder, Container,
-    Dialog, Grid, GridExt, HeaderBar, InfoBar, Menu, MenuButton, MenuItem, Notebook, ShortcutsWindow,
-    Widget, Window,
+    Dialog, FlowBox, FlowBoxChild, Grid, GridExt, HeaderBar, InfoBar, ListBox, ListBoxRow, Menu,
+    MenuButton, MenuItem, Notebook, Paned, ShortcutsWindow, Stack, Widget, Window,
 };
 
-use super::State;
+use super::diff::{self, Change, Reason};
+use super::error::{set_property, PatchError, PatchErrorKind, PatchOutcome};
+use super::{key_of, transfer, State};
+use crate::affinity;
 use crate::component::Component;
+use crate::debug;
+use crate::error;
+use crate::ext::{is_placeholder_titlebar, GridExtHelpers};
 use crate::scope::Scope;
-use crate::vnode::{VHandler, VNode, VObject, VProperty};
+use crate::types::GridPosition;
+use crate::vnode::{VConstructProperty, VHandler, VNode, VObject, VProperty};
 
 pub(crate) struct GtkState<Model: Component> {
     pub(crate) object: Object,
-    handlers: HashMap<(&'static str, &'static str), SignalHandlerId>,
+    // The handlers connected for the current view, in the order they were
+    // declared, with the signal they're connected to and their IDs.
+    handlers: Vec<(&'static str, Cow<'static, str>, SignalHandlerId)>,
     children: Vec<State<Model>>,
+    // The children's `key` attributes, hashed.
+    child_keys: Vec<Option<u64>>,
+    // The names of the properties the object was last given, which can
+    // change from one render to the next if they're spread over it.
+    property_names: Vec<&'static str>,
+    // Whether the object can be reused for a sibling with another key.
+    recycle: bool,
+    // The fingerprints of the property values we last set, which are
+    // forgotten as soon as the object says a property has changed, and the
+    // handler which listens for that, once there's anything to forget.
+    applied: Rc<RefCell<HashMap<&'static str, u64>>>,
+    notify: Option<SignalHandlerId>,
+    tooltip: Option<Tooltip<Model>>,
+}
+
+// A custom tooltip built from a widget's `tooltip` attribute, and the
+// `query-tooltip` handler which shows it.
+struct Tooltip<Model: Component> {
+    state: Box<State<Model>>,
+    // The tooltip's top level widget, which changes if it's rebuilt.
+    widget: Rc<RefCell<Widget>>,
+    handler: SignalHandlerId,
+}
+
+impl<Model: 'static + Component> Tooltip<Model> {
+    fn build(object: &Object, spec: &VNode<Model>, scope: &Scope<Model>) -> Self {
+        let owner = object.downcast_ref::<Widget>().unwrap_or_else(|| {
+            panic!(
+                "only widgets can have tooltips, but {} was given one",
+                object.get_type()
+            )
+        });
+        let state = State::build(spec, None, scope);
+        let widget = Rc::new(RefCell::new(Self::widget(&state)));
+        let shown = widget.clone();
+        owner.set_has_tooltip(true);
+        let handler = owner.connect_query_tooltip(move |_, _, _, _, tooltip| {
+            tooltip.set_custom(Some(&*shown.borrow()));
+            true
+        });
+        Tooltip {
+            state: Box::new(state),
+            widget,
+            handler,
+        }
+    }
+
+    fn widget(state: &State<Model>) -> Widget {
+        let widget = state.widget().unwrap_or_else(|| {
+            panic!(
+                "a tooltip must be a widget, but {} was found",
+                state.object().get_type()
+            )
+        });
+        widget.show();
+        widget
+    }
+
+    fn patch(
+        &mut self,
+        spec: &VNode<Model>,
+        scope: &Scope<Model>,
+    ) -> Result<PatchOutcome, PatchError> {
+        match self.state.patch(spec, None, scope) {
+            Err(error) if error.needs_rebuild() => {
+                let old = std::mem::replace(&mut *self.state, State::build(spec, None, scope));
+                old.unmount();
+                *self.widget.borrow_mut() = Self::widget(&self.state);
+                Ok(PatchOutcome {
+                    added: 1,
+                    removed: 1,
+                    ..PatchOutcome::default()
+                })
+            }
+            result => result,
+        }
+    }
+
+    fn unmount(self, object: &Object) {
+        object.disconnect(self.handler);
+        if let Some(owner) = object.downcast_ref::<Widget>() {
+            owner.set_has_tooltip(false);
+        }
+        self.state.unmount();
+    }
+}
+
+thread_local! {
+    // While a patch is in progress, the handlers it's blocked, to unblock
+    // once it's done.
+    static MUTED: RefCell<Option<Vec<(Object, c_ulong)>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, keeping the handlers of every object it patches from sending
+/// messages until it's done.
+///
+/// Patching a widget can make it emit signals, which aren't the user's
+/// doing, so its handlers are blocked while it's being patched. Only the
+/// handlers of the widgets being patched are, though, so messages sent to
+/// the component from anywhere else in the meantime still get through.
+pub(crate) fn muted<R>(f: impl FnOnce() -> R) -> R {
+    let outer = MUTED.with(|muted| {
+        let mut muted = muted.borrow_mut();
+        let outer = muted.is_some();
+        muted.get_or_insert_with(Vec::new);
+        outer
+    });
+    let result = f();
+    if !outer {
+        let blocked = MUTED.with(|muted| muted.borrow_mut().take().unwrap_or_default());
+        for (object, handle) in blocked {
+            // A handler can have been disconnected since, if its object was
+            // patched again or taken out of the view.
+            #[allow(unsafe_code)]
+            unsafe {
+                let instance = object.to_glib_none().0;
+                if gobject_sys::g_signal_handler_is_connected(instance, handle) != 0 {
+                    gobject_sys::g_signal_handler_unblock(instance, handle);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Block a handler until the patch in progress is done, if there is one.
+fn mute(object: &Object, handle: &SignalHandlerId) {
+    MUTED.with(|muted| {
+        if let Some(ref mut blocked) = *muted.borrow_mut() {
+            glib::signal_handler_block(object, handle);
+            blocked.push((object.clone(), handle.to_glib()));
+        }
+    })
 }
 
 fn build_obj<A: IsA<Object>, Model: Component>(spec: &VObject<Model>) -> A {
     let class = spec.object_type;
     let obj = if let Some(ref cons) = spec.constructor {
         cons()
+    } else if !spec.construct_props.is_empty() {
+        // Construct-only properties have to be given to the object as it's
+        // created, which the builder has no way to do.
+        let props: Vec<(&str, &dyn ToValue)> = spec
+            .construct_props
+            .iter()
+            .map(|prop| (prop.name, &prop.value as &dyn ToValue))
+            .collect();
+        Object::new(class, &props)
+            .unwrap_or_else(|err| panic!("unable to construct {}: {}", class, err))
     } else {
         let mut ui = String::new();
         ui += &format!("<interface><object class=\"{}\"", class);
@@ -39,6 +197,44 @@ fn build_obj<A: IsA<Object>, Model: Component>(spec: &VObject<Model>) -> A {
         .unwrap_or_else(|_| panic!("build_obj: cannot cast {} to {}", class, A::static_type()))
 }
 
+// Test whether two property values are the same, as far as we can tell.
+// Values we can't compare count as the same, so we don't rebuild an object
+// over and over because of them.
+fn same_value(left: &Value, right: &Value) -> bool {
+    if let (Ok(Some(left)), Ok(Some(right))) = (left.get::<Object>(), right.get::<Object>()) {
+        return left == right;
+    }
+    match (left.transform::<String>(), right.transform::<String>()) {
+        (Some(left), Some(right)) => left.get::<String>() == right.get::<String>(),
+        _ => true,
+    }
+}
+
+// Find a construct-only property which an object doesn't have the value of,
+// meaning the object will have to be built again.
+pub(super) fn changed_construct_prop(
+    object: &Object,
+    props: &[VConstructProperty],
+) -> Option<&'static str> {
+    props
+        .iter()
+        .find(|prop| match object.get_property(prop.name) {
+            Ok(value) => !same_value(&value, &prop.value),
+            Err(_) => false,
+        })
+        .map(|prop| prop.name)
+}
+
+// Test whether a property name from an attribute, like `use_underline`, is
+// the same as a property name from GObject, like `use-underline`.
+fn same_property_name(attribute: &str, property: &str) -> bool {
+    attribute.len() == property.len()
+        && attribute
+            .bytes()
+            .zip(property.bytes())
+            .all(|(left, right)| left == right || (left == b'_' && right == b'-'))
+}
+
 // Gtk has many strange ways of adding children to a parent.
 fn add_child<Model: Component>(
     parent: &Object,
@@ -59,9 +255,13 @@ fn add_child<Model: Component>(
             );
         }
     } else if let Some(parent) = parent.downcast_ref::<InfoBar>() {
+        // InfoBar: children go in its content area, except those with a
+        // `response` child property, which puts them in its action area.
         if let Some(box_) = parent.get_content_area().downcast_ref::<GtkBox>() {
             if let Some(widget) = child.downcast_ref::<Widget>() {
-                if child_spec.get_child_prop("center_widget").is_some() {
+                if child_spec.get_child_prop("response").is_some() {
+                    // The child property adds it to the action area.
+                } else if child_spec.get_child_prop("center_widget").is_some() {
                     box_.set_center_widget(Some(widget));
                 } else {
                     box_.add(widget);
@@ -134,7 +334,11 @@ fn add_child<Model: Component>(
         } else if let Some(widget) = child.downcast_ref::<Widget>() {
             match window.get_child() {
                 None => window.add(widget),
-                Some(ref titlebar) if window.get_titlebar().is_none() => {
+                Some(ref titlebar)
+                    if window
+                        .get_titlebar()
+                        .is_none_or(|titlebar| is_placeholder_titlebar(&titlebar)) =>
+                {
                     window.remove(titlebar);
                     window.set_titlebar(Some(titlebar));
                     window.add(widget);
@@ -260,6 +464,24 @@ fn add_child<Model: Component>(
     }
 }
 
+/// Add a child built while patching, turning a panic, like when the parent
+/// doesn't take children of its type, into an error.
+fn try_add_child<Model: Component>(
+    parent: &Object,
+    index: usize,
+    total: usize,
+    child_spec: &VNode<Model>,
+    child: &Object,
+) -> Result<(), PatchError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        add_child(parent, index, total, child_spec, child)
+    }))
+    .map_err(|payload| {
+        let reason = PatchErrorKind::ChildAddFailed(error::panic_message(payload));
+        PatchError::new(parent.get_type().name(), reason)
+    })
+}
+
 fn remove_child(parent: &Object, child: &Object) {
     // There are also special cases for removing children.
     if let Some(application) = parent.downcast_ref::<Application>() {
@@ -273,6 +495,18 @@ fn remove_child(parent: &Object, child: &Object) {
                 child.get_type()
             );
         }
+    } else if let (Some(_), Some(child_widget)) = (
+        parent.downcast_ref::<InfoBar>(),
+        child.downcast_ref::<Widget>(),
+    ) {
+        // InfoBar: children live in its content or action area, rather than
+        // in the InfoBar itself.
+        if let Some(area) = child_widget
+            .get_parent()
+            .and_then(|area| area.downcast::<Container>().ok())
+        {
+            area.remove(child_widget);
+        }
     } else if let Some(container) = parent.downcast_ref::<Container>() {
         // For a Container and a Widget child, we should always be able to call
         // `Container::remove`.
@@ -292,6 +526,364 @@ fn remove_child(parent: &Object, child: &Object) {
     }
 }
 
+// ListBox and FlowBox wrap children which aren't rows or cells in a row or a
+// cell of their own. Find the row or cell a child was wrapped in, if it was.
+fn wrapper(parent: &Object, child: &Object) -> Option<Widget> {
+    if !parent.is::<ListBox>() && !parent.is::<FlowBox>() {
+        return None;
+    }
+    let wrapper = child.downcast_ref::<Widget>()?.get_parent()?;
+    if wrapper.upcast_ref::<Object>() == parent {
+        None
+    } else {
+        Some(wrapper.upcast())
+    }
+}
+
+// Find the longest run of `values` which is in increasing order, though not
+// necessarily next to each other, returning the indices of its values.
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    // The index of the last value of the best run of each length so far, and
+    // the index of the value before each value in its run.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; values.len()];
+    for (index, value) in values.iter().enumerate() {
+        let length = match tails.binary_search_by(|tail| values[*tail].cmp(value)) {
+            Ok(length) | Err(length) => length,
+        };
+        if length > 0 {
+            previous[index] = Some(tails[length - 1]);
+        }
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+    let mut run = Vec::with_capacity(tails.len());
+    let mut next = tails.last().copied();
+    while let Some(index) = next {
+        run.push(index);
+        next = previous[index];
+    }
+    run.reverse();
+    run
+}
+
+/// The position of a widget inside its parent container, or of a top level
+/// window in its application, so that a replacement widget can be put in the
+/// same place.
+pub(crate) enum Slot {
+    Child {
+        container: Container,
+        packing: Packing,
+        visible: bool,
+    },
+    Toplevel {
+        application: Option<Application>,
+        transient_for: Option<Window>,
+        visible: bool,
+    },
+}
+
+/// How a widget was added to its container, and where, with the child
+/// properties which went with it.
+pub(crate) enum Packing {
+    Box(bool, bool, u32, gtk::PackType, i32),
+    BoxCenter,
+    Grid(GridPosition),
+    Stack {
+        position: i32,
+        name: Option<GString>,
+        title: Option<GString>,
+        icon_name: Option<GString>,
+        needs_attention: bool,
+        current: bool,
+    },
+    Notebook {
+        page: u32,
+        tab_label: Option<Widget>,
+        menu_label: Option<Widget>,
+        tab_expand: bool,
+        tab_fill: bool,
+        reorderable: bool,
+        detachable: bool,
+        current: bool,
+    },
+    NotebookAction(gtk::PackType),
+    HeaderBar(gtk::PackType, i32),
+    CustomTitle,
+    Paned(bool, bool, bool),
+    ListBox(i32),
+    FlowBox(i32),
+    Plain,
+}
+
+impl Packing {
+    fn find(container: &Container, widget: &Widget) -> Option<Self> {
+        let packing = if let Some(box_) = container.downcast_ref::<GtkBox>() {
+            if box_.get_center_widget().as_ref() == Some(widget) {
+                Packing::BoxCenter
+            } else {
+                let (expand, fill, padding, pack_type) = box_.query_child_packing(widget);
+                let position = box_.get_child_position(widget);
+                Packing::Box(expand, fill, padding, pack_type, position)
+            }
+        } else if let Some(grid) = container.downcast_ref::<Grid>() {
+            Packing::Grid(grid.get_child_position(widget))
+        } else if let Some(stack) = container.downcast_ref::<Stack>() {
+            Packing::Stack {
+                position: stack.get_child_position(widget),
+                name: stack.get_child_name(widget),
+                title: stack.get_child_title(widget),
+                icon_name: stack.get_child_icon_name(widget),
+                needs_attention: stack.get_child_needs_attention(widget),
+                current: stack.get_visible_child().as_ref() == Some(widget),
+            }
+        } else if let Some(notebook) = container.downcast_ref::<Notebook>() {
+            let action = [gtk::PackType::Start, gtk::PackType::End]
+                .iter()
+                .copied()
+                .find(|pack_type| notebook.get_action_widget(*pack_type).as_ref() == Some(widget));
+            match action {
+                Some(pack_type) => Packing::NotebookAction(pack_type),
+                None => Packing::Notebook {
+                    page: notebook.page_num(widget)?,
+                    // The labels are widgets of their own, which outlive the
+                    // page as long as we hold on to them.
+                    tab_label: notebook.get_tab_label(widget),
+                    menu_label: notebook.get_menu_label(widget),
+                    tab_expand: notebook.get_child_tab_expand(widget),
+                    tab_fill: notebook.get_child_tab_fill(widget),
+                    reorderable: notebook.get_tab_reorderable(widget),
+                    detachable: notebook.get_tab_detachable(widget),
+                    current: notebook.get_current_page() == notebook.page_num(widget),
+                },
+            }
+        } else if let Some(header_bar) = container.downcast_ref::<HeaderBar>() {
+            if header_bar.get_custom_title().as_ref() == Some(widget) {
+                Packing::CustomTitle
+            } else {
+                let pack_type = header_bar.get_child_pack_type(widget);
+                Packing::HeaderBar(pack_type, header_bar.get_child_position(widget))
+            }
+        } else if let Some(paned) = container.downcast_ref::<Paned>() {
+            Packing::Paned(
+                paned.get_child1().as_ref() == Some(widget),
+                paned.get_child_resize(widget),
+                paned.get_child_shrink(widget),
+            )
+        } else if container.is::<ListBox>() {
+            Packing::ListBox(widget.downcast_ref::<ListBoxRow>()?.get_index())
+        } else if container.is::<FlowBox>() {
+            Packing::FlowBox(widget.downcast_ref::<FlowBoxChild>()?.get_index())
+        } else {
+            Packing::Plain
+        };
+        Some(packing)
+    }
+
+    /// Add `widget` to `container` as the widget this was found for was.
+    fn add(&self, container: &Container, widget: &Widget) {
+        match self {
+            Packing::Box(expand, fill, padding, pack_type, position) => {
+                let box_ = container.downcast_ref::<GtkBox>().unwrap();
+                box_.add(widget);
+                box_.set_child_packing(widget, *expand, *fill, *padding, *pack_type);
+                box_.reorder_child(widget, *position);
+            }
+            Packing::BoxCenter => {
+                let box_ = container.downcast_ref::<GtkBox>().unwrap();
+                box_.set_center_widget(Some(widget));
+            }
+            Packing::Grid(position) => {
+                let grid = container.downcast_ref::<Grid>().unwrap();
+                grid.attach(
+                    widget,
+                    position.left,
+                    position.top,
+                    position.width,
+                    position.height,
+                );
+            }
+            Packing::Stack {
+                position,
+                name,
+                title,
+                icon_name,
+                needs_attention,
+                ..
+            } => {
+                let stack = container.downcast_ref::<Stack>().unwrap();
+                stack.add(widget);
+                stack.set_child_name(widget, name.as_deref());
+                stack.set_child_title(widget, title.as_deref());
+                stack.set_child_icon_name(widget, icon_name.as_deref());
+                stack.set_child_needs_attention(widget, *needs_attention);
+                stack.set_child_position(widget, *position);
+            }
+            Packing::Notebook {
+                page,
+                tab_label,
+                menu_label,
+                tab_expand,
+                tab_fill,
+                reorderable,
+                detachable,
+                ..
+            } => {
+                let notebook = container.downcast_ref::<Notebook>().unwrap();
+                notebook.insert_page_menu(
+                    widget,
+                    tab_label.as_ref(),
+                    menu_label.as_ref(),
+                    Some(*page),
+                );
+                notebook.set_child_tab_expand(widget, *tab_expand);
+                notebook.set_child_tab_fill(widget, *tab_fill);
+                notebook.set_tab_reorderable(widget, *reorderable);
+                notebook.set_tab_detachable(widget, *detachable);
+            }
+            Packing::NotebookAction(pack_type) => {
+                let notebook = container.downcast_ref::<Notebook>().unwrap();
+                notebook.set_action_widget(widget, *pack_type);
+            }
+            Packing::HeaderBar(pack_type, position) => {
+                let header_bar = container.downcast_ref::<HeaderBar>().unwrap();
+                match pack_type {
+                    gtk::PackType::End => header_bar.pack_end(widget),
+                    _ => header_bar.pack_start(widget),
+                }
+                header_bar.set_child_position(widget, *position);
+            }
+            Packing::CustomTitle => {
+                let header_bar = container.downcast_ref::<HeaderBar>().unwrap();
+                header_bar.set_custom_title(Some(widget));
+            }
+            Packing::Paned(first, resize, shrink) => {
+                let paned = container.downcast_ref::<Paned>().unwrap();
+                if *first {
+                    paned.pack1(widget, *resize, *shrink);
+                } else {
+                    paned.pack2(widget, *resize, *shrink);
+                }
+            }
+            Packing::ListBox(position) => {
+                let list_box = container.downcast_ref::<ListBox>().unwrap();
+                list_box.insert(widget, *position);
+            }
+            Packing::FlowBox(position) => {
+                let flow_box = container.downcast_ref::<FlowBox>().unwrap();
+                flow_box.insert(widget, *position);
+            }
+            Packing::Plain => container.add(widget),
+        }
+    }
+
+    /// Make `widget` the container's current child again, if the widget this
+    /// was found for was, which only works once it's visible.
+    fn select(&self, container: &Container, widget: &Widget) {
+        match self {
+            Packing::Stack { current: true, .. } => {
+                let stack = container.downcast_ref::<Stack>().unwrap();
+                stack.set_visible_child(widget);
+            }
+            Packing::Notebook { current: true, .. } => {
+                let notebook = container.downcast_ref::<Notebook>().unwrap();
+                notebook.set_current_page(notebook.page_num(widget));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Slot {
+    pub(crate) fn find(object: &Object) -> Option<Self> {
+        let widget = object.downcast_ref::<Widget>()?;
+        if let Some(window) = widget.downcast_ref::<Window>() {
+            if window.is_toplevel() {
+                return Some(Slot::Toplevel {
+                    application: window.get_application(),
+                    transient_for: window.get_transient_for(),
+                    visible: window.is_visible(),
+                });
+            }
+        }
+        let container = widget.get_parent()?.downcast::<Container>().ok()?;
+        let packing = Packing::find(&container, widget)?;
+        Some(Slot::Child {
+            container,
+            packing,
+            visible: widget.get_visible(),
+        })
+    }
+
+    /// Run `f`, which empties and refills the slot, without letting the
+    /// application quit for lack of windows in between, if the slot held its
+    /// only one.
+    pub(crate) fn hold<R>(&self, f: impl FnOnce() -> R) -> R {
+        match self {
+            Slot::Toplevel {
+                application: Some(application),
+                ..
+            } => {
+                application.hold();
+                let result = f();
+                application.release();
+                result
+            }
+            _ => f(),
+        }
+    }
+
+    pub(crate) fn fill(&self, object: &Object) {
+        let widget = object.downcast_ref::<Widget>().unwrap_or_else(|| {
+            panic!(
+                "can't put a {} in place of a Widget in a {}",
+                object.get_type(),
+                match self {
+                    Slot::Child { container, .. } => container.get_type(),
+                    Slot::Toplevel { .. } => Window::static_type(),
+                }
+            )
+        });
+        match self {
+            Slot::Child {
+                container,
+                packing,
+                visible,
+            } => {
+                packing.add(container, widget);
+                widget.set_visible(*visible);
+                packing.select(container, widget);
+            }
+            Slot::Toplevel {
+                application,
+                transient_for,
+                visible,
+            } => {
+                let window = widget.downcast_ref::<Window>().unwrap_or_else(|| {
+                    panic!(
+                        "can't put a {} in place of a top level Window",
+                        object.get_type()
+                    )
+                });
+                if let Some(application) = application {
+                    application.add_window(window);
+                }
+                // The view can say what the window belongs to, but if it
+                // doesn't, it goes on belonging to the same window.
+                if window.get_transient_for().is_none() {
+                    window.set_transient_for(transient_for.as_ref());
+                }
+                if *visible {
+                    window.show();
+                }
+            }
+        }
+    }
+}
+
 impl<Model: 'static + Component> GtkState<Model> {
     // This function build the root object, but not its children. You must call
     // `build_children()` to finalise construction.
@@ -301,7 +893,8 @@ impl<Model: 'static + Component> GtkState<Model> {
         scope: &Scope<Model>,
     ) -> Self {
         // Build this object
-        let object: Object = build_obj(&vobj);
+        let object: Object = build_obj(vobj);
+        affinity::tag(&object, scope.name());
 
         // Apply properties
         for prop in &vobj.properties {
@@ -309,16 +902,29 @@ impl<Model: 'static + Component> GtkState<Model> {
         }
 
         // Apply handlers
-        let mut handlers = HashMap::new();
+        let mut handlers = Vec::with_capacity(vobj.handlers.len());
         for handler in &vobj.handlers {
             let handle = (handler.set)(object.upcast_ref(), scope);
-            handlers.insert((handler.name, handler.id), handle);
+            mute(&object, &handle);
+            handlers.push((handler.name, handler.id.clone(), handle));
         }
+        debug::record_handlers(&object, &handlers);
+
+        let tooltip = vobj
+            .tooltip
+            .as_ref()
+            .map(|tooltip| Tooltip::build(&object, tooltip, scope));
 
         GtkState {
             object: object.upcast(),
             handlers,
             children: Vec::new(),
+            child_keys: Vec::new(),
+            property_names: vobj.properties.iter().map(|prop| prop.name).collect(),
+            recycle: vobj.recycle,
+            applied: Default::default(),
+            notify: None,
+            tooltip,
         }
     }
 
@@ -327,11 +933,12 @@ impl<Model: 'static + Component> GtkState<Model> {
         // Build children
         let total_children = vobj.children.len();
         for (index, child_spec) in vobj.children.iter().enumerate() {
-            let child = State::build(child_spec, Some(&object), &scope);
-            let child_object = child.object().clone();
-            add_child(&object, index, total_children, child_spec, &child_object);
+            let child = State::build(child_spec, Some(object), scope);
+            let child_object = child.object();
+            add_child(object, index, total_children, child_spec, &child_object);
             self.children.push(child);
         }
+        self.child_keys = vobj.children.iter().map(VNode::key).collect();
 
         // Show this object, if it's a widget
         if let Some(widget) = self.object.downcast_ref::<Widget>() {
@@ -339,6 +946,88 @@ impl<Model: 'static + Component> GtkState<Model> {
         }
     }
 
+    /// Carry on building the children, which `build_root()` left out, until
+    /// `budget` objects have been built, returning `true` once they're all
+    /// done.
+    ///
+    /// Children are built depth first, so only the last one built so far
+    /// can be unfinished. If `attach` is `false`, this object's own
+    /// children aren't added to it yet, which is left to `attach_children()`.
+    pub(crate) fn build_some_children(
+        &mut self,
+        vobj: &VObject<Model>,
+        scope: &Scope<Model>,
+        budget: &mut usize,
+        attach: bool,
+    ) -> bool {
+        let last_spec = self
+            .children
+            .len()
+            .checked_sub(1)
+            .map(|last| &vobj.children[last]);
+        if let (Some(State::Gtk(last)), Some(VNode::Object(last_spec))) =
+            (self.children.last_mut(), last_spec)
+        {
+            if !last.build_some_children(last_spec, scope, budget, true) {
+                return false;
+            }
+        }
+        let total_children = vobj.children.len();
+        while self.children.len() < total_children {
+            if *budget == 0 {
+                return false;
+            }
+            *budget -= 1;
+            let index = self.children.len();
+            let child_spec = &vobj.children[index];
+            let mut child = match child_spec {
+                VNode::Object(child_obj) => {
+                    State::Gtk(GtkState::build_root(child_obj, Some(&self.object), scope))
+                }
+                VNode::Component(_) => State::build(child_spec, Some(&self.object), scope),
+            };
+            if attach {
+                add_child(
+                    &self.object,
+                    index,
+                    total_children,
+                    child_spec,
+                    &child.object(),
+                );
+            }
+            let done = match (&mut child, child_spec) {
+                (State::Gtk(state), VNode::Object(child_obj)) => {
+                    state.build_some_children(child_obj, scope, budget, true)
+                }
+                _ => true,
+            };
+            self.children.push(child);
+            if !done {
+                return false;
+            }
+        }
+        self.child_keys = vobj.children.iter().map(VNode::key).collect();
+        if let Some(widget) = self.object.downcast_ref::<Widget>() {
+            widget.show();
+        }
+        true
+    }
+
+    /// Add the children built by `build_some_children()` with `attach`
+    /// turned off.
+    pub(crate) fn attach_children(&self, vobj: &VObject<Model>) {
+        let total_children = vobj.children.len();
+        for (index, (child_spec, child)) in vobj.children.iter().zip(&self.children).enumerate() {
+            add_child(
+                &self.object,
+                index,
+                total_children,
+                child_spec,
+                &child.object(),
+            );
+        }
+    }
+
     pub(crate) fn build(
         vobj: &VObject<Model>,
         parent: Option<&Object>,
@@ -354,24 +1043,114 @@ impl<Model: 'static + Component> GtkState<Model> {
         vobj: &VObject<Model>,
         parent: Option<&Object>,
         scope: &Scope<Model>,
-    ) -> bool {
+    ) -> Result<PatchOutcome, PatchError> {
+        diff::enter(|| self.object.get_type().name());
+        for (_, _, handle) in &self.handlers {
+            mute(&self.object, handle);
+        }
+        self.recycle = vobj.recycle;
+        let mut outcome = PatchOutcome::default();
         // Patch children
+        let children = if vobj.children.iter().any(|child| child.key().is_some())
+            || self.child_keys.iter().any(Option::is_some)
+        {
+            self.patch_keyed_children(vobj, scope, &mut outcome)
+        } else {
+            self.patch_children(vobj, scope, &mut outcome)
+        };
+        self.child_keys = match children {
+            Ok(()) => vobj.children.iter().map(VNode::key).collect(),
+            // Children which couldn't be added are missing.
+            Err(_) => self
+                .children
+                .iter()
+                .map(|child| key_of(&child.object()))
+                .collect(),
+        };
+
+        // Patch properties
+        let reset = self.reset_removed_properties(&vobj.properties, &mut outcome);
+        let properties = self.patch_properties(&vobj.properties, parent, true, &mut outcome);
+
+        // Patch child properties
+        let child_props = self.patch_properties(&vobj.child_props, parent, false, &mut outcome);
+
+        // Patch handlers
+        self.patch_handlers(&vobj.handlers, scope);
+
+        // Patch the tooltip
+        let tooltip = self.patch_tooltip(vobj.tooltip.as_deref(), scope, &mut outcome);
+
+        diff::leave();
+        children
+            .and(reset)
+            .and(properties)
+            .and(child_props)
+            .and(tooltip)?;
+        Ok(outcome)
+    }
+
+    fn patch_tooltip(
+        &mut self,
+        spec: Option<&VNode<Model>>,
+        scope: &Scope<Model>,
+        outcome: &mut PatchOutcome,
+    ) -> Result<(), PatchError> {
+        match (self.tooltip.take(), spec) {
+            (Some(mut tooltip), Some(spec)) => {
+                let patched = tooltip.patch(spec, scope);
+                self.tooltip = Some(tooltip);
+                *outcome += patched?;
+            }
+            (None, Some(spec)) => {
+                diff::node(|| diff::spec_name(spec), Change::Added);
+                self.tooltip = Some(Tooltip::build(&self.object, spec, scope));
+                outcome.added += 1;
+            }
+            (Some(tooltip), None) => {
+                diff::node(|| diff::state_name(&tooltip.state), Change::Removed);
+                tooltip.unmount(&self.object);
+                outcome.removed += 1;
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    // Patch children by position, rebuilding every child from the first one
+    // which can't be patched onwards.
+    fn patch_children(
+        &mut self,
+        vobj: &VObject<Model>,
+        scope: &Scope<Model>,
+        outcome: &mut PatchOutcome,
+    ) -> Result<(), PatchError> {
         let mut to_remove = None;
         let mut to_append = Vec::new();
         let mut reconstruct_from = None;
+        let mut failure = None;
         for index in 0..(self.children.len().max(vobj.children.len())) {
             match (self.children.get_mut(index), vobj.children.get(index)) {
                 (Some(State::Component(target)), Some(spec_item)) => {
                     match spec_item {
                         VNode::Object(_) => {
                             // Component has become a widget; reconstruct from here
-                            reconstruct_from = Some(index);
+                            reconstruct_from = Some((index, Reason::BecameWidget));
                             break;
                         }
                         VNode::Component(ref spec) => {
-                            if !target.patch(spec, Some(&self.object), scope) {
-                                reconstruct_from = Some(index);
-                                break;
+                            match target.patch(spec, Some(&self.object), scope) {
+                                Ok(patched) => *outcome += patched,
+                                Err(PatchError {
+                                    kind: PatchErrorKind::Rebuild(reason),
+                                    ..
+                                }) => {
+                                    reconstruct_from = Some((index, reason));
+                                    break;
+                                }
+                                Err(error) => {
+                                    failure.get_or_insert(error);
+                                }
                             }
                         }
                     }
@@ -379,18 +1158,35 @@ impl<Model: 'static + Component> GtkState<Model> {
                 (Some(State::Gtk(target)), Some(spec_item)) => {
                     match spec_item {
                         VNode::Object(ref spec) => {
-                            if target.object.get_type() == spec.object_type {
-                                // Objects have same type; patch down
-                                target.patch(spec, Some(&self.object), scope);
-                            } else {
+                            if target.object.get_type() != spec.object_type {
                                 // Objects are different, need to reconstruct everything from here
-                                reconstruct_from = Some(index);
+                                let reason = Reason::TypeChanged {
+                                    from: target.object.get_type().name(),
+                                    to: spec.object_type.name(),
+                                };
+                                reconstruct_from = Some((index, reason));
                                 break;
+                            } else if let Some(property) =
+                                changed_construct_prop(&target.object, &spec.construct_props)
+                            {
+                                // A construct-only property has changed, so
+                                // this object has to be built again too
+                                let reason = Reason::ConstructPropertyChanged { property };
+                                reconstruct_from = Some((index, reason));
+                                break;
+                            } else {
+                                // Objects have same type; patch down
+                                match target.patch(spec, Some(&self.object), scope) {
+                                    Ok(patched) => *outcome += patched,
+                                    Err(error) => {
+                                        failure.get_or_insert(error);
+                                    }
+                                }
                             }
                         }
                         VNode::Component(_) => {
                             // Gtk object has turned into a component; reconstruct from here
-                            reconstruct_from = Some(index);
+                            reconstruct_from = Some((index, Reason::BecameComponent));
                             break;
                         }
                     }
@@ -404,115 +1200,446 @@ impl<Model: 'static + Component> GtkState<Model> {
                 }
                 (None, Some(spec)) => {
                     // New spec; construct
+                    diff::node(|| diff::spec_name(spec), Change::Added);
                     let state = State::build(spec, Some(&self.object), scope);
-                    add_child(
-                        &self.object,
-                        index,
-                        vobj.children.len(),
-                        spec,
-                        state.object(),
-                    );
-                    to_append.push(state);
+                    let total = vobj.children.len();
+                    match try_add_child(&self.object, index, total, spec, &state.object()) {
+                        Ok(()) => to_append.push(state),
+                        Err(error) => {
+                            failure.get_or_insert(error);
+                            state.unmount();
+                        }
+                    }
                 }
                 (None, None) => break,
             }
         }
-        if let Some(index) = reconstruct_from {
+        if let Some((index, reason)) = reconstruct_from {
             // Remove all previous children from here onwards
             if self.object.is::<Window>() && index == 0 && self.children.len() == 2 {
-                panic!("Can't remove a title bar widget from an existing Window!");
+                return Err(self.title_bar_error(PatchErrorKind::ChildRemoveFailed));
+            }
+            // An application quits once it has no windows, so keep it going
+            // while they're replaced.
+            let application = self.object.downcast_ref::<Application>();
+            if let Some(application) = application {
+                application.hold();
             }
+            let old_len = self.children.len();
+            let removed: Vec<_> = if diff::is_recording() {
+                let kept = index.max(vobj.children.len());
+                self.children
+                    .iter()
+                    .skip(kept)
+                    .map(diff::state_name)
+                    .collect()
+            } else {
+                Vec::new()
+            };
             for child in self.children.drain(index..) {
-                remove_child(&self.object, child.object());
+                remove_child(&self.object, &child.object());
                 child.unmount();
+                outcome.removed += 1;
+            }
+            if diff::is_recording() {
+                let mut reason = Some(reason);
+                for (index, child_spec) in vobj.children.iter().enumerate().skip(index) {
+                    let change = if index >= old_len {
+                        Change::Added
+                    } else {
+                        Change::Rebuilt(reason.take().unwrap_or(Reason::SiblingRebuilt))
+                    };
+                    diff::node(|| diff::spec_name(child_spec), change);
+                }
+                for name in removed {
+                    diff::node(|| name, Change::Removed);
+                }
             }
             // Rebuild children from new specs
             for (index, child_spec) in vobj.children.iter().enumerate().skip(index) {
                 let state = State::build(child_spec, Some(&self.object), scope);
-                add_child(
-                    &self.object,
-                    index,
-                    vobj.children.len(),
-                    child_spec,
-                    state.object(),
-                );
+                let total = vobj.children.len();
+                if let Err(error) =
+                    try_add_child(&self.object, index, total, child_spec, &state.object())
+                {
+                    failure.get_or_insert(error);
+                    state.unmount();
+                    continue;
+                }
                 if let Some(w) = state.widget() {
                     w.show()
                 }
                 self.children.push(state);
+                outcome.added += 1;
+            }
+            if let Some(application) = application {
+                application.release();
             }
         } else {
             // Remove children flagged as extraneous
             if let Some(remove_from) = to_remove {
                 if self.object.is::<Window>() && remove_from == 1 && self.children.len() == 2 {
-                    panic!("Can't remove a title bar widget from an existing Window!");
+                    return Err(self.title_bar_error(PatchErrorKind::ChildRemoveFailed));
                 }
                 for child in self.children.drain(remove_from..) {
+                    diff::node(|| diff::state_name(&child), Change::Removed);
                     remove_child(&self.object, &child.object());
                     child.unmount();
+                    outcome.removed += 1;
                 }
             }
             // Or append newly constructed children
             if self.object.is::<Window>() && !to_append.is_empty() && self.children.len() == 1 {
-                panic!("Can't add a title bar widget to an existing Window!");
+                for child in to_append {
+                    remove_child(&self.object, &child.object());
+                    child.unmount();
+                }
+                return Err(self.title_bar_error(PatchErrorKind::ChildAddFailed));
             }
             for child in to_append {
                 if let Some(w) = child.widget() {
                     w.show()
                 }
                 self.children.push(child);
+                outcome.added += 1;
             }
         }
+        failure.map_or(Ok(()), Err)
+    }
 
-        // Patch properties
-        self.patch_properties(&vobj.properties, parent);
+    // The error for trying to add or remove a Window's title bar, which GTK
+    // can't do once the Window exists.
+    fn title_bar_error(&self, kind: fn(String) -> PatchErrorKind) -> PatchError {
+        let reason = "a Window's title bar can't be added or removed once it exists";
+        PatchError::new(self.object.get_type().name(), kind(reason.to_string()))
+    }
 
-        // Patch child properties
-        self.patch_properties(&vobj.child_props, parent);
+    // Patch children which have `key` attributes, by matching each of them up
+    // with the old child which had the same key, wherever it was, so that it
+    // keeps its widget. Children without keys are matched up with the old
+    // children without keys in order.
+    fn patch_keyed_children(
+        &mut self,
+        vobj: &VObject<Model>,
+        scope: &Scope<Model>,
+        outcome: &mut PatchOutcome,
+    ) -> Result<(), PatchError> {
+        let old_keys = std::mem::take(&mut self.child_keys);
+        let mut old: Vec<Option<State<Model>>> = self.children.drain(..).map(Some).collect();
+        let old_key = |index: usize| old_keys.get(index).copied().flatten();
+        let mut by_key = HashMap::new();
+        for index in (0..old.len()).rev() {
+            if let Some(key) = old_key(index) {
+                by_key.insert(key, index);
+            }
+        }
+        let mut unkeyed = (0..old.len()).filter(|index| old_key(*index).is_none());
+        let matches: Vec<Option<usize>> = vobj
+            .children
+            .iter()
+            .map(|spec| match spec.key() {
+                Some(key) => by_key.remove(&key),
+                None => unkeyed.next(),
+            })
+            .collect();
 
-        // Patch handlers
-        self.patch_handlers(&vobj.handlers, scope);
+        // Where each new child used to be, if it can be kept, and which of
+        // the kept children have to move for the others to stay put.
+        let mut origins: Vec<Option<usize>> = matches
+            .iter()
+            .zip(&vobj.children)
+            .map(|(index, spec)| {
+                index.filter(|index| old[*index].as_ref().unwrap().can_patch(spec))
+            })
+            .collect();
 
-        true
-    }
+        // Children whose keys have gone can be reused for new children
+        // built the same way, if both were marked `key(recycle)`, rather
+        // than being thrown away while new ones are built.
+        let recyclable = |state: &Option<State<Model>>| matches!(state, Some(State::Gtk(ref state)) if state.recycle);
+        let mut spare: Vec<usize> = (0..old.len())
+            .filter(|index| !origins.contains(&Some(*index)) && recyclable(&old[*index]))
+            .collect();
+        for (origin, spec) in origins.iter_mut().zip(&vobj.children) {
+            if origin.is_some() || !matches!(spec, VNode::Object(object) if object.recycle) {
+                continue;
+            }
+            let reusable = spare
+                .iter()
+                .position(|index| old[*index].as_ref().unwrap().can_patch(spec));
+            if let Some(position) = reusable {
+                *origin = Some(spare.remove(position));
+            }
+        }
 
-    fn patch_properties(&mut self, properties: &[VProperty], parent: Option<&Object>) {
-        for prop in properties {
-            (prop.set)(self.object.upcast_ref(), parent, false);
+        let kept: Vec<usize> = origins.iter().copied().flatten().collect();
+        let mut moved = vec![true; kept.len()];
+        for index in longest_increasing(&kept) {
+            moved[index] = false;
+        }
+        let mut moved = moved.into_iter();
+        let mut failure = None;
+
+        for ((spec, index), origin) in vobj.children.iter().zip(&matches).zip(&origins) {
+            if let Some(origin) = origin {
+                let mut state = old[*origin].take().unwrap();
+                match state.patch(spec, Some(&self.object), scope) {
+                    Ok(patched) => *outcome += patched,
+                    Err(error) => {
+                        failure.get_or_insert(error);
+                    }
+                }
+                if moved.next().unwrap_or(false) {
+                    diff::moved(*origin);
+                }
+                self.children.push(state);
+                continue;
+            }
+            match index.and_then(|index| old[index].take()) {
+                Some(state) => {
+                    let reason = state.rebuild_reason(spec);
+                    diff::node(|| diff::spec_name(spec), Change::Rebuilt(reason));
+                    remove_child(&self.object, &state.object());
+                    state.unmount();
+                    outcome.removed += 1;
+                }
+                None => diff::node(|| diff::spec_name(spec), Change::Added),
+            }
+            self.children
+                .push(State::build(spec, Some(&self.object), scope));
+        }
+        for state in old.into_iter().flatten() {
+            diff::node(|| diff::state_name(&state), Change::Removed);
+            remove_child(&self.object, &state.object());
+            match state {
+                // It may be on its way somewhere else.
+                State::Component(state) => transfer::depart(state, &self.object),
+                state => state.unmount(),
+            }
+            outcome.removed += 1;
         }
+
+        // The kept children are still in the container in their old order,
+        // so put them, and the new children, where they belong, leaving out
+        // any new children which can't be added.
+        let total = vobj.children.len();
+        let mut failed = Vec::new();
+        let children = self.children.iter().zip(&vobj.children).zip(&origins);
+        if let Some(box_) = self.object.downcast_ref::<GtkBox>() {
+            let mut position = 0;
+            for (index, ((state, spec), origin)) in children.enumerate() {
+                let object = state.object();
+                if origin.is_none() {
+                    if let Err(error) = try_add_child(&self.object, index, total, spec, &object) {
+                        failure.get_or_insert(error);
+                        failed.push(index);
+                        continue;
+                    }
+                    outcome.added += 1;
+                }
+                // The center widget isn't one of the Box's list of children.
+                if let (Some(widget), None) = (state.widget(), spec.get_child_prop("center_widget"))
+                {
+                    box_.reorder_child(&widget, position);
+                    position += 1;
+                    if origin.is_none() {
+                        widget.show();
+                    }
+                }
+            }
+        } else {
+            // Other containers can only add children at the end, so take out
+            // every kept child from the first one which isn't in its old
+            // place, and add them back in the right order.
+            let mut in_order = kept.clone();
+            in_order.sort_unstable();
+            let in_place = origins
+                .iter()
+                .zip(&in_order)
+                .take_while(|(origin, index)| **origin == Some(**index))
+                .count();
+            let mut taken = Vec::new();
+            for ((state, _), origin) in children.clone().skip(in_place) {
+                let object = state.object();
+                taken.push(match (origin, wrapper(&self.object, &object)) {
+                    (None, _) => None,
+                    (Some(_), Some(wrapper)) => {
+                        self.object
+                            .downcast_ref::<Container>()
+                            .unwrap()
+                            .remove(&wrapper);
+                        Some(wrapper)
+                    }
+                    (Some(_), None) => {
+                        remove_child(&self.object, &object);
+                        None
+                    }
+                });
+            }
+            let children = children.zip(taken).enumerate().skip(in_place);
+            for (index, (((state, spec), origin), wrapper)) in children {
+                match wrapper {
+                    Some(wrapper) => self
+                        .object
+                        .downcast_ref::<Container>()
+                        .unwrap()
+                        .add(&wrapper),
+                    None => {
+                        let object = state.object();
+                        if let Err(error) = try_add_child(&self.object, index, total, spec, &object)
+                        {
+                            failure.get_or_insert(error);
+                            failed.push(index);
+                            continue;
+                        }
+                        outcome.added += 1;
+                    }
+                }
+                if let (Some(widget), None) = (state.widget(), origin) {
+                    widget.show();
+                }
+            }
+        }
+        for index in failed.into_iter().rev() {
+            self.children.remove(index).unmount();
+        }
+        failure.map_or(Ok(()), Err)
     }
 
-    fn patch_handlers(&mut self, handlers: &[VHandler<Model>], scope: &Scope<Model>) {
-        // FIXME need to store and match IDs
-        let mut seen = HashSet::new();
-        let mut remove = Vec::new();
-        for handler in handlers {
-            let key = (handler.name, handler.id);
-            seen.insert(key.to_owned());
-            if let std::collections::hash_map::Entry::Vacant(entry) = self.handlers.entry(key) {
-                let handle = (handler.set)(self.object.upcast_ref(), scope);
-                entry.insert(handle);
+    // Put properties which the object isn't being given any more back to
+    // their defaults, which only happens when they were spread over it.
+    fn reset_removed_properties(
+        &mut self,
+        properties: &[VProperty],
+        outcome: &mut PatchOutcome,
+    ) -> Result<(), PatchError> {
+        let names = properties.iter().map(|prop| prop.name);
+        if names.clone().eq(self.property_names.iter().copied()) {
+            return Ok(());
+        }
+        let mut failure = None;
+        let current: HashSet<&'static str> = names.clone().collect();
+        for &name in &self.property_names {
+            if current.contains(name) {
+                continue;
+            }
+            let default = self
+                .object
+                .find_property(name)
+                .and_then(|pspec| pspec.get_default_value());
+            if let Some(default) = default {
+                match self.object.set_property(name, &default) {
+                    Ok(()) => {
+                        diff::property(name);
+                        outcome.properties += 1;
+                    }
+                    Err(error) => {
+                        let widget = self.object.get_type().name();
+                        failure.get_or_insert(PatchError::property(widget, name, error.message));
+                    }
+                }
             }
         }
-        for key in self.handlers.keys() {
-            if !seen.contains(key) {
-                remove.push(key.to_owned());
+        self.property_names = names.collect();
+        failure.map_or(Ok(()), Err)
+    }
+
+    // Child properties belong to the parent, which doesn't tell us when
+    // they change, so we can only `remember` the object's own properties.
+    fn patch_properties(
+        &mut self,
+        properties: &[VProperty],
+        parent: Option<&Object>,
+        remember: bool,
+        outcome: &mut PatchOutcome,
+    ) -> Result<(), PatchError> {
+        let mut failure = None;
+        for prop in properties {
+            let fingerprint = prop.fingerprint.filter(|_| remember);
+            if let Some(fingerprint) = fingerprint {
+                if self.applied.borrow().get(prop.name) == Some(&fingerprint) {
+                    continue;
+                }
+            }
+            match set_property(&self.object, prop, parent) {
+                Ok(changed) => {
+                    if changed {
+                        diff::property(prop.name);
+                        outcome.properties += 1;
+                    }
+                    if let Some(fingerprint) = fingerprint {
+                        self.remember(prop.name, fingerprint);
+                    }
+                }
+                Err(error) => {
+                    failure.get_or_insert(error);
+                }
             }
         }
-        for key in remove {
-            let obj: &Object = self.object.upcast_ref();
-            obj.disconnect(self.handlers.remove(&key).unwrap());
+        failure.map_or(Ok(()), Err)
+    }
+
+    // Remember that a property has the value with this fingerprint, until
+    // the object says it's changed.
+    fn remember(&mut self, name: &'static str, fingerprint: u64) {
+        if self.notify.is_none() {
+            let applied = self.applied.clone();
+            let handler = self.object.connect_local("notify", false, move |args| {
+                if let Ok(Some(pspec)) = args[1].get::<ParamSpec>() {
+                    let changed = pspec.get_name();
+                    applied
+                        .borrow_mut()
+                        .retain(|name, _| !same_property_name(name, &changed));
+                }
+                None
+            });
+            self.notify = handler.ok();
+        }
+        if self.notify.is_some() {
+            self.applied.borrow_mut().insert(name, fingerprint);
         }
     }
 
+    fn patch_handlers(&mut self, handlers: &[VHandler<Model>], scope: &Scope<Model>) {
+        // A handler's closure can capture values from the view it came from,
+        // so keeping the connection from an earlier render, even one with
+        // the same ID, would send messages built from stale values. Instead,
+        // every handler is reconnected to match the current view exactly,
+        // and any rate limiters they use are kept on the object, so they
+        // carry on where they left off.
+        for (_, _, handle) in self.handlers.drain(..) {
+            self.object.disconnect(handle);
+        }
+        for handler in handlers {
+            let handle = (handler.set)(self.object.upcast_ref(), scope);
+            mute(&self.object, &handle);
+            self.handlers
+                .push((handler.name, handler.id.clone(), handle));
+        }
+        debug::record_handlers(&self.object, &self.handlers);
+    }
+
     pub(crate) fn unmount(self) {
         for child in self.children {
             child.unmount();
         }
-        if let Ok(widget) = self.object.downcast::<Widget>() {
-            #[allow(unsafe_code)]
-            unsafe {
-                widget.destroy();
+        if let Some(tooltip) = self.tooltip {
+            tooltip.unmount(&self.object);
+        }
+        match self.object.downcast::<Widget>() {
+            Ok(widget) => {
+                #[allow(unsafe_code)]
+                unsafe {
+                    widget.destroy();
+                }
+            }
+            // Something other than a widget, like an action, can outlive
+            // us, so it mustn't go on sending us messages.
+            Err(object) => {
+                for (_, _, handle) in self.handlers {
+                    object.disconnect(handle);
+                }
+                debug::record_handlers(&object, &[]);
             }
         }
     }
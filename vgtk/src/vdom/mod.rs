@@ -1,4 +1,4 @@
-use glib::{Cast, Object};
+use glib::{Cast, Object, ObjectExt};
 use gtk::{self, Widget};
 
 use crate::component::Component;
@@ -6,10 +6,45 @@ use crate::scope::Scope;
 use crate::vnode::VNode;
 
 mod component_state;
-pub(crate) use component_state::ComponentState;
+pub(crate) use component_state::{teardown, ComponentState};
+
+pub(crate) mod diff;
+use diff::{Change, Reason};
+
+pub(crate) mod error;
+pub(crate) use error::{PatchError, PatchErrorKind, PatchOutcome};
 
 mod gtk_state;
-use gtk_state::GtkState;
+pub(crate) use gtk_state::muted;
+use gtk_state::{changed_construct_prop, GtkState, Slot};
+
+mod transfer;
+
+// The object data key under which an object's `key` attribute is kept.
+const KEY: &str = "vgtk-key";
+
+// Remember the `key` attribute of the node an object was built or patched
+// from, for `key_of()`.
+fn set_key(object: &Object, key: Option<u64>) {
+    #[allow(unsafe_code)]
+    unsafe {
+        match key {
+            Some(key) => object.set_data(KEY, key),
+            None => {
+                object.steal_data::<u64>(KEY);
+            }
+        }
+    }
+}
+
+/// Get the `key` attribute, hashed, of the node an object was last built or
+/// patched from.
+pub(crate) fn key_of(object: &Object) -> Option<u64> {
+    #[allow(unsafe_code)]
+    unsafe {
+        object.get_data::<u64>(KEY).copied()
+    }
+}
 
 pub(crate) enum State<Model: Component> {
     Gtk(GtkState<Model>),
@@ -23,13 +58,17 @@ impl<Model: 'static + Component> State<Model> {
         parent: Option<&Object>,
         scope: &Scope<Model>,
     ) -> Self {
-        match vnode {
+        let state = match vnode {
             VNode::Object(object) => State::Gtk(GtkState::build(object, parent, scope)),
-            VNode::Component(vcomp) => {
-                let comp = (vcomp.constructor)(&vcomp.props, parent, &vcomp.child_props, scope);
-                State::Component(comp)
-            }
+            VNode::Component(vcomp) => State::Component(match vnode.movable_key() {
+                Some(key) => transfer::arrive(vcomp, key, parent, scope),
+                None => (vcomp.constructor)(&vcomp.props, parent, &vcomp.child_props, scope),
+            }),
+        };
+        if let Some(key) = vnode.key() {
+            set_key(&state.object(), Some(key));
         }
+        state
     }
 
     /// Build a full state from a `VItem` spec.
@@ -40,46 +79,163 @@ impl<Model: 'static + Component> State<Model> {
     ) -> Self {
         match vnode {
             VNode::Object(object) => State::Gtk(GtkState::build_root(object, parent, scope)),
-            VNode::Component(_vcomp) => {
-                // let comp = (vcomp.constructor)(&vcomp.props, parent, &vcomp.child_props, scope);
-                // State::Component(comp)
-                unimplemented!()
-            }
+            // A subcomponent builds its own children, so there's nothing to
+            // leave out.
+            VNode::Component(_) => State::build(vnode, parent, scope),
         }
     }
 
+    /// Build the children left out by `build_root()`.
     pub(crate) fn build_children(&mut self, vnode: &VNode<Model>, scope: &Scope<Model>) {
-        match vnode {
-            VNode::Object(vobject) => match self {
-                State::Gtk(gtk_state) => gtk_state.build_children(vobject, scope),
-                _ => unimplemented!(),
-            },
-            _ => unimplemented!(),
+        match (self, vnode) {
+            (State::Gtk(gtk_state), VNode::Object(vobject)) => {
+                gtk_state.build_children(vobject, scope)
+            }
+            (State::Component(_), VNode::Component(_)) => {}
+            _ => unreachable!("a root state is only finished with the view it was built from"),
+        }
+    }
+
+    /// Build up to `budget` of the children left out by `build_root()`,
+    /// without adding the root's own children to it, returning `true` once
+    /// they're all built.
+    pub(crate) fn build_some_children(
+        &mut self,
+        vnode: &VNode<Model>,
+        scope: &Scope<Model>,
+        budget: &mut usize,
+    ) -> bool {
+        match (self, vnode) {
+            (State::Gtk(gtk_state), VNode::Object(vobject)) => {
+                gtk_state.build_some_children(vobject, scope, budget, false)
+            }
+            (State::Component(_), VNode::Component(_)) => true,
+            _ => unreachable!("a root state is only finished with the view it was built from"),
+        }
+    }
+
+    /// Add the root's children, once `build_some_children()` is done.
+    pub(crate) fn attach_children(&self, vnode: &VNode<Model>) {
+        match (self, vnode) {
+            (State::Gtk(gtk_state), VNode::Object(vobject)) => gtk_state.attach_children(vobject),
+            (State::Component(_), VNode::Component(_)) => {}
+            _ => unreachable!("a root state is only finished with the view it was built from"),
         }
     }
 
     /// Patch a state in place with a `VItem` spec.
     ///
-    /// Returns true if patching succeeded, or false if a rebuild is required.
-    #[must_use]
+    /// Fails with a `PatchErrorKind::Rebuild` error if the state has to be
+    /// built again instead, or with any other error if part of the patch
+    /// failed, in which case the rest of it has still been done.
     pub(crate) fn patch(
         &mut self,
         vnode: &VNode<Model>,
         parent: Option<&Object>,
         scope: &Scope<Model>,
-    ) -> bool {
-        match vnode {
+    ) -> Result<PatchOutcome, PatchError> {
+        let result = match vnode {
             VNode::Object(object) => match self {
-                State::Gtk(state) => state.patch(object, parent, scope),
-                State::Component(_) => false,
+                State::Gtk(state)
+                    if state.object.get_type() == object.object_type
+                        && changed_construct_prop(&state.object, &object.construct_props)
+                            .is_none() =>
+                {
+                    state.patch(object, parent, scope)
+                }
+                _ => Err(self.rebuild_error(vnode)),
             },
             VNode::Component(vcomp) => match self {
                 State::Component(state) => state.patch(vcomp, parent, scope),
-                State::Gtk(_) => false,
+                State::Gtk(_) => Err(self.rebuild_error(vnode)),
+            },
+        };
+        match &result {
+            Err(PatchError {
+                kind: PatchErrorKind::Rebuild(reason),
+                ..
+            }) => diff::node(|| diff::spec_name(vnode), Change::Rebuilt(reason.clone())),
+            _ => set_key(&self.object(), vnode.key()),
+        }
+        result
+    }
+
+    /// Test whether this state can be patched to match a `VItem` spec, rather
+    /// than having to be built again.
+    pub(crate) fn can_patch(&self, vnode: &VNode<Model>) -> bool {
+        match (self, vnode) {
+            (State::Gtk(state), VNode::Object(object)) => {
+                state.object.get_type() == object.object_type
+                    && changed_construct_prop(&state.object, &object.construct_props).is_none()
+            }
+            (State::Component(state), VNode::Component(vcomp)) => state.can_patch(vcomp),
+            _ => false,
+        }
+    }
+
+    /// Explain why this state can't be patched to match a `VItem` spec.
+    pub(crate) fn rebuild_reason(&self, vnode: &VNode<Model>) -> Reason {
+        let (from, component) = diff::state_name(self);
+        let (to, _) = diff::spec_name(vnode);
+        match (self, vnode) {
+            (State::Gtk(state), VNode::Object(object))
+                if state.object.get_type() == object.object_type =>
+            {
+                match changed_construct_prop(&state.object, &object.construct_props) {
+                    Some(property) => Reason::ConstructPropertyChanged { property },
+                    None => Reason::TypeChanged { from, to },
+                }
+            }
+            _ => match (component, vnode) {
+                (false, VNode::Object(_)) => Reason::TypeChanged { from, to },
+                (false, VNode::Component(_)) => Reason::BecameComponent,
+                (true, VNode::Object(_)) => Reason::BecameWidget,
+                (true, VNode::Component(_)) => Reason::ComponentChanged { from, to },
             },
         }
     }
 
+    /// The error for when this state can't be patched to match a `VItem`
+    /// spec, and has to be built again.
+    fn rebuild_error(&self, vnode: &VNode<Model>) -> PatchError {
+        let (name, _) = diff::state_name(self);
+        PatchError::new(name, PatchErrorKind::Rebuild(self.rebuild_reason(vnode)))
+    }
+
+    /// Find the place where a replacement for this state would go, which
+    /// fails if it's neither a window nor in a container, like a `Popover`,
+    /// so there's nowhere to put one.
+    pub(crate) fn slot(&self, vnode: &VNode<Model>) -> Result<Slot, PatchError> {
+        Slot::find(&self.object()).ok_or_else(|| {
+            let (name, _) = diff::state_name(self);
+            let reason = self.rebuild_reason(vnode);
+            PatchError::new(name, PatchErrorKind::RebuildFailed(reason))
+        })
+    }
+
+    /// Tear down this state and build a new one from a `VItem` spec in its
+    /// place, found with [`slot()`][slot], at the same position in the parent
+    /// container, or in the same application if it's a top level window.
+    ///
+    /// This is how a component recovers when patching its top level object
+    /// fails, for instance because the object's type has changed.
+    ///
+    /// [slot]: #method.slot
+    pub(crate) fn replace(
+        self,
+        slot: Slot,
+        vnode: &VNode<Model>,
+        parent: Option<&Object>,
+        scope: &Scope<Model>,
+    ) -> Self {
+        slot.hold(|| {
+            self.unmount();
+            let state = State::build(vnode, parent, scope);
+            slot.fill(&state.object());
+            state
+        })
+    }
+
     pub(crate) fn unmount(self) {
         match self {
             State::Gtk(state) => state.unmount(),
@@ -88,18 +244,15 @@ impl<Model: 'static + Component> State<Model> {
     }
 
     /// Get the Glib `Object` represented by this state.
-    pub(crate) fn object(&self) -> &Object {
+    pub(crate) fn object(&self) -> Object {
         match self {
-            State::Gtk(state) => &state.object,
-            State::Component(state) => &state.object,
+            State::Gtk(state) => state.object.clone(),
+            State::Component(state) => state.object(),
         }
     }
 
     /// Get the Gtk `Widget` represented by this state, if it has a `Widget`.
-    pub(crate) fn widget(&self) -> Option<&Widget> {
-        match self {
-            State::Gtk(state) => state.object.downcast_ref::<Widget>(),
-            State::Component(state) => state.object.downcast_ref::<Widget>(),
-        }
+    pub(crate) fn widget(&self) -> Option<Widget> {
+        self.object().downcast::<Widget>().ok()
     }
 }
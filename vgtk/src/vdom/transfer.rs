@@ -0,0 +1,215 @@
+//! Moving keyed subcomponents from one place to another, possibly in another
+//! window, without tearing them down and building them again.
+//!
+//! A keyed subcomponent which leaves a view isn't unmounted straight away:
+//! it's taken out of its container and parked until the main loop is about
+//! to draw. If a subcomponent of the same type with the same key turns up
+//! somewhere else in the meantime, the parked one goes there instead of a new
+//! one being built. Either can happen first, because the two places can be
+//! in the views of different components, rendering in any order.
+
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use glib::{Cast, Continue, Object};
+use gtk::{Widget, WidgetExt, Window};
+
+use super::component_state::Task;
+use super::gtk_state::Slot;
+use super::ComponentState;
+use crate::component::{Component, Moved, RenderHandle};
+use crate::scope::{ParentScope, Scope};
+use crate::vnode::VComponent;
+
+/// A subcomponent which has left a view, waiting to see if it turns up in
+/// another one.
+struct Departure {
+    model_type: TypeId,
+    name: &'static str,
+    key: u64,
+    // Unmounts it from the view of whichever component it left.
+    unmount: Box<dyn FnOnce()>,
+    task: Rc<RefCell<Task>>,
+    from: Option<Window>,
+    owner: Option<RenderHandle>,
+}
+
+/// A subcomponent which has been built from scratch, which a departure with
+/// the same key can still take the place of.
+struct Arrival {
+    model_type: TypeId,
+    key: u64,
+    task: Rc<RefCell<Task>>,
+    parent: ParentScope,
+    owner: Option<RenderHandle>,
+}
+
+#[derive(Default)]
+struct Moves {
+    departed: Vec<Departure>,
+    arrived: Vec<Arrival>,
+    settling: bool,
+}
+
+thread_local! {
+    static MOVES: RefCell<Moves> = RefCell::new(Default::default());
+}
+
+/// The top level window a widget is in, if it's in one.
+fn window_of(object: &Object) -> Option<Window> {
+    object
+        .downcast_ref::<Widget>()?
+        .get_toplevel()?
+        .downcast::<Window>()
+        .ok()
+        .filter(|window| window.is_toplevel())
+}
+
+/// Settle what's left once the main loop is about to draw, which is after
+/// every component which had to has rendered.
+fn settle_soon(moves: &mut Moves) {
+    if !moves.settling {
+        moves.settling = true;
+        glib::idle_source_new(None, glib::PRIORITY_HIGH_IDLE, || {
+            settle();
+            Continue(false)
+        })
+        .attach(None);
+    }
+}
+
+/// Park a keyed subcomponent which has been taken out of `container`,
+/// rather than unmounting it, or unmount it if it can't go anywhere else.
+pub(super) fn depart<Model: 'static + Component>(state: ComponentState<Model>, container: &Object) {
+    let key = match state.movable_key {
+        Some(key) => key,
+        None => return state.unmount(),
+    };
+    let departure = Departure {
+        model_type: state.model_type(),
+        name: state.name(),
+        key,
+        task: state.task(),
+        from: window_of(container),
+        owner: RenderHandle::current(),
+        unmount: Box::new(move || state.unmount()),
+    };
+    MOVES.with(|moves| {
+        let mut moves = moves.borrow_mut();
+        moves.departed.push(departure);
+        settle_soon(&mut moves);
+    });
+}
+
+/// Build a keyed subcomponent, or adopt one which has departed from
+/// somewhere else, if there is one.
+pub(super) fn arrive<Model: 'static + Component>(
+    spec: &VComponent<Model>,
+    key: u64,
+    parent: Option<&Object>,
+    scope: &Scope<Model>,
+) -> ComponentState<Model> {
+    let departure = MOVES.with(|moves| {
+        let mut moves = moves.borrow_mut();
+        let index = moves.departed.iter().position(|departure| {
+            departure.model_type == spec.model_type && departure.key == key
+        })?;
+        Some(moves.departed.remove(index))
+    });
+    if let Some(departure) = departure {
+        let mut state = ComponentState::adopted(departure.task, spec, parent, scope);
+        state.movable_key = Some(key);
+        report(
+            Moved::new(
+                departure.name,
+                key,
+                departure.from,
+                parent.and_then(window_of),
+            ),
+            departure.owner,
+            RenderHandle::current(),
+        );
+        return state;
+    }
+    let mut state = (spec.constructor)(&spec.props, parent, &spec.child_props, scope);
+    state.movable_key = Some(key);
+    let arrival = Arrival {
+        model_type: spec.model_type,
+        key,
+        task: state.task(),
+        parent: (spec.parent_scope)(scope),
+        owner: RenderHandle::current(),
+    };
+    MOVES.with(|moves| {
+        let mut moves = moves.borrow_mut();
+        moves.arrived.push(arrival);
+        settle_soon(&mut moves);
+    });
+    state
+}
+
+/// Tell the components a subcomponent moved between that it did, once each.
+fn report(moved: Moved, from: Option<RenderHandle>, to: Option<RenderHandle>) {
+    if let Some(from) = &from {
+        from.report(moved.clone());
+    }
+    if let Some(to) = to.filter(|to| from.as_ref() != Some(to)) {
+        to.report(moved);
+    }
+}
+
+/// Move the departures which were built again from scratch elsewhere into
+/// the places of their replacements, and unmount the rest.
+fn settle() {
+    let Moves {
+        departed, arrived, ..
+    } = MOVES.with(|moves| std::mem::take(&mut *moves.borrow_mut()));
+    for departure in departed {
+        let Departure {
+            model_type,
+            name,
+            key,
+            unmount,
+            task,
+            from,
+            owner,
+        } = departure;
+        let arrival = arrived.iter().find(|arrival| {
+            arrival.model_type == model_type
+                && arrival.key == key
+                && !arrival.task.borrow().unmounted()
+        });
+        let slot = arrival.and_then(|arrival| Slot::find(&arrival.task.borrow().object()));
+        let (arrival, slot) = match (arrival, slot) {
+            (Some(arrival), Some(slot)) => (arrival, slot),
+            _ => {
+                unmount();
+                continue;
+            }
+        };
+        let to = window_of(&arrival.task.borrow().object());
+        slot.hold(|| {
+            // Swap the tasks, so that the replacement is the one unmounted
+            // along with the parked state, and the arrival's state carries
+            // on with the task which moved.
+            arrival.task.swap(&task);
+            arrival
+                .task
+                .borrow_mut()
+                .reparent_later(arrival.parent.clone());
+            unmount();
+            slot.fill(&arrival.task.borrow().object());
+        });
+        // The arrival's owner passes on the properties it was built with,
+        // which the moved task hasn't seen.
+        if let Some(owner) = &arrival.owner {
+            owner.request();
+        }
+        report(
+            Moved::new(name, key, from, to),
+            owner,
+            arrival.owner.clone(),
+        );
+    }
+}
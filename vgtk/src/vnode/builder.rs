@@ -0,0 +1,514 @@
+use std::borrow::Cow;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::panic::Location;
+use std::rc::Rc;
+
+use glib::signal::SignalHandlerId;
+use glib::{Cast, IsA, Object, ObjectExt, ToValue, Value};
+use gtk::prelude::*;
+use gtk::{Button, Editable, Entry, Range, ToggleButton, Widget};
+
+use super::{key, PropTransform, VComponent, VHandler, VNode, VObject, VProperty};
+use crate::properties;
+use crate::scope::Scope;
+use crate::Component;
+
+/// Builds a [`VNode`][VNode] for a GTK widget in plain Rust, as an
+/// alternative to the [`gtk!`][gtk!] macro.
+///
+/// Make one with [`VNode::widget()`][VNode::widget]. It builds the same kind
+/// of node the macro does, so the two can be mixed freely: a builder can
+/// go in a code block in the macro, and a `gtk!` invocation can be passed to
+/// [`child()`][child].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use vgtk::{Component, VNode};
+/// # use vgtk::lib::gtk::*;
+/// # #[derive(Clone, Debug)] enum Message { Inc }
+/// # #[derive(Default)] struct Model { counter: usize, items: Vec<String> }
+/// # impl Component for Model { type Message = Message; type Properties = ();
+/// # fn create(_props: ()) -> Self { Self::default() }
+/// fn view(&self) -> VNode<Model> {
+///     VNode::widget::<Box>()
+///         .prop("orientation", Orientation::Vertical)
+///         .set("spacing", Box::get_spacing, Box::set_spacing, 10)
+///         .child(
+///             VNode::widget::<Label>()
+///                 .prop("label", format!("Clicked {} times", self.counter)),
+///         )
+///         .child(
+///             VNode::widget::<Button>()
+///                 .prop("label", "Click me")
+///                 .child_prop("expand", Box::get_child_expand, Box::set_child_expand, false)
+///                 .on_clicked(|_| Message::Inc),
+///         )
+///         .children(self.items.iter().map(|item| {
+///             VNode::widget::<Label>().key(item).prop("label", item).build()
+///         }))
+///         .build()
+/// }
+/// # }
+/// ```
+///
+/// [VNode]: enum.VNode.html
+/// [VNode::widget]: enum.VNode.html#method.widget
+/// [gtk!]: macro.gtk.html
+/// [child]: #method.child
+pub struct WidgetBuilder<Model: Component, W> {
+    object: VObject<Model>,
+    widget: PhantomData<W>,
+}
+
+impl<Model, W> WidgetBuilder<Model, W>
+where
+    Model: 'static + Component,
+    W: IsA<Object>,
+{
+    pub(crate) fn new() -> Self {
+        WidgetBuilder {
+            object: VObject {
+                object_type: W::static_type(),
+                constructor: None,
+                properties: Vec::new(),
+                child_props: Vec::new(),
+                construct_props: Vec::new(),
+                handlers: Vec::new(),
+                children: Vec::new(),
+                key: None,
+                recycle: false,
+                tooltip: None,
+            },
+            widget: PhantomData,
+        }
+    }
+
+    /// Create the widget with a function, rather than with its default
+    /// constructor. This is the builder's equivalent of
+    /// `<Button::with_label("OK")>` in the [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    pub fn constructor(mut self, constructor: impl Fn() -> W + 'static) -> Self {
+        self.object.constructor = Some(Box::new(move || constructor().upcast()));
+        self
+    }
+
+    /// Identify the widget among its siblings, like the `key` attribute in
+    /// the [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    pub fn key<K: Hash + ?Sized>(mut self, value: &K) -> Self {
+        self.object.key = Some(key(value));
+        self
+    }
+
+    /// Identify the widget among its siblings, and let it be reused for a
+    /// sibling with another key, like the `key(recycle)` attribute in the
+    /// [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    pub fn recycled_key<K: Hash + ?Sized>(mut self, value: &K) -> Self {
+        self.object.key = Some(key(value));
+        self.object.recycle = true;
+        self
+    }
+
+    /// Set a GObject property by name, like `"label"` or `"use-underline"`.
+    ///
+    /// Like the properties of a `{..spread}` attribute, it's only set when its
+    /// value has changed, and it's put back to its default if a later render
+    /// leaves it out. Setting a property the widget doesn't have, or to a
+    /// value of the wrong type, panics.
+    pub fn prop<V: ToValue>(mut self, name: &'static str, value: V) -> Self {
+        let value: [(&'static str, Value); 1] = [(name, value.to_value())];
+        self.object
+            .properties
+            .extend(properties::spread(&value[..]));
+        self
+    }
+
+    /// Set a property through its getter and setter, like
+    /// `.set("spacing", Box::get_spacing, Box::set_spacing, 10)`.
+    ///
+    /// The setter's only called when the getter's result differs from
+    /// `value`. This is checked at compile time, and also works for the
+    /// helper properties in [`vgtk::ext`][ext], which aren't GObject
+    /// properties.
+    ///
+    /// [ext]: ext/index.html
+    pub fn set<T>(
+        mut self,
+        name: &'static str,
+        get: impl Fn(&W) -> T + 'static,
+        set: impl Fn(&W, T) + 'static,
+        value: T,
+    ) -> Self
+    where
+        T: PartialEq + Clone + 'static,
+    {
+        self.object.properties.push(VProperty {
+            name,
+            fingerprint: None,
+            set: Box::new(move |object: &Object, _parent, force| {
+                let object: &W = object.downcast_ref().unwrap_or_else(|| {
+                    panic!(
+                        "downcast to {:?} failed in property setter",
+                        W::static_type()
+                    )
+                });
+                if force || get(object) != value {
+                    set(object, value.clone());
+                    true
+                } else {
+                    false
+                }
+            }),
+        });
+        self
+    }
+
+    /// Set a child property, which belongs to the widget's place in its
+    /// parent of type `P`, through its getter and setter, like
+    /// `.child_prop("expand", Box::get_child_expand, Box::set_child_expand, true)`.
+    /// This is the builder's equivalent of the `Box::expand=true` attribute
+    /// in the [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    pub fn child_prop<P, T>(
+        mut self,
+        name: &'static str,
+        get: impl Fn(&P, &W) -> T + 'static,
+        set: impl Fn(&P, &W, T) + 'static,
+        value: T,
+    ) -> Self
+    where
+        P: IsA<Object>,
+        T: PartialEq + Clone + 'static,
+    {
+        self.object
+            .child_props
+            .push(child_prop(name, get, set, value));
+        self
+    }
+
+    /// Show a node of its own as the widget's tooltip, like the `tooltip`
+    /// attribute in the [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    pub fn tooltip(mut self, tooltip: impl Into<VNode<Model>>) -> Self {
+        self.object.tooltip = Some(Box::new(tooltip.into()));
+        self
+    }
+
+    /// Add a child node.
+    pub fn child(mut self, child: impl Into<VNode<Model>>) -> Self {
+        self.object.children.push(child.into());
+        self
+    }
+
+    /// Add a list of child nodes.
+    pub fn children(mut self, children: impl IntoIterator<Item = VNode<Model>>) -> Self {
+        self.object.children.extend(children);
+        self
+    }
+
+    /// Handle a signal by name, like `"clicked"` or `"notify::label"`.
+    ///
+    /// The handler gets the widget and the signal's other arguments, and
+    /// returns a message for the component along with the signal's return
+    /// value, if it has one. For the most common signals, the typed handlers
+    /// like [`on_clicked()`][on_clicked] are more convenient. Like handlers
+    /// in the [`gtk!`][gtk!] macro, it's connected when the widget is built,
+    /// and not replaced on later renders.
+    ///
+    /// [gtk!]: macro.gtk.html
+    /// [on_clicked]: #method.on_clicked
+    #[track_caller]
+    pub fn on_signal<F>(self, signal: &'static str, handler: F) -> Self
+    where
+        F: Fn(&W, &[Value]) -> (Model::Message, Option<Value>) + 'static,
+    {
+        self.on_signal_named(signal, signal.to_string(), handler)
+    }
+
+    /// Handle changes to a property, like the `on notify_label` attribute in
+    /// the [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    #[track_caller]
+    pub fn on_notify<F>(self, property: &'static str, handler: F) -> Self
+    where
+        F: Fn(&W) -> Model::Message + 'static,
+    {
+        self.on_signal_named(
+            property,
+            format!("notify::{}", property),
+            move |object, _| (handler(object), None),
+        )
+    }
+
+    #[track_caller]
+    fn on_signal_named<F>(self, name: &'static str, signal: String, handler: F) -> Self
+    where
+        F: Fn(&W, &[Value]) -> (Model::Message, Option<Value>) + 'static,
+    {
+        let handler = Rc::new(handler);
+        self.connect(name, Location::caller(), move |object, scope| {
+            let handler = handler.clone();
+            object
+                .connect_local(signal.as_str(), false, move |args| {
+                    let object = args[0]
+                        .get::<Object>()
+                        .ok()
+                        .flatten()
+                        .and_then(|object| object.downcast::<W>().ok())
+                        .unwrap_or_else(|| {
+                            panic!("signal wasn't emitted by a {}", W::static_type())
+                        });
+                    let (msg, ret) = handler(&object, &args[1..]);
+                    scope.send_message(msg);
+                    ret
+                })
+                .unwrap_or_else(|error| {
+                    panic!(
+                        "can't connect to signal {} on {}: {}",
+                        signal,
+                        W::static_type(),
+                        error
+                    )
+                })
+        })
+    }
+
+    /// Add a signal handler, given a function which connects it.
+    fn connect<F>(mut self, name: &'static str, location: &Location<'_>, connect: F) -> Self
+    where
+        F: Fn(&W, Scope<Model>) -> SignalHandlerId + 'static,
+    {
+        self.object.handlers.push(VHandler {
+            name,
+            // The builder's call site tells its handlers apart, like the
+            // handler's place in the source does for the `gtk!` macro.
+            id: Cow::Owned(location.to_string()),
+            set: Box::new(move |object: &Object, scope: &Scope<Model>| {
+                let object: &W = object.downcast_ref().unwrap_or_else(|| {
+                    panic!("downcast to {:?} failed in signal setter", W::static_type())
+                });
+                connect(object, scope.clone())
+            }),
+        });
+        self
+    }
+
+    /// Finish building the node.
+    pub fn build(self) -> VNode<Model> {
+        VNode::Object(self.object)
+    }
+}
+
+macro_rules! typed_handlers {
+    ($($class:ident { $($(#[$doc:meta])* $method:ident => $signal:literal, $connect:ident;)* })*) => {$(
+        impl<Model, W> WidgetBuilder<Model, W>
+        where
+            Model: 'static + Component,
+            W: IsA<Object> + IsA<$class>,
+        {
+            $(
+                $(#[$doc])*
+                #[track_caller]
+                pub fn $method<F>(self, handler: F) -> Self
+                where
+                    F: Fn(&W) -> Model::Message + 'static,
+                {
+                    let handler = Rc::new(handler);
+                    self.connect($signal, Location::caller(), move |object, scope| {
+                        let handler = handler.clone();
+                        object.$connect(move |object| scope.send_message(handler(object)))
+                    })
+                }
+            )*
+        }
+    )*};
+}
+
+typed_handlers! {
+    Widget {
+        /// Handle the widget's `destroy` signal.
+        on_destroy => "destroy", connect_destroy;
+    }
+    Button {
+        /// Handle the button's `clicked` signal.
+        on_clicked => "clicked", connect_clicked;
+    }
+    ToggleButton {
+        /// Handle the toggle button's `toggled` signal.
+        on_toggled => "toggled", connect_toggled;
+    }
+    Entry {
+        /// Handle the entry's `activate` signal, which it emits when the
+        /// user presses Enter.
+        on_activate => "activate", connect_activate;
+    }
+    Editable {
+        /// Handle the `changed` signal, which an editable widget emits when
+        /// its text changes.
+        on_changed => "changed", connect_changed;
+    }
+    Range {
+        /// Handle the `value-changed` signal of a range, like a scale or a
+        /// scroll bar.
+        on_value_changed => "value_changed", connect_value_changed;
+    }
+}
+
+impl<Model, W> From<WidgetBuilder<Model, W>> for VNode<Model>
+where
+    Model: 'static + Component,
+    W: IsA<Object>,
+{
+    fn from(builder: WidgetBuilder<Model, W>) -> Self {
+        builder.build()
+    }
+}
+
+/// Builds a [`VNode`][VNode] for a subcomponent in plain Rust, as an
+/// alternative to the [`gtk!`][gtk!] macro.
+///
+/// Make one with [`VNode::component()`][VNode::component].
+///
+/// [VNode]: enum.VNode.html
+/// [VNode::component]: enum.VNode.html#method.component
+/// [gtk!]: macro.gtk.html
+pub struct ComponentBuilder<Model: Component, C: Component> {
+    component: VComponent<Model>,
+    props: C::Properties,
+}
+
+impl<Model, C> ComponentBuilder<Model, C>
+where
+    Model: 'static + Component,
+    C: 'static + Component,
+{
+    pub(crate) fn new() -> Self {
+        ComponentBuilder {
+            component: VComponent::new::<C>(),
+            props: Default::default(),
+        }
+    }
+
+    /// Identify the subcomponent among its siblings, like the `key`
+    /// attribute in the [`gtk!`][gtk!] macro.
+    ///
+    /// [gtk!]: macro.gtk.html
+    pub fn key<K: Hash + ?Sized>(mut self, value: &K) -> Self {
+        self.component.key = Some(key(value));
+        self
+    }
+
+    /// Replace all of the subcomponent's properties.
+    pub fn props(mut self, props: C::Properties) -> Self {
+        self.props = props;
+        self
+    }
+
+    /// Set one of the subcomponent's properties, given a function which
+    /// picks it out of the properties struct, like
+    /// `.prop(|props| &mut props.on_changed, |value| Message::Changed(value))`.
+    ///
+    /// The value's converted just like an attribute on a component in the
+    /// [`gtk!`][gtk!] macro, so a closure returning a message can be given
+    /// for a [`Callback`][Callback], or a `&str` for a `String`.
+    ///
+    /// [gtk!]: macro.gtk.html
+    /// [Callback]: struct.Callback.html
+    pub fn prop<From, To>(
+        mut self,
+        field: impl FnOnce(&mut C::Properties) -> &mut To,
+        value: From,
+    ) -> Self
+    where
+        VComponent<Model>: PropTransform<Model, From, To>,
+    {
+        *field(&mut self.props) = self.component.transform(value);
+        self
+    }
+
+    /// Set a child property on the subcomponent's top level widget, like
+    /// [`WidgetBuilder::child_prop()`][child_prop].
+    ///
+    /// [child_prop]: struct.WidgetBuilder.html#method.child_prop
+    pub fn child_prop<P, T>(
+        mut self,
+        name: &'static str,
+        get: impl Fn(&P, &Widget) -> T + 'static,
+        set: impl Fn(&P, &Widget, T) + 'static,
+        value: T,
+    ) -> Self
+    where
+        P: IsA<Object>,
+        T: PartialEq + Clone + 'static,
+    {
+        self.component
+            .child_props
+            .push(child_prop(name, get, set, value));
+        self
+    }
+
+    /// Finish building the node.
+    pub fn build(mut self) -> VNode<Model> {
+        self.component.set_props::<C>(self.props);
+        VNode::Component(self.component)
+    }
+}
+
+impl<Model, C> From<ComponentBuilder<Model, C>> for VNode<Model>
+where
+    Model: 'static + Component,
+    C: 'static + Component,
+{
+    fn from(builder: ComponentBuilder<Model, C>) -> Self {
+        builder.build()
+    }
+}
+
+pub(crate) fn child_prop<P, W, T>(
+    name: &'static str,
+    get: impl Fn(&P, &W) -> T + 'static,
+    set: impl Fn(&P, &W, T) + 'static,
+    value: T,
+) -> VProperty
+where
+    P: IsA<Object>,
+    W: IsA<Object>,
+    T: PartialEq + Clone + 'static,
+{
+    VProperty {
+        name,
+        fingerprint: None,
+        set: Box::new(move |object: &Object, parent: Option<&Object>, force| {
+            let object: &W = object.downcast_ref().unwrap_or_else(|| {
+                panic!(
+                    "downcast to {:?} failed in property setter",
+                    W::static_type()
+                )
+            });
+            let parent: &P = parent
+                .expect("child attribute without a reachable parent")
+                .downcast_ref()
+                .unwrap_or_else(|| {
+                    panic!(
+                        "downcast to {:?} failed on parent in property setter",
+                        P::static_type()
+                    )
+                });
+            if force || get(parent, object) != value {
+                set(parent, object, value.clone());
+                true
+            } else {
+                false
+            }
+        }),
+    }
+}
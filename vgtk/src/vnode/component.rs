@@ -6,10 +6,11 @@ use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::callback::Callback;
-use crate::component::Component;
-use crate::scope::Scope;
+use crate::component::{current_scope, Component};
+use crate::render::Render;
+use crate::scope::{ParentScope, Scope};
 use crate::vdom::ComponentState;
-use crate::vnode::VProperty;
+use crate::vnode::{VNode, VProperty};
 
 pub struct AnyProps {
     valid: AtomicBool,
@@ -58,9 +59,18 @@ type Constructor<Model> =
 pub struct VComponent<Model: Component> {
     parent: PhantomData<Model>,
     pub model_type: TypeId,
+    pub name: &'static str,
     pub props: AnyProps,
     pub constructor: Box<Constructor<Model>>,
     pub child_props: Vec<VProperty>,
+    pub key: Option<u64>,
+    // Whether the key was made up by `match_arm()` from where the node is,
+    // which only means something among its siblings, so it can't be moved
+    // anywhere else by it.
+    pub(crate) positional: bool,
+    // The parent the subcomponent's messages go to, given the scope the
+    // node is built with, for when it moves here from somewhere else.
+    pub(crate) parent_scope: Box<dyn Fn(&Scope<Model>) -> ParentScope>,
 }
 
 impl<Model: 'static + Component> VComponent<Model> {
@@ -69,9 +79,13 @@ impl<Model: 'static + Component> VComponent<Model> {
         VComponent {
             parent: PhantomData,
             model_type: TypeId::of::<Child>(),
+            name: std::any::type_name::<Child>(),
             props: AnyProps::null(),
             constructor,
             child_props: Vec::new(),
+            key: None,
+            positional: false,
+            parent_scope: Box::new(Scope::as_parent),
         }
     }
 
@@ -79,6 +93,24 @@ impl<Model: 'static + Component> VComponent<Model> {
         assert_eq!(self.model_type, TypeId::of::<Child>());
         self.props = AnyProps::new(props);
     }
+
+    pub(crate) fn retarget<C: 'static + Component>(self, scope: &Scope<Model>) -> VComponent<C> {
+        let (constructor, scope) = (self.constructor, scope.clone());
+        let parent = scope.as_parent();
+        VComponent {
+            parent: PhantomData,
+            model_type: self.model_type,
+            name: self.name,
+            props: self.props,
+            constructor: Box::new(move |props, parent, child_props, _| {
+                constructor(props, parent, child_props, &scope).retarget()
+            }),
+            child_props: self.child_props,
+            key: self.key,
+            positional: self.positional,
+            parent_scope: Box::new(move |_| parent.clone()),
+        }
+    }
 }
 
 pub trait PropTransform<Model: Component, From, To> {
@@ -117,3 +149,20 @@ where
         Callback(Some(callback))
     }
 }
+
+impl<Model, F, A> PropTransform<Model, F, Render<A>> for VComponent<Model>
+where
+    Model: Component + 'static,
+    F: Fn(&A) -> VNode<Model> + 'static,
+{
+    fn transform(&self, from: F) -> Render<A> {
+        // The parent's view is running, so this is its scope. Hold on to it,
+        // in case the subcomponent passes the `Render` on to one of its own.
+        let scope = current_scope::<Model>();
+        let render: Rc<dyn Fn(&A) -> VNode<()>> = Rc::new(move |value| {
+            let scope = scope.clone().unwrap_or_else(Scope::<Model>::current_parent);
+            from(value).retarget(&scope)
+        });
+        Render(Some(render))
+    }
+}
@@ -2,7 +2,8 @@ use std::borrow::Borrow;
 
 use glib::{Object, Type};
 
-use super::{VHandler, VNode, VProperty};
+use super::{VConstructProperty, VHandler, VNode, VProperty};
+use crate::scope::Scope;
 use crate::Component;
 
 pub struct VObject<Model: Component> {
@@ -10,18 +11,46 @@ pub struct VObject<Model: Component> {
     pub constructor: Option<Box<dyn Fn() -> Object>>,
     pub properties: Vec<VProperty>,
     pub child_props: Vec<VProperty>,
+    pub construct_props: Vec<VConstructProperty>,
     pub handlers: Vec<VHandler<Model>>,
     pub children: Vec<VNode<Model>>,
+    pub key: Option<u64>,
+    pub recycle: bool,
+    // A custom tooltip, shown in place of the usual text.
+    pub tooltip: Option<Box<VNode<Model>>>,
 }
 
 impl<Model: Component> VObject<Model> {
     pub fn get_prop<S: Borrow<str>>(&self, name: S) -> Option<&VProperty> {
         let name = name.borrow();
-        for prop in &self.properties {
-            if prop.name == name {
-                return Some(prop);
-            }
+        self.properties.iter().find(|prop| prop.name == name)
+    }
+
+    pub(crate) fn retarget<C: 'static + Component>(self, scope: &Scope<Model>) -> VObject<C>
+    where
+        Model: 'static,
+    {
+        VObject {
+            object_type: self.object_type,
+            constructor: self.constructor,
+            properties: self.properties,
+            child_props: self.child_props,
+            construct_props: self.construct_props,
+            handlers: self
+                .handlers
+                .into_iter()
+                .map(|handler| handler.retarget(scope))
+                .collect(),
+            children: self
+                .children
+                .into_iter()
+                .map(|child| child.retarget(scope))
+                .collect(),
+            key: self.key,
+            recycle: self.recycle,
+            tooltip: self
+                .tooltip
+                .map(|tooltip| Box::new(tooltip.retarget(scope))),
         }
-        None
     }
 }
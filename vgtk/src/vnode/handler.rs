@@ -1,9 +1,139 @@
-use glib::{signal::SignalHandlerId, Object};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
 
+use glib::{signal::SignalHandlerId, Object, ObjectExt};
+
+use crate::clock::{self, Timer};
 use crate::{scope::Scope, Component};
 
 pub struct VHandler<Model: Component> {
     pub name: &'static str,
-    pub id: &'static str,
+    pub id: Cow<'static, str>,
     pub set: Box<dyn Fn(&Object, &Scope<Model>) -> SignalHandlerId>,
 }
+
+impl<Model: 'static + Component> VHandler<Model> {
+    pub(crate) fn retarget<C: Component>(self, scope: &Scope<Model>) -> VHandler<C> {
+        let (set, scope) = (self.set, scope.clone());
+        VHandler {
+            name: self.name,
+            id: self.id,
+            set: Box::new(move |object, _| set(object, &scope)),
+        }
+    }
+}
+
+/// Limits how often a signal handler's messages reach its component, for
+/// the `throttle` and `debounce` handler modifiers in the `gtk!` macro.
+///
+/// A throttled handler's first message is sent straight away, and then at
+/// most one per interval, which is the latest one sent during it. A
+/// debounced handler's message is only sent once the handler has been quiet
+/// for a whole interval. Either way, the signal still gets its return value
+/// straight away, and messages in between are dropped.
+pub struct RateLimit<C: Component>(Rc<RefCell<Limiter<C>>>);
+
+impl<C: Component> Clone for RateLimit<C> {
+    fn clone(&self) -> Self {
+        RateLimit(self.0.clone())
+    }
+}
+
+struct Limiter<C: Component> {
+    scope: Scope<C>,
+    debounce: bool,
+    interval: Duration,
+    pending: Option<C::Message>,
+    timer: Option<Timer>,
+}
+
+impl<C: 'static + Component> RateLimit<C> {
+    fn new(scope: &Scope<C>, interval: Duration, debounce: bool) -> Self {
+        RateLimit(Rc::new(RefCell::new(Limiter {
+            scope: scope.clone(),
+            debounce,
+            interval: interval.max(Duration::from_millis(1)),
+            pending: None,
+            timer: None,
+        })))
+    }
+
+    /// Send at most one message per `interval`.
+    pub fn throttle(scope: &Scope<C>, interval: Duration) -> Self {
+        Self::new(scope, interval, false)
+    }
+
+    /// Send a message once no more have come for `interval`.
+    pub fn debounce(scope: &Scope<C>, interval: Duration) -> Self {
+        Self::new(scope, interval, true)
+    }
+
+    /// Use the rate limiter already kept on `object` for the handler `id`,
+    /// if there is one, or keep this one there for next time.
+    ///
+    /// Handlers are reconnected on every render, and this is how a
+    /// handler's limiter survives that.
+    pub fn attach(self, object: &Object, id: &str) -> Self {
+        let key = format!("vgtk-limit-{}", id);
+        #[allow(unsafe_code)]
+        unsafe {
+            if let Some(limit) = object.get_data::<Self>(&key) {
+                return limit.clone();
+            }
+            object.set_data(&key, self.clone());
+        }
+        self
+    }
+
+    fn send(&self, message: C::Message) {
+        let mut limiter = self.0.borrow_mut();
+        if limiter.debounce {
+            limiter.pending = Some(message);
+            if let Some(timer) = limiter.timer.take() {
+                timer.cancel();
+            }
+            limiter.timer = Some(self.start(limiter.interval));
+        } else if limiter.timer.is_some() {
+            limiter.pending = Some(message);
+        } else {
+            limiter.scope.send_message(message);
+            limiter.timer = Some(self.start(limiter.interval));
+        }
+    }
+
+    fn start(&self, interval: Duration) -> Timer {
+        let limiter = self.0.clone();
+        clock::timeout_add(interval, move || {
+            let mut limiter = limiter.borrow_mut();
+            match limiter.pending.take() {
+                Some(message) => {
+                    // The component may have gone by now.
+                    let _ = limiter.scope.try_send(message);
+                    // A throttle waits out another interval after sending.
+                    if limiter.debounce {
+                        limiter.timer = None;
+                    }
+                    glib::Continue(!limiter.debounce)
+                }
+                None => {
+                    limiter.timer = None;
+                    glib::Continue(false)
+                }
+            }
+        })
+    }
+}
+
+/// Like [`dispatch()`][dispatch], but for a handler with a `throttle` or
+/// `debounce` modifier.
+///
+/// [dispatch]: fn.dispatch.html
+pub fn dispatch_limited<C: 'static + Component, R>(
+    limit: &RateLimit<C>,
+    (msg, ret): (C::Message, R),
+) -> R {
+    limit.send(msg);
+    ret
+}
@@ -1,26 +1,58 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use glib::{IsA, Object};
+
+use crate::scope::Scope;
 use crate::Component;
 
+pub(crate) mod builder;
 pub(crate) mod component;
 mod gobject;
 mod handler;
 mod property;
 
+pub use builder::{ComponentBuilder, WidgetBuilder};
 pub use component::{PropTransform, VComponent};
 pub use gobject::VObject;
-pub use handler::VHandler;
-pub use property::VProperty;
+pub use handler::{dispatch_limited, RateLimit, VHandler};
+pub use property::{VConstructProperty, VProperty};
 
 /// A node in the virtual component tree representing a [`Component`][Component] or a Gtk widget.
 ///
-/// Don't attempt to construct these directly. Use the [`gtk!`][gtk!] macro instead.
+/// These are usually made with the [`gtk!`][gtk!] macro, or else in plain
+/// Rust with [`VNode::widget()`][widget] and [`VNode::component()`][component].
 ///
 /// [gtk!]: macro.gtk.html
+/// [widget]: #method.widget
+/// [component]: #method.component
 /// [Component]: trait.Component.html
 pub enum VNode<Model: Component> {
     Object(VObject<Model>),
     Component(VComponent<Model>),
 }
 
+impl<Model: 'static + Component> VNode<Model> {
+    /// Start building a node for a widget of type `W`, as an alternative to
+    /// the [`gtk!`][gtk!] macro. See [`WidgetBuilder`][WidgetBuilder].
+    ///
+    /// [gtk!]: macro.gtk.html
+    /// [WidgetBuilder]: struct.WidgetBuilder.html
+    pub fn widget<W: IsA<Object>>() -> WidgetBuilder<Model, W> {
+        WidgetBuilder::new()
+    }
+
+    /// Start building a node for a subcomponent of type `C`, as an
+    /// alternative to the [`gtk!`][gtk!] macro. See
+    /// [`ComponentBuilder`][ComponentBuilder].
+    ///
+    /// [gtk!]: macro.gtk.html
+    /// [ComponentBuilder]: struct.ComponentBuilder.html
+    pub fn component<C: 'static + Component>() -> ComponentBuilder<Model, C> {
+        ComponentBuilder::new()
+    }
+}
+
 impl<Model: Component> VNode<Model> {
     pub(crate) fn get_child_props(&self) -> &[VProperty] {
         match self {
@@ -29,15 +61,87 @@ impl<Model: Component> VNode<Model> {
         }
     }
 
-    pub(crate) fn get_child_prop(&self, name: &str) -> Option<&VProperty> {
-        let props = self.get_child_props();
-        for prop in props {
-            if prop.name == name {
-                return Some(prop);
-            }
+    /// Turn this into a node for another component's view, whose signal
+    /// handlers and subcomponents still belong to `scope`.
+    pub(crate) fn retarget<C: 'static + Component>(self, scope: &Scope<Model>) -> VNode<C>
+    where
+        Model: 'static,
+    {
+        match self {
+            VNode::Object(object) => VNode::Object(object.retarget(scope)),
+            VNode::Component(component) => VNode::Component(component.retarget(scope)),
+        }
+    }
+
+    /// Get the node's `key` attribute, hashed by [`key()`][key].
+    ///
+    /// [key]: fn.key.html
+    pub(crate) fn key(&self) -> Option<u64> {
+        match self {
+            VNode::Object(object) => object.key,
+            VNode::Component(comp) => comp.key,
         }
-        None
     }
+
+    /// Get the key of a subcomponent which can move here from elsewhere, or
+    /// elsewhere from here, which it can if it was given a `key` attribute.
+    pub(crate) fn movable_key(&self) -> Option<u64> {
+        match self {
+            VNode::Component(comp) if !comp.positional => comp.key,
+            _ => None,
+        }
+    }
+
+    fn set_key(&mut self, key: u64) {
+        match self {
+            VNode::Object(object) => object.key = Some(key),
+            VNode::Component(comp) => comp.key = Some(key),
+        }
+    }
+
+    pub(crate) fn get_child_prop(&self, name: &str) -> Option<&VProperty> {
+        self.get_child_props().iter().find(|prop| prop.name == name)
+    }
+}
+
+/// Hash the value of a `key` attribute, which identifies a child node
+/// among its siblings from one render to the next.
+pub fn key<K: Hash + ?Sized>(value: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Collect the nodes an arm of a `match` expression in the `gtk!` macro
+/// produced, keying the ones without a `key` attribute by where the `match`
+/// is among its siblings, which arm they came from, and their place in it.
+pub fn match_arm<Model: Component>(
+    site: usize,
+    arm: usize,
+    nodes: impl IntoIterator<Item = VNode<Model>>,
+) -> Vec<VNode<Model>> {
+    nodes
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut node)| {
+            if node.key().is_none() {
+                node.set_key(key(&(site, arm, index)));
+                if let VNode::Component(comp) = &mut node {
+                    comp.positional = true;
+                }
+            }
+            node
+        })
+        .collect()
+}
+
+/// Send the message a signal handler in the `gtk!` macro produced and pass
+/// its return value back to the signal. Going through a function, rather than
+/// destructuring the handler's result in place, puts any type error on the
+/// handler's body instead of on the whole macro.
+pub fn dispatch<C: 'static + Component, R>(scope: &Scope<C>, (msg, ret): (C::Message, R)) -> R {
+    scope.send_message(msg);
+    ret
 }
 
 /// An iterator over zero or one [`VNode`][VNode]s.
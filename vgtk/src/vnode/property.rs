@@ -1,6 +1,19 @@
-use glib::Object;
+use glib::{Object, Value};
 
 pub struct VProperty {
     pub name: &'static str,
-    pub set: Box<dyn Fn(&Object, Option<&Object>, bool) + 'static>,
+    // A hash of the value, if it can be hashed, which lets a patch skip the
+    // property without asking the object for its current value, if it was
+    // the last value set and the object hasn't changed it since.
+    pub fingerprint: Option<u64>,
+    // Sets the property on an object, given its parent and whether to set it
+    // even if it already has the right value. Returns whether it was set.
+    pub set: Box<dyn Fn(&Object, Option<&Object>, bool) -> bool + 'static>,
+}
+
+// A construct-only property, which can only be given to an object when it's
+// created, so changing it means creating a new object.
+pub struct VConstructProperty {
+    pub name: &'static str,
+    pub value: Value,
 }
@@ -0,0 +1,16 @@
+//! Widgets provided by `vgtk` in addition to GTK's own.
+//!
+//! These are ordinary GTK widgets, which you can use in the [`gtk!`][gtk!]
+//! macro just like any other widget once you've imported them.
+//!
+//! [gtk!]: ../macro.gtk.html
+
+mod responsive;
+mod section;
+mod toaster;
+mod triple_pane;
+
+pub use responsive::Responsive;
+pub use section::Section;
+pub use toaster::Toaster;
+pub use triple_pane::{Pane, TriplePane};
@@ -0,0 +1,202 @@
+use glib::subclass;
+use glib::subclass::prelude::*;
+use glib::translate::*;
+use glib::{
+    glib_object_impl, glib_object_subclass, glib_wrapper, Cast, IsA, ObjectExt, StaticType,
+};
+use gtk::subclass::prelude::*;
+use gtk::{Allocation, ContainerExt, StackExt, Widget, WidgetExt};
+
+const BREAKPOINT_KEY: &str = "vgtk-responsive-breakpoint";
+
+/// The smallest size a child of a `Responsive` is meant for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Breakpoint {
+    min_width: i32,
+    min_height: i32,
+}
+
+mod imp {
+    use super::*;
+
+    pub struct Responsive;
+
+    impl ObjectSubclass for Responsive {
+        const NAME: &'static str = "VgtkResponsive";
+        type ParentType = gtk::Stack;
+        type Instance = subclass::simple::InstanceStruct<Self>;
+        type Class = subclass::simple::ClassStruct<Self>;
+
+        glib_object_subclass!();
+
+        fn new() -> Self {
+            Responsive
+        }
+    }
+
+    impl ObjectImpl for Responsive {
+        glib_object_impl!();
+
+        fn constructed(&self, object: &glib::Object) {
+            self.parent_constructed(object);
+            // Only the visible child should count towards our size, or the
+            // largest layout would stop us from ever getting small enough
+            // for the smaller ones.
+            let stack = object.downcast_ref::<gtk::Stack>().unwrap();
+            stack.set_hhomogeneous(false);
+            stack.set_vhomogeneous(false);
+        }
+    }
+
+    impl WidgetImpl for Responsive {
+        fn size_allocate(&self, widget: &Widget, allocation: &Allocation) {
+            self.parent_size_allocate(widget, allocation);
+            let stack = widget.downcast_ref::<gtk::Stack>().unwrap();
+            if let Some(child) = pick_child(stack, allocation.width, allocation.height) {
+                if stack.get_visible_child().as_ref() != Some(&child) {
+                    // Changing the visible child causes a resize, which we
+                    // shouldn't do in the middle of an allocation.
+                    let stack = stack.downgrade();
+                    let child = child.downgrade();
+                    glib::idle_add_local(move || {
+                        if let (Some(stack), Some(child)) = (stack.upgrade(), child.upgrade()) {
+                            stack.set_visible_child(&child);
+                        }
+                        glib::Continue(false)
+                    });
+                }
+            }
+        }
+    }
+
+    impl ContainerImpl for Responsive {}
+
+    impl StackImpl for Responsive {}
+}
+
+/// Pick the child meant for the largest size which fits, or the first child
+/// if none of them fit.
+fn pick_child(stack: &gtk::Stack, width: i32, height: i32) -> Option<Widget> {
+    let children = stack.get_children();
+    children
+        .iter()
+        .filter(|child| {
+            let breakpoint = breakpoint(child);
+            breakpoint.min_width <= width && breakpoint.min_height <= height
+        })
+        .max_by_key(|child| breakpoint(child))
+        .or_else(|| children.first())
+        .cloned()
+}
+
+fn breakpoint(child: &Widget) -> Breakpoint {
+    #[allow(unsafe_code)]
+    unsafe { child.get_data::<Breakpoint>(BREAKPOINT_KEY) }
+        .copied()
+        .unwrap_or_default()
+}
+
+fn set_breakpoint(child: &Widget, breakpoint: Breakpoint) {
+    #[allow(unsafe_code)]
+    unsafe {
+        child.set_data(BREAKPOINT_KEY, breakpoint);
+    }
+}
+
+glib_wrapper! {
+    /// A container which shows one of its children at a time, depending on
+    /// how much space it's been given.
+    ///
+    /// Each child declares the smallest size it's meant for using the
+    /// `Responsive::min_width` and `Responsive::min_height` child attributes,
+    /// which default to zero. Whenever the `Responsive` is resized, it shows
+    /// the child meant for the largest size which fits, so you can provide
+    /// separate layouts for phones, tablets and desktops:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{gtk, VNode};
+    /// # use vgtk::widgets::Responsive;
+    /// # use vgtk::lib::gtk::{Box, Orientation, OrientableExt};
+    /// # fn view() -> VNode<()> {
+    /// gtk! {
+    ///     <Responsive>
+    ///         <Box orientation=Orientation::Vertical />
+    ///         <Box orientation=Orientation::Horizontal Responsive::min_width=720 />
+    ///     </Responsive>
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// All of the children stay mounted while they're hidden, so any state
+    /// they hold, including the state of subcomponents, survives switching
+    /// between them. Because it's a [`Stack`][Stack], you can animate the
+    /// switch using its `transition_type` property. If the layouts are
+    /// expensive to keep around, the
+    /// [`components::Responsive`][components::Responsive] subcomponent only
+    /// builds the one being shown.
+    ///
+    /// [Stack]: ../../gtk/struct.Stack.html
+    /// [components::Responsive]: ../components/struct.Responsive.html
+    pub struct Responsive(
+        Object<subclass::simple::InstanceStruct<imp::Responsive>,
+        subclass::simple::ClassStruct<imp::Responsive>,
+        ResponsiveClass>)
+        @extends gtk::Stack, gtk::Container, gtk::Widget,
+        @implements gtk::Buildable;
+
+    match fn {
+        get_type => || imp::Responsive::get_type().to_glib(),
+    }
+}
+
+impl Responsive {
+    /// Construct a new, empty `Responsive`.
+    pub fn new() -> Self {
+        glib::Object::new(Self::static_type(), &[])
+            .expect("unable to create Responsive")
+            .downcast()
+            .unwrap()
+    }
+
+    /// Get the smallest width a child is meant for.
+    pub fn get_child_min_width<P: IsA<Widget>>(&self, child: &P) -> i32 {
+        breakpoint(child.as_ref()).min_width
+    }
+
+    /// Set the smallest width a child is meant for.
+    pub fn set_child_min_width<P: IsA<Widget>>(&self, child: &P, min_width: i32) {
+        let child = child.as_ref();
+        set_breakpoint(
+            child,
+            Breakpoint {
+                min_width,
+                ..breakpoint(child)
+            },
+        );
+        self.queue_resize();
+    }
+
+    /// Get the smallest height a child is meant for.
+    pub fn get_child_min_height<P: IsA<Widget>>(&self, child: &P) -> i32 {
+        breakpoint(child.as_ref()).min_height
+    }
+
+    /// Set the smallest height a child is meant for.
+    pub fn set_child_min_height<P: IsA<Widget>>(&self, child: &P, min_height: i32) {
+        let child = child.as_ref();
+        set_breakpoint(
+            child,
+            Breakpoint {
+                min_height,
+                ..breakpoint(child)
+            },
+        );
+        self.queue_resize();
+    }
+}
+
+impl Default for Responsive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,284 @@
+use std::cell::RefCell;
+
+use glib::subclass;
+use glib::subclass::prelude::*;
+use glib::translate::*;
+use glib::{
+    glib_object_impl, glib_object_subclass, glib_wrapper, Cast, GString, ObjectExt, StaticType,
+};
+use gtk::subclass::prelude::*;
+use gtk::{
+    Align, Box as GtkBox, BoxExt, Container, ContainerExt, Expander, ExpanderExt, Frame, FrameExt,
+    Label, LabelExt, OrientableExt, Orientation, Revealer, RevealerExt, ShadowType,
+    StyleContextExt, Widget, WidgetExt,
+};
+
+/// The widgets making up a `Section`, apart from its children.
+#[derive(Clone)]
+struct Parts {
+    title: Label,
+    expander: Expander,
+    description: Label,
+    revealer: Revealer,
+    frame: Frame,
+    content: GtkBox,
+}
+
+mod imp {
+    use super::*;
+
+    pub struct Section {
+        pub(super) parts: RefCell<Option<Parts>>,
+    }
+
+    impl ObjectSubclass for Section {
+        const NAME: &'static str = "VgtkSection";
+        type ParentType = gtk::Box;
+        type Instance = subclass::simple::InstanceStruct<Self>;
+        type Class = subclass::simple::ClassStruct<Self>;
+
+        glib_object_subclass!();
+
+        fn new() -> Self {
+            Section {
+                parts: RefCell::new(None),
+            }
+        }
+    }
+
+    impl ObjectImpl for Section {
+        glib_object_impl!();
+
+        fn constructed(&self, object: &glib::Object) {
+            self.parent_constructed(object);
+            let section = object.downcast_ref::<gtk::Box>().unwrap();
+            section.set_orientation(Orientation::Vertical);
+            section.set_spacing(6);
+            section.get_style_context().add_class("vgtk-section");
+
+            let title = Label::new(None);
+            title.set_halign(Align::Start);
+            title.set_no_show_all(true);
+            let expander = Expander::new(None);
+            expander.set_use_markup(true);
+            expander.set_expanded(true);
+            expander.set_no_show_all(true);
+            let description = Label::new(None);
+            description.set_halign(Align::Start);
+            description.set_xalign(0.0);
+            description.set_line_wrap(true);
+            description.get_style_context().add_class("dim-label");
+            description.set_no_show_all(true);
+
+            let content = GtkBox::new(Orientation::Vertical, 6);
+            let frame = Frame::new(None);
+            frame.set_shadow_type(ShadowType::None);
+            frame.add(&content);
+            let revealer = Revealer::new();
+            revealer.set_reveal_child(true);
+            revealer.add(&frame);
+            let weak = revealer.downgrade();
+            expander.connect_property_expanded_notify(move |expander| {
+                if let Some(revealer) = weak.upgrade() {
+                    revealer.set_reveal_child(expander.get_expanded() || !expander.get_visible());
+                }
+            });
+
+            for widget in &[
+                title.upcast_ref::<Widget>(),
+                expander.upcast_ref(),
+                description.upcast_ref(),
+                revealer.upcast_ref(),
+            ] {
+                section.add(*widget);
+            }
+            revealer.show_all();
+            *self.parts.borrow_mut() = Some(Parts {
+                title,
+                expander,
+                description,
+                revealer,
+                frame,
+                content,
+            });
+        }
+    }
+
+    impl WidgetImpl for Section {}
+
+    impl ContainerImpl for Section {
+        // Children go in the content box, once we've added our own parts.
+        fn add(&self, container: &Container, widget: &Widget) {
+            let content = self
+                .parts
+                .borrow()
+                .as_ref()
+                .map(|parts| parts.content.clone());
+            match content {
+                Some(content) => content.add(widget),
+                None => self.parent_add(container, widget),
+            }
+        }
+
+        fn remove(&self, container: &Container, widget: &Widget) {
+            let content = self
+                .parts
+                .borrow()
+                .as_ref()
+                .map(|parts| parts.content.clone());
+            match content {
+                Some(content) if widget.get_parent().as_ref() == Some(content.upcast_ref()) => {
+                    content.remove(widget)
+                }
+                _ => self.parent_remove(container, widget),
+            }
+        }
+    }
+
+    impl BoxImpl for Section {}
+}
+
+glib_wrapper! {
+    /// A titled group of widgets, for structuring forms and settings pages.
+    ///
+    /// A `Section` shows a heading given by its `title` attribute, followed
+    /// by an optional `description` and then its children, stacked
+    /// vertically:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{gtk, VNode};
+    /// # use vgtk::widgets::Section;
+    /// # use vgtk::lib::gtk::{CheckButton, ButtonExt};
+    /// # fn view() -> VNode<()> {
+    /// gtk! {
+    ///     <Section title="Privacy" description="Control what apps can see about you."
+    ///              collapsible=true framed=true>
+    ///         <CheckButton label="Share usage statistics" />
+    ///         <CheckButton label="Report crashes" />
+    ///     </Section>
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// If it's `collapsible`, the heading becomes an expander the user can
+    /// click to hide the section's children, and `expanded` controls whether
+    /// they're shown. If it's `framed`, the children are drawn inside a
+    /// frame, otherwise they sit right under the heading.
+    ///
+    /// Because every section is laid out the same way, using them for every
+    /// group of settings gives all your settings pages a consistent
+    /// structure.
+    pub struct Section(
+        Object<subclass::simple::InstanceStruct<imp::Section>,
+        subclass::simple::ClassStruct<imp::Section>,
+        SectionClass>)
+        @extends gtk::Box, gtk::Container, gtk::Widget,
+        @implements gtk::Buildable, gtk::Orientable;
+
+    match fn {
+        get_type => || imp::Section::get_type().to_glib(),
+    }
+}
+
+impl Section {
+    /// Construct a new, empty `Section`.
+    pub fn new() -> Self {
+        glib::Object::new(Self::static_type(), &[])
+            .expect("unable to create Section")
+            .downcast()
+            .unwrap()
+    }
+
+    fn parts(&self) -> Parts {
+        imp::Section::from_instance(self)
+            .parts
+            .borrow()
+            .clone()
+            .expect("Section hasn't been constructed")
+    }
+
+    /// Get the section's title.
+    pub fn get_title(&self) -> GString {
+        self.parts().title.get_text()
+    }
+
+    /// Set the section's title.
+    pub fn set_title(&self, title: &str) {
+        let parts = self.parts();
+        let markup = format!("<b>{}</b>", glib::markup_escape_text(title));
+        parts.title.set_markup(&markup);
+        parts.expander.set_label(Some(&markup));
+        self.update_visibility();
+    }
+
+    /// Get the section's description.
+    pub fn get_description(&self) -> GString {
+        self.parts().description.get_text()
+    }
+
+    /// Set the section's description, shown under its title.
+    pub fn set_description(&self, description: &str) {
+        let parts = self.parts();
+        parts.description.set_text(description);
+        parts.description.set_visible(!description.is_empty());
+    }
+
+    /// Test whether the user can collapse the section.
+    pub fn get_collapsible(&self) -> bool {
+        self.parts().expander.get_visible()
+    }
+
+    /// Set whether the user can collapse the section.
+    ///
+    /// A section which isn't collapsible always shows its children.
+    pub fn set_collapsible(&self, collapsible: bool) {
+        let parts = self.parts();
+        parts.expander.set_visible(collapsible);
+        parts
+            .revealer
+            .set_reveal_child(parts.expander.get_expanded() || !collapsible);
+        self.update_visibility();
+    }
+
+    /// Test whether the section's children are shown.
+    pub fn get_expanded(&self) -> bool {
+        self.parts().expander.get_expanded()
+    }
+
+    /// Set whether the section's children are shown. This only has an
+    /// effect on collapsible sections.
+    pub fn set_expanded(&self, expanded: bool) {
+        self.parts().expander.set_expanded(expanded);
+    }
+
+    /// Test whether the section's children are drawn inside a frame.
+    pub fn get_framed(&self) -> bool {
+        self.parts().frame.get_shadow_type() != ShadowType::None
+    }
+
+    /// Set whether the section's children are drawn inside a frame.
+    pub fn set_framed(&self, framed: bool) {
+        let parts = self.parts();
+        parts.frame.set_shadow_type(if framed {
+            ShadowType::In
+        } else {
+            ShadowType::None
+        });
+        parts.content.set_border_width(if framed { 12 } else { 0 });
+    }
+
+    /// Show the plain title if there is one and the section isn't
+    /// collapsible, since the expander shows it otherwise.
+    fn update_visibility(&self) {
+        let parts = self.parts();
+        parts
+            .title
+            .set_visible(!parts.title.get_text().is_empty() && !parts.expander.get_visible());
+    }
+}
+
+impl Default for Section {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+
+use glib::subclass;
+use glib::subclass::prelude::*;
+use glib::translate::*;
+use glib::{glib_object_impl, glib_object_subclass, glib_wrapper, Cast, StaticType};
+use gtk::subclass::prelude::*;
+use gtk::{
+    Align, BinClass, Box as GtkBox, ContainerExt, Orientation, OverlayClass, OverlayExt, Widget,
+    WidgetExt,
+};
+
+mod imp {
+    use super::*;
+
+    pub struct Toaster {
+        pub(super) toasts: RefCell<Option<GtkBox>>,
+    }
+
+    impl ObjectSubclass for Toaster {
+        const NAME: &'static str = "VgtkToaster";
+        type ParentType = gtk::Overlay;
+        type Instance = subclass::simple::InstanceStruct<Self>;
+        type Class = subclass::simple::ClassStruct<Self>;
+
+        glib_object_subclass!();
+
+        fn new() -> Self {
+            Toaster {
+                toasts: RefCell::new(None),
+            }
+        }
+    }
+
+    impl ObjectImpl for Toaster {
+        glib_object_impl!();
+
+        fn constructed(&self, object: &glib::Object) {
+            self.parent_constructed(object);
+            let overlay = object.downcast_ref::<gtk::Overlay>().unwrap();
+            let toasts = GtkBox::new(Orientation::Vertical, 6);
+            toasts.set_halign(Align::Center);
+            toasts.set_valign(Align::Start);
+            toasts.show();
+            overlay.add_overlay(&toasts);
+            *self.toasts.borrow_mut() = Some(toasts);
+        }
+    }
+
+    impl WidgetImpl for Toaster {}
+
+    impl ContainerImpl for Toaster {}
+
+    impl BinImpl for Toaster {}
+}
+
+// The GTK bindings don't let you subclass `Overlay` yet, but it has no
+// virtual methods of its own we'd need to override, so we can treat it like
+// its parent class.
+#[allow(unsafe_code)]
+unsafe impl IsSubclassable<imp::Toaster> for OverlayClass {
+    fn override_vfuncs(&mut self) {
+        <BinClass as IsSubclassable<imp::Toaster>>::override_vfuncs(self);
+    }
+}
+
+glib_wrapper! {
+    /// A container which shows toasts on top of its child.
+    ///
+    /// Toasts are brief in-app notifications, which you show using
+    /// [`vgtk::toast::show()`][show]. They appear in the `Toaster` of the
+    /// current window, so you'll want to wrap each window's content in one:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{gtk, VNode};
+    /// # use vgtk::widgets::Toaster;
+    /// # use vgtk::lib::gtk::{Box, Window};
+    /// # fn view() -> VNode<()> {
+    /// gtk! {
+    ///     <Window>
+    ///         <Toaster>
+    ///             <Box />
+    ///         </Toaster>
+    ///     </Window>
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Like any [`Overlay`][Overlay], it has a single child.
+    ///
+    /// [show]: ../toast/fn.show.html
+    /// [Overlay]: ../../gtk/struct.Overlay.html
+    pub struct Toaster(
+        Object<subclass::simple::InstanceStruct<imp::Toaster>,
+        subclass::simple::ClassStruct<imp::Toaster>,
+        ToasterClass>)
+        @extends gtk::Overlay, gtk::Bin, gtk::Container, gtk::Widget,
+        @implements gtk::Buildable;
+
+    match fn {
+        get_type => || imp::Toaster::get_type().to_glib(),
+    }
+}
+
+impl Toaster {
+    /// Construct a new, empty `Toaster`.
+    pub fn new() -> Self {
+        glib::Object::new(Self::static_type(), &[])
+            .expect("unable to create Toaster")
+            .downcast()
+            .unwrap()
+    }
+
+    /// The box toasts are stacked in.
+    pub(crate) fn toasts(&self) -> GtkBox {
+        imp::Toaster::from_instance(self)
+            .toasts
+            .borrow()
+            .clone()
+            .expect("Toaster hasn't been constructed")
+    }
+
+    /// Find the first `Toaster` inside a widget, including the widget itself.
+    pub(crate) fn find(widget: &Widget) -> Option<Toaster> {
+        if let Some(toaster) = widget.downcast_ref::<Toaster>() {
+            return Some(toaster.clone());
+        }
+        widget
+            .downcast_ref::<gtk::Container>()?
+            .get_children()
+            .iter()
+            .find_map(Toaster::find)
+    }
+}
+
+impl Default for Toaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
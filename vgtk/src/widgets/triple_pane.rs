@@ -0,0 +1,455 @@
+use std::cell::RefCell;
+
+use gdk::ModifierType;
+use glib::subclass;
+use glib::subclass::prelude::*;
+use glib::translate::*;
+use glib::{glib_object_impl, glib_object_subclass, glib_wrapper, Cast, ObjectExt, StaticType};
+use gtk::subclass::prelude::*;
+use gtk::{
+    Allocation, Box as GtkBox, Container, ContainerExt, DirectionType, Inhibit, Orientation, Paned,
+    PanedExt, Stack, StackExt, StackTransitionType, Widget, WidgetExt,
+};
+
+use crate::session;
+
+/// One of the three panes of a [`TriplePane`][TriplePane].
+///
+/// [TriplePane]: struct.TriplePane.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pane {
+    /// The sidebar, on the left.
+    Sidebar,
+    /// The list, in the middle.
+    #[default]
+    List,
+    /// The details of what's selected in the list, on the right.
+    Detail,
+}
+
+impl Pane {
+    const ALL: [Pane; 3] = [Pane::Sidebar, Pane::List, Pane::Detail];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Pane::Sidebar => "sidebar",
+            Pane::List => "list",
+            Pane::Detail => "detail",
+        }
+    }
+}
+
+/// The widgets making up a `TriplePane`, apart from its children.
+#[derive(Clone)]
+struct Parts {
+    // Each child goes in a box of its own, which is what moves around when
+    // we switch layouts, so the children always keep the same parent.
+    holders: [GtkBox; 3],
+    outer: Paned,
+    inner: Paned,
+    stack: Stack,
+}
+
+#[derive(Debug)]
+struct Settings {
+    narrow: bool,
+    collapse_width: i32,
+    visible_pane: Pane,
+    session_key: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            narrow: false,
+            collapse_width: 720,
+            visible_pane: Pane::default(),
+            session_key: String::new(),
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    pub struct TriplePane {
+        pub(super) parts: RefCell<Option<Parts>>,
+        pub(super) settings: RefCell<Settings>,
+    }
+
+    impl ObjectSubclass for TriplePane {
+        const NAME: &'static str = "VgtkTriplePane";
+        type ParentType = gtk::Box;
+        type Instance = subclass::simple::InstanceStruct<Self>;
+        type Class = subclass::simple::ClassStruct<Self>;
+
+        glib_object_subclass!();
+
+        fn new() -> Self {
+            TriplePane {
+                parts: RefCell::new(None),
+                settings: RefCell::new(Default::default()),
+            }
+        }
+    }
+
+    impl ObjectImpl for TriplePane {
+        glib_object_impl!();
+
+        fn constructed(&self, object: &glib::Object) {
+            self.parent_constructed(object);
+            let container = object.downcast_ref::<Container>().unwrap();
+
+            let holder = || {
+                let holder = GtkBox::new(Orientation::Vertical, 0);
+                holder.set_hexpand(true);
+                holder.set_vexpand(true);
+                holder
+            };
+            let holders = [holder(), holder(), holder()];
+            let inner = Paned::new(Orientation::Horizontal);
+            inner.pack1(&holders[1], false, false);
+            inner.pack2(&holders[2], true, false);
+            let outer = Paned::new(Orientation::Horizontal);
+            outer.pack1(&holders[0], false, false);
+            outer.pack2(&inner, true, false);
+            let stack = Stack::new();
+            stack.set_transition_type(StackTransitionType::SlideLeftRight);
+            stack.set_hexpand(true);
+            stack.set_vexpand(true);
+
+            for paned in &[&outer, &inner] {
+                paned.connect_property_position_notify(|paned| {
+                    if paned.get_property_position_set() {
+                        if let Some(pane) = paned.get_ancestor(TriplePane::get_type()) {
+                            pane.downcast_ref::<super::TriplePane>()
+                                .unwrap()
+                                .save_positions();
+                        }
+                    }
+                });
+            }
+
+            self.parent_add(container, outer.upcast_ref());
+            outer.show_all();
+
+            let weak = object
+                .downcast_ref::<super::TriplePane>()
+                .unwrap()
+                .downgrade();
+            object
+                .downcast_ref::<Widget>()
+                .unwrap()
+                .connect_key_press_event(move |_, event| match weak.upgrade() {
+                    Some(pane) => Inhibit(pane.navigate(event)),
+                    None => Inhibit(false),
+                });
+
+            *self.parts.borrow_mut() = Some(Parts {
+                holders,
+                outer,
+                inner,
+                stack,
+            });
+        }
+    }
+
+    impl TriplePane {
+        fn holders(&self) -> Option<[GtkBox; 3]> {
+            self.parts
+                .borrow()
+                .as_ref()
+                .map(|parts| parts.holders.clone())
+        }
+
+        /// Switch between the side by side layout and the stacked layout.
+        pub(super) fn set_narrow(&self, container: &Container, narrow: bool) {
+            let parts = match self.parts.borrow().clone() {
+                Some(parts) => parts,
+                None => return,
+            };
+            let visible_pane = {
+                let mut settings = self.settings.borrow_mut();
+                if settings.narrow == narrow {
+                    return;
+                }
+                settings.narrow = narrow;
+                settings.visible_pane
+            };
+            if narrow {
+                parts.outer.remove(&parts.holders[0]);
+                parts.inner.remove(&parts.holders[1]);
+                parts.inner.remove(&parts.holders[2]);
+                self.parent_remove(container, parts.outer.upcast_ref());
+                for pane in Pane::ALL.iter() {
+                    parts
+                        .stack
+                        .add_named(&parts.holders[pane.index()], pane.name());
+                }
+                self.parent_add(container, parts.stack.upcast_ref());
+                parts.stack.show();
+                parts
+                    .stack
+                    .set_visible_child(&parts.holders[visible_pane.index()]);
+            } else {
+                for holder in &parts.holders {
+                    parts.stack.remove(holder);
+                }
+                self.parent_remove(container, parts.stack.upcast_ref());
+                parts.outer.pack1(&parts.holders[0], false, false);
+                parts.inner.pack1(&parts.holders[1], false, false);
+                parts.inner.pack2(&parts.holders[2], true, false);
+                self.parent_add(container, parts.outer.upcast_ref());
+                parts.outer.show();
+            }
+        }
+    }
+
+    impl WidgetImpl for TriplePane {
+        fn size_allocate(&self, widget: &Widget, allocation: &Allocation) {
+            self.parent_size_allocate(widget, allocation);
+            let narrow = allocation.width < self.settings.borrow().collapse_width;
+            if narrow != self.settings.borrow().narrow {
+                // Changing the layout causes a resize, which we shouldn't do
+                // in the middle of an allocation.
+                let pane = widget
+                    .downcast_ref::<super::TriplePane>()
+                    .unwrap()
+                    .downgrade();
+                glib::idle_add_local(move || {
+                    if let Some(pane) = pane.upgrade() {
+                        let imp = TriplePane::from_instance(&pane);
+                        imp.set_narrow(pane.upcast_ref(), narrow);
+                    }
+                    glib::Continue(false)
+                });
+            }
+        }
+    }
+
+    impl ContainerImpl for TriplePane {
+        // Children go in the first empty pane.
+        fn add(&self, container: &Container, widget: &Widget) {
+            let holders = match self.holders() {
+                Some(holders) => holders,
+                None => return self.parent_add(container, widget),
+            };
+            match holders
+                .iter()
+                .find(|holder| holder.get_children().is_empty())
+            {
+                Some(holder) => holder.add(widget),
+                None => panic!("TriplePane can only have 3 children."),
+            }
+        }
+
+        fn remove(&self, container: &Container, widget: &Widget) {
+            let parent = widget.get_parent();
+            match self.holders().and_then(|holders| {
+                holders
+                    .iter()
+                    .find(|holder| parent.as_ref() == Some(holder.upcast_ref()))
+                    .cloned()
+            }) {
+                Some(holder) => holder.remove(widget),
+                None => self.parent_remove(container, widget),
+            }
+        }
+    }
+
+    impl BoxImpl for TriplePane {}
+}
+
+glib_wrapper! {
+    /// A layout with a sidebar, a list and a detail view side by side, like
+    /// in an email or a chat app.
+    ///
+    /// Its first child goes in the sidebar, the second in the list pane and
+    /// the third in the detail pane. The user can resize the panes, and
+    /// move the keyboard focus between them with F6 and Shift+F6:
+    ///
+    /// ```rust,no_run
+    /// # use vgtk::{gtk, VNode};
+    /// # use vgtk::widgets::{Pane, TriplePane};
+    /// # use vgtk::lib::gtk::{Box, ListBox};
+    /// # fn view(selected: bool) -> VNode<()> {
+    /// gtk! {
+    ///     <TriplePane session_key="mail"
+    ///                 visible_pane={ if selected { Pane::Detail } else { Pane::List } }>
+    ///         <ListBox />
+    ///         <ListBox />
+    ///         <Box />
+    ///     </TriplePane>
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// When it's narrower than its `collapse_width`, which defaults to 720
+    /// pixels, it shows one pane at a time instead, and `visible_pane`
+    /// decides which one, so set it to `Pane::Detail` when the user selects
+    /// something in the list. Alt+Left goes back to the previous pane.
+    ///
+    /// If you give it a `session_key`, the sizes the user gives the panes
+    /// are stored in the [`session`][session] under that key, and restored
+    /// the next time the app starts.
+    ///
+    /// [session]: ../session/index.html
+    pub struct TriplePane(
+        Object<subclass::simple::InstanceStruct<imp::TriplePane>,
+        subclass::simple::ClassStruct<imp::TriplePane>,
+        TriplePaneClass>)
+        @extends gtk::Box, gtk::Container, gtk::Widget,
+        @implements gtk::Buildable, gtk::Orientable;
+
+    match fn {
+        get_type => || imp::TriplePane::get_type().to_glib(),
+    }
+}
+
+impl TriplePane {
+    /// Construct a new, empty `TriplePane`.
+    pub fn new() -> Self {
+        glib::Object::new(Self::static_type(), &[])
+            .expect("unable to create TriplePane")
+            .downcast()
+            .unwrap()
+    }
+
+    fn imp(&self) -> &imp::TriplePane {
+        imp::TriplePane::from_instance(self)
+    }
+
+    fn parts(&self) -> Parts {
+        self.imp()
+            .parts
+            .borrow()
+            .clone()
+            .expect("TriplePane hasn't been constructed")
+    }
+
+    /// Get the width below which the panes are shown one at a time.
+    pub fn get_collapse_width(&self) -> i32 {
+        self.imp().settings.borrow().collapse_width
+    }
+
+    /// Set the width below which the panes are shown one at a time.
+    pub fn set_collapse_width(&self, collapse_width: i32) {
+        self.imp().settings.borrow_mut().collapse_width = collapse_width;
+        self.queue_resize();
+    }
+
+    /// Test whether the panes are currently shown one at a time.
+    pub fn is_collapsed(&self) -> bool {
+        self.imp().settings.borrow().narrow
+    }
+
+    /// Get the pane which is shown when the panes are shown one at a time.
+    pub fn get_visible_pane(&self) -> Pane {
+        self.imp().settings.borrow().visible_pane
+    }
+
+    /// Set the pane to show when the panes are shown one at a time.
+    pub fn set_visible_pane(&self, pane: Pane) {
+        self.imp().settings.borrow_mut().visible_pane = pane;
+        let parts = self.parts();
+        if self.is_collapsed() {
+            parts.stack.set_visible_child(&parts.holders[pane.index()]);
+        }
+    }
+
+    /// Get the key the pane sizes are stored under in the session.
+    pub fn get_session_key(&self) -> String {
+        self.imp().settings.borrow().session_key.clone()
+    }
+
+    /// Set the key to store the pane sizes under in the session, and restore
+    /// the sizes stored under it, if any. An empty key stores nothing.
+    pub fn set_session_key(&self, key: &str) {
+        self.imp().settings.borrow_mut().session_key = key.to_string();
+        if key.is_empty() {
+            return;
+        }
+        let parts = self.parts();
+        for (paned, name) in &[(&parts.outer, "sidebar"), (&parts.inner, "list")] {
+            if let Some(position) =
+                session::get(&format!("{}.{}", key, name)).and_then(|value| value.parse().ok())
+            {
+                paned.set_position(position);
+            }
+        }
+    }
+
+    fn save_positions(&self) {
+        let key = self.get_session_key();
+        if key.is_empty() {
+            return;
+        }
+        let parts = self.parts();
+        for (paned, name) in &[(&parts.outer, "sidebar"), (&parts.inner, "list")] {
+            if paned.get_property_position_set() {
+                session::set(
+                    &format!("{}.{}", key, name),
+                    &paned.get_position().to_string(),
+                );
+            }
+        }
+    }
+
+    /// Move the keyboard focus to a pane, showing it if the panes are shown
+    /// one at a time.
+    pub fn focus_pane(&self, pane: Pane) {
+        if self.is_collapsed() {
+            self.set_visible_pane(pane);
+        }
+        self.parts().holders[pane.index()].child_focus(DirectionType::TabForward);
+    }
+
+    /// The pane which has the keyboard focus, if any.
+    fn focused_pane(&self) -> Option<Pane> {
+        let focus = self
+            .get_toplevel()
+            .and_then(|toplevel| toplevel.downcast::<gtk::Window>().ok())
+            .and_then(|window| gtk::GtkWindowExt::get_focus(&window))?;
+        let holders = self.parts().holders;
+        Pane::ALL
+            .iter()
+            .copied()
+            .find(|pane| focus.is_ancestor(&holders[pane.index()]))
+    }
+
+    /// Handle the keyboard shortcuts for moving between panes, returning
+    /// `true` if `event` was one of them.
+    fn navigate(&self, event: &gdk::EventKey) -> bool {
+        let state = event.get_state();
+        let current = self
+            .focused_pane()
+            .unwrap_or_else(|| self.get_visible_pane());
+        let target = match event.get_keyval() {
+            gdk::keys::constants::F6 if state.contains(ModifierType::SHIFT_MASK) => {
+                Pane::ALL[(current.index() + 2) % 3]
+            }
+            gdk::keys::constants::F6 => Pane::ALL[(current.index() + 1) % 3],
+            gdk::keys::constants::Left
+                if state.contains(ModifierType::MOD1_MASK) && self.is_collapsed() =>
+            {
+                match current {
+                    Pane::Sidebar => return false,
+                    _ => Pane::ALL[current.index() - 1],
+                }
+            }
+            _ => return false,
+        };
+        self.focus_pane(target);
+        true
+    }
+}
+
+impl Default for TriplePane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,236 @@
+//! Zooming the whole application.
+//!
+//! Users who find the text too small expect to be able to zoom in with
+//! Ctrl+Plus or Ctrl and the scroll wheel, like in a web browser, and
+//! applications started with [`vgtk::run()`][run] or [`vgtk::start()`][start]
+//! get these shortcuts for free: Ctrl+Plus and Ctrl+Minus zoom in and out,
+//! as does scrolling with Ctrl held down, and Ctrl+0 resets the zoom. An app
+//! which adds its own `zoom-in`, `zoom-out` or `zoom-reset` action replaces
+//! ours. You can also change the zoom yourself with [`set()`][set], eg. from
+//! a preferences dialog:
+//!
+//! ```rust,no_run
+//! vgtk::zoom::set(1.25);
+//! ```
+//!
+//! Zooming scales the text of every widget. It also re-renders every
+//! component, so views can use [`get()`][get] to scale anything else, like
+//! the zoom level of a web view or the size of an image:
+//!
+//! ```rust,no_run
+//! # use vgtk::{gtk, VNode};
+//! # use vgtk::lib::gtk::{Image, ImageExt};
+//! # fn view() -> VNode<()> {
+//! gtk! {
+//!     <Image pixel_size=(48.0 * vgtk::zoom::get()) as i32 icon_name="face-smile" />
+//! }
+//! # }
+//! ```
+//!
+//! For use outside of components, [`changes()`][changes] gives you a
+//! [`Stream`][Stream] of the new zoom factor every time it changes.
+//!
+//! [run]: ../fn.run.html
+//! [start]: ../fn.start.html
+//! [set]: fn.set.html
+//! [get]: fn.get.html
+//! [changes]: fn.changes.html
+//! [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+
+use std::cell::RefCell;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use gdk::{EventType, ModifierType, ScrollDirection};
+use gio::prelude::*;
+use gio::SimpleAction;
+use gtk::{Application, GtkApplicationExt, Settings, SettingsExt};
+
+use colored::Colorize;
+use log::debug;
+
+use crate::affinity;
+use crate::component::rerender_all;
+
+/// The smallest zoom factor allowed.
+pub const MIN: f64 = 0.5;
+
+/// The largest zoom factor allowed.
+pub const MAX: f64 = 3.0;
+
+/// How much [`zoom_in()`][zoom_in] and [`zoom_out()`][zoom_out] change the
+/// zoom factor by.
+///
+/// [zoom_in]: fn.zoom_in.html
+/// [zoom_out]: fn.zoom_out.html
+pub const STEP: f64 = 0.1;
+
+// GTK measures the font resolution in 1024ths of a dot per inch.
+const DEFAULT_DPI: i32 = 96 * 1024;
+
+struct Zoom {
+    factor: f64,
+    // The font resolution before we started scaling it.
+    base_dpi: Option<i32>,
+    // Smooth scrolling moves in fractions of a step.
+    scrolled: f64,
+    subscribers: Vec<UnboundedSender<f64>>,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Zoom {
+            factor: 1.0,
+            base_dpi: None,
+            scrolled: 0.0,
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+thread_local! {
+    static ZOOM: RefCell<Zoom> = RefCell::new(Default::default());
+}
+
+/// Get the current zoom factor, where `1.0` is the normal size.
+pub fn get() -> f64 {
+    ZOOM.with(|zoom| zoom.borrow().factor)
+}
+
+/// Set the zoom factor, where `1.0` is the normal size, and re-render every
+/// component on this thread.
+///
+/// The factor is kept between [`MIN`][MIN] and [`MAX`][MAX]. A factor
+/// which isn't a number is ignored.
+///
+/// [MIN]: constant.MIN.html
+/// [MAX]: constant.MAX.html
+pub fn set(factor: f64) {
+    affinity::assert_main_thread("vgtk::zoom::set");
+    if factor.is_nan() {
+        return;
+    }
+    // Round off the errors adding up steps would otherwise accumulate.
+    let factor = (factor.clamp(MIN, MAX) * 100.0).round() / 100.0;
+    let settings = Settings::get_default();
+    let dpi = ZOOM.with(|zoom| {
+        let mut zoom = zoom.borrow_mut();
+        if zoom.factor == factor {
+            return None;
+        }
+        debug!(
+            "{} {}",
+            "Changing zoom to".bright_blue(),
+            factor.to_string().magenta().bold()
+        );
+        zoom.factor = factor;
+        zoom.subscribers
+            .retain(|subscriber| subscriber.unbounded_send(factor).is_ok());
+        let base_dpi = *zoom.base_dpi.get_or_insert_with(|| {
+            settings
+                .as_ref()
+                .map(SettingsExt::get_property_gtk_xft_dpi)
+                .filter(|dpi| *dpi > 0)
+                .unwrap_or(DEFAULT_DPI)
+        });
+        Some((base_dpi as f64 * factor).round() as i32)
+    });
+    if let Some(dpi) = dpi {
+        if let Some(settings) = settings {
+            settings.set_property_gtk_xft_dpi(dpi);
+        }
+        rerender_all();
+    }
+}
+
+/// Zoom in by one [`STEP`][STEP].
+///
+/// [STEP]: constant.STEP.html
+pub fn zoom_in() {
+    set(get() + STEP);
+}
+
+/// Zoom out by one [`STEP`][STEP].
+///
+/// [STEP]: constant.STEP.html
+pub fn zoom_out() {
+    set(get() - STEP);
+}
+
+/// Go back to the normal size.
+pub fn reset() {
+    set(1.0);
+}
+
+/// Get a [`Stream`][Stream] which produces the new zoom factor every time it
+/// changes.
+///
+/// [Stream]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+pub fn changes() -> UnboundedReceiver<f64> {
+    let (sender, receiver) = unbounded();
+    ZOOM.with(|zoom| zoom.borrow_mut().subscribers.push(sender));
+    receiver
+}
+
+/// Zoom if `event` is a scroll with Ctrl held down, returning `true` if it
+/// was.
+fn scroll(event: &gdk::Event) -> bool {
+    if event.get_event_type() != EventType::Scroll
+        || !event
+            .get_state()
+            .is_some_and(|state| state.contains(ModifierType::CONTROL_MASK))
+    {
+        return false;
+    }
+    let delta = match event.get_scroll_direction() {
+        Some(ScrollDirection::Up) => -1.0,
+        Some(ScrollDirection::Down) => 1.0,
+        Some(ScrollDirection::Smooth) => event.get_scroll_deltas().map_or(0.0, |(_, dy)| dy),
+        _ => return false,
+    };
+    let steps = ZOOM.with(|zoom| {
+        let mut zoom = zoom.borrow_mut();
+        zoom.scrolled += delta;
+        let steps = zoom.scrolled.trunc();
+        zoom.scrolled -= steps;
+        steps
+    });
+    if steps != 0.0 {
+        set(get() - steps * STEP);
+    }
+    true
+}
+
+/// Install the zoom shortcuts on an application.
+pub(crate) fn install(app: &Application) {
+    let actions: [(&str, fn(), &[&str]); 3] = [
+        (
+            "zoom-in",
+            zoom_in,
+            &["<Primary>plus", "<Primary>equal", "<Primary>KP_Add"],
+        ),
+        (
+            "zoom-out",
+            zoom_out,
+            &["<Primary>minus", "<Primary>KP_Subtract"],
+        ),
+        ("zoom-reset", reset, &["<Primary>0", "<Primary>KP_0"]),
+    ];
+    for (name, activate, accels) in actions.iter() {
+        if app.lookup_action(name).is_some() {
+            // The app has its own idea of what this should do.
+            continue;
+        }
+        let action = SimpleAction::new(name, None);
+        let activate = *activate;
+        action.connect_activate(move |_, _| activate());
+        app.add_action(&action);
+        app.set_accels_for_action(&format!("app.{}", name), accels);
+    }
+    // Scroll events go to the widget under the pointer first, which would
+    // scroll instead, so catch them before GTK gets to see them.
+    gdk::Event::set_handler(Some(|event: &mut gdk::Event| {
+        if !scroll(event) {
+            gtk::main_do_event(event);
+        }
+    }));
+}
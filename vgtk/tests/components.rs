@@ -0,0 +1,576 @@
+//! Tests of the order in which components see their messages, renders and
+//! teardown.
+//!
+//! GTK only runs on the thread it was initialised on, so these run one at a
+//! time on the main thread rather than with the standard test harness. Run
+//! them all with `cargo test --test components`, or just the ones whose
+//! names contain some text with eg. `cargo test --test components -- send`.
+
+use std::sync::{Arc, Mutex};
+
+use vgtk::lib::glib::Cast;
+use vgtk::lib::gtk::*;
+use vgtk::test::{find, Executor, Offscreen};
+use vgtk::{gtk, Callback, Component, Moved, Queryable, Scope, UpdateAction, VNode};
+
+/// What the components in a test did, in order.
+///
+/// This is `Send` so that `Scope::on_drop()` callbacks can write to it.
+type Log = Arc<Mutex<Vec<String>>>;
+
+fn record(log: &Log, entry: impl Into<String>) {
+    log.lock().unwrap().push(entry.into());
+}
+
+/// Take everything recorded so far, leaving the log empty.
+fn take(log: &Log) -> Vec<String> {
+    std::mem::take(&mut *log.lock().unwrap())
+}
+
+#[derive(Debug)]
+enum Step {
+    Start(Scope<Stepper>),
+    Next(u32),
+    Nested,
+}
+
+/// A component which sends itself messages from `update()`.
+struct Stepper {
+    log: Log,
+    scope: Option<Scope<Stepper>>,
+}
+
+impl Component for Stepper {
+    type Message = Step;
+    type Properties = Log;
+
+    fn create(log: Log) -> Self {
+        Stepper { log, scope: None }
+    }
+
+    fn update(&mut self, message: Step) -> UpdateAction<Self> {
+        match message {
+            Step::Start(scope) => {
+                record(&self.log, "update Start");
+                scope.try_send(Step::Next(1)).unwrap();
+                scope.try_send(Step::Next(2)).unwrap();
+                self.scope = Some(scope);
+            }
+            Step::Next(step) => {
+                record(&self.log, format!("update Next({})", step));
+                if step == 1 {
+                    // Sent while handling a message which was itself sent
+                    // from `update()`.
+                    let scope = self.scope.as_ref().unwrap();
+                    scope.try_send(Step::Nested).unwrap();
+                }
+            }
+            Step::Nested => record(&self.log, "update Nested"),
+        }
+        UpdateAction::Render
+    }
+
+    fn view(&self) -> VNode<Self> {
+        record(&self.log, "view");
+        gtk! { <Label label="Stepper" /> }
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        record(&self.log, "rendered");
+    }
+}
+
+fn self_sends_are_handled_before_rendering() {
+    let executor = Executor::new();
+    let window = Offscreen::new(200, 100);
+    let log = Log::default();
+    let stepper = window.mount::<Stepper>(log.clone());
+    executor.run_until_stalled();
+    take(&log);
+
+    stepper.send(Step::Start(stepper.scope()));
+    executor.run_until_stalled();
+    assert_eq!(
+        take(&log),
+        [
+            "update Start",
+            "update Next(1)",
+            "update Next(2)",
+            "update Nested",
+            "view",
+            "rendered",
+        ]
+    );
+}
+
+/// A subcomponent which calls back to its parent as soon as it's created,
+/// which is while its parent is rendering.
+#[derive(Clone, Default)]
+struct Eager {
+    log: Log,
+    on_created: Callback<()>,
+}
+
+impl Component for Eager {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        record(&props.log, "child calls back");
+        props.on_created.send(());
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::None
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! { <Label label="Eager" /> }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Family {
+    AddChild,
+    ChildCreated,
+}
+
+struct Parent {
+    log: Log,
+    child: bool,
+}
+
+impl Component for Parent {
+    type Message = Family;
+    type Properties = Log;
+
+    fn create(log: Log) -> Self {
+        Parent { log, child: false }
+    }
+
+    fn update(&mut self, message: Family) -> UpdateAction<Self> {
+        record(&self.log, format!("update {:?}", message));
+        if let Family::AddChild = message {
+            self.child = true;
+        }
+        UpdateAction::Render
+    }
+
+    fn view(&self) -> VNode<Self> {
+        record(&self.log, "view");
+        let log = self.log.clone();
+        gtk! {
+            <Box>
+                {
+                    self.child.then(|| gtk! {
+                        <@Eager log=log on created=|_| Family::ChildCreated />
+                    })
+                }
+            </Box>
+        }
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        record(&self.log, "rendered");
+    }
+}
+
+fn child_callbacks_while_rendering_are_handled_after_it() {
+    let executor = Executor::new();
+    let window = Offscreen::new(200, 100);
+    let log = Log::default();
+    let parent = window.mount::<Parent>(log.clone());
+    executor.run_until_stalled();
+    take(&log);
+
+    parent.send(Family::AddChild);
+    executor.run_until_stalled();
+    assert_eq!(
+        take(&log),
+        [
+            "update AddChild",
+            "view",
+            "child calls back",
+            "rendered",
+            "update ChildCreated",
+            "view",
+            "rendered",
+        ]
+    );
+}
+
+/// A component with a chain of subcomponents inside it, one for each name
+/// after its own.
+#[derive(Clone, Default)]
+struct Node {
+    names: Vec<&'static str>,
+    log: Log,
+    widget: Option<Widget>,
+}
+
+impl Component for Node {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        self.names = props.names;
+        self.log = props.log;
+        UpdateAction::Render
+    }
+
+    fn view(&self) -> VNode<Self> {
+        let rest = &self.names[1..];
+        let child = if rest.is_empty() {
+            None
+        } else {
+            Some(gtk! { <@Node names=rest.to_vec() log=self.log.clone() /> })
+        };
+        gtk! {
+            <Box>
+                { child }
+            </Box>
+        }
+    }
+
+    fn rendered(&mut self, first_render: bool) {
+        if first_render {
+            self.widget = vgtk::current_object().and_then(|object| object.downcast().ok());
+        }
+    }
+
+    fn destroyed(&mut self) {
+        let attached = self
+            .widget
+            .as_ref()
+            .and_then(|widget| widget.get_parent())
+            .is_some();
+        let name = self.names[0];
+        if attached {
+            record(&self.log, format!("{} destroyed while attached", name));
+        } else {
+            record(&self.log, format!("{} destroyed", name));
+        }
+    }
+}
+
+fn subcomponents_are_destroyed_first_and_after_their_widgets() {
+    let executor = Executor::new();
+    let window = Offscreen::new(200, 100);
+    let log = Log::default();
+    let outer = window.mount::<Node>(Node {
+        names: vec!["Outer", "Middle", "Inner"],
+        log: log.clone(),
+        widget: None,
+    });
+    executor.run_until_stalled();
+    let dropped = log.clone();
+    outer
+        .scope()
+        .on_drop(move || record(&dropped, "Outer dropped"));
+    assert!(take(&log).is_empty());
+
+    outer.unmount();
+    executor.run_until_stalled();
+    assert_eq!(
+        take(&log),
+        [
+            "Inner destroyed",
+            "Middle destroyed",
+            "Outer destroyed",
+            "Outer dropped",
+        ]
+    );
+    assert!(window.window().get_child().is_none());
+}
+
+/// A component which exposes how many times it's been clicked.
+#[derive(Default)]
+struct Clicks {
+    clicks: usize,
+}
+
+impl Queryable for Clicks {
+    type Snapshot = usize;
+
+    fn snapshot(&self) -> usize {
+        self.clicks
+    }
+}
+
+impl Component for Clicks {
+    type Message = ();
+    type Properties = ();
+
+    fn create(_props: ()) -> Self {
+        Self::default()
+    }
+
+    fn init(&mut self) -> UpdateAction<Self> {
+        self.expose("clicks");
+        UpdateAction::None
+    }
+
+    fn update(&mut self, _message: ()) -> UpdateAction<Self> {
+        self.clicks += 1;
+        UpdateAction::None
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! { <Button label="Click" /> }
+    }
+}
+
+fn exposed_snapshots_follow_the_component() {
+    let executor = Executor::new();
+    let window = Offscreen::new(200, 100);
+    let clicks = window.mount::<Clicks>(());
+    executor.run_until_stalled();
+    assert_eq!(vgtk::query::<Clicks>("clicks"), Some(0));
+
+    clicks.send(());
+    clicks.send(());
+    executor.run_until_stalled();
+    assert_eq!(vgtk::query::<Clicks>("clicks"), Some(2));
+
+    clicks.unmount();
+    executor.run_until_stalled();
+    assert_eq!(vgtk::query::<Clicks>("clicks"), None);
+}
+
+fn a_replaced_snapshot_outlives_the_instance_it_replaced() {
+    let executor = Executor::new();
+    let old_window = Offscreen::new(200, 100);
+    let old = old_window.mount::<Clicks>(());
+    old.send(());
+    executor.run_until_stalled();
+    assert_eq!(vgtk::query::<Clicks>("clicks"), Some(1));
+
+    // The replacement exposes the same key before the old one goes away.
+    let new_window = Offscreen::new(200, 100);
+    let new = new_window.mount::<Clicks>(());
+    executor.run_until_stalled();
+    old.unmount();
+    executor.run_until_stalled();
+    assert_eq!(vgtk::query::<Clicks>("clicks"), Some(0));
+
+    new.unmount();
+    executor.run_until_stalled();
+    assert_eq!(vgtk::query::<Clicks>("clicks"), None);
+}
+
+/// A card which can be moved from one board to another.
+#[derive(Clone, Default)]
+struct Card {
+    id: u32,
+    log: Log,
+}
+
+impl Component for Card {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        record(&props.log, format!("card {} created", props.id));
+        props
+    }
+
+    fn change(&mut self, props: Self) -> UpdateAction<Self> {
+        *self = props;
+        UpdateAction::Render
+    }
+
+    fn view(&self) -> VNode<Self> {
+        gtk! { <Label label=format!("Card {}", self.id) /> }
+    }
+
+    fn destroyed(&mut self) {
+        record(&self.log, format!("card {} destroyed", self.id));
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Deal {
+    Add(u32),
+    Remove(u32),
+}
+
+/// A board of cards, each in its own window.
+#[derive(Clone, Default)]
+struct Board {
+    name: &'static str,
+    cards: Vec<u32>,
+    log: Log,
+}
+
+impl Component for Board {
+    type Message = Deal;
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn update(&mut self, message: Deal) -> UpdateAction<Self> {
+        match message {
+            Deal::Add(id) => self.cards.push(id),
+            Deal::Remove(id) => self.cards.retain(|card| *card != id),
+        }
+        UpdateAction::Render
+    }
+
+    fn moved(&mut self, moved: &Moved) -> UpdateAction<Self> {
+        let card = (1..=3).find(|id: &u32| moved.has_key(id)).unwrap();
+        record(&self.log, format!("{} saw card {} move", self.name, card));
+        UpdateAction::None
+    }
+
+    fn view(&self) -> VNode<Self> {
+        let log = &self.log;
+        gtk! {
+            <Box>
+                {
+                    self.cards.iter().map(move |id| gtk! {
+                        <@Card key=id id={*id} log=log.clone() />
+                    })
+                }
+            </Box>
+        }
+    }
+}
+
+fn keyed_subcomponents_move_between_windows() {
+    let executor = Executor::new();
+    let (left, right) = (Offscreen::new(200, 100), Offscreen::new(200, 100));
+    let log = Log::default();
+    let board = |name, cards: &[u32]| Board {
+        name,
+        cards: cards.to_vec(),
+        log: log.clone(),
+    };
+    let left_board = left.mount::<Board>(board("left", &[1, 2]));
+    let right_board = right.mount::<Board>(board("right", &[3]));
+    executor.run_until_stalled();
+    take(&log);
+    let label = find::<Label>(left.window()).with_label("Card 1").get();
+
+    // Leaving one window before turning up in the other, which has to
+    // happen before the next frame.
+    left_board.send(Deal::Remove(1));
+    right_board.send(Deal::Add(1));
+    executor.run_until_stalled();
+    let mut seen = take(&log);
+    seen.sort();
+    assert_eq!(seen, ["left saw card 1 move", "right saw card 1 move"]);
+    assert!(find::<Label>(left.window())
+        .with_label("Card 1")
+        .first()
+        .is_none());
+    assert_eq!(
+        find::<Label>(right.window()).with_label("Card 1").get(),
+        label
+    );
+
+    // Turning up in the other window before leaving this one.
+    left_board.send(Deal::Add(1));
+    right_board.send(Deal::Remove(1));
+    executor.run_until_stalled();
+    let mut seen = take(&log);
+    seen.sort();
+    assert_eq!(seen, ["left saw card 1 move", "right saw card 1 move"]);
+    assert_eq!(
+        find::<Label>(left.window()).with_label("Card 1").get(),
+        label
+    );
+
+    // Leaving without turning up anywhere else.
+    left_board.send(Deal::Remove(2));
+    executor.run_until_stalled();
+    assert_eq!(take(&log), ["card 2 destroyed"]);
+}
+
+/// A component whose view is nothing but a card.
+#[derive(Clone, Default)]
+struct Sleeve {
+    card: Card,
+}
+
+impl Component for Sleeve {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(props: Self) -> Self {
+        props
+    }
+
+    fn view(&self) -> VNode<Self> {
+        let card = &self.card;
+        gtk! { <@Card id={card.id} log=card.log.clone() /> }
+    }
+}
+
+fn a_subcomponent_can_be_the_root_of_a_view() {
+    let executor = Executor::new();
+    let window = Offscreen::new(200, 100);
+    let log = Log::default();
+    let card = Card {
+        id: 1,
+        log: log.clone(),
+    };
+    let sleeve = window.mount::<Sleeve>(Sleeve { card });
+    executor.run_until_stalled();
+    assert_eq!(take(&log), ["card 1 created"]);
+    find::<Label>(window.window()).with_label("Card 1").get();
+
+    sleeve.unmount();
+    executor.run_until_stalled();
+    assert_eq!(take(&log), ["card 1 destroyed"]);
+}
+
+fn main() {
+    let tests: &[(&str, fn())] = &[
+        (
+            "self_sends_are_handled_before_rendering",
+            self_sends_are_handled_before_rendering,
+        ),
+        (
+            "child_callbacks_while_rendering_are_handled_after_it",
+            child_callbacks_while_rendering_are_handled_after_it,
+        ),
+        (
+            "subcomponents_are_destroyed_first_and_after_their_widgets",
+            subcomponents_are_destroyed_first_and_after_their_widgets,
+        ),
+        (
+            "exposed_snapshots_follow_the_component",
+            exposed_snapshots_follow_the_component,
+        ),
+        (
+            "a_replaced_snapshot_outlives_the_instance_it_replaced",
+            a_replaced_snapshot_outlives_the_instance_it_replaced,
+        ),
+        (
+            "keyed_subcomponents_move_between_windows",
+            keyed_subcomponents_move_between_windows,
+        ),
+        (
+            "a_subcomponent_can_be_the_root_of_a_view",
+            a_subcomponent_can_be_the_root_of_a_view,
+        ),
+    ];
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    for (name, test) in tests {
+        if filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str())) {
+            test();
+            println!("test {} ... ok", name);
+        }
+    }
+}